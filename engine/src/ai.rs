@@ -0,0 +1,652 @@
+// How the AI players pick their cards and colors
+
+use crate::cards::{ALL_COLORS, Color, Hand, PLAIN_COLORS, SpecialCard, UNOCard, colors, format_card_message, get_color};
+use crate::game::{Difficulty, Table, difficulty_name};
+use crate::log::Level;
+use crate::rules::{allowed_move, check_countercards};
+use crate::rng::{Randler, Rng};
+use crate::search::{Move, Position};
+
+// How many cards the seats either side hold, going by the direction of play, so Skilled can tell when a skip or a
+// reverse is worth playing, and which colors the next seat has drawn on so it can steer the game into them
+// The keyboard mode's hint has no table to look at and goes without
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbors {
+    pub next: usize,
+    pub previous: usize,
+    pub seats: usize,
+    pub next_lacks: [bool; 6], // In ALL_COLORS order
+}
+
+impl Neighbors {
+    pub fn at(t: &Table) -> Neighbors {
+        let mut next = t.game_state;
+        next.next_turn();
+        let mut previous = t.game_state;
+        previous.reverse();
+        previous.next_turn();
+        Neighbors {
+            next: t.hands[next.current_player].len(),
+            previous: t.hands[previous.current_player].len(),
+            seats: t.game_state.max_players,
+            next_lacks: ALL_COLORS.map(|color| t.lacking[next.current_player].contains(&color)),
+        }
+    }
+
+    fn lacks(&self, color: Color) -> bool {
+        ALL_COLORS.iter().position(|c| *c == color).is_some_and(|i| self.next_lacks[i])
+    }
+
+    // Close enough to going out that they're worth stopping
+    fn close(cards: usize) -> bool {
+        cards <= 2
+    }
+
+    // A reverse hands the turn to the previous seat instead, with two players it's just another skip
+    fn hurts(&self, special: SpecialCard) -> bool {
+        special == SpecialCard::Reverse && self.seats > 2 && Neighbors::close(self.previous) && !Neighbors::close(self.next)
+    }
+
+    fn helps(&self, special: SpecialCard) -> bool {
+        match special {
+            SpecialCard::Skip => Neighbors::close(self.next),
+            SpecialCard::Reverse => Neighbors::close(self.next) && (self.seats == 2 || !Neighbors::close(self.previous)),
+            _ => false,
+        }
+    }
+
+    // Skips and reverses are kept for when they'd stop somebody
+    fn held(&self, special: SpecialCard) -> bool {
+        matches!(special, SpecialCard::Skip | SpecialCard::Reverse) && !self.helps(special)
+    }
+}
+
+// This is for the AI players
+pub fn get_move_ai(hand: &[UNOCard], last_played: UNOCard, difficulty: Difficulty, uno: bool, around: Option<Neighbors>, rand: &mut impl Rng) -> Option<usize> {
+    let picked = pick_move(hand, last_played, difficulty, uno, around, rand);
+    crate::log!(
+        Level::Debug,
+        "{} AI on {} holding [{}]: {}",
+        difficulty_name(difficulty),
+        format_card_message(&last_played),
+        hand.iter().map(format_card_message).collect::<Vec<_>>().join(", "),
+        picked.map_or(String::from("draws"), |i| format!("plays {}", format_card_message(&hand[i]))),
+    );
+    picked
+}
+
+fn pick_move(hand: &[UNOCard], last_played: UNOCard, difficulty: Difficulty, uno: bool, around: Option<Neighbors>, rand: &mut impl Rng) -> Option<usize> {
+    
+    // To adhere to the +2 stacking force
+    if last_played.special == SpecialCard::PlusTwo && check_countercards(hand)
+        && let Some(idx) = hand.iter().position(|c| {
+            c.special == SpecialCard::PlusTwo ||
+            c.special == SpecialCard::PlusFour
+        }) {
+        crate::log!(Level::Trace, "AI answers the draw card on top with another");
+        return Some(idx);
+    }
+    
+    match difficulty {
+        // Saves special cards for last
+        Difficulty::Calm => {
+            if let Some(idx) = hand.iter().position(
+                |c| { c.special == SpecialCard::Base && allowed_move(*c, last_played) }
+            ) {
+                return Some(idx);
+            }
+            
+            if let Some(idx) = hand.iter().position(|c| {
+                c.special != SpecialCard::Base &&
+                c.special != SpecialCard::PlusFour &&
+                c.special != SpecialCard::ColorChange &&
+                allowed_move(*c, last_played)
+            }) {
+                return Some(idx);
+            }
+            
+            if let Some(idx) = hand.iter().position(|c| {
+                c.special == SpecialCard::PlusFour || c.special == SpecialCard::ColorChange
+            }) {
+                return Some(idx);
+            }
+        },
+        
+        // Uses disruption cards immediately
+        Difficulty::Aggressive => {
+            if let Some(idx) = hand.iter().position(|c| {
+                c.special == SpecialCard::PlusFour
+            }) {
+                return Some(idx);
+            }
+            
+            if let Some(idx) = hand.iter().position(|c| {
+                (c.special == SpecialCard::PlusTwo ||
+                c.special == SpecialCard::Skip ||
+                c.special == SpecialCard::Reverse) &&
+                allowed_move(*c, last_played)
+            }) {
+                return Some(idx);
+            }
+            
+            if let Some(idx) = hand.iter().position(|c| {
+                (c.special == SpecialCard::ColorChange || c.special == SpecialCard::Base) &&
+                allowed_move(*c, last_played)
+            }) {
+                return Some(idx);
+            }
+        },
+        
+        // "I lost to this AI twice"
+        //                  - Alexandros3015, February 24th, 2026
+        // Ts one is impossible without a god hand
+        // Expert only ends up here without a table to search, like the keyboard mode's hint
+        Difficulty::Skilled | Difficulty::Expert => {
+            let held = |c: &UNOCard| around.is_some_and(|n| n.held(c.special));
+            let hurts = |c: &UNOCard| around.is_some_and(|n| n.hurts(c.special));
+
+            // The next seat is about to go out, so this is what the skips were saved for
+            if let Some(n) = around
+                && let Some(idx) = hand.iter().position(|c| n.helps(c.special) && allowed_move(*c, last_played)) {
+                crate::log!(Level::Trace, "Skilled AI stops the next seat on {} cards", n.next);
+                return Some(idx);
+            }
+
+            // Somebody could go out any moment, so get rid of whatever would cost the most to be caught holding
+            // The first of the most expensive cards, which puts action cards and wilds ahead of numbers
+            if uno
+                && let Some(idx) = (0..hand.len()).rev()
+                    .filter(|&i| allowed_move(hand[i], last_played) && !hurts(&hand[i]))
+                    .max_by_key(|&i| hand[i].points()) {
+                crate::log!(Level::Trace, "Skilled AI sheds {} points since somebody is on UNO", hand[idx].points());
+                return Some(idx);
+            }
+        
+            // A color the next seat drew on last time is one they'll likely have to draw on again
+            if let Some(n) = around
+                && let Some(idx) = hand.iter().position(|c| n.lacks(c.color) && !held(c) && allowed_move(*c, last_played)) {
+                crate::log!(Level::Trace, "Skilled AI steers into {}, which the next seat drew on", get_color(&hand[idx].color));
+                return Some(idx);
+            }
+        
+            let [reds, greens, yellows, blues, ..] = count_colors(hand);
+            crate::log!(Level::Trace, "Skilled AI counts red {}, blue {}, yellow {}, green {}", reds, blues, yellows, greens);
+
+            // A hand that's mostly purple or orange gets played down from there, the same way as the colors below
+            if let Some(extra) = most_common(hand).filter(|c| !PLAIN_COLORS.contains(c)) {
+                if let Some(idx) = hand.iter().position(|c| c.color == extra && c.special == SpecialCard::Base && allowed_move(*c, last_played)) {
+                    return Some(idx);
+                }
+                if let Some(idx) = hand.iter().position(|c| c.color == extra && !held(c) && allowed_move(*c, last_played)) {
+                    return Some(idx);
+                }
+            }
+            
+            if reds > blues && reds > yellows && reds > greens {
+                if let Some(idx) = hand.iter().position(|c| {
+                    c.color == Color::Red &&
+                    c.special == SpecialCard::Base &&
+                    allowed_move(*c, last_played)
+                }) {
+                    return Some(idx);
+                }
+                
+                if let Some(idx) = hand.iter().position(|c| {
+                    c.color == Color::Red &&
+                    !held(c) &&
+                    allowed_move(*c, last_played)
+                }) {
+                    return Some(idx);
+                }
+            }
+            else if blues > yellows && blues > greens {
+                if let Some(idx) = hand.iter().position(|c| {
+                    c.color == Color::Blue &&
+                    c.special == SpecialCard::Base &&
+                    allowed_move(*c, last_played)
+                }) {
+                    return Some(idx);
+                }
+                
+                if let Some(idx) = hand.iter().position(|c| {
+                    c.color == Color::Blue &&
+                    !held(c) &&
+                    allowed_move(*c, last_played)
+                }) {
+                    return Some(idx);
+                }
+            }
+            else if yellows > greens {
+                if let Some(idx) = hand.iter().position(|c| {
+                    c.color == Color::Yellow &&
+                    c.special == SpecialCard::Base &&
+                    allowed_move(*c, last_played)
+                }) {
+                    return Some(idx);
+                }
+                
+                if let Some(idx) = hand.iter().position(|c| {
+                    c.color == Color::Yellow &&
+                    !held(c) &&
+                    allowed_move(*c, last_played)
+                }) {
+                    return Some(idx);
+                }
+            }
+            
+            else if greens > 0 {
+                if let Some(idx) = hand.iter().position(|c| {
+                    c.color == Color::Green &&
+                    c.special == SpecialCard::Base &&
+                    allowed_move(*c, last_played)
+                }) {
+                    return Some(idx);
+                }
+                
+                if let Some(idx) = hand.iter().position(|c| {
+                    c.color == Color::Green &&
+                    !held(c) &&
+                    allowed_move(*c, last_played)
+                }) {
+                    return Some(idx);
+                }
+            }
+            
+            if let Some(idx) = hand.iter().position(|c| {
+                c.special == SpecialCard::Base &&
+                allowed_move(*c, last_played)
+            }) {
+                return Some(idx);
+            }
+            
+            // Whatever was held back, rather than a wild, unless it gives the turn to somebody about to go out
+            if let Some(idx) = hand.iter().position(|c| {
+                c.color != Color::NA &&
+                !hurts(c) &&
+                allowed_move(*c, last_played)
+            }) {
+                return Some(idx);
+            }
+            
+            if let Some(idx) = hand.iter().position(|c| {
+                c.special == SpecialCard::ColorChange ||
+                c.special == SpecialCard::PlusFour
+            }) {
+                return Some(idx);
+            }
+            
+            
+            
+        },
+        
+        Difficulty::Random => {
+            let playable: Vec<usize> = (0..hand.len()).filter(|&i| allowed_move(hand[i], last_played)).collect();
+            if let Some(idx) = rand.choose(&playable) {
+                return Some(*idx);
+            }
+        },
+        
+    }
+    
+    // Draw
+    None
+}
+
+// Under draw-one, whether to play the card just drawn (it can be played) or keep it and end the turn
+pub fn play_drawn_ai(card: UNOCard, difficulty: Difficulty, uno: bool, rand: &mut impl Rng) -> bool {
+    let wild = card.special == SpecialCard::ColorChange || card.special == SpecialCard::PlusFour;
+    let play = match difficulty {
+        // Wilds are saved for last, same as from the hand
+        Difficulty::Calm => !wild,
+        Difficulty::Aggressive => true,
+        // A wild is worth more held back, until somebody could go out with it still in hand
+        Difficulty::Skilled | Difficulty::Expert => !wild || uno,
+        Difficulty::Random => *rand.choose(&[true, false]).unwrap_or(&true),
+    };
+    crate::log!(Level::Debug, "{} AI drew {} and {} it", difficulty_name(difficulty), format_card_message(&card), if play { "plays" } else { "keeps" });
+    play
+}
+
+// Under challenge-penalty, whether to call the +4 just played on them a bluff. All it can go by is how many cards the
+// seat that played it holds and whether it was seen lacking the color the +4 went on. Winning turns four cards around,
+// losing adds the penalty, so Skilled challenges when the odds they held the color are worth that. Calm never
+// challenges, Aggressive always does unless they were seen lacking it, and Random flips a coin
+pub fn challenge_ai(lacking: &[Color], cards: usize, color: Color, colors: usize, penalty: u8, difficulty: Difficulty, rand: &mut impl Rng) -> bool {
+    let held = if lacking.contains(&color) { 0.0 } else { 1.0 - (1.0 - 1.0 / colors as f64).powi(cards as i32) };
+    let challenge = match difficulty {
+        Difficulty::Calm => false,
+        Difficulty::Random => rand.rand_bool(0.5),
+        Difficulty::Aggressive => held > 0.0,
+        Difficulty::Skilled | Difficulty::Expert => held * 8.0 > (1.0 - held) * penalty as f64,
+    };
+    crate::log!(Level::Debug, "{} AI {} the +4", difficulty_name(difficulty), if challenge { "challenges" } else { "takes" });
+    challenge
+}
+
+// Under the trade rule, with nothing to play, which card to trade for the top of the deck instead of drawing, if any
+// Drawing until something fits usually costs more than a card, a trade keeps the hand the size it is. The card to
+// lose is a number of the color held least of, the highest one for the points. Calm always draws, Random trades half
+// the time, and nobody trades their last card
+pub fn trade_ai(hand: &[UNOCard], difficulty: Difficulty, rand: &mut impl Rng) -> Option<usize> {
+    if hand.len() < 2 {
+        return None;
+    }
+    let held = |color: Color| hand.iter().filter(|c| c.color == color).count();
+    let picked = match difficulty {
+        Difficulty::Calm => None,
+        Difficulty::Random => rand.rand_bool(0.5).then(|| rand.gen_range(0, hand.len() as u64 - 1).unwrap_or(0) as usize),
+        Difficulty::Aggressive | Difficulty::Skilled | Difficulty::Expert => (0..hand.len())
+            .filter(|&i| hand[i].color != Color::NA)
+            .min_by_key(|&i| (held(hand[i].color), hand[i].special != SpecialCard::Base, std::cmp::Reverse(hand[i].points()))),
+    };
+    if let Some(i) = picked {
+        crate::log!(Level::Debug, "{} AI trades {} with the deck", difficulty_name(difficulty), format_card_message(&hand[i]));
+    }
+    picked
+}
+
+// The Expert AI doesn't know the other hands or the deck, so it deals them out a number of ways that fit what it can
+// see and plays each one forward until its next turn. It tries everything for its own turn and for the next seat's
+// answer, every seat picking what's best for itself (minimax, with more than two players), and the seats after that
+// just play their first card. The move that comes out best on average over all the deals gets played
+const SAMPLES: usize = 16;
+const PLIES: usize = 16; // However the round goes, a line stops after this many moves
+const BRANCHES: usize = 2; // Decisions after its own first one that get every move tried
+const WIN: f64 = 100.0;
+
+// How good a position is for a seat: a win, or how many fewer cards it holds than the others do on average
+fn score(position: &Position, seat: usize) -> f64 {
+    if let Some(winner) = position.winner() {
+        return if winner == seat + 1 { WIN } else { -WIN };
+    }
+    let seats = position.seats();
+    let others: usize = (0..seats).filter(|s| *s != seat).map(|s| position.hand_size(s)).sum();
+    others as f64 / (seats - 1) as f64 - position.hand_size(seat) as f64
+}
+
+fn scores(position: &Position) -> Vec<f64> {
+    (0..position.seats()).map(|seat| score(position, seat)).collect()
+}
+
+// Whose turn a line is in, lines end when it comes back around
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    Own,
+    Answer(usize),
+    Rest,
+}
+
+// Drawing with a card to play only ever looks good over a round or two, for the chance of drawing something better,
+// so nobody in a line does it. Also what the Expert plays
+fn choices(position: &Position) -> Vec<Move> {
+    let mut moves = position.moves();
+    if moves.iter().any(|mv| matches!(mv, Move::Play(_))) {
+        moves.retain(|mv| *mv != Move::Draw);
+    }
+    moves
+}
+
+// Scores for every seat after the best play from here, as each seat sees it. Only the first few decisions on a line
+// get every move tried, after that it's the first one
+fn lookahead(position: &Position, root: usize, stage: Stage, plies: usize, branches: usize) -> Vec<f64> {
+    let mover = position.to_move();
+    let stage = match stage {
+        _ if position.winner().is_some() || plies == 0 => return scores(position),
+        Stage::Own if mover != root => Stage::Answer(mover),
+        Stage::Answer(seat) if mover != seat => if mover == root { return scores(position) } else { Stage::Rest },
+        Stage::Rest if mover == root => return scores(position),
+        stage => stage,
+    };
+    let mut moves = choices(position);
+    let branches = if stage == Stage::Rest || branches == 0 {
+        moves.truncate(1);
+        0
+    } else {
+        branches - 1
+    };
+    moves.into_iter()
+        .map(|mv| follow(position, mv, root, stage, plies - 1, branches))
+        .max_by(|a, b| a[mover].total_cmp(&b[mover]))
+        .unwrap_or_else(|| scores(position))
+}
+
+fn follow(position: &Position, mv: Move, root: usize, stage: Stage, plies: usize, branches: usize) -> Vec<f64> {
+    let mut next = position.clone();
+    next.apply(mv);
+    lookahead(&next, root, stage, plies, branches)
+}
+
+fn move_name(mv: &Move) -> String {
+    match mv {
+        Move::Play(card) => format_card_message(card),
+        Move::Draw => String::from("draw"),
+        Move::Keep => String::from("keep"),
+    }
+}
+
+// Each move's score averaged over the deals, in the order they came
+fn average_scores(position: &Position, moves: &[Move], seed: u64) -> Vec<f64> {
+    let seat = position.to_move();
+    let mut totals = vec![0.0; moves.len()];
+    let mut rand = Randler::new(seed);
+    for _ in 0..SAMPLES {
+        let mut guess = position.clone();
+        guess.determinize(seat, &mut rand);
+        guess.reseed(rand.next_u64());
+        for (total, mv) in totals.iter_mut().zip(moves) {
+            *total += follow(&guess, *mv, seat, Stage::Own, PLIES, BRANCHES)[seat];
+        }
+    }
+    totals.iter().map(|total| total / SAMPLES as f64).collect()
+}
+
+// What the seat to move at the start of this turn should do, None if the turn won't be theirs to decide (they're
+// drawing a stack or being skipped)
+pub fn expert_move(t: &Table, seed: u64) -> Option<Move> {
+    let position = Position::from_table(t);
+    if position.to_move() != t.game_state.current_player {
+        return None;
+    }
+    let moves = choices(&position);
+    if moves.len() == 1 {
+        return moves.first().copied();
+    }
+    let scores = average_scores(&position, &moves, seed);
+    let best = (0..moves.len()).max_by(|a, b| scores[*a].total_cmp(&scores[*b]).then(b.cmp(a)))?;
+    crate::log!(
+        Level::Debug,
+        "Expert AI on {} scores {}",
+        format_card_message(&t.last_played),
+        moves.iter().zip(&scores).map(|(mv, score)| format!("{} {:.1}", move_name(mv), score)).collect::<Vec<_>>().join(", "),
+    );
+    Some(moves[best])
+}
+
+// How a suggested move looks next to the best one: a card or more worse off than it is risky, and otherwise a card
+// that makes the next seat draw or lose their turn is aggressive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rating {
+    Safe,
+    Aggressive,
+    Risky,
+}
+
+// For the suggest command: every move the seat to move has, scored by the Expert's search and best first, None if
+// the turn isn't theirs to decide. The seed only feeds the search, so the game's generators aren't touched
+pub fn suggestions(t: &Table, seed: u64) -> Option<Vec<(Move, f64, Rating)>> {
+    let position = Position::from_table(t);
+    if position.to_move() != t.game_state.current_player {
+        return None;
+    }
+    let moves = choices(&position);
+    let scores = average_scores(&position, &moves, seed);
+    let best = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let mut ranked: Vec<(Move, f64, Rating)> = moves.into_iter().zip(scores).map(|(mv, score)| {
+        let rating = match mv {
+            _ if score <= best - 1.0 => Rating::Risky,
+            Move::Play(card) if matches!(card.special, SpecialCard::PlusTwo | SpecialCard::PlusFour | SpecialCard::Skip | SpecialCard::Reverse) => Rating::Aggressive,
+            _ => Rating::Safe,
+        };
+        (mv, score, rating)
+    }).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    Some(ranked)
+}
+
+// For the analyze command: the share of the deals the Expert would try where each seat holds some of each color, in
+// PLAIN_COLORS order, the asking seat's own hand left as it is. The deals come off a copy of the AI generator, so
+// asking doesn't change what happens next
+const ODDS_SAMPLES: usize = 200;
+
+pub fn color_odds(t: &Table, seat: usize) -> Vec<Vec<f64>> {
+    let position = Position::from_table(t);
+    let mut rand = t.ai_rand;
+    let colors = colors(t.rules.colors);
+    let mut held = vec![vec![0usize; colors.len()]; t.hands.len()];
+    for _ in 0..ODDS_SAMPLES {
+        let mut guess = position.clone();
+        guess.determinize(seat, &mut rand);
+        for (other, counts) in held.iter_mut().enumerate() {
+            let hand = guess.hand(other);
+            for (count, color) in counts.iter_mut().zip(colors) {
+                *count += hand.iter().any(|c| c.color == *color) as usize;
+            }
+        }
+    }
+    held.iter().map(|counts| counts.iter().map(|&n| n as f64 / ODDS_SAMPLES as f64).collect()).collect()
+}
+
+// For --win-odds: the share of quick random playouts from this table each seat wins, with every hand as it is and only
+// the deck shuffled, the way a broadcast would show them. Playouts that run out of moves count for nobody, and like
+// color_odds it all comes off a copy of the AI generator
+const ROLLOUTS: usize = 300;
+const ROLLOUT_MOVES: usize = 400;
+
+pub fn win_odds(t: &Table) -> Vec<f64> {
+    let position = Position::from_table(t);
+    let mut rand = t.ai_rand;
+    let mut wins = vec![0usize; t.hands.len()];
+    for _ in 0..ROLLOUTS {
+        let mut line = position.clone();
+        line.shuffle_deck(&mut rand);
+        line.reseed(rand.next_u64());
+        if let Some(winner) = line.playout(ROLLOUT_MOVES) {
+            wins[winner - 1] += 1;
+        }
+    }
+    let finished: usize = wins.iter().sum();
+    if finished == 0 {
+        return vec![1.0 / wins.len() as f64; wins.len()];
+    }
+    wins.iter().map(|&n| n as f64 / finished as f64).collect()
+}
+
+// How many cards of each color a hand holds, in ALL_COLORS order
+pub fn count_colors(hand: &[UNOCard]) -> [usize; 6] {
+    ALL_COLORS.map(|color| hand.iter().filter(|card| card.color == color).count())
+}
+
+// A quick look at a hand for a newer player, from the same counts the AIs go by: how many cards fit on the top one,
+// how many of the colors it has something in (wilds go with any), and the points it hands whoever goes out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Strength {
+    pub playable: usize,
+    pub colors: usize,
+    pub wilds: usize,
+    pub points: u32,
+}
+
+pub fn hand_strength(hand: &[UNOCard], top: UNOCard) -> Strength {
+    Strength {
+        playable: hand.iter().filter(|c| allowed_move(**c, top)).count(),
+        colors: count_colors(hand).iter().filter(|&&n| n > 0).count(),
+        wilds: hand.iter().filter(|c| c.color == Color::NA).count(),
+        points: hand.points(),
+    }
+}
+
+// The color an AI calls on a wild, one of the deck's. Skilled and Expert pick one the next seat drew on, of those they
+// hold any of
+pub fn pick_color_ai(hand: &[UNOCard], colors: &[Color], difficulty: Difficulty, around: Option<Neighbors>, rand: &mut impl Rng) -> Color {
+    if matches!(difficulty, Difficulty::Skilled | Difficulty::Expert)
+        && let Some(n) = around
+        && let Some(color) = colors.iter().copied()
+            .filter(|c| n.lacks(*c))
+            .map(|c| (c, hand.iter().filter(|card| card.color == c).count()))
+            .filter(|(_, held)| *held > 0)
+            .max_by_key(|(_, held)| *held)
+            .map(|(c, _)| c) {
+        crate::log!(Level::Trace, "{} AI calls {}, which the next seat drew on", difficulty_name(difficulty), get_color(&color));
+        return color;
+    }
+    get_common_color(hand, colors, rand)
+}
+
+// Ties between colors go to the one that comes first here
+const TIE_ORDER: [Color; 6] = [Color::Red, Color::Blue, Color::Yellow, Color::Green, Color::Purple, Color::Orange];
+
+// The color a hand holds the most of, None without any colored cards
+// A color wins a tie with the ones after it in TIE_ORDER, never with one before it
+fn most_common(hand: &[UNOCard]) -> Option<Color> {
+    let count = |color: Color| hand.iter().filter(|card| card.color == color).count();
+    TIE_ORDER.iter().enumerate()
+        .find(|&(i, &color)| count(color) > TIE_ORDER[i + 1..].iter().map(|&c| count(c)).max().unwrap_or(0))
+        .map(|(_, &color)| color)
+}
+
+// Gets the most common color on the deck
+pub fn get_common_color(hand: &[UNOCard], colors: &[Color], rand: &mut impl Rng) -> Color {
+    if let Some(color) = most_common(hand) {
+        return color;
+    }
+    
+    // If there is no common color, return a random color
+    crate::log!(Level::Trace, "AI has no colored cards left, picking a color at random");
+    *rand.choose(colors).unwrap_or(&Color::Red)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{card, cards};
+
+    fn around(next: usize, previous: usize, seats: usize) -> Neighbors {
+        Neighbors { next, previous, seats, next_lacks: [false; 6] }
+    }
+
+    fn skilled(hand: &[&str], top: &str, around: Neighbors) -> UNOCard {
+        let hand = cards(hand);
+        let idx = get_move_ai(&hand, card(top), Difficulty::Skilled, false, Some(around), &mut Randler::new(0)).unwrap();
+        hand[idx]
+    }
+
+    #[test]
+    fn skips_are_saved_for_somebody_about_to_go_out() {
+        let hand = ["red skip", "red 4", "red 6"];
+        assert_eq!(skilled(&hand, "red 9", around(7, 7, 3)), card("red 4"));
+        assert_eq!(skilled(&hand, "red 9", around(1, 7, 3)), card("red skip"));
+    }
+
+    #[test]
+    fn reverses_dont_hand_the_turn_to_somebody_about_to_go_out() {
+        let hand = ["blue reverse", "blue 2", "wild"];
+        assert_eq!(skilled(&hand, "blue 9", around(2, 6, 4)), card("blue reverse"));
+        assert_eq!(skilled(&hand, "blue 9", around(2, 1, 4)), card("blue 2"), "stopping one just starts the other");
+        assert_eq!(skilled(&hand, "blue 9", around(2, 2, 2)), card("blue reverse"), "with two it's a skip");
+        assert_eq!(skilled(&["blue reverse", "wild"], "blue 9", around(6, 1, 4)), card("wild"));
+    }
+
+    #[test]
+    fn the_game_is_steered_into_colors_the_next_seat_drew_on() {
+        let hand = ["red 3", "green 4", "green 8", "blue 4", "wild"];
+        let lacks_blue = Neighbors { next_lacks: [false, false, false, true, false, false], ..around(5, 5, 3) };
+        assert_eq!(skilled(&hand, "yellow 4", around(5, 5, 3)), card("green 4"));
+        assert_eq!(skilled(&hand, "yellow 4", lacks_blue), card("blue 4"));
+
+        let mut rand = Randler::new(0);
+        assert_eq!(pick_color_ai(&cards(&hand), &PLAIN_COLORS, Difficulty::Skilled, Some(lacks_blue), &mut rand), Color::Blue);
+        assert_eq!(pick_color_ai(&cards(&hand), &PLAIN_COLORS, Difficulty::Calm, Some(lacks_blue), &mut rand), Color::Green);
+        let lacks_yellow = Neighbors { next_lacks: [false, false, true, false, false, false], ..around(5, 5, 3) };
+        assert_eq!(pick_color_ai(&cards(&hand), &PLAIN_COLORS, Difficulty::Skilled, Some(lacks_yellow), &mut rand), Color::Green, "none to follow it up with");
+    }
+}
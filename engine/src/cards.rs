@@ -0,0 +1,524 @@
+// The cards themselves, how they look and the deck they come in
+
+use std::str::FromStr;
+
+use crate::rng::Rng;
+
+// Colors for the cards
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Purple,
+    Orange,
+    NA
+}
+
+// The four colors of a standard deck
+pub const PLAIN_COLORS: [Color; 4] = [Color::Red, Color::Green, Color::Yellow, Color::Blue];
+// Every color a card can actually have, big tables can play with the extra ones too
+pub const ALL_COLORS: [Color; 6] = [Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Purple, Color::Orange];
+
+// The colors of a deck with that many, the standard four first
+pub fn colors(count: u8) -> &'static [Color] {
+    &ALL_COLORS[..(count as usize).clamp(PLAIN_COLORS.len(), ALL_COLORS.len())]
+}
+
+// Which cards one deck has: how many colors, and how high the number cards go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeckShape {
+    pub colors: u8,
+    pub highest: i8,
+}
+
+impl Default for DeckShape {
+    fn default() -> Self {
+        DeckShape { colors: 4, highest: 9 }
+    }
+}
+
+// Number cards of a standard deck go up to 9, some variants go on to 12
+pub const MAX_NUMBER: i8 = 12;
+
+pub const DECK_SIZE: usize = 108;
+// Each extra color brings its own 25 cards and a wild and a wild +4, so wilds stay as common as in a standard deck
+const EXTRA_COLOR_CARDS: usize = 27;
+const WIDE_DECK_SIZE: usize = DECK_SIZE + EXTRA_COLOR_CARDS * (ALL_COLORS.len() - PLAIN_COLORS.len());
+// What should be left after dealing: the opening card and a few rounds of draws
+const DRAW_PILE: usize = 20;
+
+// So the user can input a color
+impl FromStr for Color {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result< Self, Self::Err > {
+        let sl = s.to_lowercase();
+        match sl.as_str() {
+            "red" => Ok(Color::Red),
+            "green" => Ok(Color::Green),
+            "yellow" => Ok(Color::Yellow),
+            "blue" => Ok(Color::Blue),
+            "purple" => Ok(Color::Purple),
+            "orange" => Ok(Color::Orange),
+            _ => Err( format!("{} is not an UNO color", s) ),
+        }
+    }
+}
+
+// Special cards for the cards
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
+pub enum SpecialCard {
+    PlusFour,
+    ColorChange,
+    PlusTwo,
+    Skip,
+    Reverse,
+    Base
+}
+// One full card
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub struct UNOCard {
+    pub color: Color,
+    pub special: SpecialCard,
+    pub number: i8,
+}
+
+impl UNOCard {
+    pub fn new(color: Color, special: SpecialCard, number: i8) -> Self {
+        UNOCard { color, special, number }
+    }
+
+    // What the card is worth to whoever goes out while somebody else still holds it
+    pub fn points(&self) -> u32 {
+        match self.special {
+            SpecialCard::Base => self.number.max(0) as u32,
+            SpecialCard::PlusTwo | SpecialCard::Skip | SpecialCard::Reverse => 20,
+            SpecialCard::PlusFour | SpecialCard::ColorChange => 50,
+        }
+    }
+}
+
+// Anything holding cards, a hand is usually a Vec<UNOCard>
+pub trait Hand {
+    fn points(&self) -> u32;
+}
+
+impl Hand for [UNOCard] {
+    fn points(&self) -> u32 {
+        self.iter().map(UNOCard::points).sum()
+    }
+}
+
+// Reads cards the way they're printed ("Blue Draw 2", "Wild Draw 4 (Red)") and the short way ("blue +2", "wild +4 red")
+impl FromStr for UNOCard {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let sl = s.to_lowercase().replace(['(', ')'], " ");
+        let words: Vec<&str> = sl.split_whitespace().collect();
+        let bad = || format!("{} is not an UNO card", s.trim());
+
+        // Wilds have no color of their own, but a chosen one can follow them
+        let wild = |special: SpecialCard, rest: &[&str]| match rest {
+            [] | ["none"] => Ok(UNOCard::new(Color::NA, special, -1)),
+            [color] => Ok(UNOCard::new(color.parse()?, special, -1)),
+            _ => Err(bad()),
+        };
+        match words.as_slice() {
+            ["wild", "draw", "4", rest @ ..] | ["wild", "+4", rest @ ..] => wild(SpecialCard::PlusFour, rest),
+            ["wild", "card", rest @ ..] | ["wild", rest @ ..] => wild(SpecialCard::ColorChange, rest),
+            [color, rest @ ..] => {
+                let color: Color = color.parse()?;
+                let special = match rest {
+                    ["draw", "2"] | ["+2"] => SpecialCard::PlusTwo,
+                    ["skip"] => SpecialCard::Skip,
+                    ["reverse"] => SpecialCard::Reverse,
+                    [n] => match n.parse::<i8>() {
+                        Ok(n) if (0..=MAX_NUMBER).contains(&n) => return Ok(UNOCard::new(color, SpecialCard::Base, n)),
+                        _ => return Err(bad()),
+                    },
+                    _ => return Err(bad()),
+                };
+                Ok(UNOCard::new(color, special, -1))
+            },
+            [] => Err(bad()),
+        }
+    }
+}
+
+// Gets the name of a color from the enum
+pub fn get_color(color: &Color) -> String {
+    match color {
+        Color::Red => String::from("Red"),
+        Color::Blue => String::from("Blue"),
+        Color::Yellow => String::from("Yellow"),
+        Color::Green => String::from("Green"),
+        Color::Purple => String::from("Purple"),
+        Color::Orange => String::from("Orange"),
+        Color::NA => String::from("None"),
+    }
+}
+
+// Formats the card message to be displayed to the user
+pub fn format_card_message(card: &UNOCard) -> String {
+    let color_str = get_color(&card.color);
+    match card.special {
+        SpecialCard::PlusFour => format!("Wild Draw 4 ({})", color_str),
+        SpecialCard::ColorChange => format!("Wild Card ({})", color_str),
+        SpecialCard::PlusTwo => format!("{} Draw 2", color_str),
+        SpecialCard::Skip => format!("{} Skip", color_str),
+        SpecialCard::Reverse => format!("{} Reverse", color_str),
+        SpecialCard::Base => format!("{} {}", color_str, card.number),
+    }
+}
+
+// What an alias stands for: one card, or every card of a kind whatever its color
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Named {
+    Card(UNOCard),
+    Kind(SpecialCard),
+}
+
+impl Named {
+    fn matches(&self, card: &UNOCard) -> bool {
+        match self {
+            Named::Card(named) => named == card,
+            Named::Kind(special) => *special == card.special,
+        }
+    }
+
+    // How it's typed in the game's own words, so an alias can be swapped for it in a line of input
+    fn words(&self) -> String {
+        match self {
+            Named::Card(card) => format_card_message(card).to_lowercase(),
+            Named::Kind(SpecialCard::PlusFour) => String::from("wild draw 4"),
+            Named::Kind(SpecialCard::ColorChange) => String::from("wild"),
+            Named::Kind(SpecialCard::PlusTwo) => String::from("+2"),
+            Named::Kind(SpecialCard::Skip) => String::from("skip"),
+            Named::Kind(SpecialCard::Reverse) => String::from("reverse"),
+            Named::Kind(SpecialCard::Base) => String::new(),
+        }
+    }
+}
+
+// A group's own names for cards, like "the nuke" for a Wild Draw 4
+// A name for a kind keeps the color with it the way cards are printed, "Red the double" or "the nuke (Blue)"
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Aliases {
+    names: Vec<(Named, String)>,
+}
+
+impl Aliases {
+    // The card can be a whole card ("red 7") or a kind ("+4", "wild", "+2", "skip", "reverse"), a card or a number
+    // can't be an alias since it would stop meaning itself
+    pub fn add(&mut self, card: &str, alias: &str) -> Result<(), String> {
+        let named = match card.trim().to_lowercase().as_str() {
+            "+4" | "draw 4" | "wild +4" | "wild draw 4" => Named::Kind(SpecialCard::PlusFour),
+            "wild" | "wild card" => Named::Kind(SpecialCard::ColorChange),
+            "+2" | "draw 2" => Named::Kind(SpecialCard::PlusTwo),
+            "skip" => Named::Kind(SpecialCard::Skip),
+            "reverse" => Named::Kind(SpecialCard::Reverse),
+            _ => match card.parse::<UNOCard>()? {
+                card if card.color == Color::NA => Named::Kind(card.special),
+                card => Named::Card(card),
+            },
+        };
+        let alias = alias.split_whitespace().collect::<Vec<_>>().join(" ");
+        if alias.is_empty() || alias.chars().any(char::is_control) || alias.parse::<f64>().is_ok() || alias.parse::<UNOCard>().is_ok() {
+            return Err(format!("{} can't be a name for {}", alias, card.trim()));
+        }
+        if self.names.iter().any(|(_, a)| a.eq_ignore_ascii_case(&alias)) {
+            return Err(format!("{} names two different cards", alias));
+        }
+        self.names.retain(|(n, _)| *n != named);
+        self.names.push((named, alias));
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    // The card by the group's name for it, a name for that exact card before the one for its kind
+    pub fn name(&self, card: &UNOCard) -> String {
+        let exact = self.names.iter().find(|(named, _)| matches!(named, Named::Card(_)) && named.matches(card));
+        match exact.or_else(|| self.names.iter().find(|(named, _)| named.matches(card))) {
+            Some((Named::Card(_), alias)) => alias.clone(),
+            Some((_, alias)) if card.special == SpecialCard::PlusFour || card.special == SpecialCard::ColorChange => {
+                format!("{} ({})", alias, get_color(&card.color))
+            },
+            Some((_, alias)) => format!("{} {}", get_color(&card.color), alias),
+            None => format_card_message(card),
+        }
+    }
+
+    // A line of input with every alias in it swapped for the card it stands for, "red the double" reads as "red +2"
+    // Lines without one come back exactly as typed
+    pub fn expand(&self, line: &str) -> String {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let mut out: Vec<String> = Vec::new();
+        let mut found = false;
+        let mut i = 0;
+        'words: while i < words.len() {
+            // The longest alias first, so one can start with another
+            let mut names: Vec<&(Named, String)> = self.names.iter().collect();
+            names.sort_by_key(|(_, alias)| std::cmp::Reverse(alias.split_whitespace().count()));
+            for (named, alias) in names {
+                let alias: Vec<&str> = alias.split_whitespace().collect();
+                let here = words.get(i..i + alias.len());
+                if here.is_some_and(|here| here.iter().zip(&alias).all(|(w, a)| w.eq_ignore_ascii_case(a))) {
+                    out.push(named.words());
+                    found = true;
+                    i += alias.len();
+                    continue 'words;
+                }
+            }
+            out.push(words[i].to_string());
+            i += 1;
+        }
+        if found { out.join(" ") } else { line.to_string() }
+    }
+}
+
+// One full standard deck in a fixed order, every card of a game is one of these slots
+// Shuffling and dealing move slot numbers around and only look the cards up at the end, so several decks are just
+// more slot numbers, and two cards are the same physical card when they came from the same slot
+pub const DECK: [UNOCard; DECK_SIZE] = standard_template();
+
+// The standard deck followed by the cards of each extra color, a deck with more colors is the start of this one
+// Numbers past 9 come after all of it, two of each in every color, so a deck of any shape starts like a standard one
+const WIDE_DECK: [UNOCard; WIDE_DECK_SIZE] = deck_template();
+
+const fn deck_template() -> [UNOCard; WIDE_DECK_SIZE] {
+    let mut deck = [UNOCard { color: Color::NA, special: SpecialCard::ColorChange, number: -1 }; WIDE_DECK_SIZE];
+    let specials = [SpecialCard::Reverse, SpecialCard::Skip, SpecialCard::PlusTwo];
+    let mut i = 0;
+
+    let mut c = 0;
+    while c < ALL_COLORS.len() {
+        let color = ALL_COLORS[c];
+        let mut n = 0;
+        while n <= 9 {
+            let count = if n == 0 { 1 } else { 2 };
+            let mut k = 0;
+            while k < count {
+                deck[i] = UNOCard { color, special: SpecialCard::Base, number: n };
+                i += 1;
+                k += 1;
+            }
+            n += 1;
+        }
+        let mut s = 0;
+        while s < specials.len() {
+            deck[i] = UNOCard { color, special: specials[s], number: -1 };
+            deck[i + 1] = deck[i];
+            i += 2;
+            s += 1;
+        }
+        // The standard wilds already fill the end of the first deck, every other one becomes a +4
+        if c + 1 == PLAIN_COLORS.len() {
+            while i < DECK_SIZE {
+                deck[i + 1].special = SpecialCard::PlusFour;
+                i += 2;
+            }
+        } else if c >= PLAIN_COLORS.len() {
+            deck[i + 1].special = SpecialCard::PlusFour;
+            i += 2;
+        }
+        c += 1;
+    }
+    deck
+}
+
+const fn standard_template() -> [UNOCard; DECK_SIZE] {
+    let mut deck = [WIDE_DECK[0]; DECK_SIZE];
+    let mut i = 0;
+    while i < DECK_SIZE {
+        deck[i] = WIDE_DECK[i];
+        i += 1;
+    }
+    deck
+}
+
+// How many cards one deck of that shape has
+pub fn deck_size(shape: DeckShape) -> usize {
+    let colors = colors(shape.colors).len();
+    DECK_SIZE + EXTRA_COLOR_CARDS * (colors - PLAIN_COLORS.len()) + extra_numbers(shape) * colors
+}
+
+// The cards past 9 in each color
+fn extra_numbers(shape: DeckShape) -> usize {
+    2 * (shape.highest.clamp(9, MAX_NUMBER) - 9) as usize
+}
+
+// The card in a slot of a deck of that shape, slots past the first deck are the same cards again
+pub fn card_at(slot: u32, shape: DeckShape) -> UNOCard {
+    let slot = slot as usize % deck_size(shape);
+    let numbered = deck_size(DeckShape { highest: 9, ..shape });
+    if slot < numbered {
+        return WIDE_DECK[slot];
+    }
+    let (color, n) = ((slot - numbered) / extra_numbers(shape), (slot - numbered) % extra_numbers(shape));
+    UNOCard::new(colors(shape.colors)[color], SpecialCard::Base, 10 + n as i8 / 2)
+}
+
+// Builds a full standard deck of UNO cards
+pub fn build_deck() -> Vec<UNOCard> {
+    DECK.to_vec()
+}
+
+// One full deck of that shape
+pub fn build_deck_of(shape: DeckShape) -> Vec<UNOCard> {
+    (0..deck_size(shape) as u32).map(|slot| card_at(slot, shape)).collect()
+}
+
+// The slots of that many decks in the order they'll be drawn, last first
+pub fn shuffled_slots(rand: &mut impl Rng, decks: usize, shape: DeckShape) -> Vec<u32> {
+    let mut slots: Vec<u32> = (0..(decks.max(1) * deck_size(shape)) as u32).collect();
+    rand.shuffle(&mut slots);
+    slots
+}
+
+// A fresh deck in the order it'll be drawn from, last card first
+pub fn shuffled_deck(rand: &mut impl Rng, shape: DeckShape) -> Vec<UNOCard> {
+    shuffled_decks(rand, 1, shape)
+}
+
+// Several decks as one pile, one standard deck shuffles exactly like shuffled_deck always has
+pub fn shuffled_decks(rand: &mut impl Rng, decks: usize, shape: DeckShape) -> Vec<UNOCard> {
+    shuffled_slots(rand, decks, shape).into_iter().map(|slot| card_at(slot, shape)).collect()
+}
+
+// Enough decks to deal every hand, with the extra cards handicapped seats get, and still have a draw pile of DRAW_PILE
+// cards or more
+pub fn decks_for(seats: usize, hand_size: u8, extra_cards: usize, shape: DeckShape) -> usize {
+    (seats * hand_size as usize + extra_cards + DRAW_PILE).div_ceil(deck_size(shape))
+}
+
+// Builds a new deck and shuffles it
+pub fn refresh_deck(deck:&mut  Vec<UNOCard>, shape: DeckShape, random:&mut impl Rng) {
+    *deck = shuffled_deck(random, shape);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STANDARD: DeckShape = DeckShape { colors: 4, highest: 9 };
+
+    #[test]
+    fn printed_names_parse_back() {
+        for card in build_deck() {
+            assert_eq!(format_card_message(&card).parse::<UNOCard>(), Ok(card));
+        }
+        let mut wild = UNOCard::new(Color::NA, SpecialCard::PlusFour, -1);
+        wild.color = Color::Green;
+        assert_eq!(format_card_message(&wild).parse::<UNOCard>(), Ok(wild));
+    }
+
+    #[test]
+    fn short_names_parse() {
+        assert_eq!("blue +2".parse(), Ok(UNOCard::new(Color::Blue, SpecialCard::PlusTwo, -1)));
+        assert_eq!("Wild +4 red".parse(), Ok(UNOCard::new(Color::Red, SpecialCard::PlusFour, -1)));
+        assert_eq!("wild".parse(), Ok(UNOCard::new(Color::NA, SpecialCard::ColorChange, -1)));
+        for bad in ["", "red", "red 13", "pink 3", "wild 4", "red skip skip"] {
+            assert!(bad.parse::<UNOCard>().is_err(), "{:?} parsed", bad);
+        }
+    }
+
+    #[test]
+    fn aliases_name_cards_both_ways() {
+        let mut aliases = Aliases::default();
+        aliases.add("+4", "the nuke").unwrap();
+        aliases.add("+2", "the double").unwrap();
+        aliases.add("red 7", "lucky  seven").unwrap();
+        let card = |c: &str| c.parse::<UNOCard>().unwrap();
+
+        assert_eq!(aliases.name(&card("wild +4 blue")), "the nuke (Blue)");
+        assert_eq!(aliases.name(&card("green +2")), "Green the double");
+        assert_eq!(aliases.name(&card("red 7")), "lucky seven");
+        assert_eq!(aliases.name(&card("blue 7")), "Blue 7");
+
+        assert_eq!(aliases.expand("The Nuke"), "wild draw 4");
+        assert_eq!(aliases.expand("set-top the nuke red"), "set-top wild draw 4 red");
+        assert_eq!(aliases.expand("red the double").parse(), Ok(card("red +2")));
+        assert_eq!(aliases.expand("lucky seven").parse(), Ok(card("red 7")));
+        assert_eq!(aliases.expand("join 2 Ana"), "join 2 Ana");
+
+        assert!(aliases.add("skip", "the nuke").is_err(), "taken");
+        for bad in ["", "3", "blue 5", "\x1b[31m"] {
+            assert!(aliases.add("skip", bad).is_err(), "{:?} was allowed", bad);
+        }
+        assert!(aliases.add("pink 3", "the grape").is_err());
+    }
+
+    #[test]
+    fn hands_are_worth_the_official_points() {
+        assert_eq!(build_deck().points(), 1240);
+        let hand: Vec<UNOCard> = ["red 7", "blue skip", "wild +4", "green 0"].iter().map(|c| c.parse().unwrap()).collect();
+        assert_eq!(hand.points(), 77);
+        assert_eq!(Vec::<UNOCard>::new().points(), 0);
+    }
+
+    #[test]
+    fn slots_are_cards_of_the_template() {
+        assert_eq!(card_at(0, STANDARD), "red 0".parse().unwrap());
+        assert_eq!(card_at(DECK_SIZE as u32 - 1, STANDARD), "wild +4".parse().unwrap());
+        assert_eq!(card_at(DECK_SIZE as u32 + 5, STANDARD), DECK[5]);
+        assert_eq!(DECK.iter().filter(|c| c.special == SpecialCard::PlusFour).count(), 4);
+        assert_eq!(DECK.iter().filter(|c| c.color == Color::Blue && c.number == 7).count(), 2);
+
+        let mut slots = shuffled_slots(&mut crate::rng::Randler::new(2), 2, STANDARD);
+        slots.sort();
+        assert!(slots.iter().copied().eq(0..2 * DECK_SIZE as u32), "every slot of both decks once");
+    }
+
+    #[test]
+    fn big_tables_get_more_decks() {
+        assert_eq!(decks_for(4, 7, 0, STANDARD), 1);
+        assert_eq!(decks_for(12, 7, 0, STANDARD), 1);
+        assert_eq!(decks_for(13, 7, 0, STANDARD), 2);
+        assert_eq!(decks_for(40, 7, 0, STANDARD), 3);
+        assert_eq!(decks_for(12, 7, 40, STANDARD), 2, "handicaps are dealt from the same decks");
+        // One deck has to come out the way it always did, or every seeded game would change
+        let one = shuffled_decks(&mut crate::rng::Randler::new(9), 1, STANDARD);
+        assert_eq!(one, shuffled_deck(&mut crate::rng::Randler::new(9), STANDARD));
+        assert_eq!(shuffled_decks(&mut crate::rng::Randler::new(9), 3, STANDARD).len(), 3 * DECK_SIZE);
+    }
+
+    #[test]
+    fn extra_colors_come_with_their_own_cards() {
+        assert_eq!(colors(4), PLAIN_COLORS);
+        assert_eq!(colors(6), ALL_COLORS);
+        assert_eq!(build_deck_of(STANDARD), build_deck());
+
+        let six = build_deck_of(DeckShape { colors: 6, highest: 9 });
+        assert_eq!(six.len(), 162);
+        for color in ALL_COLORS {
+            assert_eq!(six.iter().filter(|c| c.color == color).count(), 25, "{:?}", color);
+        }
+        assert_eq!(six.iter().filter(|c| c.special == SpecialCard::PlusFour).count(), 6);
+        assert_eq!(six.iter().filter(|c| c.special == SpecialCard::ColorChange).count(), 6);
+        assert_eq!(deck_size(DeckShape { colors: 5, highest: 9 }), 135);
+        assert_eq!(decks_for(5, 7, 0, DeckShape { colors: 6, highest: 9 }), 1);
+        assert_eq!("Purple Draw 2".parse(), Ok(UNOCard::new(Color::Purple, SpecialCard::PlusTwo, -1)));
+    }
+
+    #[test]
+    fn numbers_can_go_past_nine() {
+        let shape = DeckShape { colors: 5, highest: 12 };
+        let deck = build_deck_of(shape);
+        assert_eq!(deck.len(), 135 + 5 * 6);
+        assert_eq!(deck[..135], build_deck_of(DeckShape { highest: 9, ..shape }), "starts like the deck without them");
+        for color in colors(5) {
+            for n in 10..=12 {
+                assert_eq!(deck.iter().filter(|c| c.color == *color && c.number == n).count(), 2, "{:?} {}", color, n);
+            }
+        }
+        assert_eq!(card_at(deck.len() as u32 + 140, shape), deck[140]);
+        assert_eq!(format_card_message(&deck[140]).parse::<UNOCard>(), Ok(deck[140]));
+        assert_eq!(deck.points(), build_deck_of(DeckShape { highest: 9, ..shape }).points() + 5 * 2 * 33);
+    }
+}
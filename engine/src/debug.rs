@@ -0,0 +1,161 @@
+// Commands for chasing rule bugs, typed instead of a move on a human's turn when UNOler runs with --debug
+//
+//     peek-deck 5          the next five cards of the deck, the one drawn next first
+//     give P2 red 7        a red 7 out of the deck and into seat 2's hand
+//     set-top blue skip    a blue skip out of the deck and onto the pile, as the card to follow
+//     dump-state           everything on the table, in notation's card codes
+//
+// Cards are taken from the deck instead of made up, so --check-invariants still holds, and one that isn't in the deck
+// can't be given. None of this goes into replays or notation, a game that used them won't replay the same
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cards::{Color, SpecialCard, UNOCard};
+use crate::game::{Table, difficulty_name};
+use crate::notation::card_code;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn is_wild(card: &UNOCard) -> bool {
+    card.special == SpecialCard::ColorChange || card.special == SpecialCard::PlusFour
+}
+
+fn codes<'a>(cards: impl Iterator<Item = &'a UNOCard>) -> String {
+    cards.map(card_code).collect::<Vec<_>>().join(" ")
+}
+
+pub const COMMANDS: [&str; 4] = ["peek-deck", "give", "set-top", "dump-state"];
+
+fn split(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    line.split_once(' ').map_or((line, ""), |(c, r)| (c, r.trim()))
+}
+
+// Anything else is read as a move
+pub fn is_command(line: &str) -> bool {
+    COMMANDS.contains(&split(line).0)
+}
+
+// What the command did, or why it couldn't
+pub fn run(t: &mut Table, line: &str) -> Result<String, String> {
+    let (command, rest) = split(line);
+    match command {
+        "peek-deck" => peek_deck(t, rest),
+        "give" => give(t, rest),
+        "set-top" => set_top(t, rest),
+        "dump-state" => Ok(dump_state(t)),
+        _ => Err(format!("{} is not a debug command, try {}", command, COMMANDS.join(", "))),
+    }
+}
+
+fn peek_deck(t: &Table, count: &str) -> Result<String, String> {
+    let count = match count {
+        "" => 5,
+        n => n.parse::<usize>().map_err(|_| format!("{} is not a number of cards", n))?,
+    };
+    Ok(format!("Next off the deck ({} left): {}", t.deck.len(), codes(t.deck.iter().rev().take(count))))
+}
+
+// The copy nearest the top, wilds are in the deck without a color
+fn take_from_deck(t: &mut Table, card: UNOCard) -> Result<UNOCard, String> {
+    let wanted = if is_wild(&card) { UNOCard { color: Color::NA, ..card } } else { card };
+    let index = t.deck.iter().rposition(|c| *c == wanted).ok_or(format!("There's no {} left in the deck", card_code(&wanted)))?;
+    Ok(t.deck.remove(index))
+}
+
+fn give(t: &mut Table, rest: &str) -> Result<String, String> {
+    let (seat, card) = rest.split_once(' ').ok_or("Say who and what, like give P2 red 7")?;
+    let seat = seat.trim_start_matches(['p', 'P']).parse::<usize>().ok()
+        .filter(|s| (1..=t.hands.len()).contains(s))
+        .ok_or(format!("{} is not a seat, there are {}", seat, t.hands.len()))?;
+    let card = take_from_deck(t, card.parse()?)?;
+    t.hands[seat - 1].push(card);
+    Ok(format!("{} now holds {}", t.names[seat - 1], codes(t.hands[seat - 1].iter())))
+}
+
+fn set_top(t: &mut Table, rest: &str) -> Result<String, String> {
+    let card: UNOCard = rest.parse()?;
+    if is_wild(&card) && card.color == Color::NA {
+        return Err(String::from("Say which color the wild was given, like set-top wild red"));
+    }
+    // Only a draw card can have a stack waiting on it
+    if t.add_queue > 0 && !matches!(card.special, SpecialCard::PlusTwo | SpecialCard::PlusFour) {
+        return Err(format!("{} cards are waiting on the draw card on top, only another can go there", t.add_queue));
+    }
+    let taken = take_from_deck(t, card)?;
+    t.discard.push(taken);
+    t.history.push(card);
+    t.last_played = card;
+    Ok(format!("The card to follow is now {}", card_code(&card)))
+}
+
+fn dump_state(t: &Table) -> String {
+    let turn = &t.game_state;
+    let mut lines = vec![
+        format!(
+            "Seat {} to play, going {}, {} queued to draw{}",
+            turn.player_number(), if turn.direction < 0 { "backwards" } else { "forwards" }, t.add_queue,
+            if t.skipped { ", skipped" } else { "" },
+        ),
+        format!("Top: {} (pile of {}, {} played)", card_code(&t.last_played), t.discard.len(), t.history.len()),
+        format!("Deck ({}): {}", t.deck.len(), codes(t.deck.iter().rev())),
+    ];
+    for (i, hand) in t.hands.iter().enumerate() {
+        let who = if i < t.players as usize { "human" } else { difficulty_name(t.difficulties[i]) };
+        let lacking = t.lacking[i].iter().map(|c| format!("{:?}", c).to_lowercase()).collect::<Vec<_>>();
+        let lacking = if lacking.is_empty() { String::new() } else { format!(", drew on {}", lacking.join(" and ")) };
+        lines.push(format!("{}: {} ({}{}): {}", i + 1, t.names[i], who, lacking, codes(hand.iter())));
+    }
+    lines.push(t.rules.summary());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::format_card_message;
+    use crate::game::{Difficulty, Settings, setup};
+    use crate::invariants;
+    use crate::testing::{ScriptedFrontend, TestGame, card, cards};
+
+    #[test]
+    fn cards_come_out_of_the_deck() {
+        let mut game = TestGame::new(2)
+            .hand(0, &["red 5"])
+            .hand(1, &["green 3"])
+            .top("red 1")
+            .deck(&["yellow 2", "red 7", "blue skip", "wild"])
+            .build();
+        let t = game.table_mut();
+
+        assert_eq!(run(t, "peek-deck 2"), Ok(String::from("Next off the deck (4 left): Y2 R7")));
+        assert!(run(t, "give p2 red 7").is_ok());
+        assert_eq!(t.hands[1], cards(&["green 3", "red 7"]));
+        assert!(run(t, "give 2 red 7").unwrap_err().contains("no R7"));
+        assert!(run(t, "set-top wild").is_err(), "a wild on top needs its color");
+        assert!(run(t, "set-top wild blue").is_ok());
+        assert_eq!(t.last_played, card("wild blue"));
+        assert_eq!(t.deck, cards(&["blue skip", "yellow 2"]));
+        assert!(run(t, "dump-state").unwrap().contains("Top: W:B"));
+        assert!(!is_command("3") && is_command("dump-state"));
+    }
+
+    #[test]
+    fn tables_stay_whole() {
+        let settings = Settings { players: Some(0), ai_players: Some(3), difficulty: Some(Difficulty::Calm), seed: Some(2), ..Settings::default() };
+        let mut t = setup(&mut ScriptedFrontend::default(), &settings).unwrap().unwrap();
+        let next = t.deck[t.deck.len() - 1];
+        run(&mut t, &format!("give 1 {}", format_card_message(&next))).unwrap();
+        let top = *t.deck.iter().find(|c| c.special == SpecialCard::Base).unwrap();
+        run(&mut t, &format!("set-top {}", format_card_message(&top))).unwrap();
+        invariants::check(&t).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+// House cards: a number card can be given an effect on top of being matched, built out of a few primitives
+// "swap" on the 7s and "rotate" on the 0s is the usual 7-0 game, "draw2+skip" or "color+reverse" are whatever the table
+// came up with. The rules only say which number does what, play() carries it out once the card is down
+//
+// Written out the primitives are joined with +, without spaces, so an effect fits in a flag or a notation tag
+// as it is: draw<n>, skip or skip<n>, reverse, color, swap and rotate
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Effect {
+    pub draw: u8, // Owed by the next player, stacked on like a +2
+    pub skip: u8, // How many players after this one lose their turn
+    pub reverse: bool,
+    pub choose_color: bool, // The card counts as the color the player names, like a wild
+    pub swap: bool, // The player's hand goes to whoever holds the fewest cards, and theirs comes back
+    pub rotate: bool, // Every hand passes on to the next seat in the direction of play
+}
+
+// The same primitives as a builder, Effect::default().draw(2).skip(1)
+impl Effect {
+    // Default as a constant, for rule sets written out in consts
+    pub const NONE: Effect = Effect { draw: 0, skip: 0, reverse: false, choose_color: false, swap: false, rotate: false };
+
+    pub fn draw(mut self, cards: u8) -> Self {
+        self.draw = self.draw.saturating_add(cards);
+        self
+    }
+
+    pub fn skip(mut self, players: u8) -> Self {
+        self.skip = self.skip.saturating_add(players);
+        self
+    }
+
+    pub fn reverse(mut self) -> Self {
+        self.reverse = !self.reverse; // Twice is back the way it was
+        self
+    }
+
+    pub fn choose_color(mut self) -> Self {
+        self.choose_color = true;
+        self
+    }
+
+    pub fn swap(mut self) -> Self {
+        self.swap = true;
+        self
+    }
+
+    pub fn rotate(mut self) -> Self {
+        self.rotate = true;
+        self
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Effect::NONE
+    }
+}
+
+impl FromStr for Effect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim().to_lowercase();
+        if matches!(text.as_str(), "" | "none" | "off") {
+            return Ok(Effect::default());
+        }
+        let count = |n: &str, max: u8| n.trim().parse::<u8>().ok().filter(|n| (1..=max).contains(n));
+        let mut effect = Effect::default();
+        for part in text.split(['+', ',']).map(str::trim) {
+            effect = match part {
+                "reverse" => effect.reverse(),
+                "color" | "choose color" | "wild" => effect.choose_color(),
+                "swap" | "swap hands" => effect.swap(),
+                "rotate" | "rotate hands" => effect.rotate(),
+                "skip" => effect.skip(1),
+                _ if let Some(n) = part.strip_prefix("skip") => {
+                    effect.skip(count(n, 9).ok_or(format!("{} should be skip or skip1 to skip9", part))?)
+                },
+                _ if let Some(n) = part.strip_prefix("draw") => {
+                    effect.draw(count(n, 10).ok_or(format!("{} should be draw1 to draw10", part))?)
+                },
+                _ => return Err(format!("Unknown card effect {} (try draw2, skip, reverse, color, swap or rotate, joined with +)", part)),
+            };
+        }
+        Ok(effect)
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.choose_color {
+            parts.push(String::from("color"));
+        }
+        if self.draw > 0 {
+            parts.push(format!("draw{}", self.draw));
+        }
+        match self.skip {
+            0 => {},
+            1 => parts.push(String::from("skip")),
+            n => parts.push(format!("skip{}", n)),
+        }
+        for (on, name) in [(self.reverse, "reverse"), (self.swap, "swap"), (self.rotate, "rotate")] {
+            if on {
+                parts.push(String::from(name));
+            }
+        }
+        if parts.is_empty() {
+            return write!(f, "none");
+        }
+        write!(f, "{}", parts.join("+"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects_read_back_the_way_they_are_written() {
+        for text in ["none", "swap", "rotate", "draw2+skip", "color+draw1+skip3+reverse+swap+rotate"] {
+            assert_eq!(text.parse::<Effect>().map(|e| e.to_string()), Ok(String::from(text)));
+        }
+        assert_eq!("Draw 2, Skip".parse(), Ok(Effect::default().draw(2).skip(1)));
+        assert_eq!("wild + swap hands".parse(), Ok(Effect::default().choose_color().swap()));
+    }
+
+    #[test]
+    fn bad_effects_say_what_went_wrong() {
+        assert!("draw".parse::<Effect>().unwrap_err().contains("draw1 to draw10"));
+        assert!("draw11".parse::<Effect>().is_err());
+        assert!("skip0".parse::<Effect>().is_err());
+        assert!("explode".parse::<Effect>().unwrap_err().contains("Unknown card effect explode"));
+    }
+}
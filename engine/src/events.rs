@@ -0,0 +1,1143 @@
+use std::env;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::ai::{Strength, hand_strength};
+use crate::cards::{Aliases, Color, SpecialCard, UNOCard, format_card_message, get_color};
+use crate::game::Table;
+use crate::json::Json;
+use crate::rules::{allowed_move, on_off};
+
+// Everything the game tells the players about
+// The game loop only ever emits these, the frontend decides what they look like
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Notice(String),
+    InvalidInput(String),
+    // No seed for secure games
+    GameStart { players: usize, ai_players: usize, seed: Option<u64>, names: Vec<String> },
+    TurnStart { player: usize, ai: bool, last_played: UNOCard },
+    LastCard(UNOCard),
+    Hand(Vec<UNOCard>),
+    Played { player: usize, ai: bool, card: UNOCard },
+    IllegalMove(UNOCard),
+    ColorChosen { player: usize, color: Color },
+    // The card is None when it shouldn't be shown (AI hands)
+    Drew { player: usize, card: Option<UNOCard> },
+    ForcedDraw { player: usize, card: Option<UNOCard> },
+    // Under the trade rule, a card of theirs for the top of the deck. The card given is there for notation and replays,
+    // frontends don't show an AI's
+    Traded { player: usize, gave: UNOCard, got: Option<UNOCard> },
+    Skipped { player: usize },
+    Reshuffled { cards: usize }, // How many went back into the deck, the table's history still has them
+    NewDeck,
+    Uno { player: usize },
+    // Under challenge-penalty, the player a +4 went to calling it a bluff. It was one if the seat that played it held a
+    // card of the color it went on
+    Challenge { player: usize, against: usize, bluff: bool },
+    Win { player: usize },
+}
+
+// Everything the game asks the players
+#[derive(Debug, Clone, Copy)]
+pub enum Prompt {
+    Resume,
+    Players,
+    AiPlayers,
+    Difficulty,
+    Action { player: usize },
+    Color { player: usize },
+    PlayDrawn { player: usize }, // Under draw-one, when the card just drawn can be played
+    Challenge { player: usize }, // Under challenge-penalty, when a +4 was just played on them
+    Menu { player: usize }, // "menu" or Esc on a turn, asked until the player resumes
+    Continue,
+    Rematch, // After a game, deal the same table again
+    Exit,
+}
+
+impl Prompt {
+    fn message(&self) -> &'static str {
+        match self {
+            Prompt::Resume => "A game was interrupted by a crash. Resume it? (y/n)",
+            Prompt::Players => "How many players?",
+            Prompt::AiPlayers => "How many AI players?",
+            Prompt::Difficulty => "What AI difficulty? (calm, aggressive, skilled, random, or expert)",
+            Prompt::Action { .. } => "Enter",
+            Prompt::Color { .. } => "Enter color",
+            Prompt::PlayDrawn { .. } => "Play the card you drew? (y/n)",
+            Prompt::Challenge { .. } => "Challenge the +4 as a bluff? (y/n)",
+            Prompt::Menu { .. } => "Paused (save <slot>, rules, settings [<setting> <value>], forfeit or resume)",
+            Prompt::Continue => "Press enter to continue...",
+            Prompt::Rematch => "Rematch? (y/N)",
+            Prompt::Exit => "Press enter to exit...",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Prompt::Resume => "resume",
+            Prompt::Players => "players",
+            Prompt::AiPlayers => "ai_players",
+            Prompt::Difficulty => "difficulty",
+            Prompt::Action { .. } => "action",
+            Prompt::Color { .. } => "color",
+            Prompt::PlayDrawn { .. } => "play_drawn",
+            Prompt::Challenge { .. } => "challenge",
+            Prompt::Menu { .. } => "menu",
+            Prompt::Continue => "continue",
+            Prompt::Rematch => "rematch",
+            Prompt::Exit => "exit",
+        }
+    }
+}
+
+// Where events go and where answers come from
+pub trait Frontend {
+    fn event(&mut self, event: &Event);
+    // Returns the raw answer, the game does the parsing
+    fn prompt(&mut self, prompt: &Prompt) -> String;
+    // The whole table at the start of every turn, for frontends that keep snapshots
+    fn turn_start(&mut self, _table: &Table) {}
+    // "save <slot>" typed on a turn, only frontends that have somewhere to put it can
+    fn save(&mut self, _slot: &str) -> Result<PathBuf, String> {
+        Err(String::from("saving isn't available here"))
+    }
+    // "settings" in the pause menu, None lists them and a setting with a value changes it, either way the answer is
+    // how they are now
+    fn settings(&mut self, _change: Option<(&str, &str)>) -> Result<String, String> {
+        Err(String::from("nothing can be changed here"))
+    }
+    // Once a game is over, won or not, with the table it ended on
+    fn game_over(&mut self, _table: &Table, _winner: Option<usize>) {}
+    // True after a prompt the frontend had no answer for yet (a browser can't block on one),
+    // the game then stops with a Paused error and gets played again once the answer is there
+    fn paused(&self) -> bool {
+        false
+    }
+}
+
+// Reads one line from stdin, None on EOF
+fn read_line() -> Option<String> {
+    let mut line = String::new();
+    let n = io::stdin().read_line(&mut line).expect("Input failed.");
+    if n == 0 {
+        return None;
+    }
+    Some(line)
+}
+
+// Clears the terminal, but you might just want to enable ANSI escape codes
+// If you are on windows, you should probably run the following command in your terminal:
+// reg add HKCU\Console /v VirtualTerminalLevel /t REG_DWORD /d 1
+fn clear_terminal() {
+    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+    io::stdout().flush().unwrap();
+}
+
+// Escape codes only make sense on a real terminal that understands them, not in pipes, files or TERM=dumb
+fn terminal_supports_ansi() -> bool {
+    io::stdout().is_terminal() && env::var("TERM").map_or(true, |t| t != "dumb")
+}
+
+// One AI turn squeezed into a line, for fast-forwarding
+struct AiTurn {
+    player: usize,
+    draws: usize,
+    actions: Vec<String>,
+}
+
+impl AiTurn {
+    fn line(&self, name: &str) -> String {
+        let mut parts = Vec::new();
+        if self.draws > 0 {
+            parts.push(format!("drew {} card{}", self.draws, if self.draws == 1 { "" } else { "s" }));
+        }
+        parts.extend(self.actions.iter().cloned());
+        if parts.is_empty() {
+            parts.push(String::from("passed"));
+        }
+        format!("{} {}", name, parts.join(", "))
+    }
+}
+
+// How the colors look on the terminal, picked with --palette or palette under [display]
+// Whichever it is, colored cards also start with their color's letter so nobody has to go by the color alone
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Palette {
+    #[default]
+    Standard,
+    ColorBlind, // The Okabe-Ito colors: vermillion, bluish green, yellow and blue stay apart with red-green color blindness,
+                // and so do reddish purple and orange
+    Contrast, // Bold white or black on a solid background, for dim screens and low vision
+}
+
+impl std::str::FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "standard" => Ok(Palette::Standard),
+            "colorblind" | "color-blind" => Ok(Palette::ColorBlind),
+            "contrast" => Ok(Palette::Contrast),
+            _ => Err(format!("Unknown palette {} (try standard, colorblind or contrast)", s)),
+        }
+    }
+}
+
+impl Palette {
+    // The escape code parameters for a color, None for uncolored wilds
+    fn code(self, color: Color) -> Option<&'static str> {
+        let codes = match self {
+            Palette::Standard => ["31", "32", "33", "34", "35", "38;5;208"],
+            Palette::ColorBlind => ["38;5;166", "38;5;36", "38;5;227", "38;5;25", "38;5;175", "38;5;214"],
+            Palette::Contrast => ["1;97;41", "1;30;42", "1;30;43", "1;97;44", "1;97;45", "1;30;48;5;208"],
+        };
+        match color {
+            Color::Red => Some(codes[0]),
+            Color::Green => Some(codes[1]),
+            Color::Yellow => Some(codes[2]),
+            Color::Blue => Some(codes[3]),
+            Color::Purple => Some(codes[4]),
+            Color::Orange => Some(codes[5]),
+            Color::NA => None,
+        }
+    }
+}
+
+// How fast cards slide onto the pile and the first hand fans out, picked with --animation or animation under [display]
+// Only a real terminal gets them, anything else sees the plain lines
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Animation {
+    #[default]
+    Off,
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl std::str::FromStr for Animation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "none" => Ok(Animation::Off),
+            "slow" => Ok(Animation::Slow),
+            "normal" | "on" => Ok(Animation::Normal),
+            "fast" => Ok(Animation::Fast),
+            _ => Err(format!("Unknown animation speed {} (try off, slow, normal or fast)", s)),
+        }
+    }
+}
+
+impl Animation {
+    fn frame(self) -> Option<Duration> {
+        match self {
+            Animation::Off => None,
+            Animation::Slow => Some(Duration::from_millis(50)),
+            Animation::Normal => Some(Duration::from_millis(25)),
+            Animation::Fast => Some(Duration::from_millis(10)),
+        }
+    }
+}
+
+// How a player wants to show up at the table, from [player.<name>] in the config file: an emoji or initials before
+// their name, and a color of their own for it. Lines about them are easier to pick out with a few people playing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Look {
+    pub avatar: Option<String>,
+    pub accent: Option<Accent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Accent {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl std::str::FromStr for Accent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "red" => Ok(Accent::Red),
+            "green" => Ok(Accent::Green),
+            "yellow" => Ok(Accent::Yellow),
+            "blue" => Ok(Accent::Blue),
+            "magenta" | "purple" => Ok(Accent::Magenta),
+            "cyan" => Ok(Accent::Cyan),
+            _ => Err(format!("Unknown color {} (try red, green, yellow, blue, magenta or cyan)", s)),
+        }
+    }
+}
+
+impl Accent {
+    // Bold, so a name in red doesn't read as a red card
+    fn code(self) -> &'static str {
+        match self {
+            Accent::Red => "1;31",
+            Accent::Green => "1;32",
+            Accent::Yellow => "1;33",
+            Accent::Blue => "1;34",
+            Accent::Magenta => "1;35",
+            Accent::Cyan => "1;36",
+        }
+    }
+}
+
+// "🦊 Maria" for a player with an avatar, the name alone without
+pub fn label(looks: &[(String, Look)], name: &str) -> String {
+    match looks.iter().find(|(n, _)| n == name).and_then(|(_, look)| look.avatar.as_ref()) {
+        Some(avatar) => format!("{} {}", avatar, name),
+        None => name.to_string(),
+    }
+}
+
+pub fn labels(looks: &[(String, Look)], names: &[String]) -> Vec<String> {
+    names.iter().map(|name| label(looks, name)).collect()
+}
+
+const SLIDE: usize = 12; // Frames for a card to come in from the right
+
+// A played card's line as it slides in, ending on the line that stays
+fn slide_frames(label: &str, card: &str) -> Vec<String> {
+    (0..=SLIDE).rev().map(|step| format!("{}{}{}", label, " ".repeat(step * 3), card)).collect()
+}
+
+// The classic terminal experience
+pub struct TextFrontend {
+    names: Vec<String>, // Learned from GameStart
+    looks: Vec<(String, Look)>, // By name
+    aliases: Aliases,
+    ansi: bool, // Clearing the screen, only on a real terminal
+    color: bool,
+    palette: Palette,
+    fast_forward: bool, // AI turns go by without stopping and show up as a recap before the next human turn
+    ai_turn: bool,
+    recap: Vec<AiTurn>,
+    animation: Animation,
+    dealt: bool, // The first hand of a game fans out, the ones after it are just printed
+}
+
+impl TextFrontend {
+    // Color is only used if it's wanted and the terminal can show it
+    pub fn new(color: bool, fast_forward: bool) -> Self {
+        let ansi = terminal_supports_ansi();
+        TextFrontend { names: Vec::new(), looks: Vec::new(), aliases: Aliases::default(), ansi, color: color && ansi, palette: Palette::Standard, fast_forward, ai_turn: false, recap: Vec::new(), animation: Animation::Off, dealt: false }
+    }
+
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn animation(mut self, animation: Animation) -> Self {
+        self.animation = animation;
+        self
+    }
+
+    pub fn looks(mut self, looks: Vec<(String, Look)>) -> Self {
+        self.looks = looks;
+        self
+    }
+
+    pub fn aliases(mut self, aliases: Aliases) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    // A player's name with their avatar, in their color when colors are on
+    fn shown(&self, player: usize) -> String {
+        let name = self.name(player);
+        self.accented(&name, &label(&self.looks, &name))
+    }
+
+    fn accented(&self, name: &str, text: &str) -> String {
+        match self.looks.iter().find(|(n, _)| n == name).and_then(|(_, look)| look.accent) {
+            Some(accent) if self.color => format!("\x1b[{}m{}\x1b[0m", accent.code(), text),
+            _ => text.to_string(),
+        }
+    }
+
+    // Notices like the score table name people with their avatars already, this puts their colors on
+    fn accent_notice(&self, message: &str) -> String {
+        let mut message = message.to_string();
+        for (name, _) in &self.looks {
+            let label = label(&self.looks, name);
+            if message.contains(&label) {
+                message = message.replace(&label, &self.accented(name, &label));
+            }
+        }
+        message
+    }
+
+    fn frame(&self) -> Option<Duration> {
+        self.animation.frame().filter(|_| self.ansi)
+    }
+
+    fn print_played(&self, label: &str, card: &UNOCard) {
+        let Some(frame) = self.frame() else {
+            println!("{}{}", label, self.card(card));
+            return;
+        };
+        for line in slide_frames(label, &self.card(card)) {
+            print!("\r\x1b[2K{}", line);
+            io::stdout().flush().expect("Failed to flush terminal.");
+            thread::sleep(frame);
+        }
+        println!();
+    }
+
+    // Folds an AI turn's events into the recap, false for the ones that should still be shown as they happen
+    fn summarize(&mut self, event: &Event) -> bool {
+        if let Event::TurnStart { player, .. } = event {
+            self.recap.push(AiTurn { player: *player, draws: 0, actions: Vec::new() });
+            return true;
+        }
+        let card = |c: &UNOCard| self.card(c);
+        let action = match event {
+            Event::Drew { .. } | Event::ForcedDraw { .. } => {
+                if let Some(turn) = self.recap.last_mut() {
+                    turn.draws += 1;
+                }
+                return true;
+            },
+            Event::Played { card: c, .. } => format!("played {}", card(c)),
+            Event::ColorChosen { color, .. } => format!("picked {}", get_color(color).to_lowercase()),
+            Event::Skipped { .. } => String::from("was skipped"),
+            Event::Traded { .. } => String::from("traded a card with the deck"),
+            Event::Uno { .. } => String::from("called UNO"),
+            Event::Challenge { bluff: true, .. } => String::from("caught a +4 bluff"),
+            Event::Challenge { bluff: false, .. } => String::from("challenged a +4 that wasn't a bluff"),
+            Event::Reshuffled { .. } => String::from("reshuffled the discard pile"),
+            Event::NewDeck => String::from("opened a new deck"),
+            Event::LastCard(_) | Event::Hand(_) => return true,
+            _ => return false,
+        };
+        match self.recap.last_mut() {
+            Some(turn) => turn.actions.push(action),
+            None => return false,
+        }
+        true
+    }
+
+    fn print_recap(&mut self) {
+        if self.recap.is_empty() {
+            return;
+        }
+        println!("While you waited:");
+        for turn in std::mem::take(&mut self.recap) {
+            println!("  {}", turn.line(&self.shown(turn.player)));
+        }
+    }
+
+    // A card the way this frontend prints it, in the palette with its letter when colors are on
+    pub fn card(&self, card: &UNOCard) -> String {
+        match self.palette.code(card.color) {
+            Some(code) if self.color => {
+                let letter = &get_color(&card.color)[..1];
+                format!("\x1b[{}m[{}] {}\x1b[0m", code, letter, self.aliases.name(card))
+            },
+            _ => self.aliases.name(card),
+        }
+    }
+
+    fn print_hand(&mut self, hand: &[UNOCard]) {
+        let deal = if self.dealt { None } else { self.frame() };
+        self.dealt = true;
+        for (index, item) in hand.iter().enumerate() {
+            println!("{}. {}", index + 1, self.card(item));
+            if let Some(frame) = deal {
+                io::stdout().flush().expect("Failed to flush terminal.");
+                thread::sleep(frame * 4);
+            }
+        }
+        println!("Type a card's number or its name (\"red 5\", \"wild +4\") to play it");
+        println!("Type \"d\" or \"draw\" to draw a card");
+        println!("Type \"s\" or \"see\" to see the last played card and your hand again");
+        println!("Type \"a\" or \"analyze\" to see how likely the others are to hold each color");
+        println!("Type \"suggest\" for every play ranked the way the Expert AI would, rated safe, aggressive or risky");
+        println!("Type \"join <seat> <name>\" for somebody arriving late to take over a seat the AI is playing");
+        println!("Type \"takeover\" to let the AI play your hand from here, or \"takeover expert\" for a certain one");
+        println!("Type \"t <number>\" to trade that card for the top of the deck, once a game when the trade rule is on");
+        println!("Type \"save <name>\" to save the game and \"UNOler saves resume <name>\" to continue it later");
+    }
+
+    fn name(&self, player: usize) -> String {
+        self.names.get(player.wrapping_sub(1)).cloned().unwrap_or(format!("Player #{}", player))
+    }
+}
+
+impl Frontend for TextFrontend {
+    fn event(&mut self, event: &Event) {
+        if let Event::TurnStart { ai, .. } = event {
+            self.ai_turn = *ai;
+        }
+        if self.fast_forward && self.ai_turn && self.summarize(event) {
+            return;
+        }
+        self.print_recap();
+        match event {
+            Event::Notice(message) => println!("{}", self.accent_notice(message)),
+            Event::InvalidInput(message) => println!("{}", message),
+            Event::GameStart { seed, names, .. } => {
+                self.names = names.clone();
+                self.dealt = false;
+                match seed {
+                    Some(seed) => println!("Seed: {} (pass --seed {} to replay this deal)", seed, seed),
+                    None => println!("Secure shuffle, this deal can't be replayed"),
+                }
+                println!("\n------------\n");
+            },
+            Event::TurnStart { player, ai, last_played } => {
+                println!("\n{}'s turn!", self.shown(*player));
+                println!("Last card played: {}\n", self.card(last_played));
+                if *ai { println!("AI player!"); }
+            },
+            Event::LastCard(card) => println!("Last card played: {}\n", self.card(card)),
+            Event::Hand(hand) => self.print_hand(hand),
+            Event::Played { ai, card, .. } => self.print_played(if *ai { "AI card selected: " } else { "Card selected: " }, card),
+            Event::IllegalMove(card) => println!("Playing a {} is not allowed. Pick another card or draw.\n", self.card(card)),
+            // Shows up on the next turn as the last card played
+            Event::ColorChosen { .. } => {},
+            Event::Drew { card, .. } => match card {
+                Some(card) => println!("Drawed card: {}\n", self.card(card)),
+                None => println!("AI drew a card"),
+            },
+            Event::ForcedDraw { card, .. } => match card {
+                Some(card) => println!("Force drawing: {}", self.card(card)),
+                None => println!("Force drawing"),
+            },
+            Event::Traded { got: Some(got), gave, .. } => println!("Traded {} for {}\n", self.card(gave), self.card(got)),
+            Event::Traded { got: None, .. } => println!("AI traded a card with the deck"),
+            Event::Skipped { .. } => println!("You have been skipped!"),
+            Event::Reshuffled { .. } => println!("Deck empty. Using discard pile..."),
+            Event::NewDeck => println!("Deck empty. Using new deck..."),
+            Event::Uno { .. } => println!("UNO"),
+            Event::Challenge { player, against, bluff } => println!(
+                "{} challenges {}'s +4: {}", self.shown(*player), self.shown(*against),
+                if *bluff { "it was a bluff!" } else { "it wasn't a bluff" },
+            ),
+            Event::Win { player } => println!("{} wins!", self.shown(*player)),
+        }
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        if let Prompt::Continue = prompt && self.fast_forward && self.ai_turn {
+            return String::new();
+        }
+        if let Prompt::Action { .. } = prompt {
+            println!("What would you like to play (or draw)?");
+        }
+        match prompt {
+            // The pacing toggle lives here since this is the only place the game waits between turns
+            Prompt::Continue if self.fast_forward => print!("Press enter to continue (step to see AI turns one by one)...: "),
+            Prompt::Continue => print!("Press enter to continue (ff to fast-forward AI turns)...: "),
+            _ => print!("{}: ", prompt.message()),
+        }
+        io::stdout().flush().expect("Failed to flush terminal.");
+
+        let line = read_line().unwrap_or_else(|| {
+            println!("0 Bytes read. Please retry the program or ensure EOF hasn't reached.");
+            String::new()
+        });
+
+        if let Prompt::Continue = prompt {
+            match line.trim().to_lowercase().as_str() {
+                "ff" | "fast" => self.fast_forward = true,
+                "step" => self.fast_forward = false,
+                _ => {},
+            }
+            if self.ansi {
+                clear_terminal();
+            }
+        }
+        // Cards can be typed by the group's names for them too
+        if let Prompt::Action { .. } = prompt {
+            return self.aliases.expand(&line);
+        }
+        line
+    }
+
+    // Somebody who joined late can sit down under a name of their own
+    fn turn_start(&mut self, table: &Table) {
+        self.names.clone_from(&table.names);
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        match change {
+            // Same as at launch, asking for color on a terminal that can't show it leaves it off
+            Some(("color", value)) => self.color = on_off("color", value)? && self.ansi,
+            Some(("palette", value)) => self.palette = value.parse()?,
+            Some(("animation", value)) => self.animation = value.parse()?,
+            Some(("fast-forward", value)) => self.fast_forward = on_off("fast-forward", value)?,
+            Some((setting, _)) => return Err(format!("Unknown setting {} (try color, palette, animation or fast-forward)", setting)),
+            None => {},
+        }
+        Ok(format!(
+            "color {}, palette {}, animation {}, fast-forward {}",
+            if self.color { "on" } else { "off" },
+            format!("{:?}", self.palette).to_lowercase(),
+            format!("{:?}", self.animation).to_lowercase(),
+            if self.fast_forward { "on" } else { "off" },
+        ))
+    }
+}
+
+fn special_name(special: SpecialCard) -> &'static str {
+    match special {
+        SpecialCard::PlusFour => "plus_four",
+        SpecialCard::ColorChange => "color_change",
+        SpecialCard::PlusTwo => "plus_two",
+        SpecialCard::Skip => "skip",
+        SpecialCard::Reverse => "reverse",
+        SpecialCard::Base => "base",
+    }
+}
+
+fn special_from_name(name: &str) -> Option<SpecialCard> {
+    match name {
+        "plus_four" => Some(SpecialCard::PlusFour),
+        "color_change" => Some(SpecialCard::ColorChange),
+        "plus_two" => Some(SpecialCard::PlusTwo),
+        "skip" => Some(SpecialCard::Skip),
+        "reverse" => Some(SpecialCard::Reverse),
+        "base" => Some(SpecialCard::Base),
+        _ => None,
+    }
+}
+
+pub fn card_json(card: &UNOCard) -> Json {
+    Json::object(vec![
+        ("color", Json::from(get_color(&card.color).to_lowercase())),
+        ("special", Json::from(special_name(card.special))),
+        ("number", if card.special == SpecialCard::Base { Json::Number(card.number as f64) } else { Json::Null }),
+        ("name", Json::from(format_card_message(card))),
+    ])
+}
+
+// The reverse of card_json, the name is only there for humans and is ignored
+pub fn card_from_json(value: &Json) -> Option<UNOCard> {
+    let color = match value.get("color")?.as_str()? {
+        "none" => Color::NA,
+        other => other.parse().ok()?,
+    };
+    let special = special_from_name(value.get("special")?.as_str()?)?;
+    let number = match value.get("number")? {
+        Json::Null => -1,
+        n => n.as_f64()? as i8,
+    };
+    Some(UNOCard::new(color, special, number))
+}
+
+fn optional_card_json(card: &Option<UNOCard>) -> Json {
+    card.as_ref().map(card_json).unwrap_or(Json::Null)
+}
+
+pub fn event_json(event: &Event) -> Json {
+    match event {
+        Event::Notice(message) => Json::object(vec![("event", "notice".into()), ("message", message.as_str().into())]),
+        Event::InvalidInput(message) => Json::object(vec![("event", "invalid_input".into()), ("message", message.as_str().into())]),
+        Event::GameStart { players, ai_players, seed, names } => Json::object(vec![
+            ("event", "game_start".into()),
+            ("players", (*players).into()),
+            ("ai_players", (*ai_players).into()),
+            ("names", Json::Array(names.iter().map(|n| n.as_str().into()).collect())),
+            // A string because JSON numbers can't hold every u64
+            ("seed", seed.map_or(Json::Null, |s| s.to_string().into())),
+        ]),
+        Event::TurnStart { player, ai, last_played } => Json::object(vec![
+            ("event", "turn_start".into()),
+            ("player", (*player).into()),
+            ("ai", (*ai).into()),
+            ("last_played", card_json(last_played)),
+        ]),
+        Event::LastCard(card) => Json::object(vec![("event", "last_card".into()), ("card", card_json(card))]),
+        Event::Hand(hand) => Json::object(vec![
+            ("event", "hand".into()),
+            ("cards", Json::Array(hand.iter().enumerate().map(|(i, c)| {
+                let mut card = card_json(c);
+                if let Json::Object(pairs) = &mut card {
+                    pairs.insert(0, (String::from("index"), (i + 1).into()));
+                }
+                card
+            }).collect())),
+        ]),
+        Event::Played { player, ai, card } => Json::object(vec![
+            ("event", "played".into()),
+            ("player", (*player).into()),
+            ("ai", (*ai).into()),
+            ("card", card_json(card)),
+        ]),
+        Event::IllegalMove(card) => Json::object(vec![("event", "illegal_move".into()), ("card", card_json(card))]),
+        Event::ColorChosen { player, color } => Json::object(vec![
+            ("event", "color_chosen".into()),
+            ("player", (*player).into()),
+            ("color", get_color(color).to_lowercase().into()),
+        ]),
+        Event::Drew { player, card } => Json::object(vec![
+            ("event", "drew".into()),
+            ("player", (*player).into()),
+            ("card", optional_card_json(card)),
+        ]),
+        Event::ForcedDraw { player, card } => Json::object(vec![
+            ("event", "forced_draw".into()),
+            ("player", (*player).into()),
+            ("card", optional_card_json(card)),
+        ]),
+        // What an AI gave is left out like what it draws
+        Event::Traded { player, gave, got } => Json::object(vec![
+            ("event", "traded".into()),
+            ("player", (*player).into()),
+            ("gave", got.map_or(Json::Null, |_| card_json(gave))),
+            ("card", optional_card_json(got)),
+        ]),
+        Event::Skipped { player } => Json::object(vec![("event", "skipped".into()), ("player", (*player).into())]),
+        Event::Reshuffled { cards } => Json::object(vec![("event", "reshuffled".into()), ("cards", (*cards).into())]),
+        Event::NewDeck => Json::object(vec![("event", "new_deck".into())]),
+        Event::Uno { player } => Json::object(vec![("event", "uno".into()), ("player", (*player).into())]),
+        Event::Challenge { player, against, bluff } => Json::object(vec![
+            ("event", "challenge".into()),
+            ("player", (*player).into()),
+            ("against", (*against).into()),
+            ("bluff", (*bluff).into()),
+        ]),
+        Event::Win { player } => Json::object(vec![("event", "win".into()), ("player", (*player).into())]),
+    }
+}
+
+// Turns a JSON action into what a human would have typed
+pub fn answer_from_json(value: &Json) -> Option<String> {
+    match value {
+        Json::Str(s) => Some(s.clone()),
+        Json::Number(_) | Json::Bool(_) => Some(value.to_string()),
+        // "uno": true calls UNO with the card, {"action": "play", "card": 2, "uno": true} or {"value": true, "uno": true}
+        Json::Object(_) if value.get("uno") == Some(&Json::Bool(true)) => {
+            let mut plain = value.clone();
+            if let Json::Object(pairs) = &mut plain {
+                pairs.retain(|(key, _)| key != "uno");
+            }
+            Some(format!("{} uno", answer_from_json(&plain)?))
+        },
+        Json::Object(_) => {
+            if let Some(inner) = value.get("value") {
+                return answer_from_json(inner);
+            }
+            match value.get("action")?.as_str()? {
+                "draw" => Some(String::from("d")),
+                "see" => Some(String::from("s")),
+                "analyze" => Some(String::from("analyze")),
+                "suggest" => Some(String::from("suggest")),
+                "menu" => Some(String::from("menu")),
+                "join" => Some(match value.get("name").and_then(|n| n.as_str()) {
+                    Some(name) => format!("join {} {}", value.get("seat")?.as_f64()?, name),
+                    None => format!("join {}", value.get("seat")?.as_f64()?),
+                }),
+                "takeover" => Some(match value.get("difficulty").and_then(|d| d.as_str()) {
+                    Some(difficulty) => format!("takeover {}", difficulty),
+                    None => String::from("takeover"),
+                }),
+                "save" => Some(format!("save {}", value.get("slot")?.as_str()?)),
+                "play" => Some(value.get("card")?.as_f64()?.to_string()),
+                "trade" => Some(format!("t {}", value.get("card")?.as_f64()?)),
+                "color" => Some(value.get("color")?.as_str()?.to_string()),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+// One JSON object per line out, one JSON value per line in, on stdout and stdin or on a socket to a GUI
+pub struct JsonFrontend {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+}
+
+impl JsonFrontend {
+    pub fn stdio() -> Self {
+        JsonFrontend::new(Box::new(io::stdin().lock()), Box::new(io::stdout()))
+    }
+
+    pub fn new(input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        JsonFrontend { input, output }
+    }
+
+    // A reader that went away shows up as the input closing, so write errors can wait for that
+    fn emit(&mut self, value: &Json) {
+        let _ = writeln!(self.output, "{}", value).and_then(|_| self.output.flush());
+    }
+
+    fn read_line(&mut self) -> Option<String> {
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line),
+        }
+    }
+}
+
+impl Frontend for JsonFrontend {
+    fn event(&mut self, event: &Event) {
+        self.emit(&event_json(event));
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        // Nobody needs pacing when a program is driving the game
+        if let Prompt::Continue | Prompt::Exit = prompt {
+            return String::new();
+        }
+
+        let mut message = vec![("prompt", Json::from(prompt.name())), ("message", Json::from(prompt.message()))];
+        if let Prompt::Action { player } | Prompt::Color { player } | Prompt::PlayDrawn { player } | Prompt::Challenge { player } | Prompt::Menu { player } = prompt {
+            message.push(("player", (*player).into()));
+        }
+        let message = Json::object(message);
+
+        loop {
+            self.emit(&message);
+
+            let Some(line) = self.read_line() else {
+                self.emit(&Json::object(vec![("event", "error".into()), ("message", "the input closed".into())]));
+                std::process::exit(1);
+            };
+
+            match Json::parse(line.trim()).map(|v| answer_from_json(&v)) {
+                Ok(Some(answer)) => return answer,
+                Ok(None) => self.event(&Event::InvalidInput(format!("Unrecognized action: {}", line.trim()))),
+                Err(e) => self.event(&Event::InvalidInput(format!("Invalid JSON: {}", e))),
+            }
+        }
+    }
+}
+
+// For CI and batch experiments: nothing is ever asked interactively
+// Human seats read their moves from a script (one answer per line), events only go to the inner frontend if there is one,
+// and a single JSON result line is printed when somebody wins
+pub struct BatchFrontend {
+    inner: Option<Box<dyn Frontend>>,
+    script: std::vec::IntoIter<(usize, String)>,
+    answered: Option<(usize, String)>, // The script line behind the last answer, until the game accepts it
+    seed: Option<u64>,
+    names: Vec<String>,
+    turns: usize,
+}
+
+impl BatchFrontend {
+    // The script is numbered lines, so a refused answer can point at the line it came from
+    pub fn new(inner: Option<Box<dyn Frontend>>, script: Vec<(usize, String)>) -> Self {
+        BatchFrontend { inner, script: script.into_iter(), answered: None, seed: None, names: Vec::new(), turns: 0 }
+    }
+
+    // Prints the result and stops, there's nobody around to ask
+    fn fail(&self, code: i32, message: &str) -> ! {
+        println!("{}", Json::object(vec![("result", "error".into()), ("message", message.into()), ("turns", self.turns.into())]));
+        io::stdout().flush().expect("Failed to flush terminal.");
+        std::process::exit(code);
+    }
+}
+
+impl Frontend for BatchFrontend {
+    fn event(&mut self, event: &Event) {
+        match event {
+            Event::GameStart { seed, names, .. } => {
+                self.seed = *seed;
+                self.names = names.clone();
+            },
+            Event::TurnStart { .. } => self.turns += 1,
+            _ => {},
+        }
+        if let Some(inner) = &mut self.inner {
+            inner.event(event);
+        }
+
+        // A script is a recording of a game that worked, so a move the game turns down means it doesn't match anymore
+        let refused = match event {
+            Event::InvalidInput(message) => Some(message.trim().to_string()),
+            Event::IllegalMove(card) => Some(format!("{} can't be played here", format_card_message(card))),
+            _ => None,
+        };
+        if let (Some(refused), Some((line, answer))) = (refused, &self.answered) {
+            self.fail(1, &format!("Line {} of the script ({}) was refused: {}", line, answer, refused));
+        }
+
+        if let Event::Win { player } = event {
+            let name = self.names.get(player - 1).cloned().unwrap_or(format!("Player #{}", player));
+            println!("{}", Json::object(vec![
+                ("result", "win".into()),
+                ("winner", (*player).into()),
+                ("name", name.into()),
+                ("turns", self.turns.into()),
+                ("seed", self.seed.map_or(Json::Null, |s| s.to_string().into())),
+            ]));
+            io::stdout().flush().expect("Failed to flush terminal.");
+        }
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        match prompt {
+            Prompt::Continue | Prompt::Exit => String::new(),
+            Prompt::Resume | Prompt::Rematch => String::from("n"),
+            Prompt::Players | Prompt::AiPlayers | Prompt::Difficulty => {
+                self.fail(2, &format!("--batch needs {} from the config file or the command line", prompt.name()))
+            },
+            Prompt::Action { player } | Prompt::Color { player } | Prompt::PlayDrawn { player } | Prompt::Challenge { player } | Prompt::Menu { player } => match self.script.next() {
+                Some((line, answer)) => {
+                    self.answered = Some((line, answer.clone()));
+                    answer
+                },
+                None => self.fail(1, &format!("The script ran out at Player #{}'s {} prompt", player, prompt.name())),
+            },
+        }
+    }
+}
+
+// --auto-move: answers a human's action prompt itself when there's only one thing they can do, drawing with nothing
+// to play or the one playable card under force play, and says what it did instead of asking
+// It sits in front of the real frontend, so recordings still get the answer like it was typed
+pub struct OnlyMove {
+    inner: Box<dyn Frontend>,
+    player: usize,
+    hand: Vec<UNOCard>, // Sorted like the game sorts it before every prompt, so the numbers match
+    last_played: Option<UNOCard>,
+    force_play: bool,
+    uno_penalty: bool,
+}
+
+impl OnlyMove {
+    pub fn new(inner: Box<dyn Frontend>) -> Self {
+        OnlyMove { inner, player: 0, hand: Vec::new(), last_played: None, force_play: false, uno_penalty: false }
+    }
+
+    fn only_move(&self) -> Option<(String, String)> {
+        let last = self.last_played?;
+        let playable: Vec<usize> = (0..self.hand.len()).filter(|&i| allowed_move(self.hand[i], last)).collect();
+        match playable.as_slice() {
+            [] => Some((String::from("d"), String::from("Nothing to play, drawing a card"))),
+            // Going down to one card it calls UNO too, when forgetting would cost cards
+            [i] if self.force_play => {
+                let call = if self.uno_penalty && self.hand.len() == 2 { " uno" } else { "" };
+                Some((format!("{}{}", i + 1, call), format!("Only one card to play, playing {}", format_card_message(&self.hand[*i]))))
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Frontend for OnlyMove {
+    fn event(&mut self, event: &Event) {
+        if let Event::Drew { player, card: Some(card) } = event
+            && *player == self.player
+        {
+            self.hand.push(*card);
+            self.hand.sort();
+        }
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        if let Prompt::Action { player } = prompt
+            && *player == self.player
+            && let Some((answer, message)) = self.only_move()
+        {
+            self.inner.event(&Event::Notice(message));
+            return answer;
+        }
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.player = table.game_state.player_number();
+        self.hand = table.hands[self.player - 1].clone();
+        self.hand.sort();
+        self.last_played = Some(table.last_played);
+        self.force_play = table.rules.force_play;
+        self.uno_penalty = table.rules.uno_penalty > 0;
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        self.inner.game_over(table, winner);
+    }
+}
+
+// --hand-strength: a line about the hand before it's shown on a human's turn, see ai::hand_strength
+pub struct HandStrength {
+    inner: Box<dyn Frontend>,
+    top: Option<UNOCard>, // Only on a human's turn
+}
+
+impl HandStrength {
+    pub fn new(inner: Box<dyn Frontend>) -> Self {
+        HandStrength { inner, top: None }
+    }
+}
+
+pub fn strength_line(strength: &Strength, cards: usize) -> String {
+    let wilds = match strength.wilds {
+        0 => String::new(),
+        1 => String::from(" and a wild"),
+        n => format!(" and {} wilds", n),
+    };
+    format!(
+        "Hand: {} of {} card{} playable, {} of 4 colors{}, {} points at risk",
+        strength.playable, cards, if cards == 1 { "" } else { "s" }, strength.colors, wilds, strength.points,
+    )
+}
+
+impl Frontend for HandStrength {
+    fn event(&mut self, event: &Event) {
+        if let (Event::Hand(hand), Some(top)) = (event, self.top) {
+            self.inner.event(&Event::Notice(strength_line(&hand_strength(hand, top), hand.len())));
+        }
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        let human = !table.is_ai(table.game_state.current_player);
+        self.top = human.then_some(table.last_played);
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        self.inner.game_over(table, winner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    #[test]
+    fn the_hand_gets_sized_up() {
+        let hand = [
+            UNOCard::new(Color::Red, SpecialCard::Base, 5),
+            UNOCard::new(Color::Red, SpecialCard::Skip, 0),
+            UNOCard::new(Color::Blue, SpecialCard::Base, 7),
+            UNOCard::new(Color::NA, SpecialCard::PlusFour, 0),
+        ];
+        let line = strength_line(&hand_strength(&hand, UNOCard::new(Color::Red, SpecialCard::Base, 9)), hand.len());
+        assert_eq!(line, "Hand: 3 of 4 cards playable, 2 of 4 colors and a wild, 82 points at risk");
+    }
+
+    #[test]
+    fn fast_forwarded_turns_become_one_line() {
+        let mut fe = TextFrontend::new(false, true);
+        let red = UNOCard::new(Color::Red, SpecialCard::Base, 5);
+        for event in [
+            Event::TurnStart { player: 2, ai: true, last_played: red },
+            Event::Drew { player: 2, card: None },
+            Event::Drew { player: 2, card: None },
+            Event::Played { player: 2, ai: true, card: red },
+            Event::Uno { player: 2 },
+            Event::TurnStart { player: 3, ai: true, last_played: red },
+            Event::Skipped { player: 3 },
+        ] {
+            fe.event(&event);
+        }
+        let lines: Vec<String> = fe.recap.iter().map(|turn| turn.line(&fe.name(turn.player))).collect();
+        assert_eq!(lines, ["Player #2 drew 2 cards, played Red 5, called UNO", "Player #3 was skipped"]);
+        assert_eq!(fe.prompt(&Prompt::Continue), "");
+    }
+
+    #[test]
+    fn players_show_up_with_their_avatar_and_color() {
+        let maria = Look { avatar: Some(String::from("🦊")), accent: Some(Accent::Green) };
+        let looks = vec![(String::from("Maria"), maria), (String::from("Bo"), Look { avatar: None, accent: Some(Accent::Cyan) })];
+        let mut fe = TextFrontend::new(false, false).looks(looks.clone());
+        fe.names = vec![String::from("Maria"), String::from("Bo"), String::from("Cy")];
+        assert_eq!(labels(&looks, &fe.names), ["🦊 Maria", "Bo", "Cy"]);
+        assert_eq!(fe.shown(1), "🦊 Maria");
+
+        fe.color = true;
+        assert_eq!(fe.shown(1), "\x1b[1;32m🦊 Maria\x1b[0m");
+        assert_eq!(fe.shown(3), "Cy");
+        assert_eq!(fe.accent_notice("  🦊 Maria: 40 points"), "  \x1b[1;32m🦊 Maria\x1b[0m: 40 points");
+        assert!("plaid".parse::<Accent>().is_err());
+    }
+
+    #[test]
+    fn display_settings_change_mid_game() {
+        let mut fe = TextFrontend::new(false, false);
+        assert_eq!(fe.settings(None).unwrap(), "color off, palette standard, animation off, fast-forward off");
+        assert_eq!(fe.settings(Some(("palette", "colorblind"))).unwrap(), "color off, palette colorblind, animation off, fast-forward off");
+        assert!(fe.settings(Some(("fast-forward", "on"))).unwrap().ends_with("fast-forward on"));
+        assert!(fe.settings(Some(("animation", "warp"))).is_err());
+        assert!(fe.settings(Some(("volume", "11"))).is_err());
+    }
+
+    #[test]
+    fn colored_cards_carry_their_letter() {
+        let mut fe = TextFrontend::new(false, false).palette("colorblind".parse().unwrap());
+        let green = UNOCard::new(Color::Green, SpecialCard::Skip, -1);
+        assert_eq!(fe.card(&green), "Green Skip");
+        fe.color = true;
+        assert_eq!(fe.card(&green), "\x1b[38;5;36m[G] Green Skip\x1b[0m");
+        assert_eq!(fe.card(&UNOCard::new(Color::NA, SpecialCard::PlusFour, -1)), format_card_message(&UNOCard::new(Color::NA, SpecialCard::PlusFour, -1)));
+        assert!("plaid".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn played_cards_slide_in_and_stop_on_the_plain_line() {
+        let frames = slide_frames("Card selected: ", "Red 5");
+        assert_eq!(frames.len(), SLIDE + 1);
+        assert!(frames[0].ends_with(&format!("{}Red 5", " ".repeat(SLIDE * 3))));
+        assert_eq!(frames[SLIDE], "Card selected: Red 5");
+        assert_eq!("off".parse::<Animation>().unwrap().frame(), None);
+        assert!("fast".parse::<Animation>().unwrap().frame() < "slow".parse::<Animation>().unwrap().frame());
+    }
+
+    #[test]
+    fn only_move_answers_when_there_is_no_choice() {
+        use crate::testing::{ScriptedFrontend, TestGame, card};
+
+        let mut inner = ScriptedFrontend::default();
+        inner.actions.push_back(String::from("asked"));
+        let mut fe = OnlyMove::new(Box::new(inner));
+        let mut game = TestGame::new(2).hand(0, &["blue 7"]).hand(1, &["red 1"]).top("green 5")
+            .rules(RuleSet { force_play: true, ..RuleSet::default() })
+            .build();
+        fe.turn_start(game.table());
+        assert_eq!(fe.prompt(&Prompt::Action { player: 1 }), "d");
+        fe.event(&Event::Drew { player: 1, card: Some(card("green 2")) });
+        assert_eq!(fe.prompt(&Prompt::Action { player: 1 }), "1");
+        game.table_mut().rules.uno_penalty = 2;
+        fe.turn_start(game.table());
+        fe.event(&Event::Drew { player: 1, card: Some(card("green 2")) });
+        assert_eq!(fe.prompt(&Prompt::Action { player: 1 }), "1 uno", "with UNO called, forgetting costs cards");
+        game.table_mut().rules.uno_penalty = 0;
+
+        // Without force play drawing is always an option too, so that's up to the player
+        game.table_mut().rules.force_play = false;
+        fe.turn_start(game.table());
+        fe.event(&Event::Drew { player: 1, card: Some(card("green 2")) });
+        assert_eq!(fe.prompt(&Prompt::Action { player: 1 }), "asked");
+    }
+}
@@ -0,0 +1,173 @@
+// A C API for embedding the engine, built with --features ffi (include/unoler.h has the declarations)
+// Games are Sessions behind an opaque pointer, and everything going in or out is JSON or plain text in C strings.
+// Strings the library hands out are freed with unoler_string_free, games with unoler_game_free
+
+// The pointer rules are in the normal comments like everything else
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::ptr;
+
+use crate::game::Difficulty;
+use crate::json::Json;
+use crate::session::{Session, settings_from_json};
+
+pub struct UnolerGame {
+    session: Session,
+}
+
+// Interior NULs can't happen in JSON output, but a name with one would otherwise panic across the boundary
+fn c_string(text: String) -> *mut c_char {
+    CString::new(text.replace('\0', "")).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+unsafe fn read(text: *const c_char) -> Result<String, String> {
+    if text.is_null() {
+        return Err(String::from("Got a NULL string"));
+    }
+    unsafe { CStr::from_ptr(text) }.to_str().map(String::from).map_err(|_| String::from("The string isn't UTF-8"))
+}
+
+// Leaves the message for the caller if they asked for one
+unsafe fn report(error: *mut *mut c_char, message: String) {
+    if !error.is_null() {
+        unsafe { *error = c_string(message) };
+    }
+}
+
+// Settings JSON like {"players": 1, "ai_players": 2, "difficulty": "skilled", "seed": "42"}
+// Returns NULL on failure, with the reason in *error when error isn't NULL
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unoler_game_new(settings: *const c_char, error: *mut *mut c_char) -> *mut UnolerGame {
+    let started = unsafe { read(settings) }
+        .and_then(|text| Json::parse(&text))
+        .and_then(|json| settings_from_json(&json))
+        .and_then(|settings| Session::start(&settings));
+    match started {
+        Ok(session) => Box::into_raw(Box::new(UnolerGame { session })),
+        Err(e) => {
+            unsafe { report(error, e) };
+            ptr::null_mut()
+        },
+    }
+}
+
+// The table as JSON, see Session::view. NULL if game is NULL
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unoler_game_view(game: *const UnolerGame) -> *mut c_char {
+    match unsafe { game.as_ref() } {
+        Some(game) => c_string(game.session.view().to_string()),
+        None => ptr::null_mut(),
+    }
+}
+
+// A JSON array of every event from number `since` on
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unoler_game_events(game: *const UnolerGame, since: usize) -> *mut c_char {
+    match unsafe { game.as_ref() } {
+        Some(game) => c_string(game.session.events_json(since).to_string()),
+        None => ptr::null_mut(),
+    }
+}
+
+// What the player being asked would have typed. 0 when the game took it (a refused move still counts,
+// it shows up as an event and the same prompt comes back), -1 with the reason in *error otherwise
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unoler_game_act(game: *mut UnolerGame, answer: *const c_char, error: *mut *mut c_char) -> c_int {
+    let Some(game) = (unsafe { game.as_mut() }) else {
+        unsafe { report(error, String::from("Got a NULL game")) };
+        return -1;
+    };
+    match unsafe { read(answer) }.and_then(|answer| game.session.act(&answer)) {
+        Ok(()) => 0,
+        Err(e) => {
+            unsafe { report(error, e) };
+            -1
+        },
+    }
+}
+
+// What a built in AI ("calm", "aggressive", "skilled", "random" or "expert") would answer in the waiting player's place, to be passed
+// to unoler_game_act or ignored. NULL when nobody is being asked, or with the reason in *error for a bad difficulty
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unoler_game_suggest(game: *const UnolerGame, difficulty: *const c_char, error: *mut *mut c_char) -> *mut c_char {
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        unsafe { report(error, String::from("Got a NULL game")) };
+        return ptr::null_mut();
+    };
+    match unsafe { read(difficulty) }.and_then(|d| d.parse::<Difficulty>()) {
+        Ok(difficulty) => game.session.suggest(difficulty).map_or(ptr::null_mut(), c_string),
+        Err(e) => {
+            unsafe { report(error, e) };
+            ptr::null_mut()
+        },
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unoler_game_free(game: *mut UnolerGame) {
+    if !game.is_null() {
+        drop(unsafe { Box::from_raw(game) });
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unoler_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(text: *mut c_char) -> String {
+        let owned = unsafe { CStr::from_ptr(text) }.to_str().unwrap().to_string();
+        unsafe { unoler_string_free(text) };
+        owned
+    }
+
+    #[test]
+    fn a_game_through_the_c_api() {
+        unsafe {
+            let settings = CString::new(r#"{"players": 1, "ai_players": 1, "difficulty": "calm", "seed": "3"}"#).unwrap();
+            let game = unoler_game_new(settings.as_ptr(), ptr::null_mut());
+            assert!(!game.is_null());
+
+            let view = Json::parse(&take(unoler_game_view(game))).unwrap();
+            assert_eq!(view.get("waiting").and_then(|w| w.get("prompt")), Some(&Json::from("action")));
+
+            let draw = CString::new("d").unwrap();
+            assert_eq!(unoler_game_act(game, draw.as_ptr(), ptr::null_mut()), 0);
+            assert!(take(unoler_game_events(game, 0)).contains("\"drew\""));
+
+            let skilled = CString::new("skilled").unwrap();
+            let answer = unoler_game_suggest(game, skilled.as_ptr(), ptr::null_mut());
+            assert_eq!(unoler_game_act(game, answer, ptr::null_mut()), 0);
+            unoler_string_free(answer);
+            unoler_game_free(game);
+        }
+    }
+
+    #[test]
+    fn errors_come_back_as_strings() {
+        unsafe {
+            let mut error = ptr::null_mut();
+            let settings = CString::new("{\"players\": 1, \"ai_players\": 1}").unwrap();
+            assert!(unoler_game_new(settings.as_ptr(), &mut error).is_null());
+            assert!(take(error).contains("difficulty"));
+
+            assert_eq!(unoler_game_act(ptr::null_mut(), ptr::null(), &mut error), -1);
+            assert_eq!(take(error), "Got a NULL game");
+            assert!(unoler_game_view(ptr::null()).is_null());
+
+            let settings = CString::new("{\"players\": 1, \"ai_players\": 1, \"difficulty\": \"calm\"}").unwrap();
+            let game = unoler_game_new(settings.as_ptr(), ptr::null_mut());
+            let nonsense = CString::new("genius").unwrap();
+            assert!(unoler_game_suggest(game, nonsense.as_ptr(), &mut error).is_null());
+            assert!(!take(error).is_empty());
+            unoler_game_free(game);
+        }
+    }
+}
@@ -0,0 +1,1153 @@
+// The table and the turn loop, everything a game needs no matter who is playing or how it is shown
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::ai::{Neighbors, Rating, challenge_ai, color_odds, expert_move, get_move_ai, pick_color_ai, play_drawn_ai, suggestions, trade_ai};
+use crate::cards::{Color, DeckShape, Hand, SpecialCard, UNOCard, colors, deck_size, decks_for, format_card_message, get_color, refresh_deck, shuffled_decks};
+use crate::debug;
+use crate::effects::Effect;
+use crate::events::{Event, Frontend, Prompt};
+use crate::invariants;
+use crate::log::Level;
+use crate::rng::{ChaChaRng, DeckRng, Randler, Rng};
+use crate::plugins::{RulePlugin, legal_moves};
+use crate::rules::{Handicap, RuleSet, TurnStart, TurnState, check_countercards, has_won, is_uno};
+use crate::search::Move;
+use crate::stack::Stack;
+use crate::trace::{self, Traced};
+
+// The frontend had nothing to answer this prompt with yet, see Frontend::paused
+#[derive(Debug, Clone, Copy)]
+pub struct Paused(pub Prompt);
+
+impl Display for Paused {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "The game is waiting for an answer to the {} prompt", self.0.name())
+    }
+}
+
+impl std::error::Error for Paused {}
+
+// A player gave up from the pause menu, the game ends there without a winner
+#[derive(Debug, Clone, Copy)]
+pub struct Forfeited(pub usize);
+
+impl Display for Forfeited {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Player #{} forfeited the game", self.0)
+    }
+}
+
+impl std::error::Error for Forfeited {}
+
+// Asks until the answer parses
+pub fn input<T, E>(fe: &mut dyn Frontend, prompt: Prompt, error: &str) -> std::result::Result<T, Paused>
+where 
+    T: FromStr<Err = E>,
+    E: Display,
+    
+{
+    loop {
+        let u_input = fe.prompt(&prompt);
+        if fe.paused() {
+            return Err(Paused(prompt));
+        }
+        
+        match u_input.trim().parse::<T>() {
+            Ok(val) => return Ok(val),
+            Err(e) => fe.event(&Event::InvalidInput(format!("Error: {} ({})", error, e))),
+        }
+    }
+    
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Difficulty {
+    Calm,
+    Aggressive,
+    Skilled,
+    Random, // Any card it can play, a baseline for the others
+    Expert, // Searches ahead over guesses at the other hands, see ai::expert_move
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result< Self, Self::Err > {
+        let sl = s.to_lowercase();
+        match sl.as_str() {
+            "calm" => Ok(Difficulty::Calm),
+            "aggressive" => Ok(Difficulty::Aggressive),
+            "skilled" => Ok(Difficulty::Skilled),
+            "random" => Ok(Difficulty::Random),
+            "expert" => Ok(Difficulty::Expert),
+            _ => Err( format!("{} is not an avaliable difficulty", s) ),
+        }
+    }
+}
+
+pub fn difficulty_name(difficulty: Difficulty) -> &'static str {
+    match difficulty {
+        Difficulty::Calm => "calm",
+        Difficulty::Aggressive => "aggressive",
+        Difficulty::Skilled => "skilled",
+        Difficulty::Random => "random",
+        Difficulty::Expert => "expert",
+    }
+}
+
+// For yes or no questions
+#[derive(Debug, Copy, Clone)]
+pub struct YesNo(pub bool);
+
+impl FromStr for YesNo {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result< Self, Self::Err > {
+        let sl = s.to_lowercase();
+        match sl.as_str() {
+            "y" | "yes" | "true" => Ok(YesNo(true)),
+            "n" | "no" | "false" => Ok(YesNo(false)),
+            _ => Err( format!("{} is not yes or no", s) ),
+        }
+    }
+}
+
+// Current game state, handling turns and reverses
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Game {
+    pub current_player: usize, // The seat index, 0 based
+    pub max_players: usize,
+    pub direction: i8, // 1 or -1
+}
+
+impl Game {
+    pub fn new(c: usize, m: usize, d: i8) -> Self {
+        Self { current_player: c, max_players: m, direction: d }
+    }
+    
+    // Adding max_players - 1 is a step back that can't go below zero
+    pub fn next_turn(&mut self) {
+        let step = if self.direction < 0 { self.max_players - 1 } else { 1 };
+        self.current_player = (self.current_player + step) % self.max_players;
+    }
+    
+    pub fn reverse(&mut self) {
+        self.direction *= -1;
+    }
+    
+    pub fn player_number(&self) -> usize {
+        self.current_player + 1
+    }
+}
+
+// Ensures the deck is full
+// If there is a discard pile, a new deck is made from the discard pile and shuffled
+// If there is no discard pile, an entirely new deck is made and shuffled
+pub fn ensure_deck_full(deck: &mut Vec<UNOCard>, discard: &mut Vec<UNOCard>, shape: DeckShape, rand: &mut impl Rng, fe: &mut dyn Frontend) {
+    if deck.is_empty() {
+        if discard.len() > 1 {
+            fe.event(&Event::Reshuffled { cards: discard.len() - 1 });
+            
+            discard.iter_mut().for_each(|c| {
+                if c.special == SpecialCard::ColorChange || c.special == SpecialCard::PlusFour {
+                    c.color = Color::NA;
+                }
+            });
+            
+            let top = discard.pop().unwrap();
+            crate::log!(Level::Info, "Deck empty, reshuffling {} cards from the discard pile", discard.len());
+            deck.append(discard);
+            rand.shuffle(deck);
+            discard.push(top);
+        } else {
+            crate::log!(Level::Info, "Deck empty and nothing to reshuffle, opening a new deck");
+            fe.event(&Event::NewDeck);
+            refresh_deck(deck, shape, rand);
+        }
+    }
+}
+
+// What everybody at the table has seen of a seat's hand: drawing on a color means they had none of it then, playing
+// one takes it back off, and a forced draw could have brought them anything. The one card drawn could be the color
+// too, which the AIs live with
+fn note_draw(lacking: &mut Vec<Color>, top: Option<Color>) {
+    match top {
+        Some(color) if !lacking.contains(&color) => lacking.push(color),
+        Some(_) => {},
+        None => lacking.clear(),
+    }
+}
+
+// One line per other seat with the chance they hold each color, see ai::color_odds
+pub fn analysis(t: &Table, seat: usize) -> Vec<String> {
+    let odds = color_odds(t, seat);
+    (0..t.hands.len()).filter(|other| *other != seat).map(|other| {
+        let colors = colors(t.rules.colors).iter().zip(&odds[other])
+            .map(|(color, odds)| format!("{} {:.0}%", get_color(color).to_lowercase(), odds * 100.0))
+            .collect::<Vec<_>>();
+        let cards = t.hands[other].len();
+        format!("{} ({} card{}): {}", t.names[other], cards, if cards == 1 { "" } else { "s" }, colors.join(", "))
+    }).collect()
+}
+
+// A human seat played by the AI from here to the end of the game, same hand and same place at the table. For a player
+// who has to go or has stopped answering, and it can be handed back
+pub fn take_over(t: &mut Table, seat: usize, difficulty: Difficulty) -> String {
+    t.taken_over[seat] = true;
+    t.difficulties[seat] = difficulty;
+    format!("The {} AI plays {}'s seat from here", difficulty_name(difficulty), t.names[seat])
+}
+
+pub fn hand_back(t: &mut Table, seat: usize) -> Option<String> {
+    let taken = std::mem::take(t.taken_over.get_mut(seat)?);
+    taken.then(|| format!("{} is back in their seat", t.names[seat]))
+}
+
+// Somebody arriving late sits down at a seat the AI is playing and picks up its hand from its next turn, under their
+// own name if they give one
+pub fn join(t: &mut Table, seat: usize, name: Option<&str>) -> Result<String, String> {
+    let Some(old) = t.names.get(seat).cloned() else {
+        return Err(format!("There's no seat {}, the table has {}", seat + 1, t.hands.len()));
+    };
+    if !t.is_ai(seat) {
+        return Err(format!("{} is already playing seat {}", old, seat + 1));
+    }
+    if seat < t.players as usize {
+        t.taken_over[seat] = false;
+    } else {
+        t.joined[seat] = true;
+    }
+    if let Some(name) = name {
+        t.names[seat] = name.to_string();
+    }
+    Ok(format!("{} sits down at seat {} and plays {}'s hand from its next turn", t.names[seat], seat + 1, old))
+}
+
+// The moves the Expert would weigh up for the seat to move, best first, each with the number to type for it
+pub fn suggestion_lines(t: &Table) -> Vec<String> {
+    let mut rand = t.ai_rand;
+    let Some(ranked) = suggestions(t, rand.next_u64()) else { return Vec::new() };
+    let hand = &t.hands[t.game_state.current_player];
+    let mut lines = vec![String::from("Suggested, best first:")];
+    let mut listed = Vec::new(); // A wild comes up once for each color, the best one is enough
+    for (mv, _, rating) in ranked {
+        let (answer, name) = match mv {
+            // Wilds are in the hand without their color
+            Move::Play(card) => match hand.iter().position(|c| *c == card || (c.color == Color::NA && c.special == card.special)) {
+                Some(i) => ((i + 1).to_string(), format_card_message(&card)),
+                None => continue,
+            },
+            Move::Draw => (String::from("d"), String::from("Draw")),
+            Move::Keep => (String::from("n"), String::from("Keep it")),
+        };
+        if listed.contains(&answer) {
+            continue;
+        }
+        listed.push(answer.clone());
+        let rating = match rating {
+            Rating::Safe => "safe",
+            Rating::Aggressive => "aggressive",
+            Rating::Risky => "risky",
+        };
+        lines.push(format!("  {}. {}: {}", answer, name, rating));
+    }
+    lines
+}
+
+// Everything needed to pick a game back up, saved every turn for crash recovery
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub hands: Vec<Vec<UNOCard>>, // All decks
+    pub deck: Vec<UNOCard>, // The deck
+    pub discard: Vec<UNOCard>, // The discard pile
+    pub history: Vec<UNOCard>, // Every card played, opening card first and wilds with their color. Reshuffles leave it alone
+    pub lacking: Vec<Vec<Color>>, // One per seat, the colors they drew on instead of matching, see note_draw
+    pub last_played: UNOCard,
+    pub game_state: Game, // The game state
+    pub add_queue: u32, // The queue for adding cards to the next player
+    pub skipped: bool, // Whether or not the player has been skipped
+    pub uno_detection_panic: bool,
+    pub players: u8, // Human players, they sit before the AIs
+    pub names: Vec<String>, // One per seat
+    pub difficulties: Vec<Difficulty>, // One per seat, only the AI seats use theirs
+    pub rules: RuleSet,
+    pub handicaps: Vec<Handicap>, // One per seat
+    pub traded: Vec<bool>, // One per seat, whether they've used their trade with the deck this game
+    pub taken_over: Vec<bool>, // One per seat, human seats the AI has played since they were handed over, see take_over
+    pub joined: Vec<bool>, // One per seat, AI seats a human has sat down at since the deal, see join
+    pub seed: Option<u64>, // What rand started from so the game can be reproduced, None for secure games
+    pub rand: DeckRng, // The deck
+    pub ai_rand: Randler, // AI choices
+}
+
+// Simulations play tables on other threads, so this stops compiling if anything in one gets tied to a thread
+const _: () = {
+    const fn send_sync<T: Send + Sync>() {}
+    send_sync::<Table>();
+    send_sync::<Settings>();
+    send_sync::<DeckRng>();
+    send_sync::<crate::session::Session>();
+    send_sync::<crate::search::Position>();
+};
+
+impl Table {
+    // Whether the AI plays this seat, the AI seats nobody has joined and human ones that have been handed over
+    pub fn is_ai(&self, seat: usize) -> bool {
+        let flag = |flags: &[bool]| flags.get(seat) == Some(&true);
+        flag(&self.taken_over) || (seat >= self.players as usize && !flag(&self.joined))
+    }
+
+    pub fn turn_state(&self) -> TurnState {
+        TurnState { game: self.game_state, add_queue: self.add_queue, skipped: self.skipped }
+    }
+
+    pub fn set_turn_state(&mut self, turn: TurnState) {
+        self.game_state = turn.game;
+        self.add_queue = turn.add_queue;
+        self.skipped = turn.skipped;
+    }
+}
+
+// The default name for a seat, 1 based
+pub fn default_name(player: usize) -> String {
+    format!("Player #{}", player)
+}
+
+// What setup would otherwise ask, filled from the config file and then the command line
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    pub players: Option<u8>,
+    pub ai_players: Option<u8>,
+    pub difficulty: Option<Difficulty>,
+    pub ai_mix: Vec<Difficulty>, // Difficulties for the AI seats in turn, instead of one for all of them
+    pub names: Vec<String>, // For the human seats, in order
+    pub ai_names: Vec<String>, // For the AI seats after them, the ones left out are Player #n
+    pub rules: RuleSet,
+    pub seed: Option<u64>,
+    pub secure: bool,
+    pub autoplay: bool, // The AI takes over the human seats too
+    pub decks: Option<u8>, // Decks shuffled together, None for enough to deal everybody in with a draw pile left
+    pub first: usize, // The seat that plays first, the one before it deals. Rematches move it one seat along
+    pub handicaps: Vec<Handicap>, // By seat, the seats left out have none
+    pub stack: Option<Stack>, // Hands and a deck order to play instead of a shuffle
+}
+
+pub fn game_start_event(t: &Table) -> Event {
+    Event::GameStart {
+        players: t.players as usize,
+        ai_players: t.hands.len() - t.players as usize,
+        seed: t.seed,
+        names: t.names.clone(),
+    }
+}
+
+// The official scoring: whoever goes out gets the points of every card still in the other hands
+pub fn round_points(t: &Table) -> u32 {
+    t.hands.iter().map(|h| h.points()).sum()
+}
+
+// What everybody was left holding once somebody went out, biggest hands last, under the names to show them by
+pub fn round_table(t: &Table, names: &[String], winner: usize) -> Vec<String> {
+    let mut seats: Vec<usize> = (0..t.hands.len()).filter(|&i| i + 1 != winner).collect();
+    seats.sort_by_key(|&i| t.hands[i].points());
+    std::iter::once(format!("{} scores {} points", names[winner - 1], round_points(t)))
+        .chain(seats.iter().map(|&i| format!("  {}: {} cards, {} points", names[i], t.hands[i].len(), t.hands[i].points())))
+        .collect()
+}
+
+// Points added up over a series of rematches, whoever goes out gets round_points each game
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scores {
+    pub games: usize, // Finished or forfeited, also how many seats along the next deal moves
+    pub points: Vec<u32>, // By seat
+}
+
+impl Scores {
+    pub fn record(&mut self, t: &Table, winner: Option<usize>) {
+        self.points.resize(t.hands.len(), 0);
+        if let Some(winner) = winner {
+            self.points[winner - 1] += round_points(t);
+        }
+        self.games += 1;
+    }
+
+    // Highest first, ties in seat order
+    pub fn lines(&self, names: &[String]) -> Vec<String> {
+        let mut seats: Vec<usize> = (0..self.points.len()).collect();
+        seats.sort_by_key(|&i| std::cmp::Reverse(self.points[i]));
+        std::iter::once(format!("Scores after {} games:", self.games))
+            .chain(seats.iter().map(|&i| format!("  {}: {} points", names[i], self.points[i])))
+            .collect()
+    }
+}
+
+// Settings that deal the same table again: the same seats, names, difficulties and rules, with `first` going first
+// A seeded game's next seed comes out of its own deck generator, so a seeded series plays the same every time
+pub fn rematch(t: &mut Table, settings: &Settings, first: usize) -> Settings {
+    let players = t.players as usize;
+    Settings {
+        players: Some(t.players),
+        ai_players: Some((t.hands.len() - players) as u8),
+        difficulty: t.difficulties.get(players).copied(),
+        ai_mix: t.difficulties[players..].to_vec(),
+        names: t.names[..players].to_vec(),
+        ai_names: t.names[players..].to_vec(),
+        rules: t.rules,
+        seed: t.seed.map(|_| t.rand.next_u64()),
+        secure: t.seed.is_none(),
+        autoplay: false,
+        decks: settings.decks,
+        first,
+        handicaps: t.handicaps.clone(),
+        stack: None, // The next game is shuffled again
+    }
+}
+
+// Asks whatever setup questions the settings didn't answer and deals, None if there's nobody to play
+// A fixed seed makes the deal and every later shuffle repeat exactly, a secure game has no seed at all
+pub fn setup(fe: &mut dyn Frontend, settings: &Settings) -> std::result::Result<Option<Table>, Box<dyn std::error::Error>> {
+    
+    let players: u8 = match settings.players {
+        Some(players) => players,
+        None => input(fe, Prompt::Players, "Please enter a proper number that is not too big.")?,
+    };
+    let ai_players: u8 = match settings.ai_players {
+        Some(ai_players) => ai_players,
+        None => input(fe, Prompt::AiPlayers, "Please enter a proper number that is not too big.")?,
+    };
+    let total_players = players as usize + ai_players as usize;
+    let (players, ai_players) = if settings.autoplay { (0, total_players) } else { (players, ai_players as usize) };
+    
+    
+    let difficulty: Difficulty = match settings.difficulty {
+        Some(difficulty) => difficulty,
+        None if ai_players > 0 && settings.ai_mix.is_empty() => input(fe, Prompt::Difficulty, "Please enter a proper difficulty")?,
+        None => Difficulty::Calm,
+    };
+    
+    let names: Vec<String> = (0..total_players)
+        .map(|i| match settings.names.get(i) {
+            Some(name) if i < players as usize || settings.autoplay => name.clone(),
+            _ => i.checked_sub(players as usize).and_then(|ai| settings.ai_names.get(ai)).cloned().unwrap_or(default_name(i + 1)),
+        })
+        .collect();
+    
+    let difficulties: Vec<Difficulty> = (0..total_players)
+        .map(|i| match settings.ai_mix.len() {
+            0 => difficulty,
+            n => settings.ai_mix[i.saturating_sub(players as usize) % n],
+        })
+        .collect();
+    
+    // All randomness in the game comes from this one seed
+    // The AI gets its own stream, so AI decisions can't change the order of the deck
+    let (seed, mut rand, ai_rand) = if settings.secure {
+        (None, DeckRng::Secure(ChaChaRng::from_os()?), Randler::new(Randler::get_base_random_udev()?))
+    } else {
+        let seed = match settings.seed {
+            Some(seed) => seed,
+            None => Randler::get_base_random_udev()?,
+        };
+        (Some(seed), DeckRng::Seeded(Randler::new(seed)), Randler::stream(seed, 1))
+    };
+    
+    // Warnings
+    if total_players == 0 {
+        fe.event(&Event::Notice(String::from("ZERO PLAYERS?? Without a doubt. Right away sir!")));
+        fe.event(&Event::Notice(String::from("Player 0 wins? Is this the outcome you desire?")));
+        return Ok(None);
+    }
+    if total_players == 1 {
+        fe.event(&Event::Notice(String::from("Sure bro, one player")));
+    } else if total_players == 2 {
+        fe.event(&Event::Notice(String::from("WARNING: Reverse cards now count as skip cards!")));
+    }
+    
+    let mut hands: Vec<Vec<UNOCard>> = Vec::new();
+    
+    // Big tables get more decks shuffled together, so everyone is dealt in and there's still a draw pile
+    // A number of decks asked for has to hold every hand and the opening card, new decks halfway through the deal would
+    // change how often each card comes up
+    let handicaps: Vec<Handicap> = (0..total_players).map(|i| settings.handicaps.get(i).copied().unwrap_or_default()).collect();
+    let extra_cards: usize = handicaps.iter().map(|h| h.extra_cards as usize).sum();
+    let decks = match settings.decks {
+        None => decks_for(total_players, settings.rules.hand_size, extra_cards, settings.rules.deck()),
+        Some(decks) => {
+            let dealt = total_players * settings.rules.hand_size as usize + extra_cards + 1;
+            let needed = dealt.div_ceil(deck_size(settings.rules.deck()));
+            if (decks as usize) < needed {
+                return Err(format!(
+                    "{} players with {} cards each and an opening card need {} cards, {} deck{} only {} {}. Ask for {} decks or more, or leave the number out to get enough",
+                    total_players, settings.rules.hand_size, dealt, decks, if decks == 1 { "" } else { "s" },
+                    if decks == 1 { "has" } else { "have" }, decks as usize * deck_size(settings.rules.deck()), needed,
+                ).into());
+            }
+            decks as usize
+        },
+    };
+    let stack = settings.stack.as_ref();
+    if let Some(stack) = stack {
+        stack.check(total_players, settings.rules.deck())?;
+        fe.event(&Event::Notice(format!("Playing a stacked deck of {} cards, nothing is shuffled", stack.deck.len())));
+    } else if decks > 1 {
+        fe.event(&Event::Notice(format!("Shuffling {} decks together for {} players", decks, total_players)));
+    }
+    let mut deck = match stack {
+        Some(stack) => stack.deck.iter().rev().copied().collect(),
+        None => shuffled_decks(&mut rand, decks, settings.rules.deck()),
+    };
+    let out = || if stack.is_some() { "The stacked deck ran out during the deal, it needs more cards" } else { "Error, out of cards" };
+    
+    // Deal everybody a hand, seven cards unless the rules say otherwise
+    // A stacked deal's own hands are kept as they are, handicap or not
+    let stacked = stack.map_or(0, |s| s.hands.len());
+    hands.extend(stack.iter().flat_map(|s| s.hands.iter().cloned()));
+    for _ in stacked..total_players {
+        let mut temp: Vec<UNOCard> = Vec::new();
+        for _ in 0..settings.rules.hand_size {
+            temp.push( deck.pop().ok_or(out())? );
+        }
+        hands.push(temp);
+    }
+    // Handicapped seats draw theirs after the deal, so everybody else gets the same hand they would have without
+    for (hand, handicap) in hands.iter_mut().zip(&handicaps).skip(stacked) {
+        for _ in 0..handicap.extra_cards {
+            hand.push(deck.pop().ok_or(out())?);
+        }
+    }
+    
+    // Game time:
+    
+    // The initial card, a stacked wild on top can come with its color already called
+    let mut last_played = match stack.and_then(|s| s.top) {
+        Some(top) => top,
+        None => deck.pop().ok_or(out())?, // Promise this'll be the last unsafe thing done with popping
+    };
+    let opening = if matches!(last_played.special, SpecialCard::ColorChange | SpecialCard::PlusFour) {
+        UNOCard { color: Color::NA, ..last_played }
+    } else {
+        last_played
+    };
+    
+    if last_played.color == Color::NA {
+        last_played.color = *rand.choose(colors(settings.rules.colors)).ok_or("Error with randomization")?;
+    }
+    
+    let first = TurnState::opening(&settings.rules, opening.special, total_players, settings.first % total_players);
+    let table = Table {
+        hands,
+        deck,
+        discard: vec![opening],
+        history: vec![last_played],
+        lacking: vec![Vec::new(); total_players],
+        last_played,
+        game_state: first.game,
+        add_queue: first.add_queue,
+        skipped: first.skipped,
+        uno_detection_panic: false,
+        players,
+        names,
+        difficulties,
+        rules: settings.rules,
+        handicaps,
+        traded: vec![false; total_players],
+        taken_over: vec![false; total_players],
+        joined: vec![false; total_players],
+        seed,
+        rand,
+        ai_rand,
+    };
+    let start = game_start_event(&table);
+    trace::action(&start);
+    fe.event(&start);
+    if first.game.direction < 0 {
+        let dealer = &table.names[first.game.current_player];
+        fe.event(&Event::Notice(format!("The opening Reverse turns the table around, {} deals and goes first", dealer)));
+    }
+    
+    Ok(Some(table))
+}
+
+// Where a card typed by name is in the hand, counted from 1 like the numbers shown
+// Wilds in a hand have no color yet, so the one named matches whatever color was said with it
+fn card_in_hand(hand: &[UNOCard], card: UNOCard) -> Option<usize> {
+    let wild = matches!(card.special, SpecialCard::ColorChange | SpecialCard::PlusFour);
+    hand.iter().position(|c| *c == card || (wild && c.special == card.special)).map(|i| i + 1)
+}
+
+// Where the +4 facing this seat came from, the seat before it, and the color of the card it went on
+pub fn plus_four_from(t: &Table, seat: usize) -> Option<(usize, Color)> {
+    let seats = t.hands.len();
+    let before = if t.game_state.direction > 0 { (seat + seats - 1) % seats } else { (seat + 1) % seats };
+    Some((before, t.history.iter().rev().nth(1)?.color))
+}
+
+// "3 uno", "uno 3", "red 5 uno" or "y uno": the answer without the call, and whether UNO was called with it
+fn uno_call(answer: &str) -> (String, bool) {
+    let words: Vec<&str> = answer.split_whitespace().collect();
+    let rest: Vec<&str> = words.iter().copied().filter(|w| !w.eq_ignore_ascii_case("uno")).collect();
+    if rest.is_empty() || rest.len() == words.len() {
+        return (answer.to_string(), false);
+    }
+    (rest.join(" "), true)
+}
+
+// Cards off the deck for somebody who owes them, shown only to a human whose turn it is. That's None for the seat a
+// challenge goes against, it's somebody else's turn
+fn forced_draw(t: &mut Table, fe: &mut dyn Frontend, seat: usize, cards: u32, shown: bool) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    for _ in 0..cards {
+        ensure_deck_full(&mut t.deck, &mut t.discard, t.rules.deck(), &mut t.rand, fe);
+        let drawed: UNOCard = t.deck.pop().ok_or("Error, out of cards")?;
+        t.hands[seat].push(drawed);
+        fe.event(&Event::ForcedDraw { player: seat + 1, card: shown.then_some(drawed) });
+    }
+    note_draw(&mut t.lacking[seat], None);
+    Ok(())
+}
+
+// The card drawn last goes from the hand onto the discard pile
+fn play_drawn(hand: &mut Vec<UNOCard>, discard: &mut Vec<UNOCard>, fe: &mut dyn Frontend, player: usize, ai: bool) -> UNOCard {
+    let card = hand.pop().expect("a card was just drawn");
+    discard.push(card);
+    fe.event(&Event::Played { player, ai, card });
+    card
+}
+
+// "save <slot>" from a turn or the pause menu, either way it's the start of the turn that gets saved
+fn save_turn(fe: &mut dyn Frontend, slot: &str) {
+    match fe.save(slot) {
+        Ok(path) => fe.event(&Event::Notice(format!("Saved the start of this turn to {}", path.display()))),
+        Err(e) => fe.event(&Event::InvalidInput(format!("Could not save: {}", e))),
+    }
+}
+
+// The pause menu, asked until the player goes back to their turn. Nothing in the game moves while it's up
+// True when they forfeit
+fn menu(fe: &mut dyn Frontend, player: usize, rules: &RuleSet) -> std::result::Result<bool, Paused> {
+    loop {
+        let answer: String = input(fe, Prompt::Menu { player }, "Please enter a menu choice")?;
+        let answer = answer.to_lowercase();
+        let (choice, rest) = answer.split_once(' ').map_or((answer.as_str(), ""), |(c, r)| (c, r.trim()));
+        match choice {
+            "" | "resume" | "r" | "\u{1b}" => return Ok(false),
+            "save" if rest.is_empty() => fe.event(&Event::InvalidInput(String::from("Please name a slot to save to, like save 1"))),
+            "save" => save_turn(fe, rest),
+            "rules" => fe.event(&Event::Notice(rules.summary())),
+            "settings" => {
+                let change = rest.split_once(' ').map(|(setting, value)| (setting, value.trim()));
+                if !rest.is_empty() && change.is_none() {
+                    fe.event(&Event::InvalidInput(format!("Please give {} a value, like settings palette colorblind", rest)));
+                    continue;
+                }
+                match fe.settings(change) {
+                    Ok(now) => fe.event(&Event::Notice(format!("Settings: {}", now))),
+                    Err(e) => fe.event(&Event::InvalidInput(format!("Could not change that: {}", e))),
+                }
+            },
+            "forfeit" => return Ok(true),
+            _ => fe.event(&Event::InvalidInput(String::from("Please enter save <slot>, rules, settings, forfeit or resume"))),
+        }
+    }
+}
+
+// Swaps and rotations from a house card, the colors a seat was seen lacking go with its hand
+fn move_hands(t: &mut Table, fe: &mut dyn Frontend, effect: Effect, current_idx: usize) {
+    let seats = t.hands.len();
+    if effect.swap {
+        // Whoever holds the fewest, the first of them going round from the player
+        let other = (1..seats).map(|k| (current_idx + k) % seats).min_by_key(|&i| t.hands[i].len());
+        if let Some(other) = other {
+            t.hands.swap(current_idx, other);
+            t.lacking.swap(current_idx, other);
+            fe.event(&Event::Notice(format!("{} swaps hands with {}", t.names[current_idx], t.names[other])));
+        }
+    }
+    if effect.rotate {
+        if t.game_state.direction > 0 {
+            t.hands.rotate_right(1);
+            t.lacking.rotate_right(1);
+        } else {
+            t.hands.rotate_left(1);
+            t.lacking.rotate_left(1);
+        }
+        fe.event(&Event::Notice(String::from("Every hand passes to the next player")));
+    }
+}
+
+// Plays turns until somebody wins and returns who, or None if the turn limit ran out first
+// A forfeit from the pause menu is a Forfeited error, callers that keep score count it as the forfeiting seat's loss
+pub fn play(t: &mut Table, fe: &mut dyn Frontend, turn_limit: Option<usize>) -> std::result::Result<Option<usize>, Box<dyn std::error::Error>> {
+    play_with(t, fe, turn_limit, &mut [])
+}
+
+// play() with rule plugins layered on top, see plugins.rs
+pub fn play_with(
+    t: &mut Table, fe: &mut dyn Frontend, turn_limit: Option<usize>, plugins: &mut [Box<dyn RulePlugin>],
+) -> std::result::Result<Option<usize>, Box<dyn std::error::Error>> {
+    let mut traced = Traced(fe);
+    let fe: &mut dyn Frontend = &mut traced;
+    for _ in 0..turn_limit.unwrap_or(usize::MAX) {
+        fe.turn_start(t);
+        
+        let mut turn = t.turn_state();
+        for plugin in plugins.iter_mut() {
+            plugin.on_turn_start(t, &mut turn);
+        }
+        let current_idx = t.game_state.current_player;
+        let player = t.game_state.player_number();
+        let mut is_ai: bool = t.is_ai(current_idx);
+
+        // The Expert AI looks at the whole table before the turn touches it, the others only need their hand
+        let mut expert = if is_ai && t.difficulties[current_idx] == Difficulty::Expert {
+            let seed = t.ai_rand.next_u64();
+            expert_move(t, seed)
+        } else {
+            None
+        };
+        let mut expert_color = None;
+        let around = Neighbors::at(t);
+
+        let mut player_hand = &mut t.hands[current_idx]; // The player's hand
+
+        player_hand.sort();
+        crate::log!(
+            Level::Debug,
+            "Turn of seat {} ({}) with {} cards, {} queued to draw, {} left in the deck",
+            player, if is_ai { "AI" } else { "human" }, player_hand.len(), turn.add_queue, t.deck.len()
+        );
+        
+        let _turn = trace::span("turn", || vec![
+            ("seat", player.into()),
+            ("ai", is_ai.into()),
+            ("cards", player_hand.len().into()),
+            ("queued", (turn.add_queue as usize).into()),
+            ("deck", t.deck.len().into()),
+        ]);
+        fe.event(&Event::TurnStart { player, ai: is_ai, last_played: t.last_played });
+        
+        if !is_ai {
+            fe.event(&Event::Hand(player_hand.clone()));
+        }
+        
+        // Under challenge-penalty a +4 just played on them can be called a bluff, which it was if the seat before held a
+        // card of the color it went on. Then that seat takes everything waiting and this turn goes on like any other,
+        // otherwise the challenger takes the penalty on top with no answering it
+        let mut challenge_lost = false;
+        if t.rules.challenge_penalty > 0 && turn.add_queue > 0 && t.last_played.special == SpecialCard::PlusFour
+            && let Some((against, color)) = plus_four_from(t, current_idx)
+        {
+            let challenge = if is_ai {
+                let (lacking, cards) = (&t.lacking[against], t.hands[against].len());
+                challenge_ai(lacking, cards, color, t.rules.colors as usize, t.rules.challenge_penalty, t.difficulties[current_idx], &mut t.ai_rand)
+            } else {
+                let YesNo(yes) = input(fe, Prompt::Challenge { player }, "Please enter y or n")?;
+                yes
+            };
+            if challenge {
+                let bluff = t.hands[against].iter().any(|c| c.color == color);
+                fe.event(&Event::Challenge { player, against: against + 1, bluff });
+                if bluff {
+                    let cards = std::mem::take(&mut turn.add_queue);
+                    turn.skipped = false;
+                    forced_draw(t, fe, against, cards, false)?;
+                } else {
+                    turn.add_queue += t.rules.challenge_penalty as u32;
+                    challenge_lost = true;
+                }
+            }
+        }
+        player_hand = &mut t.hands[current_idx];
+        
+        // Without stacking nobody gets to answer a draw card, and a handicap can take it away from one seat or make
+        // that seat play whenever it can
+        let handicap = t.handicaps.get(current_idx).copied().unwrap_or_default();
+        let force_play = t.rules.force_play || handicap.force_play;
+        let countercards = t.rules.stacking && !handicap.no_stacking && !challenge_lost && check_countercards(player_hand);
+        let start = turn.start(countercards);
+        let mut answer: String;
+        let card_selected: Option<UNOCard>;
+        // A trade takes the whole turn, so it has to come before any drawing
+        let mut drew = false;
+        let mut trade = None;
+        let mut called = false; // UNO, with the card that was played
+        loop {
+            // If the player cannot counter the current plus two and the adding queue is not empty, then add the cards to the player
+            if let TurnStart::Draw(cards) = start {
+                card_selected = None;
+                for _ in 0..cards {
+                    
+                    ensure_deck_full(&mut t.deck, &mut t.discard, t.rules.deck(), &mut t.rand, fe);
+                    
+                    let drawed: UNOCard = t.deck.pop().ok_or("Error, out of cards")?;
+                    player_hand.push(drawed);
+                    
+                    fe.event(&Event::ForcedDraw { player, card: if is_ai { None } else { Some(drawed) } });
+                }
+                note_draw(&mut t.lacking[current_idx], None);
+                break;
+            // If the player has been skipped, then skip the card
+            } else if start == TurnStart::Skipped {
+                fe.event(&Event::Skipped { player });
+                card_selected = None;
+                break;
+            }
+            
+            player_hand.sort();
+            
+            if is_ai {
+                // Only the turn's first decision is searched, after a draw it goes on like Skilled
+                let mut ai_move: Option<usize> = match expert.take() {
+                    Some(Move::Play(card)) => {
+                        expert_color = Some(card.color);
+                        let wild = |c: &UNOCard| matches!(c.special, SpecialCard::ColorChange | SpecialCard::PlusFour);
+                        player_hand.iter().position(|c| *c == card || (wild(c) && c.special == card.special))
+                    },
+                    Some(_) => None,
+                    None => get_move_ai(player_hand, t.last_played, t.difficulties[current_idx], t.uno_detection_panic, Some(around), &mut t.ai_rand),
+                };
+                
+                // The AI picks by the core rules, a plugin may have taken that card away
+                let legal = legal_moves(player_hand, t.last_played, plugins);
+                ai_move = ai_move.filter(|i| legal.contains(i));
+                
+                // The AI sometimes holds cards back, which force play doesn't allow
+                if ai_move.is_none() && force_play {
+                    ai_move = legal.first().copied();
+                }
+                
+                if let Some( play_move ) = ai_move {
+                    card_selected = Some(player_hand[play_move]);
+                    t.discard.push( card_selected.unwrap() );
+                    player_hand.remove(play_move);
+                    
+                    fe.event(&Event::Played { player, ai: true, card: card_selected.unwrap() });
+                    break;
+                }
+                else if t.rules.trade && !t.traded[current_idx] && !drew && let Some(index) = trade_ai(player_hand, t.difficulties[current_idx], &mut t.ai_rand) {
+                    trade = Some(index);
+                    card_selected = None;
+                    break;
+                }
+                else {
+                    ensure_deck_full(&mut t.deck, &mut t.discard, t.rules.deck(), &mut t.rand, fe);
+                    
+                    let drawed: UNOCard = t.deck.pop().ok_or("Error, out of cards")?;
+                    player_hand.push(drawed);
+                    drew = true;
+                    note_draw(&mut t.lacking[current_idx], Some(t.last_played.color));
+                    fe.event(&Event::Drew { player, card: None });
+                    
+                    // Under draw-one that was the turn, unless the card just drawn gets played
+                    if t.rules.draw_one {
+                        let play_it = legal_moves(player_hand, t.last_played, plugins).contains(&(player_hand.len() - 1))
+                            && (force_play || play_drawn_ai(drawed, t.difficulties[current_idx], t.uno_detection_panic, &mut t.ai_rand));
+                        card_selected = play_it.then(|| play_drawn(player_hand, &mut t.discard, fe, player, true));
+                        break;
+                    }
+                }
+            }
+            else {
+        
+    
+                answer = input(fe, Prompt::Action { player }, "Please enter a card that you have!")?;
+                // Names are kept the way they were typed
+                let typed = answer.trim().to_string();
+                
+                answer = answer.to_lowercase();
+                
+                // If the player wants to draw a card, then draw a card
+                if answer == "draw" || answer == "d" {
+                    
+                    if force_play && !legal_moves(player_hand, t.last_played, plugins).is_empty() {
+                        fe.event(&Event::InvalidInput(String::from("You have a card you can play, force play is on!")));
+                        continue;
+                    }
+                
+                    if player_hand.len() == 1 && t.uno_detection_panic {
+                        t.uno_detection_panic = false;
+                    }
+                
+                    ensure_deck_full(&mut t.deck, &mut t.discard, t.rules.deck(), &mut t.rand, fe);
+                    
+                    let drawed: UNOCard = t.deck.pop().ok_or("Error, out of cards")?;
+                    player_hand.push(drawed);
+                    drew = true;
+                    note_draw(&mut t.lacking[current_idx], Some(t.last_played.color));
+                    fe.event(&Event::Drew { player, card: Some(drawed) });
+                    
+                    if t.rules.draw_one {
+                        // Force play doesn't leave a choice here either, or a moment to call UNO, so it's called for them
+                        let play_it = legal_moves(player_hand, t.last_played, plugins).contains(&(player_hand.len() - 1)) && (force_play || loop {
+                            let answer: String = input(fe, Prompt::PlayDrawn { player }, "Please enter y or n")?;
+                            let (answer, call) = uno_call(&answer);
+                            match answer.parse::<YesNo>() {
+                                Ok(YesNo(yes)) => {
+                                    called = call;
+                                    break yes;
+                                },
+                                Err(e) => fe.event(&Event::InvalidInput(format!("Error: Please enter y or n ({})", e))),
+                            }
+                        });
+                        called |= play_it && force_play;
+                        card_selected = play_it.then(|| play_drawn(player_hand, &mut t.discard, fe, player, false));
+                        break;
+                    }
+                    continue;
+                // Trade a card for the top of the deck, "t 3" or "trade 3"
+                } else if let Some(number) = answer.strip_prefix("t ").or_else(|| answer.strip_prefix("trade ")) {
+                    let refusal = if !t.rules.trade {
+                        Some("Trading with the deck is off for this game")
+                    } else if t.traded[current_idx] {
+                        Some("You've already traded with the deck this game")
+                    } else if drew {
+                        Some("Trading takes the whole turn, it can't come after a draw")
+                    } else if force_play && !legal_moves(player_hand, t.last_played, plugins).is_empty() {
+                        Some("You have a card you can play, force play is on!")
+                    } else {
+                        None
+                    };
+                    if let Some(refusal) = refusal {
+                        fe.event(&Event::InvalidInput(String::from(refusal)));
+                        continue;
+                    }
+                    match number.trim().parse::<usize>() {
+                        Ok(n) if (1..=player_hand.len()).contains(&n) => {
+                            trade = Some(n - 1);
+                            card_selected = None;
+                            break;
+                        },
+                        _ => fe.event(&Event::InvalidInput(String::from("Please enter the number of the card to trade, like t 3"))),
+                    }
+                    continue;
+                // Display the last played card and the player's hand
+                } else if answer == "s" || answer == "see" {
+                    
+                    fe.event(&Event::LastCard(t.last_played));
+                    fe.event(&Event::Hand(player_hand.clone()));
+                    continue;
+                // Save the game as it was when this turn started
+                } else if let Some(slot) = answer.strip_prefix("save ") {
+                    save_turn(fe, slot.trim());
+                    continue;
+                // Esc comes through a plain prompt as the escape character itself
+                } else if answer == "menu" || answer == "m" || answer == "\u{1b}" {
+                    if menu(fe, player, &t.rules)? {
+                        fe.event(&Event::Notice(format!("{} forfeits the game", t.names[current_idx])));
+                        return Err(Box::new(Forfeited(player)));
+                    }
+                    fe.event(&Event::LastCard(t.last_played));
+                    fe.event(&Event::Hand(player_hand.clone()));
+                    continue;
+                // The AI plays this turn and every one after it, "takeover expert" for a certain one
+                } else if let Some(difficulty) = answer.strip_prefix("takeover").filter(|d| d.is_empty() || d.starts_with(' ')) {
+                    let difficulty = match difficulty.trim() {
+                        "" => Difficulty::Skilled,
+                        name => match name.parse() {
+                            Ok(difficulty) => difficulty,
+                            Err(e) => {
+                                fe.event(&Event::InvalidInput(e));
+                                continue;
+                            },
+                        },
+                    };
+                    let message = take_over(t, current_idx, difficulty);
+                    fe.event(&Event::Notice(message));
+                    if difficulty == Difficulty::Expert && !drew {
+                        let seed = t.ai_rand.next_u64();
+                        expert = expert_move(t, seed);
+                    }
+                    is_ai = true;
+                    player_hand = &mut t.hands[current_idx];
+                    continue;
+                // A late arrival takes an AI seat, "join 3" or "join 3 maria"
+                } else if answer.starts_with("join ") {
+                    let mut words = typed.split_whitespace().skip(1);
+                    let seat = words.next().unwrap_or_default();
+                    let name = words.collect::<Vec<_>>().join(" ");
+                    let joined = match seat.parse::<usize>() {
+                        Ok(seat) if seat > 0 => join(t, seat - 1, Some(name.as_str()).filter(|n| !n.is_empty())),
+                        _ => Err(String::from("Please enter the number of the seat to join, like join 3")),
+                    };
+                    match joined {
+                        Ok(message) => fe.event(&Event::Notice(message)),
+                        Err(e) => fe.event(&Event::InvalidInput(e)),
+                    }
+                    player_hand = &mut t.hands[current_idx];
+                    continue;
+                // Every play ranked the way the Expert AI sees it
+                } else if answer == "suggest" {
+                    for line in suggestion_lines(t) {
+                        fe.event(&Event::Notice(line));
+                    }
+                    player_hand = &mut t.hands[current_idx];
+                    continue;
+                // How likely the others are to hold each color, going only by what the seat can see
+                } else if answer == "analyze" || answer == "a" {
+                    for line in analysis(t, current_idx) {
+                        fe.event(&Event::Notice(line));
+                    }
+                    player_hand = &mut t.hands[current_idx];
+                    continue;
+                // --debug commands, see debug.rs, which can change any hand and the top card
+                } else if debug::enabled() && debug::is_command(&answer) {
+                    match debug::run(t, &answer) {
+                        Ok(reply) => fe.event(&Event::Notice(reply)),
+                        Err(e) => fe.event(&Event::InvalidInput(e)),
+                    }
+                    player_hand = &mut t.hands[current_idx];
+                    fe.event(&Event::LastCard(t.last_played));
+                    fe.event(&Event::Hand(player_hand.clone()));
+                    continue;
+                }
+                // Parse the answer, a card can be named instead of numbered ("red 5", "wild +4"), with UNO called
+                // along with it ("3 uno")
+                if answer == "uno" {
+                    fe.event(&Event::InvalidInput(String::from("Call UNO with the card that gets you down to one, like 3 uno")));
+                    continue;
+                }
+                let (answer, call) = uno_call(&answer);
+                let named = answer.parse::<UNOCard>().ok().and_then(|card| card_in_hand(player_hand, card));
+                let Some(answer_usize) = answer.trim().parse::<usize>().ok().or(named) else {
+                    fe.event(&Event::InvalidInput(String::from("Please enter the number or name of a card you have, draw, see or menu")));
+                    continue; 
+                };
+                
+                // Ensure the answer is within the bounds of the player's hand
+                if answer_usize == 0 {
+                    fe.event(&Event::InvalidInput(String::from("Please enter a card that you can use")));
+                    continue;
+                }
+                
+                let answer_usize = answer_usize - 1; // Zero indexing fix
+                
+                // Check if the card is valid
+                if answer_usize >= player_hand.len() {
+                    fe.event(&Event::InvalidInput(String::from("Please enter a card that you have!\n")));
+                } else if !legal_moves(player_hand, t.last_played, plugins).contains(&answer_usize) {
+                    fe.event(&Event::IllegalMove(player_hand[answer_usize]));
+                    
+                    // Some tables make trying cost cards, and then it's still their turn
+                    if t.rules.illegal_penalty > 0 {
+                        if player_hand.len() == 1 && t.uno_detection_panic {
+                            t.uno_detection_panic = false;
+                        }
+                        for _ in 0..t.rules.illegal_penalty {
+                            ensure_deck_full(&mut t.deck, &mut t.discard, t.rules.deck(), &mut t.rand, fe);
+                            let drawed: UNOCard = t.deck.pop().ok_or("Error, out of cards")?;
+                            player_hand.push(drawed);
+                            fe.event(&Event::ForcedDraw { player, card: Some(drawed) });
+                        }
+                        note_draw(&mut t.lacking[current_idx], None);
+                    }
+                } 
+                // If the card is valid, then play it
+                else {
+                    card_selected = Some(player_hand[answer_usize]);
+                    t.discard.push( card_selected.unwrap() );
+                    player_hand.remove(answer_usize);
+                    called = call;
+                    fe.event(&Event::Played { player, ai: false, card: card_selected.unwrap() });
+                    break;
+                }
+            }
+        }
+        
+        // The card traded away goes on top of the deck for whoever draws next, and the player gets what was there
+        // Nobody at the table knows what that is, so the colors they were lacking could be anything now
+        if let Some(index) = trade {
+            ensure_deck_full(&mut t.deck, &mut t.discard, t.rules.deck(), &mut t.rand, fe);
+            let got: UNOCard = t.deck.pop().ok_or("Error, out of cards")?;
+            let gave = std::mem::replace(&mut player_hand[index], got);
+            t.deck.push(gave);
+            t.traded[current_idx] = true;
+            note_draw(&mut t.lacking[current_idx], None);
+            fe.event(&Event::Traded { player, gave, got: if is_ai { None } else { Some(got) } });
+        }
+        
+        // Wild cards get their color here, what every card does to the next turns is up to the rules
+        if let Some(card) = card_selected {
+            t.last_played = card;
+            turn.played(card.special);
+
+            // A house card can name its color too
+            if card.special == SpecialCard::PlusFour || card.special == SpecialCard::ColorChange || t.rules.effect(&card).choose_color {
+                if is_ai {
+                    t.last_played.color = match expert_color {
+                        Some(color) if color != Color::NA => color,
+                        _ => pick_color_ai(player_hand, colors(t.rules.colors), t.difficulties[current_idx], Some(around), &mut t.ai_rand),
+                    };
+                }
+                else {
+                    // Purple and orange are only there when the deck has them
+                    t.last_played.color = loop {
+                        let chosen_color: Color = input(fe, Prompt::Color { player }, "Please enter an UNO color")?;
+                        if colors(t.rules.colors).contains(&chosen_color) {
+                            break chosen_color;
+                        }
+                        fe.event(&Event::InvalidInput(format!("There's no {} in this deck", get_color(&chosen_color).to_lowercase())));
+                    };
+                }
+                fe.event(&Event::ColorChosen { player, color: t.last_played.color });
+            }
+            t.history.push(t.last_played);
+            t.lacking[current_idx].retain(|c| *c != card.color);
+        }
+        
+        // If the player has a countercard but decided not to use it, then they draw at the end of the turn
+        for _ in 0..turn.end(countercards, card_selected.map(|c| c.special)) {
+            ensure_deck_full(&mut t.deck, &mut t.discard, t.rules.deck(), &mut t.rand, fe);
+            let drawed: UNOCard = t.deck.pop().ok_or("Error, out of cards")?;
+            player_hand.push(drawed);
+            fe.event(&Event::ForcedDraw { player, card: if is_ai { None } else { Some(drawed) } });
+            note_draw(&mut t.lacking[current_idx], None);
+        }
+        
+        // The rest of what a house card does, hands only move on one that didn't win the game
+        let mut moved = false;
+        if let Some(card) = card_selected {
+            let effect = t.rules.effect(&card);
+            turn.house_card(effect);
+            if !player_hand.is_empty() {
+                move_hands(t, fe, effect, current_idx);
+                moved = effect.swap || effect.rotate;
+            }
+            for plugin in plugins.iter_mut() {
+                plugin.on_card_played(t, card, &mut turn);
+            }
+        }
+        
+        // UNO! Under uno-penalty a human has to call it with the card that got them down to one, forgetting is caught
+        // right away. The AIs never forget, and it's called for everybody without the rule or when the hands moved
+        if is_uno(&t.hands[current_idx]) {
+            let forgot = t.rules.uno_penalty > 0 && !is_ai && !called && card_selected.is_some() && !moved;
+            if forgot {
+                let penalty = t.rules.uno_penalty;
+                fe.event(&Event::Notice(format!("{} didn't call UNO and draws {}", t.names[current_idx], penalty)));
+                forced_draw(t, fe, current_idx, penalty as u32, true)?;
+            } else {
+                t.uno_detection_panic = true;
+                fe.event(&Event::Uno { player });
+            }
+        }
+        let player_hand = &t.hands[current_idx];
+        
+        // Exit the loop if a player has won (no cards left)
+        let won = has_won(player_hand);
+        t.set_turn_state(turn);
+        invariants::after_turn(t);
+        if won {
+            fe.event(&Event::Win { player });
+            return Ok(Some(player));
+        }
+        
+        // Clear the terminal and move to the next turn
+        let _: String = input(fe, Prompt::Continue, "Error")?;
+        
+        
+        t.game_state.next_turn();
+    }
+    
+    Ok(None)
+}
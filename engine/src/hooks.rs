@@ -0,0 +1,136 @@
+// Scoring hooks: a club's own scoring or ranking plugs in here instead of into a fork of the game
+// Event callbacks see every event as it happens, in the same JSON as --json prints, and game end callbacks get one
+// summary per game once it's over, won, forfeited or stopped
+//
+//     let fe = Hooks::new(inner)
+//         .on_event(|event| println!("{}", event))
+//         .on_game_end(|result| println!("{}", result));
+
+use std::path::PathBuf;
+
+use crate::cards::Hand;
+use crate::events::{Event, Frontend, Prompt, event_json};
+use crate::game::{Table, difficulty_name, round_points};
+use crate::json::Json;
+
+type Callback = Box<dyn FnMut(&Json)>;
+
+pub struct Hooks {
+    inner: Box<dyn Frontend>,
+    on_event: Vec<Callback>,
+    on_game_end: Vec<Callback>,
+    turns: usize, // Since the last game ended
+}
+
+impl Hooks {
+    pub fn new(inner: Box<dyn Frontend>) -> Self {
+        Hooks { inner, on_event: Vec::new(), on_game_end: Vec::new(), turns: 0 }
+    }
+
+    pub fn on_event(mut self, callback: impl FnMut(&Json) + 'static) -> Self {
+        self.on_event.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_game_end(mut self, callback: impl FnMut(&Json) + 'static) -> Self {
+        self.on_game_end.push(Box::new(callback));
+        self
+    }
+}
+
+// Everything a scoring system should need about a finished game, the seats in seat order
+// Points are the official scoring, what every hand left is worth, and go to the winner
+pub fn game_result(t: &Table, winner: Option<usize>, turns: usize) -> Json {
+    let seats = t.hands.iter().enumerate().map(|(i, hand)| Json::object(vec![
+        ("seat", (i + 1).into()),
+        ("name", t.names[i].as_str().into()),
+        ("ai", if i < t.players as usize { Json::Null } else { difficulty_name(t.difficulties[i]).into() }),
+        ("cards", hand.len().into()),
+        ("points", (hand.points() as usize).into()),
+    ]));
+    Json::object(vec![
+        ("winner", winner.map_or(Json::Null, Json::from)),
+        ("points", winner.map_or(0, |_| round_points(t) as usize).into()),
+        ("turns", turns.into()),
+        ("seed", t.seed.map_or(Json::Null, |s| s.to_string().into())),
+        ("rules", t.rules.to_json()),
+        ("seats", Json::Array(seats.collect())),
+    ])
+}
+
+impl Frontend for Hooks {
+    fn event(&mut self, event: &Event) {
+        if let Event::TurnStart { .. } = event {
+            self.turns += 1;
+        }
+        if !self.on_event.is_empty() {
+            let json = event_json(event);
+            self.on_event.iter_mut().for_each(|callback| callback(&json));
+        }
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        let result = game_result(table, winner, std::mem::take(&mut self.turns));
+        self.on_game_end.iter_mut().for_each(|callback| callback(&result));
+        self.inner.game_over(table, winner);
+    }
+
+    fn paused(&self) -> bool {
+        self.inner.paused()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::play;
+    use crate::testing::{ScriptedFrontend, TestGame};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn callbacks_hear_every_event_and_one_result() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let (e, r) = (events.clone(), results.clone());
+        let mut fe = Hooks::new(Box::new(ScriptedFrontend::default()))
+            .on_event(move |event| e.borrow_mut().push(event.clone()))
+            .on_game_end(move |result| r.borrow_mut().push(result.clone()));
+
+        let mut game = TestGame::new(2)
+            .hand(0, &["red 5"])
+            .hand(1, &["green 3", "blue 9"])
+            .top("red 1")
+            .ai_from(0, crate::game::Difficulty::Calm)
+            .build();
+        let winner = play(game.table_mut(), &mut fe, None).unwrap();
+        fe.game_over(game.table(), winner);
+
+        assert!(events.borrow().iter().any(|e| e.get("event").and_then(|t| t.as_str()) == Some("win")));
+        let results = results.borrow();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].get("winner"), Some(&Json::from(1)));
+        assert_eq!(results[0].get("points"), Some(&Json::from(12)));
+        assert_eq!(results[0].get("turns"), Some(&Json::from(1)));
+        let seats = results[0].get("seats").and_then(|s| s.as_array()).unwrap();
+        assert_eq!(seats[1].get("cards"), Some(&Json::from(2)));
+        assert_eq!(seats[0].get("ai"), Some(&Json::from("calm")));
+    }
+}
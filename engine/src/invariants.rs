@@ -0,0 +1,89 @@
+// Sanity checks on the table after every turn, turned on with --check-invariants
+// A failed check panics on purpose, so the crash recovery file keeps the game and the events that led up to it
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cards::{Color, MAX_NUMBER, SpecialCard, UNOCard, card_at, deck_size};
+use crate::game::Table;
+use crate::rules::RuleSet;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+fn is_wild(card: &UNOCard) -> bool {
+    card.special == SpecialCard::ColorChange || card.special == SpecialCard::PlusFour
+}
+
+// Wilds only get a color while they're the active card, and a house card that names one is played as that color
+fn uncolored(rules: &RuleSet, card: &UNOCard) -> UNOCard {
+    let mut card = *card;
+    if is_wild(&card) || rules.effect(&card).choose_color {
+        card.color = Color::NA;
+    }
+    card
+}
+
+fn check_card(card: &UNOCard, active: bool) -> Result<(), String> {
+    let ok = match card.special {
+        _ if is_wild(card) => (card.color != Color::NA) == active && card.number == -1,
+        SpecialCard::Base => card.color != Color::NA && (0..=MAX_NUMBER).contains(&card.number),
+        _ => card.color != Color::NA && card.number == -1,
+    };
+    if ok { Ok(()) } else { Err(format!("impossible card {:?}", card)) }
+}
+
+pub fn check(t: &Table) -> Result<(), String> {
+    if t.hands.len() != t.game_state.max_players || t.lacking.len() != t.hands.len() || t.game_state.current_player >= t.game_state.max_players {
+        return Err(format!("turn order is off: player index {} of {} hands", t.game_state.current_player, t.hands.len()));
+    }
+
+    // Every card comes from a full deck, and the only cards that leave the game are none
+    // Big tables start with several decks and new ones get added when everything is in people's hands
+    let uncolored = |card: &UNOCard| uncolored(&t.rules, card);
+    let mut cards: Vec<UNOCard> = t.hands.iter().flatten().chain(&t.deck).chain(&t.discard).map(uncolored).collect();
+    let size = deck_size(t.rules.deck());
+    let decks = cards.len() / size;
+    let mut expected: Vec<UNOCard> = (0..(decks * size) as u32).map(|slot| uncolored(&card_at(slot, t.rules.deck()))).collect();
+    cards.sort();
+    expected.sort();
+    if !cards.len().is_multiple_of(size) || cards != expected {
+        return Err(format!("{} cards are in play, which isn't a whole number of full decks", cards.len()));
+    }
+
+    for (i, hand) in t.hands.iter().enumerate() {
+        for card in hand {
+            check_card(card, false).map_err(|e| format!("{} in Player #{}'s hand", e, i + 1))?;
+        }
+    }
+    for card in t.deck.iter().chain(&t.discard) {
+        check_card(card, false).map_err(|e| format!("{} in the deck or the discard pile", e))?;
+    }
+    check_card(&t.last_played, true).map_err(|e| format!("{} on top of the pile", e))?;
+    if t.discard.last().map(uncolored) != Some(uncolored(&t.last_played)) {
+        return Err(format!("the last played card {:?} isn't on top of the discard pile", t.last_played));
+    }
+    // The pile is whatever was played since the last reshuffle
+    let since_reshuffle = t.history.len().checked_sub(t.discard.len()).map(|start| &t.history[start..]);
+    if t.history.last() != Some(&t.last_played) || since_reshuffle.is_none_or(|played| !played.iter().map(uncolored).eq(t.discard.iter().map(uncolored))) {
+        return Err(format!("the history of {} played cards doesn't end with the discard pile", t.history.len()));
+    }
+
+    // Only +2s, +4s and house cards that make the next player draw add to the queue, an odd house draw makes for odd queues
+    let draws = |card: &UNOCard| matches!(card.special, SpecialCard::PlusTwo | SpecialCard::PlusFour) || t.rules.effect(card).draw > 0;
+    let odd = t.rules.cards.iter().any(|effect| effect.draw % 2 == 1);
+    if (!odd && !t.add_queue.is_multiple_of(2)) || (t.add_queue > 0 && !draws(&t.last_played)) {
+        return Err(format!("{} cards are queued up after a {:?}", t.add_queue, t.last_played.special));
+    }
+
+    Ok(())
+}
+
+// Called by the game loop at the end of every turn
+pub fn after_turn(t: &Table) {
+    if ENABLED.load(Ordering::Relaxed) && let Err(e) = check(t) {
+        panic!("Invariant broken after Player #{}'s turn: {}", t.game_state.player_number(), e);
+    }
+}
@@ -0,0 +1,44 @@
+// The parts of UNOler that are useful outside the game binary, published as the unoler-engine crate
+// Everything but the command line, config and save files lives here, so other programs and tests can drive a game
+//
+// The names re-exported at the bottom are the stable API: a Session to deal and play a game one answer at a time,
+// or setup and play with a Frontend of your own, plus the cards, rules and events they talk in. They only change
+// incompatibly with a new 0.x minor version. The modules stay public for the game binary and the server, but
+// anything reached through them alone can change in any release
+
+pub mod ai;
+pub mod cards;
+pub mod debug;
+pub mod effects;
+pub mod events;
+pub mod game;
+pub mod hooks;
+pub mod invariants;
+pub mod json;
+pub mod log;
+pub mod notation;
+pub mod odds;
+pub mod persist;
+pub mod plugins;
+pub mod pool;
+pub mod repl;
+pub mod review;
+pub mod rng;
+pub mod rules;
+pub mod search;
+pub mod session;
+pub mod stack;
+pub mod trace;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod testing;
+
+pub use cards::{Color, SpecialCard, UNOCard};
+pub use events::{Event, Frontend, Prompt};
+pub use game::{Difficulty, Forfeited, Settings, Table, play, setup};
+pub use json::Json;
+pub use rules::{Handicap, RuleSet};
+pub use session::{Session, settings_from_json};
+pub use stack::Stack;
@@ -0,0 +1,96 @@
+// Leveled logging for what the engine is doing behind the events: reshuffles, AI decisions, random draws
+// Nothing is logged below Warn unless asked for (-v, -vv, -vvv), and it goes to stderr or a --log-file so it never
+// mixes with stdout, which JSON and batch mode keep for themselves
+//
+//     unoler::log!(Level::Debug, "{} cards left in the deck", deck.len());
+
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    // -v is Info, -vv Debug and anything more Trace
+    pub fn from_verbosity(count: u8) -> Level {
+        match count {
+            0 => Level::Warn,
+            1 => Level::Info,
+            2 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+static FILE: Mutex<Option<File>> = Mutex::new(None);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+// Checked before anything gets formatted, so a quiet game pays next to nothing for its log lines
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+// Everything from now on goes to the file instead of stderr
+pub fn log_to(file: File) {
+    *FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+}
+
+pub fn write(level: Level, args: fmt::Arguments) {
+    let line = format!("[{}] {}", level.name(), args);
+    let mut file = FILE.lock().unwrap_or_else(|e| e.into_inner());
+    match file.as_mut() {
+        // A full disk shouldn't take the game down with it
+        Some(file) => {
+            let _ = writeln!(file, "{}", line);
+        },
+        None => eprintln!("{}", line),
+    }
+}
+
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::log::enabled($level) {
+            $crate::log::write($level, format_args!($($arg)*));
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_turns_levels_on() {
+        assert_eq!(Level::from_verbosity(0), Level::Warn);
+        assert_eq!(Level::from_verbosity(2), Level::Debug);
+        assert_eq!(Level::from_verbosity(9), Level::Trace);
+        // The default, which every other test runs with
+        assert!(enabled(Level::Warn));
+        assert!(!enabled(Level::Info));
+        assert!(Level::Error < Level::Trace);
+    }
+}
@@ -0,0 +1,797 @@
+// Game notation: a whole game as a few lines of text, like PGN for chess, for sharing and archiving games
+// Tags first, then the deal, then every turn with the moves made in it:
+//
+//     [Format "UGN 1"]
+//     [Seed "10"]
+//     [Rules "stacking=on force-play=off hand-size=3"]
+//     [Seat1 "human Ana"]
+//     [Seat2 "calm Player #2"]
+//     [Result "1"]
+//
+//     1: R8 G2 W
+//     2: B+2 YS Y7
+//     Top: B4
+//     Pile: G5 RR B0 ...
+//
+//     1.p1 D R4 2.p2 B+2 3.p1 - 4.p2 YS 5.p1 W:Y ...
+//
+// A card is its color (R, G, Y or B) and then a number (R12 under highest-number), S for skip, R for reverse or +2.
+// Wilds are W and W+4, with the color picked for them after a colon. D draws a card, T and a card trades it with the
+// deck (TR5), and - is a turn with nothing to choose (skipped or drawing a penalty). Under an illegal play penalty,
+// ! and a card is one that was tried and didn't fit (!R5). Under uno-penalty UNO follows the card UNO was called with
+// (R5 UNO), and under challenge-penalty C starts a turn that challenged the +4 played on it. The pile is top first.
+// Other tags are allowed and ignored, like [Date "..."]
+//
+// The deal is written out in full so reading a game doesn't depend on how a version shuffles, the seed is only
+// there for shuffling the discard pile back in when the pile runs out
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cards::{Color, MAX_NUMBER, SpecialCard, UNOCard, get_color};
+use crate::events::{Event, Frontend, Prompt};
+use crate::game::{Difficulty, Settings, Table, difficulty_name, setup};
+use crate::invariants;
+use crate::rules::{Handicap, RuleSet, TurnState};
+use crate::session::Session;
+
+const FORMAT: &str = "UGN 1";
+
+// Movetext gets wrapped around this width, like PGN's 80 but the tokens are short
+const LINE_WIDTH: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Move {
+    Play(UNOCard), // A wild has the color that was picked for it
+    Draw,
+    Trade(UNOCard), // The card given for the top of the deck
+    Refused(UNOCard), // Tried and didn't fit, only written down when that costs cards
+    Uno, // Called with the card before it, only written down when forgetting costs cards
+    Challenge, // The +4 played on this turn was called a bluff
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    pub seat: usize, // 1 based
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notation {
+    pub seed: Option<u64>,
+    pub rules: RuleSet,
+    pub seats: Vec<(String, Option<Difficulty>)>, // Names and the AI playing them, None for humans
+    pub handicaps: Vec<Handicap>, // One per seat, or none at all without any
+    pub hands: Vec<Vec<UNOCard>>,
+    pub top: UNOCard, // The opening card, colored if it's a wild
+    pub pile: Vec<UNOCard>, // Top first
+    pub turns: Vec<Turn>,
+    pub result: Option<usize>, // None for games stopped before anybody won
+}
+
+fn is_wild(card: &UNOCard) -> bool {
+    card.special == SpecialCard::ColorChange || card.special == SpecialCard::PlusFour
+}
+
+fn color_letter(color: Color) -> &'static str {
+    match color {
+        Color::Red => "R",
+        Color::Green => "G",
+        Color::Yellow => "Y",
+        Color::Blue => "B",
+        Color::Purple => "P",
+        Color::Orange => "O",
+        Color::NA => "",
+    }
+}
+
+pub fn card_code(card: &UNOCard) -> String {
+    let color = color_letter(card.color);
+    match card.special {
+        SpecialCard::Base => format!("{}{}", color, card.number),
+        SpecialCard::Skip => format!("{}S", color),
+        SpecialCard::Reverse => format!("{}R", color),
+        SpecialCard::PlusTwo => format!("{}+2", color),
+        SpecialCard::ColorChange if card.color == Color::NA => String::from("W"),
+        SpecialCard::ColorChange => format!("W:{}", color),
+        SpecialCard::PlusFour if card.color == Color::NA => String::from("W+4"),
+        SpecialCard::PlusFour => format!("W+4:{}", color),
+    }
+}
+
+pub fn parse_card_code(code: &str) -> Result<UNOCard, String> {
+    let bad = || format!("{} is not a card", code);
+    let upper = code.trim().to_uppercase();
+    let color = |letter: &str| match letter {
+        "R" => Ok(Color::Red),
+        "G" => Ok(Color::Green),
+        "Y" => Ok(Color::Yellow),
+        "B" => Ok(Color::Blue),
+        "P" => Ok(Color::Purple),
+        "O" => Ok(Color::Orange),
+        _ => Err(bad()),
+    };
+
+    let (special, rest) = if let Some(rest) = upper.strip_prefix("W+4") {
+        (SpecialCard::PlusFour, rest)
+    } else if let Some(rest) = upper.strip_prefix('W') {
+        (SpecialCard::ColorChange, rest)
+    } else {
+        let (letter, rest) = upper.split_at_checked(1).ok_or_else(bad)?;
+        let color = color(letter)?;
+        return match rest {
+            "S" => Ok(UNOCard::new(color, SpecialCard::Skip, -1)),
+            "R" => Ok(UNOCard::new(color, SpecialCard::Reverse, -1)),
+            "+2" => Ok(UNOCard::new(color, SpecialCard::PlusTwo, -1)),
+            n => match n.parse::<i8>() {
+                Ok(n) if (0..=MAX_NUMBER).contains(&n) && n.to_string() == rest => Ok(UNOCard::new(color, SpecialCard::Base, n)),
+                _ => Err(bad()),
+            },
+        };
+    };
+    match rest {
+        "" => Ok(UNOCard::new(Color::NA, special, -1)),
+        _ => Ok(UNOCard::new(color(rest.strip_prefix(':').ok_or_else(bad)?)?, special, -1)),
+    }
+}
+
+fn codes(cards: &[UNOCard]) -> String {
+    cards.iter().map(card_code).collect::<Vec<_>>().join(" ")
+}
+
+fn parse_codes(text: &str) -> Result<Vec<UNOCard>, String> {
+    text.split_whitespace().map(parse_card_code).collect()
+}
+
+// Tag values are quoted, with \" and \\ inside like PGN
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        out.push(if c == '\\' { chars.next()? } else { c });
+    }
+    Some(out)
+}
+
+impl Notation {
+    // `dealt` is the table as setup left it, `events` everything the game did after that
+    pub fn from_game(dealt: &Table, events: &[Event]) -> Notation {
+        let mut turns: Vec<Turn> = Vec::new();
+        let mut result = None;
+        for event in events {
+            match event {
+                Event::TurnStart { player, .. } => turns.push(Turn { seat: *player, moves: Vec::new() }),
+                Event::Played { card, .. } => turns.last_mut().into_iter().for_each(|t| t.moves.push(Move::Play(*card))),
+                Event::Drew { .. } => turns.last_mut().into_iter().for_each(|t| t.moves.push(Move::Draw)),
+                Event::Traded { gave, .. } => turns.last_mut().into_iter().for_each(|t| t.moves.push(Move::Trade(*gave))),
+                Event::IllegalMove(card) if dealt.rules.illegal_penalty > 0 => {
+                    turns.last_mut().into_iter().for_each(|t| t.moves.push(Move::Refused(*card)));
+                },
+                Event::Uno { .. } if dealt.rules.uno_penalty > 0 => turns.last_mut().into_iter().for_each(|t| t.moves.push(Move::Uno)),
+                Event::Challenge { .. } => turns.last_mut().into_iter().for_each(|t| t.moves.push(Move::Challenge)),
+                // Only a wild is written with its color, a house card that names one keeps its own
+                Event::ColorChosen { color, .. } => {
+                    if let Some(Move::Play(card)) = turns.last_mut().and_then(|t| t.moves.last_mut()) && is_wild(card) {
+                        card.color = *color;
+                    }
+                },
+                Event::Win { player } => result = Some(*player),
+                _ => {},
+            }
+        }
+
+        Notation {
+            seed: dealt.seed,
+            rules: dealt.rules,
+            seats: dealt.names.iter().enumerate()
+                .map(|(i, name)| (name.clone(), (i >= dealt.players as usize).then(|| dealt.difficulties[i])))
+                .collect(),
+            handicaps: if dealt.handicaps.iter().any(|h| !h.is_none()) { dealt.handicaps.clone() } else { Vec::new() },
+            hands: dealt.hands.clone(),
+            top: dealt.last_played,
+            pile: dealt.deck.iter().rev().copied().collect(),
+            turns,
+            result,
+        }
+    }
+
+    // The dealt table, with every seat played from the moves instead of by a person or an AI
+    pub fn table(&self) -> Result<Table, String> {
+        if is_wild(&self.top) && self.top.color == Color::NA {
+            return Err(format!("The top card {} needs the color it was given, like W:R", card_code(&self.top)));
+        }
+        let settings = Settings {
+            players: Some(u8::try_from(self.seats.len()).map_err(|_| "There are too many seats")?),
+            ai_players: Some(0),
+            difficulty: Some(Difficulty::Calm),
+            names: self.seats.iter().map(|(name, _)| name.clone()).collect(),
+            rules: self.rules,
+            seed: self.seed,
+            ..Settings::default()
+        };
+        // Setup is only for everything around the cards, which come from the deal
+        let mut table = setup(&mut Quiet, &settings).map_err(|e| e.to_string())?.ok_or("The game has no seats")?;
+
+        let mut opening = self.top;
+        if is_wild(&opening) {
+            opening.color = Color::NA;
+        }
+        table.hands = self.hands.clone();
+        table.handicaps = (0..self.seats.len()).map(|i| self.handicaps.get(i).copied().unwrap_or_default()).collect();
+        table.deck = self.pile.iter().rev().copied().collect();
+        table.discard = vec![opening];
+        table.history = vec![self.top];
+        table.last_played = self.top;
+        table.set_turn_state(TurnState::opening(&self.rules, self.top.special, self.seats.len(), 0));
+        table.difficulties = self.seats.iter().map(|(_, d)| d.unwrap_or(Difficulty::Calm)).collect();
+        invariants::check(&table).map_err(|e| format!("The deal doesn't work: {}", e))?;
+        Ok(table)
+    }
+
+    // Plays the moves on this version of the engine and checks every one of them is allowed and the result is the same
+    pub fn replay(&self) -> Result<Session, String> {
+        let mut session = Session::from_table(self.table()?)?;
+
+        for (n, turn) in self.turns.iter().enumerate() {
+            let at = format!("Turn {}.p{}", n + 1, turn.seat);
+            let mut drew = false;
+            // A +4 played on the turn before is asked about first, whether or not it was challenged
+            let challenged = turn.moves.first() == Some(&Move::Challenge);
+            let asked = matches!(session.waiting(), Some(Prompt::Challenge { player }) if player == turn.seat);
+            match (asked, challenged) {
+                (true, _) => act(&mut session, if challenged { "y" } else { "n" }, &at)?,
+                (false, true) => return Err(format!("{}: there's no +4 to challenge", at)),
+                (false, false) => {},
+            }
+            for (k, mv) in turn.moves.iter().enumerate() {
+                // Wilds are in the hand without a color
+                let plain = match mv {
+                    Move::Play(card) if is_wild(card) => Some(UNOCard { color: Color::NA, ..*card }),
+                    Move::Play(card) => Some(*card),
+                    Move::Draw | Move::Trade(_) | Move::Refused(_) => None,
+                    // Gone with the card before, or at the start of the turn
+                    Move::Uno | Move::Challenge => continue,
+                };
+                let call = if turn.moves.get(k + 1) == Some(&Move::Uno) { " uno" } else { "" };
+
+                if self.rules.draw_one && drew {
+                    // Under draw-one only the card just drawn can follow a draw, the game asks about it or puts it
+                    // down by itself under force play
+                    let drawn = session.current().hands[turn.seat - 1].last().copied();
+                    let asked = matches!(session.waiting(), Some(Prompt::PlayDrawn { player }) if player == turn.seat);
+                    if asked && plain.is_some() && drawn == plain {
+                        act(&mut session, &format!("y{}", call), &at)?;
+                    } else if asked || !self.rules.force_play || plain != Some(session.current().last_played) {
+                        return Err(format!("{}: only the card just drawn can be played after a draw", at));
+                    }
+                } else {
+                    // Turns play on by themselves until somebody has a choice, so this counts the ones in between
+                    let turn_starts = session.events().filter(|e| matches!(e, Event::TurnStart { .. })).count();
+                    match session.waiting() {
+                        Some(Prompt::Action { player }) if player == turn.seat && turn_starts == n + 1 => {},
+                        _ => return Err(format!("{}: the game is at turn {} and isn't asking that seat for a move", at, turn_starts)),
+                    }
+
+                    let answer = match (mv, plain) {
+                        (Move::Play(card), Some(wanted)) => {
+                            let hand = &session.current().hands[turn.seat - 1];
+                            let index = hand.iter().position(|c| *c == wanted)
+                                .ok_or(format!("{}: {} isn't in the hand", at, card_code(card)))?;
+                            format!("{}{}", index + 1, call)
+                        },
+                        (Move::Trade(card), _) => {
+                            let index = session.current().hands[turn.seat - 1].iter().position(|c| c == card)
+                                .ok_or(format!("{}: {} isn't in the hand to trade", at, card_code(card)))?;
+                            format!("t {}", index + 1)
+                        },
+                        (Move::Refused(card), _) => {
+                            let index = session.current().hands[turn.seat - 1].iter().position(|c| c == card)
+                                .ok_or(format!("{}: {} isn't in the hand", at, card_code(card)))?;
+                            refused(&mut session, &(index + 1).to_string(), &at)?;
+                            continue;
+                        },
+                        _ => String::from("d"),
+                    };
+                    act(&mut session, &answer, &at)?;
+                }
+                drew = *mv == Move::Draw;
+
+                if let Move::Play(card) = mv && is_wild(card) {
+                    if !matches!(session.waiting(), Some(Prompt::Color { player }) if player == turn.seat) {
+                        return Err(format!("{}: the game didn't ask for a color after {}", at, card_code(card)));
+                    }
+                    act(&mut session, &get_color(&card.color).to_lowercase(), &at)?;
+                } else if let Move::Play(card) = mv && matches!(session.waiting(), Some(Prompt::Color { player }) if player == turn.seat) {
+                    return Err(format!("{}: {} is a house card that names a color, which notation can't write down yet", at, card_code(card)));
+                }
+            }
+            // A draw with nothing after it kept the card
+            if matches!(session.waiting(), Some(Prompt::PlayDrawn { player }) if player == turn.seat) {
+                act(&mut session, "n", &at)?;
+            }
+        }
+
+        match (session.winner(), self.result) {
+            (None, Some(_)) if session.waiting().is_some() => Err(String::from("The moves ran out before anybody won")),
+            (winner, result) if winner != result => Err(format!(
+                "The game ended with {} winning, the notation says {}",
+                winner.map_or(String::from("nobody"), |w| format!("seat {}", w)),
+                result.map_or(String::from("nobody"), |w| format!("seat {}", w)),
+            )),
+            _ => Ok(session),
+        }
+    }
+}
+
+// Answers setup never asks for, the settings are complete
+struct Quiet;
+
+impl Frontend for Quiet {
+    fn event(&mut self, _event: &Event) {}
+
+    fn prompt(&mut self, _prompt: &Prompt) -> String {
+        String::new()
+    }
+}
+
+// A refused answer comes back as an event with the same question, which means the notation is wrong
+fn act(session: &mut Session, answer: &str, at: &str) -> Result<(), String> {
+    let before = session.events().count();
+    session.act(answer)?;
+    match session.events().skip(before).find(|e| matches!(e, Event::InvalidInput(_) | Event::IllegalMove(_))) {
+        Some(Event::IllegalMove(card)) => Err(format!("{}: {} can't be played there", at, card_code(card))),
+        Some(Event::InvalidInput(message)) => Err(format!("{}: {}", at, message.trim())),
+        _ => Ok(()),
+    }
+}
+
+// The other way around, a card written down as refused has to be
+fn refused(session: &mut Session, answer: &str, at: &str) -> Result<(), String> {
+    let before = session.events().count();
+    session.act(answer)?;
+    match session.events().skip(before).find(|e| matches!(e, Event::IllegalMove(_))) {
+        Some(_) => Ok(()),
+        None => Err(format!("{}: a card written as refused was allowed", at)),
+    }
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        writeln!(f, "[Format {}]", quote(FORMAT))?;
+        if let Some(seed) = self.seed {
+            writeln!(f, "[Seed {}]", quote(&seed.to_string()))?;
+        }
+        let rules = format!(
+            "stacking={} force-play={} hand-size={} opening-action={} draw-one={}",
+            on_off(self.rules.stacking), on_off(self.rules.force_play), self.rules.hand_size, on_off(self.rules.opening_action),
+            on_off(self.rules.draw_one),
+        ) + if self.rules.trade { " trade=on" } else { "" }
+            + &match self.rules.illegal_penalty {
+                0 => String::new(),
+                n => format!(" illegal-penalty={}", n),
+            }
+            + &match self.rules.uno_penalty {
+                0 => String::new(),
+                n => format!(" uno-penalty={}", n),
+            }
+            + &match self.rules.challenge_penalty {
+                0 => String::new(),
+                n => format!(" challenge-penalty={}", n),
+            }
+            + &match self.rules.colors {
+                4 => String::new(),
+                n => format!(" colors={}", n),
+            }
+            + &match self.rules.highest_number {
+                9 => String::new(),
+                n => format!(" highest-number={}", n),
+            }
+            + &self.rules.house_cards().map(|(n, effect)| format!(" card-{}={}", n, effect)).collect::<String>();
+        writeln!(f, "[Rules {}]", quote(&rules))?;
+        if !self.handicaps.is_empty() {
+            writeln!(f, "[Handicaps {}]", quote(&self.handicaps.iter().map(|h| h.to_string()).collect::<Vec<_>>().join(" ")))?;
+        }
+        for (i, (name, difficulty)) in self.seats.iter().enumerate() {
+            let kind = difficulty.map_or("human", difficulty_name);
+            writeln!(f, "[Seat{} {}]", i + 1, quote(&format!("{} {}", kind, name)))?;
+        }
+        let result = self.result.map_or(String::from("*"), |w| w.to_string());
+        writeln!(f, "[Result {}]\n", quote(&result))?;
+
+        for (i, hand) in self.hands.iter().enumerate() {
+            writeln!(f, "{}: {}", i + 1, codes(hand))?;
+        }
+        writeln!(f, "Top: {}", card_code(&self.top))?;
+        writeln!(f, "Pile: {}\n", codes(&self.pile))?;
+
+        let mut line = String::new();
+        for (n, turn) in self.turns.iter().enumerate() {
+            let mut item = format!("{}.p{}", n + 1, turn.seat);
+            if turn.moves.is_empty() {
+                item.push_str(" -");
+            }
+            for mv in &turn.moves {
+                item.push(' ');
+                item.push_str(&match mv {
+                    Move::Play(card) => card_code(card),
+                    Move::Draw => String::from("D"),
+                    Move::Trade(card) => format!("T{}", card_code(card)),
+                    Move::Refused(card) => format!("!{}", card_code(card)),
+                    Move::Uno => String::from("UNO"),
+                    Move::Challenge => String::from("C"),
+                });
+            }
+            if !line.is_empty() && line.len() + item.len() + 1 > LINE_WIDTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&item);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut seed = None;
+        let mut rules = RuleSet::default();
+        let mut seats: Vec<(usize, String, Option<Difficulty>)> = Vec::new();
+        let mut handicaps = Vec::new();
+        let mut result = None;
+        let mut hands: Vec<(usize, Vec<UNOCard>)> = Vec::new();
+        let mut top = None;
+        let mut pile = None;
+        let mut format = None;
+        let mut movetext = String::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let at = |e: String| format!("Line {}: {}", i + 1, e);
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(tag) = line.strip_prefix('[') {
+                let (key, value) = tag.strip_suffix(']').and_then(|t| t.split_once(' '))
+                    .ok_or(at(String::from("tags look like [Key \"value\"]")))?;
+                let value = unquote(value.trim()).ok_or(at(String::from("the tag's value should be in quotes")))?;
+                match key {
+                    "Format" => format = Some(value),
+                    "Seed" => seed = Some(value.parse().map_err(|_| at(format!("{} is not a valid seed", value)))?),
+                    "Rules" => {
+                        for rule in value.split_whitespace() {
+                            let (key, value) = rule.split_once('=').ok_or(at(format!("{} should look like stacking=on", rule)))?;
+                            rules.set(key, value).map_err(at)?;
+                        }
+                    },
+                    "Handicaps" => {
+                        handicaps = value.split_whitespace().map(|h| h.parse()).collect::<Result<_, _>>().map_err(at)?;
+                    },
+                    "Result" if value == "*" => result = None,
+                    "Result" => result = Some(value.parse().map_err(|_| at(format!("{} is not a seat or *", value)))?),
+                    _ if key.starts_with("Seat") && let Ok(seat) = key[4..].parse::<usize>() => {
+                        let (kind, name) = value.split_once(' ').unwrap_or((&value, ""));
+                        let difficulty = match kind {
+                            "human" => None,
+                            _ => Some(kind.parse().map_err(at)?),
+                        };
+                        seats.push((seat, name.to_string(), difficulty));
+                    },
+                    _ => {},
+                }
+            } else if let Some((label, cards)) = line.split_once(':').filter(|(label, _)| !label.contains(' ')) {
+                match label {
+                    "Top" => top = Some(parse_card_code(cards).map_err(at)?),
+                    "Pile" => pile = Some(parse_codes(cards).map_err(at)?),
+                    _ => match label.parse::<usize>() {
+                        Ok(seat) => hands.push((seat, parse_codes(cards).map_err(at)?)),
+                        Err(_) => return Err(at(format!("{} is not a seat number, Top or Pile", label))),
+                    },
+                }
+            } else {
+                movetext.push_str(line);
+                movetext.push(' ');
+            }
+        }
+
+        match format.as_deref() {
+            Some(FORMAT) => {},
+            Some(other) => return Err(format!("This is {} notation, this version reads {}", other, FORMAT)),
+            None => return Err(String::from("The [Format] tag is missing, this doesn't look like game notation")),
+        }
+
+        // Seats and hands can come in any order, but every one from 1 up has to be there exactly once
+        seats.sort_by_key(|(seat, ..)| *seat);
+        hands.sort_by_key(|(seat, _)| *seat);
+        if seats.is_empty() || seats.iter().enumerate().any(|(i, (seat, ..))| *seat != i + 1) {
+            return Err(String::from("The Seat tags should be Seat1, Seat2 and so on, once each"));
+        }
+        if hands.len() != seats.len() || hands.iter().enumerate().any(|(i, (seat, _))| *seat != i + 1) {
+            return Err(String::from("There should be one hand for every seat"));
+        }
+        if !handicaps.is_empty() && handicaps.len() != seats.len() {
+            return Err(String::from("The Handicaps tag should have one for every seat, none for a seat without"));
+        }
+
+        let mut turns: Vec<Turn> = Vec::new();
+        for token in movetext.split_whitespace() {
+            if let Some((number, seat)) = token.split_once(".p") {
+                let (Ok(number), Ok(seat)) = (number.parse::<usize>(), seat.parse::<usize>()) else {
+                    return Err(format!("{} should look like 12.p3", token));
+                };
+                if number != turns.len() + 1 || !(1..=seats.len()).contains(&seat) {
+                    return Err(format!("{} doesn't follow turn {} or has no seat", token, turns.len()));
+                }
+                turns.push(Turn { seat, moves: Vec::new() });
+                continue;
+            }
+            let turn = turns.last_mut().ok_or(format!("{} comes before the first turn number", token))?;
+            match token {
+                "-" => {},
+                "D" | "d" => turn.moves.push(Move::Draw),
+                "UNO" | "uno" => turn.moves.push(Move::Uno),
+                "C" | "c" => turn.moves.push(Move::Challenge),
+                _ if let Some(code) = token.strip_prefix('T') => turn.moves.push(Move::Trade(parse_card_code(code)?)),
+                _ if let Some(code) = token.strip_prefix('!') => turn.moves.push(Move::Refused(parse_card_code(code)?)),
+                code => turn.moves.push(Move::Play(parse_card_code(code)?)),
+            }
+        }
+
+        Ok(Notation {
+            seed,
+            rules,
+            seats: seats.into_iter().map(|(_, name, difficulty)| (name, difficulty)).collect(),
+            handicaps,
+            hands: hands.into_iter().map(|(_, hand)| hand).collect(),
+            top: top.ok_or("The Top card is missing")?,
+            pile: pile.ok_or("The Pile is missing")?,
+            turns,
+            result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{ALL_COLORS, DeckShape, build_deck_of};
+    use crate::game::play;
+
+    // Every event after the deal, and the deal itself
+    #[derive(Default)]
+    struct Keep(Vec<Event>);
+
+    impl Frontend for Keep {
+        fn event(&mut self, event: &Event) {
+            self.0.push(event.clone());
+        }
+
+        fn prompt(&mut self, _prompt: &Prompt) -> String {
+            String::new()
+        }
+    }
+
+    fn ai_game(seed: u64, seats: u8, rules: RuleSet) -> (Table, Vec<Event>, Option<usize>) {
+        let settings = Settings {
+            rules,
+            players: Some(0),
+            ai_players: Some(seats),
+            ai_mix: vec![Difficulty::Skilled, Difficulty::Aggressive, Difficulty::Calm],
+            seed: Some(seed),
+            ..Settings::default()
+        };
+        let mut fe = Keep::default();
+        let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+        let dealt = table.clone();
+        fe.0.clear();
+        let winner = play(&mut table, &mut fe, Some(10_000)).unwrap();
+        (dealt, fe.0, winner)
+    }
+
+    #[test]
+    fn every_card_has_a_code() {
+        let mut cards = build_deck_of(DeckShape { colors: 6, highest: MAX_NUMBER });
+        for color in ALL_COLORS {
+            cards.push(UNOCard::new(color, SpecialCard::ColorChange, -1));
+            cards.push(UNOCard::new(color, SpecialCard::PlusFour, -1));
+        }
+        for card in cards {
+            assert_eq!(parse_card_code(&card_code(&card)), Ok(card));
+        }
+        assert_eq!(parse_card_code("w+4:g"), Ok(UNOCard::new(Color::Green, SpecialCard::PlusFour, -1)));
+        for bad in ["", "R", "R13", "R05", "X5", "W:", "W+4:N", "RS2"] {
+            assert!(parse_card_code(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn games_read_back_and_replay_the_same() {
+        // Draw-one games have the drawn card played after a draw, and force play puts it down without asking
+        let draw_one = RuleSet { draw_one: true, force_play: true, ..RuleSet::default() };
+        let trade = RuleSet { trade: true, ..RuleSet::default() };
+        let six = RuleSet { colors: 6, ..RuleSet::default() };
+        let twelve = RuleSet { highest_number: 12, ..RuleSet::default() };
+        let penalties = RuleSet { uno_penalty: 2, challenge_penalty: 2, ..RuleSet::default() };
+        for (seed, seats, rules) in [(9, 3, RuleSet::default()), (4, 2, RuleSet::default()), (31, 5, RuleSet::default()),
+            (9, 3, "official".parse().unwrap()), (5, 4, draw_one), (9, 3, trade), (7, 6, six), (3, 4, twelve), (9, 4, penalties)] {
+            let (dealt, events, winner) = ai_game(seed, seats, rules);
+            let notation = Notation::from_game(&dealt, &events);
+            assert_eq!(notation.result, winner);
+            if rules.trade {
+                assert!(notation.turns.iter().any(|t| matches!(t.moves[..], [Move::Trade(_)])), "nobody traded");
+            }
+
+            let read: Notation = notation.to_string().parse().unwrap();
+            assert_eq!(read, notation);
+            let session = read.replay().unwrap();
+            assert_eq!(session.winner(), winner);
+            assert_eq!(session.current().hands, {
+                let mut table = dealt.clone();
+                play(&mut table, &mut Keep::default(), Some(10_000)).unwrap();
+                table.hands
+            });
+        }
+    }
+
+    #[test]
+    fn handicaps_and_house_cards_come_along() {
+        let mut rules = RuleSet::default();
+        rules.set("card-7", "swap").unwrap();
+        let settings = Settings {
+            rules,
+            players: Some(0),
+            ai_players: Some(3),
+            difficulty: Some(Difficulty::Skilled),
+            seed: Some(12),
+            handicaps: vec!["draw2+no-stack".parse().unwrap(), Handicap::default(), "force-play".parse().unwrap()],
+            ..Settings::default()
+        };
+        let mut fe = Keep::default();
+        let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+        assert_eq!(table.hands.iter().map(|h| h.len()).collect::<Vec<_>>(), [9, 7, 7]);
+        let dealt = table.clone();
+        fe.0.clear();
+        let winner = play(&mut table, &mut fe, Some(10_000)).unwrap();
+
+        let notation = Notation::from_game(&dealt, &fe.0);
+        let text = notation.to_string();
+        assert!(text.contains("[Handicaps \"draw2+no-stack none force-play\"]"), "{}", text);
+        assert!(text.contains(" card-7=swap\"]"), "{}", text);
+        let read: Notation = text.parse().unwrap();
+        assert_eq!(read, notation);
+        assert_eq!(read.replay().unwrap().winner(), winner);
+    }
+
+    #[test]
+    fn refused_cards_come_along_when_they_cost_cards() {
+        let settings = Settings {
+            rules: RuleSet { illegal_penalty: 2, ..RuleSet::default() },
+            players: Some(1),
+            ai_players: Some(2),
+            difficulty: Some(Difficulty::Calm),
+            seed: Some(3),
+            ..Settings::default()
+        };
+        let dealt = setup(&mut Quiet, &settings).unwrap().unwrap();
+        let mut session = Session::from_table(dealt.clone()).unwrap();
+        // The first few turns try a card that doesn't fit first
+        let mut tries = 3;
+        while session.winner().is_none() && let Some(prompt) = session.waiting() {
+            let answer = match prompt {
+                Prompt::Action { .. } => {
+                    let table = session.current();
+                    let fits = |fits: bool| table.hands[0].iter().position(|c| crate::rules::allowed_move(*c, table.last_played) == fits);
+                    match (fits(false), fits(true)) {
+                        (Some(card), _) if tries > 0 => {
+                            tries -= 1;
+                            (card + 1).to_string()
+                        },
+                        (_, Some(card)) => (card + 1).to_string(),
+                        _ => String::from("d"),
+                    }
+                },
+                _ => String::from("red"),
+            };
+            session.act(&answer).unwrap();
+        }
+
+        let events: Vec<Event> = session.events().cloned().collect();
+        assert_eq!(events.iter().filter(|e| matches!(e, Event::IllegalMove(_))).count(), 3);
+        let notation = Notation::from_game(&dealt, &events);
+        let text = notation.to_string();
+        assert!(text.contains(" illegal-penalty=2\"]"), "{}", text);
+        assert_eq!(text.matches(" !").count(), 3, "{}", text);
+        let read: Notation = text.parse().unwrap();
+        assert_eq!(read, notation);
+        let replayed = read.replay().unwrap();
+        assert_eq!(replayed.winner(), session.winner());
+        assert_eq!(replayed.current().hands, session.current().hands);
+    }
+
+    #[test]
+    fn uno_calls_and_challenges_come_along() {
+        let settings = Settings {
+            rules: RuleSet { uno_penalty: 2, challenge_penalty: 2, ..RuleSet::default() },
+            players: Some(1),
+            ai_players: Some(2),
+            difficulty: Some(Difficulty::Aggressive),
+            seed: Some(1),
+            ..Settings::default()
+        };
+        let dealt = setup(&mut Quiet, &settings).unwrap().unwrap();
+        let mut session = Session::from_table(dealt.clone()).unwrap();
+        // Every other card that gets down to one goes without the call, and every +4 gets challenged
+        let mut forget = true;
+        while session.winner().is_none() && let Some(prompt) = session.waiting() {
+            let answer = match prompt {
+                Prompt::Action { .. } => {
+                    let table = session.current();
+                    match table.hands[0].iter().position(|c| crate::rules::allowed_move(*c, table.last_played)) {
+                        Some(card) if table.hands[0].len() == 2 => {
+                            forget = !forget;
+                            format!("{}{}", card + 1, if forget { "" } else { " uno" })
+                        },
+                        Some(card) => (card + 1).to_string(),
+                        None => String::from("d"),
+                    }
+                },
+                Prompt::Challenge { .. } | Prompt::PlayDrawn { .. } => String::from("y"),
+                _ => String::from("red"),
+            };
+            session.act(&answer).unwrap();
+        }
+
+        let events: Vec<Event> = session.events().cloned().collect();
+        assert!(events.iter().any(|e| matches!(e, Event::Challenge { player: 1, .. })), "nobody challenged");
+        assert!(events.iter().any(|e| matches!(e, Event::Notice(n) if n.contains("didn't call UNO"))), "nobody forgot");
+        let notation = Notation::from_game(&dealt, &events);
+        let text = notation.to_string();
+        assert!(text.contains(" uno-penalty=2 challenge-penalty=2\"]"), "{}", text);
+        assert!(text.contains(" UNO") && text.contains(" C"), "{}", text);
+        let read: Notation = text.parse().unwrap();
+        assert_eq!(read, notation);
+        let replayed = read.replay().unwrap();
+        assert_eq!(replayed.winner(), session.winner());
+        assert_eq!(replayed.current().hands, session.current().hands);
+    }
+
+    #[test]
+    fn wrong_moves_are_caught() {
+        let (dealt, events, _) = ai_game(9, 3, RuleSet::default());
+        let text = Notation::from_game(&dealt, &events).to_string();
+
+        // Somebody else's result
+        let winner = text.parse::<Notation>().unwrap().result.unwrap();
+        let other = text.replace(&format!("[Result \"{}\"]", winner), &format!("[Result \"{}\"]", winner % 3 + 1));
+        assert!(other.parse::<Notation>().unwrap().replay().err().unwrap().contains("the notation says"));
+
+        // A move swapped out for a draw
+        let mut notation: Notation = text.parse().unwrap();
+        let turn = notation.turns.iter_mut().find(|t| matches!(t.moves.first(), Some(Move::Play(_)))).unwrap();
+        turn.moves.insert(0, Move::Play(UNOCard::new(Color::Red, SpecialCard::Base, 99)));
+        assert!(notation.replay().err().unwrap().contains("isn't in the hand"));
+
+        // A deal with a card missing
+        let mut notation: Notation = text.parse().unwrap();
+        notation.pile.pop();
+        assert!(notation.replay().err().unwrap().contains("The deal"));
+    }
+
+    #[test]
+    fn tags_and_errors() {
+        assert!("[Format \"UGN 9\"]".parse::<Notation>().unwrap_err().contains("UGN 9"));
+        assert!("1: R5".parse::<Notation>().unwrap_err().contains("Format"));
+        assert_eq!(unquote(&quote("Ana \"the\" \\best")), Some(String::from("Ana \"the\" \\best")));
+    }
+}
@@ -0,0 +1,153 @@
+// Each seat's chance of winning from the start of every turn, from ai::win_odds, for watching a game like a broadcast
+//
+//     Chances: Ana 48%, Bo 31%, Cy 21%
+//
+// With nobody at the keyboard --win-odds shows them every turn. With people playing they'd give away everyone's hand,
+// so they wait for the game to end and come out as the lead changes, like `replay odds` does for a recorded game
+
+use std::path::PathBuf;
+
+use crate::ai::win_odds;
+use crate::events::{Event, Frontend, Prompt};
+use crate::game::Table;
+
+pub fn odds_line(names: &[String], odds: &[f64]) -> String {
+    let chances: Vec<String> = names.iter().zip(odds).map(|(name, odds)| format!("{} {:.0}%", name, odds * 100.0)).collect();
+    format!("Chances: {}", chances.join(", "))
+}
+
+// The seat most likely to win, the first of them on a tie
+fn leader(odds: &[f64]) -> usize {
+    (0..odds.len()).fold(0, |best, seat| if odds[seat] > odds[best] { seat } else { best })
+}
+
+// Every turn's chances, fed the same calls a frontend gets
+#[derive(Default)]
+pub struct Tracker {
+    names: Vec<String>,
+    turns: Vec<Vec<f64>>, // One a turn, counted over every seat
+}
+
+impl Tracker {
+    // The chances for the turn starting
+    pub fn turn_start(&mut self, table: &Table) -> &[f64] {
+        self.names = table.names.clone();
+        self.turns.push(win_odds(table));
+        &self.turns[self.turns.len() - 1]
+    }
+
+    // Every turn, for `replay odds`
+    pub fn every_turn(&self) -> Vec<String> {
+        self.turns.iter().enumerate().map(|(turn, odds)| format!("Turn {}: {}", turn + 1, odds_line(&self.names, odds))).collect()
+    }
+
+    // How far down the winner ever was
+    pub fn comeback(&self, winner: usize) -> Option<String> {
+        let (turn, lowest) = self.turns.iter().map(|odds| odds[winner - 1]).enumerate()
+            .fold(None, |low: Option<(usize, f64)>, (turn, odds)| match low {
+                Some((_, lowest)) if lowest <= odds => low,
+                _ => Some((turn, odds)),
+            })?;
+        Some(format!("{} won, from as low as {:.0}% on turn {}", self.names[winner - 1], lowest * 100.0, turn + 1))
+    }
+
+    // The first turn, every turn the favorite changed, then the comeback
+    pub fn report(&self, winner: Option<usize>) -> Vec<String> {
+        let Some(first) = self.turns.first() else { return Vec::new() };
+        let mut lines = vec![String::from("How the chances went:"), format!("  Turn 1: {}", odds_line(&self.names, first))];
+        for (turn, pair) in self.turns.windows(2).enumerate() {
+            if leader(&pair[1]) != leader(&pair[0]) {
+                lines.push(format!("  Turn {}: {}", turn + 2, odds_line(&self.names, &pair[1])));
+            }
+        }
+        lines.extend(winner.and_then(|winner| self.comeback(winner)).map(|line| format!("  {}", line)));
+        lines
+    }
+}
+
+// --win-odds
+pub struct WinOdds {
+    inner: Box<dyn Frontend>,
+    tracker: Tracker,
+    live: bool, // No people at the table, so nothing to give away
+}
+
+impl WinOdds {
+    pub fn new(inner: Box<dyn Frontend>) -> Self {
+        WinOdds { inner, tracker: Tracker::default(), live: false }
+    }
+}
+
+impl Frontend for WinOdds {
+    fn event(&mut self, event: &Event) {
+        if let Event::GameStart { .. } = event {
+            self.tracker = Tracker::default();
+        }
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.live = table.players == 0;
+        let odds = self.tracker.turn_start(table);
+        if self.live {
+            let line = odds_line(&table.names, odds);
+            self.inner.event(&Event::Notice(line));
+        }
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        if !self.live {
+            for line in self.tracker.report(winner) {
+                self.inner.event(&Event::Notice(line));
+            }
+        }
+        self.inner.game_over(table, winner);
+    }
+
+    fn paused(&self) -> bool {
+        self.inner.paused()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestGame;
+
+    #[test]
+    fn the_seat_about_to_go_out_is_the_favorite() {
+        let game = TestGame::new(3)
+            .hand(0, &["red 5"])
+            .hand(1, &["green 3", "blue 9", "yellow 1", "green 8", "blue 2"])
+            .hand(2, &["yellow 7", "green 6", "blue 4", "yellow 0", "green 2"])
+            .top("red 1")
+            .deck(&["yellow 2", "yellow 4", "blue 7", "green 1", "red 9", "blue 5"])
+            .build();
+        let t = game.table();
+        let odds = win_odds(t);
+        assert!((odds.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(odds[0] > 0.99, "{:?}", odds);
+
+        let turns = vec![vec![0.5, 0.3, 0.2], vec![0.2, 0.6, 0.2], vec![0.1, 0.8, 0.1], vec![0.05, 0.9, 0.05]];
+        let tracker = Tracker { names: t.names.clone(), turns };
+        assert_eq!(tracker.report(Some(2)), [
+            "How the chances went:",
+            "  Turn 1: Chances: Player #1 50%, Player #2 30%, Player #3 20%",
+            "  Turn 2: Chances: Player #1 20%, Player #2 60%, Player #3 20%",
+            "  Player #2 won, from as low as 30% on turn 1",
+        ]);
+    }
+}
@@ -0,0 +1,169 @@
+// Where UNOler keeps its files, and a Table as JSON and back: the form save slots, the crash recovery file, replays
+// and the server's saved games all keep a table in. Older saves still load, with defaults for what they didn't have
+
+use std::env;
+use std::path::PathBuf;
+
+use crate::cards::{Color, UNOCard, get_color};
+use crate::events::{card_from_json, card_json};
+use crate::game::{Difficulty, Game, Table, default_name, difficulty_name};
+use crate::json::Json;
+use crate::rng::{ChaChaRng, DeckRng, Randler};
+use crate::rules::{Handicap, RuleSet};
+
+// Where saves live: XDG data dir on unix, Application Support on macOS and AppData on windows
+// Falls back to the current directory if none of the usual variables are set
+pub fn data_dir() -> PathBuf {
+    let var = |name: &str| env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+    let base = if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|h| h.join("Library").join("Application Support"))
+    } else {
+        var("XDG_DATA_HOME").or_else(|| var("HOME").map(|h| h.join(".local").join("share")))
+    };
+
+    base.map(|b| b.join("unoler")).unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn cards_json(cards: &[UNOCard]) -> Json {
+    Json::Array(cards.iter().map(card_json).collect())
+}
+
+fn cards_from_json(value: &Json) -> Option<Vec<UNOCard>> {
+    value.as_array()?.iter().map(card_from_json).collect()
+}
+
+pub fn table_to_json(t: &Table) -> Json {
+    let mut table = vec![
+        ("hands", Json::Array(t.hands.iter().map(|h| cards_json(h)).collect())),
+        ("deck", cards_json(&t.deck)),
+        ("discard", cards_json(&t.discard)),
+        ("history", cards_json(&t.history)),
+        ("lacking", Json::Array(t.lacking.iter().map(|l| Json::Array(l.iter().map(|c| get_color(c).to_lowercase().into()).collect())).collect())),
+        ("last_played", card_json(&t.last_played)),
+        ("current_player", Json::Number(t.game_state.current_player as f64)),
+        ("direction", Json::Number(t.game_state.direction as f64)),
+        ("add_queue", Json::Number(t.add_queue as f64)),
+        ("skipped", t.skipped.into()),
+        ("uno", t.uno_detection_panic.into()),
+        ("players", (t.players as usize).into()),
+        ("names", Json::Array(t.names.iter().map(|n| n.as_str().into()).collect())),
+        ("difficulties", Json::Array(t.difficulties.iter().map(|d| difficulty_name(*d).into()).collect())),
+        ("rules", t.rules.to_json()),
+        // Strings because JSON numbers can't hold every u64
+        ("seed", t.seed.map_or(Json::Null, |s| s.to_string().into())),
+        // A secure generator's key is never written down, it gets a fresh one from the OS on load
+        ("rng_state", match &t.rand {
+            DeckRng::Seeded(rand) => rand.state().to_string().into(),
+            DeckRng::Secure(_) => "secure".into(),
+        }),
+        ("ai_rng_state", t.ai_rand.state().to_string().into()),
+    ];
+    // Only tables with a handicap have them, like house cards in the rules
+    if t.handicaps.iter().any(|h| !h.is_none()) {
+        table.push(("handicaps", Json::Array(t.handicaps.iter().map(|h| h.to_string().into()).collect())));
+    }
+    if t.traded.contains(&true) {
+        table.push(("traded", Json::Array(t.traded.iter().map(|&traded| traded.into()).collect())));
+    }
+    if t.taken_over.contains(&true) {
+        table.push(("taken_over", Json::Array(t.taken_over.iter().map(|&taken| taken.into()).collect())));
+    }
+    if t.joined.contains(&true) {
+        table.push(("joined", Json::Array(t.joined.iter().map(|&joined| joined.into()).collect())));
+    }
+    Json::object(table)
+}
+
+pub fn table_from_json(value: &Json) -> Option<Table> {
+    let hands = value.get("hands")?.as_array()?.iter().map(cards_from_json).collect::<Option<Vec<_>>>()?;
+    let current_player = value.get("current_player")?.as_f64()?;
+    let direction = value.get("direction")?.as_f64()? as i8;
+
+    // Don't trust the file blindly, a broken turn order would panic again right away
+    if hands.is_empty() || current_player < 0.0 || current_player as usize >= hands.len() || (direction != 1 && direction != -1) {
+        return None;
+    }
+    let current_player = current_player as usize;
+
+    let names = match value.get("names") {
+        Some(names) => names.as_array()?.iter().map(|n| n.as_str().map(String::from)).collect::<Option<Vec<_>>>()?,
+        None => (1..=hands.len()).map(default_name).collect(),
+    };
+    // Saves from before every seat had its own difficulty have one for everybody
+    let difficulties = match value.get("difficulties") {
+        Some(difficulties) => difficulties.as_array()?.iter().map(|d| d.as_str()?.parse().ok()).collect::<Option<Vec<Difficulty>>>()?,
+        None => vec![value.get("difficulty")?.as_str()?.parse().ok()?; hands.len()],
+    };
+    if names.len() != hands.len() || difficulties.len() != hands.len() {
+        return None;
+    }
+    let discard = cards_from_json(value.get("discard")?)?;
+    let last_played = card_from_json(value.get("last_played")?)?;
+    // Saves from before the history was kept only know what's still in the discard pile
+    let history = match value.get("history") {
+        Some(history) => cards_from_json(history)?,
+        None => discard[..discard.len().saturating_sub(1)].iter().copied().chain([last_played]).collect(),
+    };
+    // Nor did they keep track of who drew on what
+    let lacking = match value.get("lacking") {
+        Some(lacking) => lacking.as_array()?.iter()
+            .map(|l| l.as_array()?.iter().map(|c| c.as_str()?.parse().ok()).collect::<Option<Vec<Color>>>())
+            .collect::<Option<Vec<_>>>()?,
+        None => vec![Vec::new(); hands.len()],
+    };
+    if lacking.len() != hands.len() {
+        return None;
+    }
+    let handicaps = match value.get("handicaps") {
+        Some(handicaps) => handicaps.as_array()?.iter().map(|h| h.as_str()?.parse().ok()).collect::<Option<Vec<Handicap>>>()?,
+        None => vec![Handicap::default(); hands.len()],
+    };
+    let traded = match value.get("traded") {
+        Some(traded) => traded.as_array()?.iter().map(|t| t.as_bool()).collect::<Option<Vec<_>>>()?,
+        None => vec![false; hands.len()],
+    };
+    let flags = |key: &str| match value.get(key) {
+        Some(flags) => flags.as_array()?.iter().map(|f| f.as_bool()).collect::<Option<Vec<_>>>(),
+        None => Some(vec![false; hands.len()]),
+    };
+    let (taken_over, joined) = (flags("taken_over")?, flags("joined")?);
+    if handicaps.len() != hands.len() || [traded.len(), taken_over.len(), joined.len()].iter().any(|&len| len != hands.len()) {
+        return None;
+    }
+
+    Some(Table {
+        names,
+        game_state: Game::new(current_player, hands.len(), direction),
+        hands,
+        deck: cards_from_json(value.get("deck")?)?,
+        discard,
+        history,
+        lacking,
+        last_played,
+        add_queue: value.get("add_queue")?.as_f64()? as u32,
+        skipped: value.get("skipped")?.as_bool()?,
+        uno_detection_panic: value.get("uno")?.as_bool()?,
+        players: value.get("players")?.as_f64()? as u8,
+        difficulties,
+        rules: match value.get("rules") {
+            Some(rules) => RuleSet::from_json(rules)?,
+            None => RuleSet::default(),
+        },
+        seed: match value.get("seed")? {
+            Json::Null => None,
+            seed => Some(seed.as_str()?.parse().ok()?),
+        },
+        rand: match value.get("rng_state")?.as_str()? {
+            "secure" => DeckRng::Secure(ChaChaRng::from_os().ok()?),
+            state => DeckRng::Seeded(Randler::from_state(state.parse().ok()?)),
+        },
+        ai_rand: Randler::from_state(value.get("ai_rng_state")?.as_str()?.parse().ok()?),
+        handicaps,
+        traded,
+        taken_over,
+        joined,
+    })
+}
@@ -0,0 +1,55 @@
+// Rule plugins: variant behavior layered on top of the core rules instead of another arm in play()
+// A plugin is handed to play_with() and hears about the turn at three points, each with a default that does nothing,
+// so one only has to write the hooks it cares about. Plugins aren't saved with the table, whoever plays the game
+// brings them again
+
+use crate::cards::{SpecialCard, UNOCard};
+use crate::game::Table;
+use crate::rules::{TurnState, allowed_move};
+
+pub trait RulePlugin {
+    // Before the player is asked anything, what they owe or whether they're skipped can still change
+    fn on_turn_start(&mut self, _table: &Table, _turn: &mut TurnState) {}
+
+    // Once a card is down and the core rules (and house cards) have had their say about the turns after it
+    fn on_card_played(&mut self, _table: &Table, _card: UNOCard, _turn: &mut TurnState) {}
+
+    // The positions in the hand that can be played on top, which the core rules worked out first
+    // Taking one out stops AIs and people alike, adding one only helps people, the AIs don't look past the core rules
+    fn modify_legal_moves(&self, _hand: &[UNOCard], _top: UNOCard, _legal: &mut Vec<usize>) {}
+}
+
+// The printed rule the core leaves out: a +4 only goes down when nothing in the hand matches the color on top
+pub struct StrictWildFour;
+
+impl RulePlugin for StrictWildFour {
+    fn modify_legal_moves(&self, hand: &[UNOCard], top: UNOCard, legal: &mut Vec<usize>) {
+        if hand.iter().any(|c| c.color == top.color) {
+            legal.retain(|&i| hand[i].special != SpecialCard::PlusFour);
+        }
+    }
+}
+
+// The cards in the hand that can go down, by the core rules and then every plugin in order
+pub fn legal_moves(hand: &[UNOCard], top: UNOCard, plugins: &[Box<dyn RulePlugin>]) -> Vec<usize> {
+    let mut legal = (0..hand.len()).filter(|&i| allowed_move(hand[i], top)).collect();
+    for plugin in plugins {
+        plugin.modify_legal_moves(hand, top, &mut legal);
+    }
+    legal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{card, cards};
+
+    #[test]
+    fn a_wild_four_waits_until_the_color_runs_out() {
+        let plugins: Vec<Box<dyn RulePlugin>> = vec![Box::new(StrictWildFour)];
+        let hand = cards(&["wild +4", "red 2", "blue 5"]);
+        assert_eq!(legal_moves(&hand, card("red 9"), &[]), [0, 1]);
+        assert_eq!(legal_moves(&hand, card("red 9"), &plugins), [1]);
+        assert_eq!(legal_moves(&hand, card("green 5"), &plugins), [0, 2], "a number match doesn't count");
+    }
+}
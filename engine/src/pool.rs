@@ -0,0 +1,154 @@
+// A fixed number of worker threads taking jobs off one bounded queue, so a big simulation or a busy server runs on
+// as many threads as it was given instead of one more for every piece of work
+//
+//     let pool = Pool::new(4, 16);
+//     let squares = pool.map((1..=100).collect(), |n: u64| n * n)?;
+//
+// A job that panics takes only itself down, the worker carries on with the next one
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// One worker per core, unless told otherwise
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+pub struct Pool {
+    queue: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+fn work(jobs: Arc<Mutex<Receiver<Job>>>) {
+    loop {
+        // The lock is only held to take the next job, not to run it
+        let job = jobs.lock().unwrap_or_else(|e| e.into_inner()).recv();
+        match job {
+            Ok(job) => {
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+            },
+            // The pool was dropped and the queue is empty
+            Err(_) => return,
+        }
+    }
+}
+
+impl Pool {
+    // At least one thread, and room for `queue` jobs waiting on top of the ones being worked on
+    pub fn new(threads: usize, queue: usize) -> Pool {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(queue);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..threads.max(1)).map(|n| {
+            let receiver = Arc::clone(&receiver);
+            thread::Builder::new()
+                .name(format!("unoler-worker-{}", n + 1))
+                .spawn(move || work(receiver))
+                .expect("Could not start a worker thread")
+        }).collect();
+        Pool { queue: Some(sender), workers }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    // Waits for room in the queue
+    pub fn submit(&self, job: impl FnOnce() + Send + 'static) {
+        if let Some(queue) = &self.queue {
+            let _ = queue.send(Box::new(job));
+        }
+    }
+
+    // Gives the job back instead of waiting when the queue is full, so whatever it carried can be turned away
+    pub fn try_submit<F: FnOnce() + Send + 'static>(&self, job: F) -> Result<(), F> {
+        // The job waits in a slot the queued closure takes it from, a job that never got queued is still in it
+        let slot = Arc::new(Mutex::new(Some(job)));
+        let queued = Arc::clone(&slot);
+        let take = |slot: &Mutex<Option<F>>| slot.lock().unwrap_or_else(|e| e.into_inner()).take();
+        let sent = self.queue.as_ref().is_some_and(|queue| {
+            queue.try_send(Box::new(move || {
+                if let Some(job) = take(&queued) {
+                    job();
+                }
+            })).is_ok()
+        });
+        match sent {
+            true => Ok(()),
+            false => Err(take(&slot).expect("A job that wasn't queued is still in its slot")),
+        }
+    }
+
+    // Runs f on every item across the workers, with the results in the items' order
+    pub fn map<T: Send + 'static, R: Send + 'static>(&self, items: Vec<T>, f: impl Fn(T) -> R + Send + Sync + 'static) -> Result<Vec<R>, String> {
+        let f = Arc::new(f);
+        let (sender, results) = mpsc::channel();
+        let count = items.len();
+        for (i, item) in items.into_iter().enumerate() {
+            let (f, sender) = (Arc::clone(&f), sender.clone());
+            self.submit(move || {
+                let _ = sender.send((i, f(item)));
+            });
+        }
+        drop(sender);
+        let mut ordered: Vec<Option<R>> = (0..count).map(|_| None).collect();
+        for (i, result) in results {
+            ordered[i] = Some(result);
+        }
+        ordered.into_iter().collect::<Option<Vec<R>>>().ok_or_else(|| String::from("A worker thread panicked"))
+    }
+}
+
+// Whatever is queued still gets done, then the workers stop
+impl Drop for Pool {
+    fn drop(&mut self) {
+        self.queue = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn jobs_come_back_in_order_whatever_thread_ran_them() {
+        let pool = Pool::new(3, 2);
+        assert_eq!(pool.threads(), 3);
+        assert_eq!(pool.map((1..=50).collect(), |n: u64| n * n).unwrap(), (1..=50).map(|n| n * n).collect::<Vec<_>>());
+        assert_eq!(Pool::new(0, 0).threads(), 1, "there's always somebody to do the work");
+
+        assert!(pool.map(vec![1, 2, 3], |n: u32| if n == 2 { panic!("two") } else { n }).is_err());
+        assert_eq!(pool.map(vec![4], |n: u32| n).unwrap(), [4], "the worker lived through it");
+    }
+
+    #[test]
+    fn a_full_queue_turns_jobs_away() {
+        let pool = Pool::new(1, 1);
+        let (block, blocked) = mpsc::channel::<()>();
+        let (start, started) = mpsc::channel::<()>();
+        // One job holds the only worker up, the next fills the queue
+        pool.submit(move || {
+            let _ = start.send(());
+            let _ = blocked.recv_timeout(Duration::from_secs(5));
+        });
+        started.recv().unwrap();
+        let done = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&done);
+        assert!(pool.try_submit(move || { counted.fetch_add(1, Ordering::Relaxed); }).is_ok());
+        let (back, came_back) = mpsc::channel::<u8>();
+        let turned_away = pool.try_submit(move || back.send(7).unwrap()).err();
+        turned_away.expect("the queue is full")();
+        assert_eq!(came_back.try_recv(), Ok(7), "the job comes back to be run or dropped");
+        block.send(()).unwrap();
+        drop(pool);
+        assert_eq!(done.load(Ordering::Relaxed), 1, "queued jobs still get done");
+    }
+}
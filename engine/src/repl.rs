@@ -0,0 +1,259 @@
+// A debug REPL on a channel of its own, so the game's own input and output are left alone. A line sent to it while a
+// game is going breaks in at the start of the next turn, before anyone is asked anything, and it answers questions
+// about the table until it's told to go on
+//
+//     state.hands[2]                 the third seat's hand, 0 based like the Vec
+//     state.deck                     in Vec order, so the next card drawn is last. Also discard, history,
+//                                    last_played, add_queue, direction, turn, skipped, names, lacking, traded,
+//                                    rules and seed
+//     events.last(10)                the last ten events, in the JSON --json prints
+//     allowed_move(red 5, blue 5)    whether the first card can go on the second
+//     next                           play one turn and break in again
+//     continue                       back to the game
+//
+// It only looks, --debug is what changes the table
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+
+use crate::cards::UNOCard;
+use crate::events::{Event, Frontend, Prompt, event_json};
+use crate::game::Table;
+use crate::json::Json;
+use crate::notation::card_code;
+use crate::rules::allowed_move;
+
+pub const HELP: &str = "state.<field>, state.hands[<seat>], events.last(<n>), events.len(), allowed_move(<card>, <card on top>), next or continue";
+
+pub struct Repl {
+    inner: Box<dyn Frontend>,
+    commands: Receiver<String>,
+    out: Box<dyn Write>,
+    events: Vec<Json>, // Everything so far, for events.last
+    step: bool, // Break in at the next turn without waiting for a line
+}
+
+impl Repl {
+    pub fn new(inner: Box<dyn Frontend>, commands: Receiver<String>, out: Box<dyn Write>) -> Self {
+        Repl { inner, commands, out, events: Vec::new(), step: false }
+    }
+
+    // Until continue, or until nothing more can come
+    fn stop(&mut self, table: &Table) {
+        let mut line = if self.step { None } else {
+            match self.commands.try_recv() {
+                Ok(line) => Some(line),
+                Err(_) => return,
+            }
+        };
+        self.step = false;
+        let _ = writeln!(self.out, "Stopped before {}'s turn", table.names[table.game_state.current_player]);
+        loop {
+            let Some(next) = line.take().or_else(|| self.commands.recv().ok()) else { return };
+            match next.trim() {
+                "continue" | "c" => return,
+                "next" | "n" => return self.step = true,
+                "" => {},
+                q => {
+                    let answer = query(table, &self.events, q).unwrap_or_else(|e| e);
+                    let _ = writeln!(self.out, "{}", answer);
+                },
+            }
+        }
+    }
+}
+
+// The lines of a file, read on a thread of their own. A named pipe kept open from another terminal
+// (mkfifo /tmp/uno-repl, then cat > /tmp/uno-repl) makes it interactive. Once the file runs out the game goes on
+pub fn listen(path: &Path) -> Receiver<String> {
+    let (send, commands) = channel();
+    let path = PathBuf::from(path);
+    std::thread::spawn(move || {
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) => return eprintln!("Could not open {} for the REPL: {}", path.display(), e),
+        };
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if send.send(line).is_err() {
+                return;
+            }
+        }
+    });
+    commands
+}
+
+fn codes(cards: &[UNOCard]) -> String {
+    cards.iter().map(card_code).collect::<Vec<_>>().join(" ")
+}
+
+// What's between the brackets and parentheses of name[index] and name(args), None if there aren't any
+fn inside<'a>(line: &'a str, name: &str, open: char, close: char) -> Option<&'a str> {
+    line.strip_prefix(name)?.trim().strip_prefix(open)?.strip_suffix(close).map(str::trim)
+}
+
+fn seat(t: &Table, index: &str) -> Result<usize, String> {
+    index.parse::<usize>().ok().filter(|&i| i < t.hands.len())
+        .ok_or(format!("{} is not a seat, they go from 0 to {}", index, t.hands.len() - 1))
+}
+
+fn state(t: &Table, field: &str) -> Result<String, String> {
+    if let Some(index) = inside(field, "hands", '[', ']') {
+        return Ok(codes(&t.hands[seat(t, index)?]));
+    }
+    if let Some(index) = inside(field, "lacking", '[', ']') {
+        return Ok(format!("{:?}", t.lacking[seat(t, index)?]));
+    }
+    Ok(match field {
+        "hands" => t.hands.iter().enumerate().map(|(i, hand)| format!("[{}] {}", i, codes(hand))).collect::<Vec<_>>().join("\n"),
+        "deck" => codes(&t.deck),
+        "discard" => codes(&t.discard),
+        "history" => codes(&t.history),
+        "last_played" => card_code(&t.last_played),
+        "add_queue" => t.add_queue.to_string(),
+        "direction" => t.game_state.direction.to_string(),
+        "turn" => t.game_state.current_player.to_string(),
+        "skipped" => t.skipped.to_string(),
+        "names" => format!("{:?}", t.names),
+        "lacking" => format!("{:?}", t.lacking),
+        "traded" => format!("{:?}", t.traded),
+        "rules" => t.rules.summary(),
+        "seed" => t.seed.map_or(String::from("none"), |s| s.to_string()),
+        _ => return Err(format!("The table has no {}", field)),
+    })
+}
+
+fn events(events: &[Json], call: &str) -> Result<String, String> {
+    if call == "len()" {
+        return Ok(events.len().to_string());
+    }
+    let count = inside(call, "last", '(', ')').ok_or(format!("events.{} isn't a query, try events.last(10)", call))?;
+    let count = match count {
+        "" => 1,
+        n => n.parse::<usize>().map_err(|_| format!("{} is not a number of events", n))?,
+    };
+    Ok(events[events.len().saturating_sub(count)..].iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n"))
+}
+
+// One line typed at the REPL, answered from the table it stopped at and the events up to it
+pub fn query(t: &Table, history: &[Json], line: &str) -> Result<String, String> {
+    let line = line.trim();
+    if let Some(call) = line.strip_prefix("events.") {
+        return events(history, call);
+    }
+    if let Some(cards) = inside(line, "allowed_move", '(', ')') {
+        let (card, top) = cards.split_once(',').ok_or("Give it two cards, like allowed_move(red 5, blue 5)")?;
+        return Ok(allowed_move(card.trim().parse()?, top.trim().parse()?).to_string());
+    }
+    if let Some(field) = line.strip_prefix("state.") {
+        return state(t, field);
+    }
+    match line {
+        "help" => Ok(String::from(HELP)),
+        _ => Err(format!("{} isn't a query, try {}", line, HELP)),
+    }
+}
+
+impl Frontend for Repl {
+    fn event(&mut self, event: &Event) {
+        self.events.push(event_json(event));
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.stop(table);
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        self.inner.game_over(table, winner);
+    }
+
+    fn paused(&self) -> bool {
+        self.inner.paused()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Difficulty, play};
+    use crate::testing::{ScriptedFrontend, TestGame};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // What the REPL wrote, kept where the test can still see it
+    #[derive(Clone, Default)]
+    struct Out(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Out {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn queries_read_the_table() {
+        let game = TestGame::new(2)
+            .hand(0, &["red 5", "wild"])
+            .hand(1, &["green 3"])
+            .top("red 1")
+            .deck(&["yellow 2", "blue skip"])
+            .build();
+        let t = game.table();
+        let events = vec![Json::from("one"), Json::from("two"), Json::from("three")];
+
+        assert_eq!(query(t, &events, "state.hands[0]"), Ok(String::from("R5 W")));
+        assert_eq!(query(t, &events, "state.deck"), Ok(String::from("BS Y2")), "in Vec order, drawn from the end");
+        assert_eq!(query(t, &events, "state.last_played"), Ok(String::from("R1")));
+        assert!(query(t, &events, "state.hands[2]").is_err());
+        assert_eq!(query(t, &events, "events.last(2)"), Ok(String::from("\"two\"\n\"three\"")));
+        assert_eq!(query(t, &events, "events.len()"), Ok(String::from("3")));
+        assert_eq!(query(t, &events, "allowed_move(red 5, blue 5)"), Ok(String::from("true")));
+        assert_eq!(query(t, &events, "allowed_move(red 5, blue 6)"), Ok(String::from("false")));
+        assert!(query(t, &events, "state.nothing").is_err());
+    }
+
+    #[test]
+    fn it_breaks_in_between_turns_and_lets_the_game_go_on() {
+        let (send, commands) = channel();
+        let out = Out::default();
+        let mut fe = Repl::new(Box::new(ScriptedFrontend::default()), commands, Box::new(out.clone()));
+        for line in ["state.turn", "next", "state.turn", "events.last(1)", "continue"] {
+            send.send(String::from(line)).unwrap();
+        }
+        drop(send);
+
+        let mut game = TestGame::new(2)
+            .hand(0, &["red 5", "red 6"])
+            .hand(1, &["green 3", "blue 9"])
+            .top("red 1")
+            .deck(&["yellow 2", "yellow 4", "yellow 7"])
+            .ai_from(0, Difficulty::Calm)
+            .build();
+        play(game.table_mut(), &mut fe, None).unwrap();
+
+        let out = String::from_utf8(out.0.borrow().clone()).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[..4], ["Stopped before Player #1's turn", "0", "Stopped before Player #2's turn", "1"]);
+        assert!(lines[4].contains("\"event\""), "{}", lines[4]);
+        assert_eq!(lines.len(), 5, "nothing more once it was told to continue");
+    }
+}
@@ -0,0 +1,238 @@
+// Post-game review: every human turn held up against what the Expert AI would have done from the same table, with
+// the usual mistakes called out by name
+//
+//     Turn 3: drew with Red 5 to play (missed a playable card), the Expert would have played Red 5
+//     Turn 7: played Wild Card (Blue) with 6 cards and Red 2 to play (wasted a wild early)
+//
+// --review prints it once a game is over, `replay review` for a game recorded with --record
+
+use std::path::PathBuf;
+
+use crate::ai::expert_move;
+use crate::cards::{SpecialCard, UNOCard, format_card_message};
+use crate::events::{Event, Frontend, Prompt};
+use crate::game::Table;
+use crate::plugins::legal_moves;
+use crate::rng::Rng;
+use crate::search::Move;
+
+// Playing a wild with more cards than this, and something else to play, is spending it too soon
+const EARLY: usize = 4;
+
+fn is_wild(card: &UNOCard) -> bool {
+    matches!(card.special, SpecialCard::ColorChange | SpecialCard::PlusFour)
+}
+
+fn is_draw_card(card: &UNOCard) -> bool {
+    matches!(card.special, SpecialCard::PlusTwo | SpecialCard::PlusFour)
+}
+
+// A wild is the same play whatever color it was given
+fn same(a: &UNOCard, b: &UNOCard) -> bool {
+    a.special == b.special && a.number == b.number && (a.color == b.color || is_wild(a))
+}
+
+// What a seat did with its turn, the first thing it chose
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Did {
+    Played(UNOCard),
+    Drew,
+    Traded,
+}
+
+struct Turn {
+    number: usize, // Counted over every seat, from 1
+    table: Table, // As it was when the turn started
+    events: Vec<Event>,
+}
+
+impl Turn {
+    fn seat(&self) -> usize {
+        self.table.game_state.current_player
+    }
+
+    // None for a stack drawn or a turn skipped, which aren't decisions. Wilds come with the color they were given
+    fn did(&self) -> Option<Did> {
+        let player = self.seat() + 1;
+        let chosen = self.events.iter().find_map(|event| match event {
+            Event::ColorChosen { player: p, color } if *p == player => Some(*color),
+            _ => None,
+        });
+        self.events.iter().find_map(|event| match event {
+            Event::Played { player: p, card, .. } if *p == player => Some(Did::Played(UNOCard { color: chosen.unwrap_or(card.color), ..*card })),
+            Event::Drew { player: p, .. } if *p == player => Some(Did::Drew),
+            Event::Traded { player: p, .. } if *p == player => Some(Did::Traded),
+            _ => None,
+        })
+    }
+
+    // The line about it, None when it matched the Expert and nothing was wrong with it
+    fn review(&self) -> Option<(String, bool)> {
+        let t = &self.table;
+        let did = self.did()?;
+        let hand = &t.hands[self.seat()];
+        let legal: Vec<UNOCard> = legal_moves(hand, t.last_played, &[]).into_iter().map(|i| hand[i]).collect();
+        let mut rand = t.ai_rand;
+        let expert = expert_move(t, rand.next_u64());
+
+        let (what, mistake) = match did {
+            Did::Drew => (
+                match legal.iter().find(|c| !is_wild(c)).or(legal.first()) {
+                    Some(card) => format!("drew with {} to play", format_card_message(card)),
+                    None => String::from("drew"),
+                },
+                (!legal.is_empty()).then_some("missed a playable card"),
+            ),
+            Did::Played(card) => {
+                let stack = t.rules.stacking && t.add_queue > 0;
+                let other = legal.iter().find(|c| !is_wild(c)).filter(|_| is_wild(&card) && hand.len() > EARLY);
+                match other {
+                    _ if stack && !is_draw_card(&card) && legal.iter().any(is_draw_card) => {
+                        (format!("played {}", format_card_message(&card)), Some("didn't stack"))
+                    },
+                    Some(other) => (
+                        format!("played {} with {} cards and {} to play", format_card_message(&card), hand.len(), format_card_message(other)),
+                        Some("wasted a wild early"),
+                    ),
+                    None => (format!("played {}", format_card_message(&card)), None),
+                }
+            },
+            Did::Traded => (String::from("traded with the deck"), None),
+        };
+        let matched = match (expert, did) {
+            (Some(Move::Play(best)), Did::Played(card)) => same(&card, &best),
+            (Some(Move::Draw), Did::Drew) | (None, _) => true,
+            _ => false,
+        };
+        if matched && mistake.is_none() {
+            return None;
+        }
+        let mut line = format!("Turn {}: {}", self.number, what);
+        if let Some(mistake) = mistake {
+            line.push_str(&format!(" ({})", mistake));
+        }
+        match expert {
+            Some(Move::Play(best)) if !matched => line.push_str(&format!(", the Expert would have played {}", format_card_message(&best))),
+            Some(Move::Draw) if !matched => line.push_str(", the Expert would have drawn"),
+            _ => {},
+        }
+        Some((line, matched))
+    }
+}
+
+// Keeps every human turn of a game, fed the same calls a frontend gets
+#[derive(Default)]
+pub struct Reviewer {
+    turns: Vec<Turn>,
+    number: usize,
+    human: bool, // Whether the turn going on is one to keep
+}
+
+impl Reviewer {
+    pub fn turn_start(&mut self, table: &Table) {
+        self.number += 1;
+        self.human = !table.is_ai(table.game_state.current_player);
+        if self.human {
+            self.turns.push(Turn { number: self.number, table: table.clone(), events: Vec::new() });
+        }
+    }
+
+    pub fn event(&mut self, event: &Event) {
+        if self.human && let Some(turn) = self.turns.last_mut() {
+            turn.events.push(event.clone());
+        }
+    }
+
+    // Every human seat's turns that are worth a word, then how often they went the Expert's way
+    pub fn report(&self) -> Vec<String> {
+        let Some(first) = self.turns.first() else { return Vec::new() };
+        let mut lines = Vec::new();
+        // Somebody who joined an AI seat late has turns of their own to review
+        let joined = |seat: usize| self.turns.iter().any(|turn| turn.seat() == seat);
+        for seat in (0..first.table.hands.len()).filter(|&seat| seat < first.table.players as usize || joined(seat)) {
+            let turns: Vec<&Turn> = self.turns.iter().filter(|turn| turn.seat() == seat && turn.did().is_some()).collect();
+            let reviews: Vec<(String, bool)> = turns.iter().filter_map(|turn| turn.review()).collect();
+            let differed = reviews.iter().filter(|(_, matched)| !matched).count();
+            lines.push(format!("Review of {}'s turns, next to the Expert AI:", first.table.names[seat]));
+            lines.extend(reviews.into_iter().map(|(line, _)| format!("  {}", line)));
+            lines.push(format!("  {} of {} moves were the Expert's", turns.len() - differed, turns.len()));
+        }
+        lines
+    }
+}
+
+// --review: the report goes out as notices when the game is over, before the frontend's own game over
+pub struct Review {
+    inner: Box<dyn Frontend>,
+    reviewer: Reviewer,
+}
+
+impl Review {
+    pub fn new(inner: Box<dyn Frontend>) -> Self {
+        Review { inner, reviewer: Reviewer::default() }
+    }
+}
+
+impl Frontend for Review {
+    fn event(&mut self, event: &Event) {
+        self.reviewer.event(event);
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.reviewer.turn_start(table);
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        for line in std::mem::take(&mut self.reviewer).report() {
+            self.inner.event(&Event::Notice(line));
+        }
+        self.inner.game_over(table, winner);
+    }
+
+    fn paused(&self) -> bool {
+        self.inner.paused()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Difficulty, play};
+    use crate::testing::{ScriptedFrontend, TestGame};
+
+    #[test]
+    fn mistakes_get_called_out() {
+        let mut game = TestGame::new(2)
+            .hand(0, &["red 5", "red 7", "wild", "blue 2", "blue 3", "green 4"])
+            .hand(1, &["red 9", "red 8", "yellow 3"])
+            .top("red 1")
+            .deck(&["yellow 1", "yellow 2", "yellow 4", "yellow 5"])
+            .ai_from(1, Difficulty::Calm)
+            .build();
+        let actions = ["d", "play red 5", "play wild", "blue"].map(String::from);
+        let mut script = ScriptedFrontend::default();
+        script.actions = actions.into();
+        let mut fe = Review::new(Box::new(script));
+        play(game.table_mut(), &mut fe, Some(3)).unwrap();
+
+        let report = fe.reviewer.report();
+        assert_eq!(report[0], "Review of Player #1's turns, next to the Expert AI:");
+        assert!(report[1].starts_with("  Turn 1: drew with Red 5 to play (missed a playable card)"), "{}", report[1]);
+        assert!(report[2].starts_with("  Turn 3: played Wild Card (Blue) with 6 cards and Red 7 to play (wasted a wild early)"), "{}", report[2]);
+        assert!(report[3].ends_with("of 2 moves were the Expert's"), "{}", report[3]);
+    }
+}
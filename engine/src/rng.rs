@@ -0,0 +1,571 @@
+// Random numbers, seeded from the OS
+// Randler is the fast, reproducible generator, ChaChaRng is the one you can't predict
+
+use std::io::{Result, Error};
+
+// For a random number generator on windows
+#[cfg(windows)]
+#[link(name = "bcrypt")]
+unsafe extern "system" {
+    unsafe fn BCryptGenRandom(
+        hAlgorithm: *mut core::ffi::c_void,
+        pbBuffer: *mut u8,
+        cbBuffer: u32,
+        dwFlags: u32,
+    ) -> i32;
+}
+
+// The getrandom syscall wrapper, so seeding works even without /dev mounted (containers, chroots)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+unsafe extern "C" {
+    unsafe fn getrandom(buf: *mut u8, buflen: usize, flags: u32) -> isize;
+}
+
+// macOS and the BSDs have getentropy, which is fine for anything up to 256 bytes
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "dragonfly"))]
+unsafe extern "C" {
+    unsafe fn getentropy(buf: *mut u8, buflen: usize) -> i32;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Randler {
+    state: u64
+}
+
+// The golden ratio increment SplitMix64 walks by
+const SPLITMIX_GAMMA: u64 = 0x9E3779B97F4A7C15;
+
+// One step of SplitMix64, used to turn any seed into a well mixed xorshift state
+fn splitmix64(x: &mut u64) -> u64 {
+    *x = x.wrapping_add(SPLITMIX_GAMMA);
+    let mut z = *x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+// For good practice, add default
+impl Default for Randler {
+    fn default() -> Self {
+        // Bro if you went out of your way to use a custom OS not derived from Linux or Windows AND are compiling this project? That's all on you
+        Self::urandom_seed_init().expect("Failed to seed from getrandom, getentropy, /dev/urandom or bcryptprimitives. Use new(seed) instead.")
+    }
+}
+
+impl Randler {
+    // Creates a new instance based on a seed
+    // Any u64 works, 0 and other low-entropy seeds get spread out by SplitMix64 first
+    pub fn new(seed: u64) -> Self {
+        Self::stream(seed, 0)
+    }
+
+    // An independent generator derived from the same seed, stream 0 is the one new() gives
+    pub fn stream(seed: u64, stream: u64) -> Self {
+        let mut x = seed.wrapping_add(stream.wrapping_mul(SPLITMIX_GAMMA));
+        let mut state = splitmix64(&mut x);
+        // xorshift gets stuck on zero forever, SplitMix64 only hits it for one input
+        while state == 0 {
+            state = splitmix64(&mut x);
+        }
+        Randler { state }
+    }
+
+    // Hands out a new generator with its own stream and moves this one forward
+    // The same master seed always splits into the same children, so work spread across threads stays reproducible
+    // (xorshift64* is one big cycle, so every stream is a random spot on it and overlaps are astronomically unlikely)
+    pub fn split(&mut self) -> Randler {
+        let mut x = self.rand();
+        let mut state = splitmix64(&mut x);
+        while state == 0 {
+            state = splitmix64(&mut x);
+        }
+        Randler { state }
+    }
+
+    // Continues exactly where another generator left off, for saved games
+    pub fn from_state(state: u64) -> Self {
+        if state == 0 {
+            return Self::new(0);
+        }
+        Randler { state }
+    }
+
+    // The raw xorshift state, which is what from_state takes back
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    // Fills the buffer straight from the kernel with getrandom
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn get_base_random_syscall(buffer: &mut [u8; 8]) -> Result<()> {
+        // Requests this small never come back short, so anything but 8 is a failure
+        let n = unsafe { getrandom(buffer.as_mut_ptr(), buffer.len(), 0) };
+        if n != buffer.len() as isize {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Fills the buffer straight from the kernel with getentropy
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "dragonfly"))]
+    fn get_base_random_syscall(buffer: &mut [u8; 8]) -> Result<()> {
+        let status = unsafe { getentropy(buffer.as_mut_ptr(), buffer.len()) };
+        if status != 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Every other unix only gets the device file
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "dragonfly"))))]
+    fn get_base_random_syscall(_buffer: &mut [u8; 8]) -> Result<()> {
+        Err(Error::new(std::io::ErrorKind::Unsupported, "No random syscall on this platform"))
+    }
+
+    // Gets a seed from the kernel, falling back to urandom if the syscall isn't there (old kernels, odd libcs)
+    #[cfg(unix)]
+    pub fn get_base_random_udev() -> Result<u64> {
+        use std::fs::File;
+        use std::io::Read;
+    
+        let mut buffer = [0u8; 8];
+        
+        if Self::get_base_random_syscall(&mut buffer).is_err() {
+            let mut file = File::open("/dev/urandom")?;
+            file.read_exact(&mut buffer)?;
+        }
+        
+        Ok(u64::from_ne_bytes(buffer))
+    }
+
+    // Gets a random seed based off of bcryptprimitives
+    #[cfg(windows)]
+    pub fn get_base_random_udev() -> Result<u64> {
+        let mut buf = [0u8; 8];
+        const BCRYPT_USE_SYSTEM_PREFERRED_RNG: u32 = 0x00000002;
+
+        let status = unsafe {
+            BCryptGenRandom(
+                core::ptr::null_mut(),
+                buf.as_mut_ptr(),
+                buf.len() as u32,
+                BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+            )
+        };
+
+        // NTSTATUS: success is >= 0
+        if status < 0 {
+            return Err(Error::other(
+                format!("BCryptGenRandom failed with status {status:#x}"),
+            ));
+        }
+
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    // No OS to ask on bare targets like the browser build, the host has to pass in a seed
+    #[cfg(not(any(unix, windows)))]
+    pub fn get_base_random_udev() -> Result<u64> {
+        Err(Error::new(std::io::ErrorKind::Unsupported, "No random source on this platform, pass in a seed"))
+    }
+
+    // Automatically instantiates an instance based off of the seed
+    pub fn urandom_seed_init() -> Result<Self> {
+        Ok(Self::new(Randler::get_base_random_udev()?))
+    }
+
+    // Creates a random number based on xorshift64* (Marsaglia's shift triple plus Vigna's multiplier)
+    // The old shift directions weren't a proper triple and had a short, streaky period
+    pub fn rand(&mut self) -> u64 {
+        let mut x = self.state;
+        
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        
+        self.state = x;
+        x.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+}
+
+// Anything that can hand out random numbers
+// Randler is the default, but the game only ever asks for this, so the rand crate or a mock can be swapped in
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    // Creates a random number within a defined range
+    fn gen_range(&mut self, min: u64, max: u64) -> Option<u64>  {
+        // Ensure input is proper, logged rather than printed since stdout belongs to whoever drives the engine
+        if min > max {
+            crate::log!(crate::log::Level::Warn, "gen_range got a max of {} below the min of {}", max, min);
+            return None;
+        }
+
+        // Ensure integer overflow doesn't occur
+        if min == 0 && max == u64::MAX { return Some(self.next_u64()); }
+
+        // Normalize
+        let range = max - min + 1;
+        let limit = u64::MAX - (u64::MAX % range);
+
+        // Prevent modulo bias
+        let mut x = self.next_u64();
+        while x >= limit {
+            x = self.next_u64();
+        }
+        
+        let ranged = (x % range) + min;
+        
+        Some(ranged)
+    }
+
+    // Same as gen_range, but for ranges that go below zero
+    fn rand_range_i64(&mut self, min: i64, max: i64) -> Option<i64> {
+        if min > max {
+            return None;
+        }
+        // The distance always fits in a u64, even for i64::MIN..=i64::MAX
+        let span = max.wrapping_sub(min) as u64;
+        let offset = self.gen_range(0, span)?;
+        Some(min.wrapping_add(offset as i64))
+    }
+
+    // True with the given probability, anything at or below 0 is never and at or above 1 is always
+    fn rand_bool(&mut self, probability: f64) -> bool {
+        // The top 53 bits give every float in 0..1 a fair chance
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        unit < probability
+    }
+
+    // A random element, None for an empty slice
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> where Self: Sized {
+        if items.is_empty() {
+            return None;
+        }
+        let idx = self.gen_range(0, items.len() as u64 - 1)?;
+        crate::log!(crate::log::Level::Trace, "Random pick {} of {}", idx + 1, items.len());
+        items.get(idx as usize)
+    }
+
+    // Fisher-Yates, every order is equally likely
+    fn shuffle<T>(&mut self, items: &mut [T]) where Self: Sized {
+        crate::log!(crate::log::Level::Trace, "Shuffling {} items", items.len());
+        for i in (1..items.len()).rev() {
+            if let Some(j) = self.gen_range(0, i as u64) {
+                items.swap(i, j as usize);
+            }
+        }
+    }
+}
+
+impl Rng for Randler {
+    fn next_u64(&mut self) -> u64 {
+        self.rand()
+    }
+}
+
+// How many 64 byte blocks ChaChaRng hands out before mixing in fresh OS entropy
+const CHACHA_REKEY_BLOCKS: u32 = 1024;
+
+fn chacha_quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]); x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]); x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]); x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]); x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
+// The ChaCha20 block function from RFC 8439
+fn chacha20_block(key: &[u32; 8], counter: u32, nonce: &[u32; 3]) -> [u32; 16] {
+    let mut state = [0u32; 16];
+    // "expand 32-byte k"
+    state[..4].copy_from_slice(&[0x61707865, 0x3320646e, 0x79622d32, 0x6b206574]);
+    state[4..12].copy_from_slice(key);
+    state[12] = counter;
+    state[13..].copy_from_slice(nonce);
+
+    let mut x = state;
+    for _ in 0..10 {
+        chacha_quarter_round(&mut x, 0, 4, 8, 12);
+        chacha_quarter_round(&mut x, 1, 5, 9, 13);
+        chacha_quarter_round(&mut x, 2, 6, 10, 14);
+        chacha_quarter_round(&mut x, 3, 7, 11, 15);
+        chacha_quarter_round(&mut x, 0, 5, 10, 15);
+        chacha_quarter_round(&mut x, 1, 6, 11, 12);
+        chacha_quarter_round(&mut x, 2, 7, 8, 13);
+        chacha_quarter_round(&mut x, 3, 4, 9, 14);
+    }
+    for (word, initial) in x.iter_mut().zip(state) {
+        *word = word.wrapping_add(initial);
+    }
+    x
+}
+
+// A cryptographically strong generator for games where players must not be able to predict the deck
+// Randler can be followed by anyone who sees enough of its output, this one can't
+// Keyed from the OS, and rekeyed every CHACHA_REKEY_BLOCKS blocks
+#[derive(Debug, Clone)]
+pub struct ChaChaRng {
+    key: [u32; 8],
+    nonce: [u32; 3],
+    counter: u32,
+    buffer: [u32; 16],
+    index: usize, // Next unused word of the buffer, 16 means empty
+}
+
+impl ChaChaRng {
+    fn from_key(key: [u32; 8], nonce: [u32; 3]) -> Self {
+        ChaChaRng { key, nonce, counter: 0, buffer: [0; 16], index: 16 }
+    }
+
+    // Keys the generator from the same OS sources Randler seeds from
+    pub fn from_os() -> Result<Self> {
+        let mut key = [0u32; 8];
+        for pair in key.chunks_mut(2) {
+            let bits = Randler::get_base_random_udev()?;
+            pair[0] = bits as u32;
+            pair[1] = (bits >> 32) as u32;
+        }
+        Ok(Self::from_key(key, [0; 3]))
+    }
+
+    // The new key is the next block of output mixed with fresh OS entropy
+    // If the OS can't be reached the output alone still erases the old key
+    fn rekey(&mut self) {
+        let block = chacha20_block(&self.key, self.counter, &self.nonce);
+        for (i, word) in self.key.iter_mut().enumerate() {
+            *word = block[i];
+        }
+        if let Ok(fresh) = Self::from_os() {
+            for (word, extra) in self.key.iter_mut().zip(fresh.key) {
+                *word ^= extra;
+            }
+        }
+        self.nonce[0] = self.nonce[0].wrapping_add(1);
+        self.counter = 0;
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.index == 16 {
+            if self.counter == CHACHA_REKEY_BLOCKS {
+                self.rekey();
+            }
+            self.buffer = chacha20_block(&self.key, self.counter, &self.nonce);
+            self.counter += 1;
+            self.index = 0;
+        }
+        let word = self.buffer[self.index];
+        self.index += 1;
+        word
+    }
+}
+
+impl Rng for ChaChaRng {
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32() as u64;
+        let high = self.next_u32() as u64;
+        (high << 32) | low
+    }
+}
+
+// The deck's generator: reproducible from a seed by default, or unpredictable with --secure-rng
+#[derive(Debug, Clone)]
+pub enum DeckRng {
+    Seeded(Randler),
+    Secure(ChaChaRng),
+}
+
+impl Rng for DeckRng {
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            DeckRng::Seeded(rand) => rand.next_u64(),
+            DeckRng::Secure(rand) => rand.next_u64(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference outputs for xorshift64*, computed independently
+    #[test]
+    fn randler_known_answers() {
+        let mut rand = Randler::from_state(1);
+        assert_eq!(rand.rand(), 0x47e4ce4b896cdd1d);
+        assert_eq!(rand.rand(), 0xabcfa6a8e079651d);
+        assert_eq!(rand.rand(), 0xb9d10d8feb731f57);
+        assert_eq!(rand.rand(), 0x4db418a0bb1b019d);
+
+        let mut rand = Randler::from_state(0xDEADBEEF);
+        assert_eq!(rand.rand(), 0x46151251b681bada);
+        assert_eq!(rand.rand(), 0x7db211d8263ef2a6);
+        assert_eq!(rand.rand(), 0x4bfdeea98d3b4d52);
+    }
+
+    // Reference outputs for SplitMix64
+    #[test]
+    fn splitmix_known_answers() {
+        let mut x = 0;
+        assert_eq!(splitmix64(&mut x), 0xe220a8397b1dcdaf);
+        assert_eq!(splitmix64(&mut x), 0x6e789e6aa1b965f4);
+        assert_eq!(splitmix64(&mut x), 0x06c45d188009454f);
+
+        let mut x = 1234567;
+        assert_eq!(splitmix64(&mut x), 0x599ed017fb08fc85);
+        assert_eq!(splitmix64(&mut x), 0x2c73f08458540fa5);
+    }
+
+    // A seed of 0 used to repeat 0 forever
+    #[test]
+    fn zero_seed_is_healthy() {
+        let mut rand = Randler::new(0);
+        let values: Vec<u64> = (0..8).map(|_| rand.rand()).collect();
+        assert!(values.iter().all(|&v| v != 0));
+        assert!(values.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    // RFC 8439 section 2.3.2
+    #[test]
+    fn chacha20_block_test_vector() {
+        let key: [u32; 8] = std::array::from_fn(|i| u32::from_le_bytes([4 * i as u8, 4 * i as u8 + 1, 4 * i as u8 + 2, 4 * i as u8 + 3]));
+        let nonce = [0x09000000, 0x4a000000, 0x00000000];
+        let block = chacha20_block(&key, 1, &nonce);
+        assert_eq!(block, [
+            0xe4e7f110, 0x15593bd1, 0x1fdd0f50, 0xc47120a3,
+            0xc7f4d1c7, 0x0368c033, 0x9aaa2204, 0x4e6cd4c3,
+            0x466482d2, 0x09aa9f07, 0x05d7c214, 0xa2028bd9,
+            0xd19c12b5, 0xb94e16de, 0xe883d0cb, 0x4e3c50a2,
+        ]);
+    }
+
+    // The generator walks the keystream in order and rekeys without repeating itself
+    #[test]
+    fn chacha_rng_follows_keystream() {
+        let mut rand = ChaChaRng::from_key([7; 8], [0; 3]);
+        let first = chacha20_block(&[7; 8], 0, &[0; 3]);
+        assert_eq!(rand.next_u64(), (first[1] as u64) << 32 | first[0] as u64);
+
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..(CHACHA_REKEY_BLOCKS as usize * 8 + 64) {
+            assert!(seen.insert(rand.next_u64()));
+        }
+    }
+
+    // Streams of one seed start from the SplitMix64 outputs in order
+    #[test]
+    fn streams_are_independent() {
+        let mut x = 99;
+        assert_eq!(Randler::new(99).state, splitmix64(&mut x));
+        assert_eq!(Randler::stream(99, 1).state, splitmix64(&mut x));
+        assert_ne!(Randler::stream(99, 1).rand(), Randler::stream(99, 2).rand());
+    }
+
+    #[test]
+    fn split_is_reproducible_and_independent() {
+        let mut master = Randler::new(77);
+        let children: Vec<Randler> = (0..4).map(|_| master.split()).collect();
+
+        let mut again = Randler::new(77);
+        let repeat: Vec<Randler> = (0..4).map(|_| again.split()).collect();
+        assert_eq!(children.iter().map(|c| c.state()).collect::<Vec<_>>(), repeat.iter().map(|c| c.state()).collect::<Vec<_>>());
+
+        // No two children (or the parent) should walk in lockstep
+        let mut firsts: Vec<u64> = children.into_iter().map(|mut c| c.rand()).collect();
+        firsts.push(master.rand());
+        let mut unique = firsts.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), firsts.len());
+    }
+
+    // Every position of a small slice should see every element about equally often
+    #[test]
+    fn shuffle_is_roughly_uniform() {
+        let mut rand = Randler::new(12345);
+        let mut counts = [[0u32; 4]; 4];
+        let rounds = 40_000;
+
+        for _ in 0..rounds {
+            let mut items = [0usize, 1, 2, 3];
+            rand.shuffle(&mut items);
+            for (pos, item) in items.iter().enumerate() {
+                counts[pos][*item] += 1;
+            }
+        }
+
+        let expected = rounds as f64 / 4.0;
+        for row in counts {
+            for count in row {
+                assert!((count as f64 - expected).abs() < expected * 0.05, "{:?}", counts);
+            }
+        }
+    }
+
+    #[test]
+    fn shuffle_keeps_every_element() {
+        let mut rand = Randler::new(3);
+        let mut items: Vec<u32> = (0..108).collect();
+        rand.shuffle(&mut items);
+        items.sort();
+        assert_eq!(items, (0..108).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn ranges_stay_in_bounds() {
+        let mut rand = Randler::new(7);
+        for _ in 0..10_000 {
+            let x = rand.gen_range(5, 9).unwrap();
+            assert!((5..=9).contains(&x));
+            let y = rand.rand_range_i64(-3, 2).unwrap();
+            assert!((-3..=2).contains(&y));
+        }
+        assert_eq!(rand.gen_range(4, 4), Some(4));
+        assert_eq!(rand.rand_range_i64(-8, -8), Some(-8));
+        assert_eq!(rand.gen_range(9, 5), None);
+        assert_eq!(rand.rand_range_i64(1, -1), None);
+        // The full ranges can't overflow
+        rand.gen_range(0, u64::MAX).unwrap();
+        rand.rand_range_i64(i64::MIN, i64::MAX).unwrap();
+    }
+
+    // Three buckets don't divide 2^64 evenly, so a plain modulo would be (slightly) biased
+    #[test]
+    fn ranges_are_unbiased() {
+        let mut rand = Randler::new(2026);
+        let mut counts = [0u32; 3];
+        let mut signed_counts = [0u32; 3];
+        let rounds = 60_000;
+        for _ in 0..rounds {
+            counts[rand.gen_range(0, 2).unwrap() as usize] += 1;
+            signed_counts[(rand.rand_range_i64(-1, 1).unwrap() + 1) as usize] += 1;
+        }
+        let expected = rounds as f64 / 3.0;
+        for count in counts.iter().chain(&signed_counts) {
+            assert!((*count as f64 - expected).abs() < expected * 0.03, "{:?} {:?}", counts, signed_counts);
+        }
+    }
+
+    #[test]
+    fn rand_bool_follows_probability() {
+        let mut rand = Randler::new(11);
+        assert!((0..1000).all(|_| !rand.rand_bool(0.0)));
+        assert!((0..1000).all(|_| rand.rand_bool(1.0)));
+
+        let hits = (0..20_000).filter(|_| rand.rand_bool(0.25)).count();
+        assert!((hits as f64 - 5000.0).abs() < 300.0, "{}", hits);
+    }
+
+    #[test]
+    fn choose_picks_from_the_slice() {
+        let mut rand = Randler::new(5);
+        let empty: [u8; 0] = [];
+        assert_eq!(rand.choose(&empty), None);
+        assert_eq!(rand.choose(&[42]), Some(&42));
+
+        let mut seen = [false; 4];
+        for _ in 0..200 {
+            seen[*rand.choose(&[0, 1, 2, 3]).unwrap()] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+}
@@ -0,0 +1,596 @@
+// The rules: the ones that can be changed before a game starts, and what each card does to the turns after it
+// "house" is how UNOler has always played, "official" is closer to the rules printed in the box
+//
+// Nothing in here prints, asks or shuffles, play() does that and asks these functions what happens next
+
+use std::str::FromStr;
+
+use crate::cards::{DeckShape, MAX_NUMBER, SpecialCard, UNOCard};
+use crate::effects::Effect;
+use crate::game::Game;
+use crate::json::Json;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuleSet {
+    pub stacking: bool, // +2 and +4 can be answered with another draw card instead of drawing
+    pub force_play: bool, // No drawing while holding a card that can be played
+    pub hand_size: u8,
+    pub opening_action: bool, // A Skip or Reverse turned up to start the game counts as if it had been played
+    pub draw_one: bool, // Drawing takes one card and ends the turn, unless that card gets played right away
+    pub trade: bool, // Once a game each seat can spend a turn swapping a card for the top of the deck, unseen
+    pub illegal_penalty: u8, // Cards drawn for trying to play a card that doesn't fit, the turn goes on after
+    pub uno_penalty: u8, // Cards drawn for getting down to one card without calling UNO, 0 calls it for everybody
+    pub challenge_penalty: u8, // Cards on top of the +4 for challenging one that wasn't a bluff, 0 for no challenging
+    pub colors: u8, // Four for a standard deck, five or six add purple and orange cards, for big tables
+    pub highest_number: i8, // Number cards go from 0 to this, 9 in a standard deck
+    pub cards: [Effect; HOUSE_CARDS], // What each number does besides being matched, house cards
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet { stacking: true, force_play: false, hand_size: 7, opening_action: false, draw_one: false, trade: false, illegal_penalty: 0, uno_penalty: 0, challenge_penalty: 0, colors: 4, highest_number: 9, cards: [Effect::NONE; HOUSE_CARDS] }
+    }
+}
+
+// One effect for every number a deck can have, whether or not highest_number says it's in the deck
+pub const HOUSE_CARDS: usize = MAX_NUMBER as usize + 1;
+
+// Presets by name
+impl FromStr for RuleSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "house" => Ok(RuleSet::default()),
+            "official" => Ok(RuleSet { stacking: false, opening_action: true, draw_one: true, ..RuleSet::default() }),
+            _ => Err(format!("Unknown rule preset {} (try house or official)", s)),
+        }
+    }
+}
+
+pub(crate) fn on_off(key: &str, value: &str) -> Result<bool, String> {
+    match value.trim().to_lowercase().as_str() {
+        "on" | "true" | "yes" | "1" => Ok(true),
+        "off" | "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("{} should be on or off, not {}", key, value)),
+    }
+}
+
+impl RuleSet {
+    // Every rule that can be set by name, the same names work as flags, config keys and save file keys
+    pub const NAMES: [&'static str; 11 + HOUSE_CARDS] = [
+        "stacking", "force-play", "hand-size", "opening-action", "draw-one", "trade", "illegal-penalty", "uno-penalty",
+        "challenge-penalty", "colors", "highest-number", "card-0", "card-1", "card-2", "card-3", "card-4", "card-5", "card-6", "card-7", "card-8", "card-9", "card-10", "card-11",
+        "card-12",
+    ];
+    pub const PRESETS: [&'static str; 2] = ["house", "official"];
+
+    // The preset these rules are exactly, None once anything's been changed
+    pub fn preset(&self) -> Option<&'static str> {
+        RuleSet::PRESETS.into_iter().find(|p| p.parse::<RuleSet>().as_ref() == Ok(self))
+    }
+
+    // One line for the pause menu, "house rules: stacking on, ..."
+    pub fn summary(&self) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        format!(
+            "{} rules: stacking {}, force play {}, {} cards each, opening action {}, draw one {}{}",
+            self.preset().map_or(String::from("Custom"), |p| format!("{}{}", p[..1].to_uppercase(), &p[1..])),
+            on_off(self.stacking), on_off(self.force_play), self.hand_size, on_off(self.opening_action), on_off(self.draw_one),
+            if self.trade { ", trading with the deck" } else { "" },
+        ) + &match self.illegal_penalty {
+            0 => String::new(),
+            n => format!(", {} card{} for an illegal play", n, if n == 1 { "" } else { "s" }),
+        } + &match self.uno_penalty {
+            0 => String::new(),
+            n => format!(", {} card{} for a forgotten UNO", n, if n == 1 { "" } else { "s" }),
+        } + &match self.challenge_penalty {
+            0 => String::new(),
+            n => format!(", +4s can be challenged for {} card{}", n, if n == 1 { "" } else { "s" }),
+        } + &match self.colors {
+            4 => String::new(),
+            n => format!(", {} colors", n),
+        } + &match self.highest_number {
+            9 => String::new(),
+            n => format!(", numbers up to {}", n),
+        } + &self.house_cards().map(|(n, effect)| format!(", {}s {}", n, effect)).collect::<String>()
+    }
+
+    // The numbers that do something, with what
+    pub fn house_cards(&self) -> impl Iterator<Item = (usize, Effect)> + '_ {
+        self.cards.iter().copied().enumerate().filter(|(_, effect)| !effect.is_none())
+    }
+
+    // Which cards a deck has under these rules
+    pub fn deck(&self) -> DeckShape {
+        DeckShape { colors: self.colors, highest: self.highest_number }
+    }
+
+    // Nothing for anything but a number card
+    pub fn effect(&self, card: &UNOCard) -> Effect {
+        match card.special {
+            SpecialCard::Base => self.cards.get(card.number as usize).copied().unwrap_or_default(),
+            _ => Effect::default(),
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key.replace('_', "-").as_str() {
+            "stacking" => self.stacking = on_off(key, value)?,
+            "force-play" => self.force_play = on_off(key, value)?,
+            "opening-action" => self.opening_action = on_off(key, value)?,
+            "draw-one" => self.draw_one = on_off(key, value)?,
+            "trade" => self.trade = on_off(key, value)?,
+            "illegal-penalty" => {
+                self.illegal_penalty = value.trim().parse().ok()
+                    .filter(|n| *n <= 10)
+                    .ok_or(format!("illegal-penalty should be a number from 0 to 10, not {}", value))?;
+            },
+            "uno-penalty" => {
+                self.uno_penalty = value.trim().parse().ok()
+                    .filter(|n| *n <= 10)
+                    .ok_or(format!("uno-penalty should be a number from 0 to 10, not {}", value))?;
+            },
+            "challenge-penalty" => {
+                self.challenge_penalty = value.trim().parse().ok()
+                    .filter(|n| *n <= 10)
+                    .ok_or(format!("challenge-penalty should be a number from 0 to 10, not {}", value))?;
+            },
+            "colors" => {
+                self.colors = value.trim().parse().ok()
+                    .filter(|n| (4..=6).contains(n))
+                    .ok_or(format!("colors should be a number from 4 to 6, not {}", value))?;
+            },
+            "highest-number" => {
+                self.highest_number = value.trim().parse().ok()
+                    .filter(|n| (9..=MAX_NUMBER).contains(n))
+                    .ok_or(format!("highest-number should be a number from 9 to {}, not {}", MAX_NUMBER, value))?;
+            },
+            "hand-size" => {
+                self.hand_size = value.trim().parse().ok()
+                    .filter(|n| (1..=30).contains(n))
+                    .ok_or(format!("hand-size should be a number from 1 to 30, not {}", value))?;
+            },
+            card if let Some(n) = card.strip_prefix("card-") && let Ok(n @ 0..HOUSE_CARDS) = n.parse::<usize>() => self.cards[n] = value.parse()?,
+            _ => return Err(format!("Unknown rule {}", key)),
+        }
+        Ok(())
+    }
+
+    // The preset first, then each override in order
+    pub fn build(preset: Option<&str>, overrides: &[(String, String)]) -> Result<RuleSet, String> {
+        let mut rules = match preset {
+            Some(preset) => preset.parse()?,
+            None => RuleSet::default(),
+        };
+        for (key, value) in overrides {
+            rules.set(key, value)?;
+        }
+        Ok(rules)
+    }
+
+    // The flags that set these rules up again, nothing for the house rules. Anything a preset doesn't cover is written
+    // as a change from the house rules
+    pub fn flags(&self) -> String {
+        if let Some(preset) = self.preset() {
+            return if preset == "house" { String::new() } else { format!("--rules {}", preset) };
+        }
+        // The house rules have every rule to_json leaves out turned off, so a rule missing here is one that's the same
+        let (rules, house) = (self.to_json(), RuleSet::default().to_json());
+        RuleSet::NAMES.into_iter()
+            .filter_map(|name| rules.get(name).filter(|&value| house.get(name) != Some(value)).map(|value| (name, value)))
+            .filter_map(|(name, value)| json_rule_value(value).map(|value| format!("--{} {}", name, value)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // Trading, the penalties, bigger decks and house cards only when they're on, so older files read back the same
+    pub fn to_json(self) -> Json {
+        let mut rules = vec![
+            ("stacking", self.stacking.into()),
+            ("force-play", self.force_play.into()),
+            ("hand-size", (self.hand_size as usize).into()),
+            ("opening-action", self.opening_action.into()),
+            ("draw-one", self.draw_one.into()),
+        ];
+        if self.trade {
+            rules.push(("trade", true.into()));
+        }
+        if self.illegal_penalty > 0 {
+            rules.push(("illegal-penalty", (self.illegal_penalty as usize).into()));
+        }
+        if self.uno_penalty > 0 {
+            rules.push(("uno-penalty", (self.uno_penalty as usize).into()));
+        }
+        if self.challenge_penalty > 0 {
+            rules.push(("challenge-penalty", (self.challenge_penalty as usize).into()));
+        }
+        if self.colors != 4 {
+            rules.push(("colors", (self.colors as usize).into()));
+        }
+        if self.highest_number != 9 {
+            rules.push(("highest-number", (self.highest_number as usize).into()));
+        }
+        // The card-N names come last
+        rules.extend(self.house_cards().map(|(n, effect)| (RuleSet::NAMES[RuleSet::NAMES.len() - HOUSE_CARDS + n], effect.to_string().into())));
+        Json::object(rules)
+    }
+
+    // Rules missing from the file keep their defaults, older saves have none at all
+    pub fn from_json(value: &Json) -> Option<RuleSet> {
+        let mut rules = RuleSet::default();
+        for name in RuleSet::NAMES {
+            // force_play works too, for formats that can't have a dash in a key
+            if let Some(value) = value.get(name).or_else(|| value.get(&name.replace('-', "_"))) {
+                rules.set(name, &json_rule_value(value)?).ok()?;
+            }
+        }
+        Some(rules)
+    }
+}
+
+// A handicap on one seat, to even out a table where one player is far ahead of the others
+// Written like a house card effect, joined with +: draw<n>, no-stack and force-play
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Handicap {
+    pub extra_cards: u8, // Drawn on top of the hand at every deal
+    pub no_stacking: bool, // Never gets to answer a draw card, whatever the rules say
+    pub force_play: bool, // Has to play a card when they can, whatever the rules say
+}
+
+impl Handicap {
+    pub fn is_none(&self) -> bool {
+        *self == Handicap::default()
+    }
+}
+
+impl FromStr for Handicap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim().to_lowercase();
+        let mut handicap = Handicap::default();
+        if matches!(text.as_str(), "" | "none" | "off") {
+            return Ok(handicap);
+        }
+        for part in text.split(['+', ',']).map(str::trim) {
+            match part {
+                "no-stack" | "no-stacking" => handicap.no_stacking = true,
+                "force-play" => handicap.force_play = true,
+                _ if let Some(n) = part.strip_prefix("draw") => {
+                    handicap.extra_cards = n.trim().parse().ok().filter(|n| (1..=10).contains(n))
+                        .ok_or(format!("{} should be draw1 to draw10", part))?;
+                },
+                _ => return Err(format!("Unknown handicap {} (try draw2, no-stack or force-play, joined with +)", part)),
+            }
+        }
+        Ok(handicap)
+    }
+}
+
+impl std::fmt::Display for Handicap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if self.extra_cards > 0 {
+            parts.push(format!("draw{}", self.extra_cards));
+        }
+        if self.no_stacking {
+            parts.push(String::from("no-stack"));
+        }
+        if self.force_play {
+            parts.push(String::from("force-play"));
+        }
+        if parts.is_empty() {
+            return write!(f, "none");
+        }
+        write!(f, "{}", parts.join("+"))
+    }
+}
+
+// Rule values as the same text a flag would take, so TOML and JSON booleans and numbers both work
+pub fn json_rule_value(value: &Json) -> Option<String> {
+    match value {
+        Json::Bool(b) => Some(String::from(if *b { "on" } else { "off" })),
+        Json::Number(n) if n.fract() == 0.0 => Some((*n as i64).to_string()),
+        Json::Str(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+// Checks if a move is legal
+pub fn allowed_move( card_chosen: UNOCard, last_card: UNOCard ) -> bool {
+    // Not doing Color == NA yet to ensure color choice shenanigans don't occur
+    if card_chosen.special == SpecialCard::ColorChange || card_chosen.special == SpecialCard::PlusFour {
+        return true; // I'm going to ignore the "you need no playable cards to play draw 4" because screw that rule
+    } 
+    
+    // Special cards on the same special card works
+    if card_chosen.special == last_card.special && card_chosen.special != SpecialCard::Base {
+        return true;
+    }
+    
+    // Default color-or-number match
+    if (card_chosen.special == SpecialCard::Base && card_chosen.number == last_card.number) || card_chosen.color == last_card.color {
+        return true;
+    }
+    
+    false // Can't play it!
+    
+}
+
+// Checks if there are any plus fours or plus twos in the hand
+pub fn check_countercards(hand: &[UNOCard]) -> bool {
+    hand.iter().any(|u: &UNOCard| u.special == SpecialCard::PlusFour || u.special == SpecialCard::PlusTwo)
+}
+
+// What the player whose turn it is gets to do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TurnStart {
+    Draw(u32), // Can't answer the draw cards waiting for them, so they take them and that's their turn
+    Skipped,
+    Play,
+}
+
+// Who plays next and what they owe, without any cards
+// play() copies this out of the Table, runs the turn through it and copies it back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TurnState {
+    pub game: Game,
+    pub add_queue: u32, // Cards waiting for the next player that can't answer with a draw card
+    pub skipped: bool, // The next player loses their turn
+}
+
+impl TurnState {
+    // can_counter is whether the player holds a draw card and stacking is on
+    pub fn start(&mut self, can_counter: bool) -> TurnStart {
+        if !can_counter && self.add_queue > 0 {
+            let cards = self.add_queue;
+            self.add_queue = 0;
+            self.skipped = false;
+            TurnStart::Draw(cards)
+        } else if self.skipped {
+            self.skipped = false;
+            TurnStart::Skipped
+        } else {
+            TurnStart::Play
+        }
+    }
+
+    // So for one +4s CANNOT be countered, but they can be played on a +2. They skip the next player too
+    pub fn played(&mut self, card: SpecialCard) {
+        match card {
+            SpecialCard::PlusFour => {
+                self.add_queue += 4;
+                self.skipped = true;
+            },
+            SpecialCard::PlusTwo => self.add_queue += 2,
+            SpecialCard::Skip => self.skipped = true,
+            // With two players going back the other way is the same as skipping
+            SpecialCard::Reverse if self.game.max_players == 2 => self.skipped = true,
+            SpecialCard::Reverse => self.game.reverse(),
+            SpecialCard::ColorChange | SpecialCard::Base => {},
+        }
+    }
+
+    // What a house card does to the turns after it, once the player has paid whatever they owed
+    // Skipping more than one moves the turn along past all but the last, who is the one told they were skipped
+    pub fn house_card(&mut self, effect: Effect) {
+        self.add_queue += effect.draw as u32;
+        if effect.reverse {
+            self.played(SpecialCard::Reverse);
+        }
+        if effect.skip > 0 {
+            for _ in 1..effect.skip {
+                self.game.next_turn();
+            }
+            self.skipped = true;
+        }
+    }
+
+    // How many cards the player draws before their turn ends
+    // Holding a draw card only helps if it gets played, otherwise the waiting cards are taken now
+    pub fn end(&mut self, can_counter: bool, played: Option<SpecialCard>) -> u32 {
+        let answered = matches!(played, Some(SpecialCard::PlusTwo | SpecialCard::PlusFour));
+        if !can_counter || answered || self.add_queue == 0 {
+            return 0;
+        }
+        let cards = self.add_queue;
+        self.add_queue = 0;
+        self.skipped = false;
+        cards
+    }
+}
+
+impl TurnState {
+    // The first turn of a game, which the opening card only changes with opening_action on
+    // A Skip skips the first player and a Reverse turns the table around so the dealer, who sits before the first
+    // player, goes first. With two players both come down to the same skip
+    pub fn opening(rules: &RuleSet, opening: SpecialCard, seats: usize, first: usize) -> TurnState {
+        let mut turn = TurnState { game: Game::new(first, seats, 1), add_queue: 0, skipped: false };
+        if rules.opening_action {
+            match opening {
+                SpecialCard::Reverse if seats > 2 => {
+                    turn.game.reverse();
+                    turn.game.next_turn();
+                },
+                SpecialCard::Skip | SpecialCard::Reverse => turn.skipped = true,
+                _ => {},
+            }
+        }
+        turn
+    }
+}
+
+pub fn is_uno(hand: &[UNOCard]) -> bool {
+    hand.len() == 1
+}
+
+pub fn has_won(hand: &[UNOCard]) -> bool {
+    hand.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{Color, PLAIN_COLORS, build_deck};
+    use crate::effects::Effect;
+
+    fn turn(seats: usize) -> TurnState {
+        TurnState { game: Game::new(0, seats, 1), add_queue: 0, skipped: false }
+    }
+
+    #[test]
+    fn every_pair_of_cards() {
+        // The active card can be any card with wilds colored in
+        let mut tops = build_deck();
+        for wild in build_deck().into_iter().filter(|c| c.color == Color::NA) {
+            tops.extend(PLAIN_COLORS.iter().map(|&color| UNOCard { color, ..wild }));
+        }
+        for top in &tops {
+            for card in build_deck() {
+                let expected = card.color == Color::NA
+                    || card.color == top.color
+                    || (card.special == SpecialCard::Base && top.special == SpecialCard::Base && card.number == top.number)
+                    || (card.special != SpecialCard::Base && card.special == top.special);
+                assert_eq!(allowed_move(card, *top), expected, "{:?} on {:?}", card, top);
+            }
+        }
+    }
+
+    #[test]
+    fn rules_know_their_preset() {
+        assert_eq!(RuleSet::default().preset(), Some("house"));
+        assert_eq!("official".parse::<RuleSet>().unwrap().preset(), Some("official"));
+        assert_eq!(RuleSet { hand_size: 5, ..RuleSet::default() }.preset(), None);
+    }
+
+    #[test]
+    fn rules_come_back_from_their_flags() {
+        assert_eq!(RuleSet::default().flags(), "");
+        assert_eq!("official".parse::<RuleSet>().unwrap().flags(), "--rules official");
+        let rules = RuleSet { stacking: false, trade: true, illegal_penalty: 2, colors: 6, highest_number: 12, ..RuleSet::default() };
+        assert_eq!(rules.flags(), "--stacking off --trade on --illegal-penalty 2 --colors 6 --highest-number 12");
+        let penalties = RuleSet { uno_penalty: 4, challenge_penalty: 2, ..RuleSet::default() };
+        assert_eq!(penalties.flags(), "--uno-penalty 4 --challenge-penalty 2");
+        assert_eq!(RuleSet::from_json(&penalties.to_json()), Some(penalties));
+        let mut card = RuleSet::default();
+        card.set("card-7", "swap").unwrap();
+        let overrides: Vec<(String, String)> = card.flags().split(" --").map(|f| {
+            let (key, value) = f.trim_start_matches("--").split_once(' ').unwrap();
+            (key.to_string(), value.to_string())
+        }).collect();
+        assert_eq!(RuleSet::build(None, &overrides), Ok(card));
+    }
+
+    #[test]
+    fn handicaps_are_written_like_card_effects() {
+        assert_eq!("draw2+no-stack".parse(), Ok(Handicap { extra_cards: 2, no_stacking: true, force_play: false }));
+        assert_eq!("Force-Play, draw1".parse::<Handicap>().map(|h| h.to_string()), Ok(String::from("draw1+force-play")));
+        assert!("none".parse::<Handicap>().unwrap().is_none());
+        assert!("draw0".parse::<Handicap>().is_err());
+        assert!("slow".parse::<Handicap>().unwrap_err().contains("Unknown handicap slow"));
+    }
+
+    #[test]
+    fn house_cards_are_set_by_number_and_kept_in_files() {
+        let mut rules = RuleSet::default();
+        rules.set("card-7", "swap").unwrap();
+        rules.set("card_0", "rotate").unwrap();
+        assert!(rules.set("card-13", "swap").is_err());
+        assert!(rules.set("card-3", "explode").is_err());
+        assert_eq!(rules.effect(&UNOCard::new(Color::Blue, SpecialCard::Base, 7)), Effect::default().swap());
+        assert!(rules.effect(&UNOCard::new(Color::Blue, SpecialCard::Skip, -1)).is_none());
+        assert!(rules.summary().ends_with(", 0s rotate, 7s swap"));
+        assert_eq!(RuleSet::from_json(&rules.to_json()), Some(rules));
+        assert!(RuleSet::default().to_json().get("card-7").is_none(), "nothing about house cards unless there are some");
+
+        // Numbers past 9 take effects too, for decks that go up to them
+        rules.set("highest-number", "12").unwrap();
+        rules.set("card-12", "draw2").unwrap();
+        assert_eq!(rules.effect(&UNOCard::new(Color::Red, SpecialCard::Base, 12)), Effect::default().draw(2));
+        assert!(rules.summary().ends_with(", 7s swap, 12s draw2"));
+        assert_eq!(rules.to_json().get("card-12").and_then(Json::as_str), Some("draw2"));
+        assert_eq!(RuleSet::from_json(&rules.to_json()), Some(rules));
+    }
+
+    #[test]
+    fn opening_actions_start_the_game_off() {
+        let official: RuleSet = "official".parse().unwrap();
+        let skip = TurnState::opening(&official, SpecialCard::Skip, 4, 0);
+        assert!(skip.skipped);
+        let reverse = TurnState::opening(&official, SpecialCard::Reverse, 4, 0);
+        assert_eq!((reverse.game.current_player, reverse.game.direction, reverse.skipped), (3, -1, false), "the dealer goes first");
+        assert!(TurnState::opening(&official, SpecialCard::Reverse, 2, 0).skipped);
+        assert_eq!(TurnState::opening(&official, SpecialCard::PlusTwo, 4, 0), turn(4));
+        assert_eq!(TurnState::opening(&RuleSet::default(), SpecialCard::Reverse, 4, 0), turn(4));
+    }
+
+    #[test]
+    fn draw_cards_pile_up_until_somebody_cant_answer() {
+        let mut state = turn(3);
+        state.played(SpecialCard::PlusTwo);
+        assert_eq!(state.start(true), TurnStart::Play);
+        assert_eq!(state.end(true, Some(SpecialCard::PlusTwo)), 0);
+        state.played(SpecialCard::PlusTwo);
+        assert_eq!(state.start(false), TurnStart::Draw(4));
+        assert_eq!(state, turn(3));
+    }
+
+    #[test]
+    fn holding_a_draw_card_without_playing_it_still_draws() {
+        let mut state = turn(3);
+        state.played(SpecialCard::PlusTwo);
+        assert_eq!(state.start(true), TurnStart::Play);
+        assert_eq!(state.end(true, Some(SpecialCard::Base)), 2);
+        assert_eq!(state.end(true, None), 0);
+    }
+
+    #[test]
+    fn draw_four_skips_and_draws_even_when_it_could_be_answered() {
+        let mut state = turn(3);
+        state.played(SpecialCard::PlusFour);
+        assert_eq!(state.start(true), TurnStart::Skipped);
+        assert_eq!(state.end(true, None), 4);
+        assert_eq!(state.start(false), TurnStart::Play);
+    }
+
+    #[test]
+    fn skip_and_reverse() {
+        let mut state = turn(3);
+        state.played(SpecialCard::Skip);
+        assert_eq!(state.start(false), TurnStart::Skipped);
+        assert_eq!(state.start(false), TurnStart::Play);
+
+        state.played(SpecialCard::Reverse);
+        assert_eq!(state.game.direction, -1);
+        assert_eq!(state.start(false), TurnStart::Play);
+        state.game.next_turn();
+        assert_eq!(state.game.current_player, 2);
+        state.game.current_player = 0;
+        state.game.next_turn();
+        assert_eq!(state.game.current_player, 2);
+
+        // Two players: a reverse is a skip
+        let mut state = turn(2);
+        state.played(SpecialCard::Reverse);
+        assert_eq!(state.game.direction, 1);
+        assert_eq!(state.start(false), TurnStart::Skipped);
+    }
+
+    #[test]
+    fn plain_cards_change_nothing() {
+        for special in [SpecialCard::Base, SpecialCard::ColorChange] {
+            let mut state = turn(4);
+            state.played(special);
+            assert_eq!(state, turn(4));
+        }
+    }
+
+    #[test]
+    fn uno_and_winning() {
+        let card = UNOCard::new(Color::Red, SpecialCard::Base, 3);
+        assert!(has_won(&[]) && !is_uno(&[]));
+        assert!(is_uno(&[card]) && !has_won(&[card]));
+        assert!(!is_uno(&[card, card]));
+    }
+}
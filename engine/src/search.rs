@@ -0,0 +1,471 @@
+// A game cut down to what a search needs, so trying moves out means copying bytes instead of a Table full of Vecs
+// Hands are counts of each of the 98 kinds of card, the deck is one byte per card and the discard pile under the top
+// card is counts too since it only ever gets shuffled back in. Cloning one is two memcpys and a handful of words,
+// with no names, generators for the real deck or frontends to come along
+//
+//     let mut what_if = Position::from_table(&table);
+//     what_if.apply(Move::Play(card));
+//     what_if.playout(500);
+
+use crate::cards::{ALL_COLORS, Color, MAX_NUMBER, SpecialCard, UNOCard, build_deck_of, colors};
+use crate::game::Table;
+use crate::rng::{Randler, Rng};
+use crate::rules::{RuleSet, TurnStart, TurnState, allowed_move};
+
+const PER_COLOR: usize = MAX_NUMBER as usize + 4; // Every number, then the skip, the reverse and the +2
+pub const KINDS: usize = PER_COLOR * ALL_COLORS.len() + 2; // Then the wild and the wild +4
+const WILD: usize = KINDS - 2;
+const WILD_FOUR: usize = KINDS - 1;
+
+const SPECIALS: [SpecialCard; 3] = [SpecialCard::Skip, SpecialCard::Reverse, SpecialCard::PlusTwo];
+
+// Wilds are one kind whatever color they were given
+fn kind(card: &UNOCard) -> usize {
+    let color = ALL_COLORS.iter().position(|c| *c == card.color).unwrap_or(0);
+    match card.special {
+        SpecialCard::ColorChange => WILD,
+        SpecialCard::PlusFour => WILD_FOUR,
+        SpecialCard::Base => color * PER_COLOR + card.number.clamp(0, MAX_NUMBER) as usize,
+        special => color * PER_COLOR + PER_COLOR - 3 + SPECIALS.iter().position(|s| *s == special).unwrap_or(0),
+    }
+}
+
+fn kind_card(kind: usize) -> UNOCard {
+    match kind {
+        WILD => UNOCard::new(Color::NA, SpecialCard::ColorChange, -1),
+        WILD_FOUR => UNOCard::new(Color::NA, SpecialCard::PlusFour, -1),
+        _ => match kind % PER_COLOR {
+            n if n < PER_COLOR - 3 => UNOCard::new(ALL_COLORS[kind / PER_COLOR], SpecialCard::Base, n as i8),
+            special => UNOCard::new(ALL_COLORS[kind / PER_COLOR], SPECIALS[special + 3 - PER_COLOR], -1),
+        },
+    }
+}
+
+fn is_wild(kind: usize) -> bool {
+    kind >= WILD
+}
+
+// What the seat being asked can do. A drawn card stays with them and they get asked again, like at the table, and
+// under draw-one the only question left is whether to play that card
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Move {
+    Play(UNOCard), // Wilds carry the color they're given
+    Draw,
+    Keep, // Under draw-one, ending the turn with the card just drawn still in hand
+}
+
+#[derive(Debug, Clone)]
+pub struct Position {
+    hands: Vec<[u16; KINDS]>,
+    deck: Vec<u8>, // Kinds, drawn from the end like the Table's deck
+    discard: [u16; KINDS], // Under the top card
+    top: UNOCard,
+    turn: TurnState,
+    can_counter: bool, // Whether the seat being asked held a draw card when their turn started
+    drawn: Option<usize>, // Under draw-one, the playable kind just drawn, which is all that's left to decide on
+    uno: bool,
+    rules: RuleSet,
+    rand: Randler, // For reshuffles and playouts, the real game's generators aren't touched
+    winner: Option<usize>,
+    lacking: Vec<[bool; 6]>, // The Table's, in ALL_COLORS order. Only guesses use it, moves don't keep it up
+}
+
+impl Position {
+    // The table at the start of a turn, as play() would pick it up
+    // Draws and skips waiting for the next seats are taken right away, so it's always somebody's decision
+    pub fn from_table(t: &Table) -> Position {
+        let counts = |cards: &[UNOCard]| {
+            let mut counts = [0u16; KINDS];
+            cards.iter().for_each(|c| counts[kind(c)] += 1);
+            counts
+        };
+        let mut discard = counts(&t.discard);
+        if let Some(top) = t.discard.last() {
+            discard[kind(top)] -= 1;
+        }
+        let mut position = Position {
+            hands: t.hands.iter().map(|h| counts(h)).collect(),
+            deck: t.deck.iter().map(|c| kind(c) as u8).collect(),
+            discard,
+            top: t.last_played,
+            turn: t.turn_state(),
+            can_counter: false,
+            drawn: None,
+            uno: t.uno_detection_panic,
+            rules: t.rules,
+            rand: t.ai_rand,
+            winner: None,
+            lacking: t.lacking.iter().map(|l| ALL_COLORS.map(|color| l.contains(&color))).collect(),
+        };
+        position.start_turn();
+        position
+    }
+
+    // A separate stream of randomness for this line of play, so sibling searches don't all reshuffle the same way
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand = Randler::new(seed);
+    }
+
+    // What seat can actually know: everybody else's cards and the deck are thrown together and dealt back out,
+    // each hand keeping its size. Its own hand, the top card and the discard pile stay as they are
+    // Seats that drew on a color get none of it, as long as there are enough other cards to go around
+    pub fn determinize(&mut self, seat: usize, rand: &mut impl Rng) {
+        let mut unseen = std::mem::take(&mut self.deck);
+        for hand in self.hands.iter().enumerate().filter(|(other, _)| *other != seat).map(|(_, hand)| hand) {
+            for (k, n) in hand.iter().enumerate() {
+                unseen.extend(std::iter::repeat_n(k as u8, *n as usize));
+            }
+        }
+        rand.shuffle(&mut unseen);
+        let mut others: Vec<usize> = (0..self.hands.len()).filter(|other| *other != seat).collect();
+        others.sort_by_key(|other| std::cmp::Reverse(self.lacking[*other].iter().filter(|l| **l).count()));
+        for other in others {
+            let size = self.hand_size(other);
+            let lacks = |k: u8| !is_wild(k as usize) && self.lacking[other][k as usize / PER_COLOR];
+            // The ones they can have go to the end, from where hands are dealt
+            let mut end = unseen.len();
+            for i in (0..unseen.len()).rev() {
+                if end + size == unseen.len() {
+                    break;
+                }
+                if !lacks(unseen[i]) {
+                    end -= 1;
+                    unseen.swap(i, end);
+                }
+            }
+            self.hands[other] = [0; KINDS];
+            for k in unseen.drain(unseen.len() - size..) {
+                self.hands[other][k as usize] += 1;
+            }
+        }
+        self.deck = unseen;
+    }
+
+    // What somebody watching doesn't know, with every hand face up: the order of the deck
+    pub fn shuffle_deck(&mut self, rand: &mut impl Rng) {
+        rand.shuffle(&mut self.deck);
+    }
+
+    pub fn seats(&self) -> usize {
+        self.hands.len()
+    }
+
+    // The seat index being asked, 0 based
+    pub fn to_move(&self) -> usize {
+        self.turn.game.current_player
+    }
+
+    // The seat number that went out, counted from 1 like play() does
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    pub fn top(&self) -> UNOCard {
+        self.top
+    }
+
+    pub fn hand_size(&self, seat: usize) -> usize {
+        self.hands[seat].iter().map(|n| *n as usize).sum()
+    }
+
+    pub fn hand(&self, seat: usize) -> Vec<UNOCard> {
+        (0..KINDS).flat_map(|k| std::iter::repeat_n(kind_card(k), self.hands[seat][k] as usize)).collect()
+    }
+
+    fn playable(&self, kind: usize) -> bool {
+        allowed_move(kind_card(kind), self.top)
+    }
+
+    // Every different play there is, wilds once for each color, and drawing when the rules allow it
+    pub fn moves(&self) -> Vec<Move> {
+        if self.winner.is_some() {
+            return Vec::new();
+        }
+        let hand = &self.hands[self.to_move()];
+        let mut moves = Vec::new();
+        for k in (0..KINDS).filter(|&k| hand[k] > 0 && self.playable(k) && self.drawn.is_none_or(|drawn| drawn == k)) {
+            let card = kind_card(k);
+            if is_wild(k) {
+                moves.extend(colors(self.rules.colors).iter().map(|&color| Move::Play(UNOCard { color, ..card })));
+            } else {
+                moves.push(Move::Play(card));
+            }
+        }
+        if self.drawn.is_some() {
+            if !self.rules.force_play {
+                moves.push(Move::Keep);
+            }
+        } else if !self.rules.force_play || moves.is_empty() {
+            moves.push(Move::Draw);
+        }
+        moves
+    }
+
+    // Refills the deck the way ensure_deck_full does: the discard pile if there is one, else a new deck
+    fn draw(&mut self, seat: usize) {
+        if self.deck.is_empty() {
+            if self.discard.iter().any(|n| *n > 0) {
+                for k in 0..KINDS {
+                    self.deck.extend(std::iter::repeat_n(k as u8, self.discard[k] as usize));
+                }
+                self.discard = [0; KINDS];
+            } else {
+                self.deck.extend(build_deck_of(self.rules.deck()).iter().map(|c| kind(c) as u8));
+            }
+            self.rand.shuffle(&mut self.deck);
+        }
+        if let Some(k) = self.deck.pop() {
+            self.hands[seat][k as usize] += 1;
+        }
+    }
+
+    // Runs the start of turns until somebody gets to decide, taking waiting draws and skips on the way
+    fn start_turn(&mut self) {
+        loop {
+            let seat = self.to_move();
+            let hand = &self.hands[seat];
+            self.can_counter = self.rules.stacking && (hand[WILD_FOUR] > 0 || (0..ALL_COLORS.len()).any(|c| hand[c * PER_COLOR + PER_COLOR - 1] > 0));
+            match self.turn.start(self.can_counter) {
+                TurnStart::Play => return,
+                TurnStart::Draw(cards) => (0..cards).for_each(|_| self.draw(seat)),
+                TurnStart::Skipped => {},
+            }
+            if self.hand_size(seat) == 1 {
+                self.uno = true;
+            }
+            self.turn.game.next_turn();
+        }
+    }
+
+    // Moves that aren't in moves() are ignored, apart from drawing under force play which a search might still want
+    pub fn apply(&mut self, mv: Move) {
+        if self.winner.is_some() {
+            return;
+        }
+        let seat = self.to_move();
+        match mv {
+            // Drawing on one card takes back the UNO call, the way it does for a human at the table
+            Move::Draw if self.drawn.is_none() => {
+                if self.hand_size(seat) == 1 {
+                    self.uno = false;
+                }
+                let before = self.hands[seat];
+                self.draw(seat);
+                // Under draw-one that's the turn, unless the card can be played
+                if self.rules.draw_one {
+                    self.drawn = (0..KINDS).find(|&k| self.hands[seat][k] > before[k]).filter(|&k| self.playable(k));
+                    if self.drawn.is_none() {
+                        self.end_turn(None);
+                    }
+                }
+            },
+            Move::Keep if self.drawn.is_some() && !self.rules.force_play => {
+                self.drawn = None;
+                self.end_turn(None);
+            },
+            Move::Draw | Move::Keep => {},
+            Move::Play(card) => {
+                let k = kind(&card);
+                if self.hands[seat][k] == 0 || !self.playable(k) || (is_wild(k) && card.color == Color::NA)
+                    || self.drawn.is_some_and(|drawn| drawn != k) {
+                    return;
+                }
+                self.drawn = None;
+                self.hands[seat][k] -= 1;
+                self.discard[kind(&self.top)] += 1;
+                self.top = card;
+                self.turn.played(card.special); // House cards are guessed to be plain numbers here
+                self.end_turn(Some(card.special));
+            },
+        }
+    }
+
+    // Waiting draws the seat didn't answer, then on to whoever decides next
+    fn end_turn(&mut self, played: Option<SpecialCard>) {
+        let seat = self.to_move();
+        for _ in 0..self.turn.end(self.can_counter, played) {
+            self.draw(seat);
+        }
+        match self.hand_size(seat) {
+            0 => self.winner = Some(seat + 1),
+            1 => self.uno = true,
+            _ => {},
+        }
+        if self.winner.is_none() {
+            self.turn.game.next_turn();
+            self.start_turn();
+        }
+    }
+
+    // Everybody plays a random legal card, drawing only when they have none, until somebody wins or the moves run out
+    pub fn playout(&mut self, max_moves: usize) -> Option<usize> {
+        for _ in 0..max_moves {
+            if self.winner.is_some() {
+                break;
+            }
+            let plays: Vec<Move> = self.moves().into_iter().filter(|m| matches!(m, Move::Play(_))).collect();
+            let mv = self.rand.choose(&plays).copied().unwrap_or(Move::Draw);
+            self.apply(mv);
+        }
+        self.winner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::DeckShape;
+    use crate::events::Event;
+    use crate::game::{Difficulty, Settings, play, setup};
+    use crate::testing::{ScriptedFrontend, TestGame, card, cards};
+
+    #[test]
+    fn every_kind_is_one_card() {
+        for k in 0..KINDS {
+            assert_eq!(kind(&kind_card(k)), k);
+        }
+        assert_eq!(kind(&card("wild +4 red")), WILD_FOUR);
+        let mut deck = [0; KINDS];
+        build_deck_of(DeckShape { colors: 6, highest: MAX_NUMBER }).iter().for_each(|c| deck[kind(c)] += 1);
+        assert!(deck.iter().all(|n| *n > 0));
+    }
+
+    #[test]
+    fn moves_follow_the_rules() {
+        let game = TestGame::new(2)
+            .hand(0, &["red 5", "blue 7", "wild", "red 5"])
+            .hand(1, &["green 1"])
+            .top("red 7")
+            .build();
+        let position = Position::from_table(game.table());
+        let moves = position.moves();
+        assert_eq!(moves.len(), 7, "red 5 once, blue 7, four colors of wild and a draw: {:?}", moves);
+        assert!(moves.contains(&Move::Play(card("wild green"))));
+
+        let forced = TestGame::new(2).hand(0, &["red 5"]).hand(1, &["green 1"]).top("red 7")
+            .rules(RuleSet { force_play: true, ..RuleSet::default() })
+            .build();
+        assert!(!Position::from_table(forced.table()).moves().contains(&Move::Draw));
+    }
+
+    #[test]
+    fn under_draw_one_only_the_drawn_card_can_follow_a_draw() {
+        let table = |deck: &[&str]| TestGame::new(2).hand(0, &["blue 5", "wild"]).hand(1, &["green 1", "green 2"]).top("red 7")
+            .deck(deck)
+            .rules(RuleSet { draw_one: true, ..RuleSet::default() })
+            .build();
+
+        let mut position = Position::from_table(table(&["red 2"]).table());
+        position.apply(Move::Draw);
+        assert_eq!(position.moves(), [Move::Play(card("red 2")), Move::Keep]);
+        position.apply(Move::Play(card("wild red")));
+        assert_eq!(position.to_move(), 0, "the wild wasn't the card drawn");
+        position.apply(Move::Keep);
+        assert_eq!((position.to_move(), position.hand_size(0)), (1, 3));
+
+        let mut position = Position::from_table(table(&["green 4"]).table());
+        position.apply(Move::Draw);
+        assert_eq!((position.to_move(), position.hand_size(0)), (1, 3), "nothing to play, the turn is over");
+    }
+
+    #[test]
+    fn draws_and_skips_pass_on_their_own() {
+        let game = TestGame::new(3)
+            .hand(0, &["red +2", "red 1"])
+            .hand(1, &["blue 4", "blue 5"])
+            .hand(2, &["red 3", "green 9"])
+            .top("red 7")
+            .deck(&["yellow 1", "yellow 2", "yellow 3"])
+            .rules("official".parse().unwrap())
+            .build();
+        let mut position = Position::from_table(game.table());
+        position.apply(Move::Play(card("red +2")));
+        assert_eq!(position.to_move(), 2, "the second seat drew two and lost their turn");
+        assert_eq!(position.hand(1), cards(&["yellow 1", "yellow 2", "blue 4", "blue 5"]));
+
+        let before = position.clone();
+        position.apply(Move::Play(card("red 3")));
+        position.apply(Move::Play(card("red 1")));
+        assert_eq!(position.winner(), Some(1));
+        assert_eq!(before.winner(), None, "a clone plays on by itself");
+        assert!(position.moves().is_empty());
+    }
+
+    #[test]
+    fn guesses_only_move_the_cards_nobody_can_see() {
+        let position = Position::from_table(&dealt(3));
+        let mut rand = Randler::new(11);
+        let mut guess = position.clone();
+        guess.determinize(0, &mut rand);
+        assert_eq!(guess.hands[0], position.hands[0]);
+        assert_eq!((guess.discard, guess.top), (position.discard, position.top));
+        let mut unseen = [0; KINDS];
+        let mut guessed = [0; KINDS];
+        for seat in 1..4 {
+            assert_eq!(guess.hand_size(seat), position.hand_size(seat));
+            (0..KINDS).for_each(|k| unseen[k] += position.hands[seat][k]);
+            (0..KINDS).for_each(|k| guessed[k] += guess.hands[seat][k]);
+        }
+        position.deck.iter().for_each(|k| unseen[*k as usize] += 1);
+        guess.deck.iter().for_each(|k| guessed[*k as usize] += 1);
+        assert_eq!(unseen, guessed);
+        assert_ne!(guess.hands[1..], position.hands[1..]);
+
+        let mut table = dealt(3);
+        table.lacking[2] = vec![Color::Red, Color::Blue];
+        let position = Position::from_table(&table);
+        for seed in 0..20 {
+            let mut guess = position.clone();
+            guess.determinize(0, &mut Randler::new(seed));
+            assert!(guess.hand(2).iter().all(|c| c.color != Color::Red && c.color != Color::Blue), "{:?}", guess.hand(2));
+            assert_eq!(guess.hand_size(2), position.hand_size(2));
+        }
+    }
+
+    fn dealt(seed: u64) -> Table {
+        let settings = Settings { players: Some(0), ai_players: Some(4), difficulty: Some(Difficulty::Aggressive), seed: Some(seed), ..Settings::default() };
+        setup(&mut ScriptedFrontend::default(), &settings).unwrap().unwrap()
+    }
+
+    // Until the first reshuffle, when the shuffles stop matching, the copy keeps up with the real thing
+    #[test]
+    fn positions_follow_the_game_they_came_from() {
+        let mut table = dealt(5);
+        let mut fe = ScriptedFrontend::default();
+        let mut position = Position::from_table(&table);
+        loop {
+            let winner = play(&mut table, &mut fe, Some(1)).unwrap();
+            let events = std::mem::take(&mut fe.events);
+            if events.iter().any(|e| matches!(e, Event::Reshuffled { .. } | Event::NewDeck)) {
+                break;
+            }
+            for event in &events {
+                match event {
+                    Event::Drew { .. } => position.apply(Move::Draw),
+                    Event::Played { card, .. } if !is_wild(kind(card)) => position.apply(Move::Play(*card)),
+                    Event::ColorChosen { color, .. } => position.apply(Move::Play(UNOCard { color: *color, ..table.last_played })),
+                    _ => {},
+                }
+            }
+            if winner.is_some() {
+                assert_eq!(position.winner(), winner);
+                break;
+            }
+            let fresh = Position::from_table(&table);
+            assert_eq!((&position.hands, &position.deck, position.top), (&fresh.hands, &fresh.deck, fresh.top));
+            assert_eq!((position.to_move(), position.discard, position.uno), (fresh.to_move(), fresh.discard, fresh.uno));
+        }
+    }
+
+    #[test]
+    fn playouts_finish_games() {
+        let start = Position::from_table(&dealt(8));
+        let mut wins = [0; 4];
+        for seed in 0..50 {
+            let mut line = start.clone();
+            line.reseed(seed);
+            wins[line.playout(10_000).expect("nobody won") - 1] += 1;
+        }
+        assert_eq!(wins.iter().sum::<usize>(), 50);
+    }
+}
@@ -0,0 +1,512 @@
+// A game driven one answer at a time, for hosts that can't sit in a blocking frontend (the browser build)
+// Every answer plays the current turn again from its start with all the answers given on it so far,
+// until the game asks for one it doesn't have. Turns are deterministic, so the replayed part comes out the same
+
+use crate::ai::{Neighbors, challenge_ai, expert_move, get_move_ai, pick_color_ai, play_drawn_ai};
+use crate::cards::{SpecialCard, UNOCard, colors, get_color};
+use crate::events::{Event, Frontend, Prompt, card_json, event_json};
+use crate::game::{Difficulty, Paused, Settings, Table, difficulty_name, hand_back, join, play, plus_four_from, setup, take_over};
+use crate::json::Json;
+use crate::rng::Rng;
+use crate::rules::{Handicap, RuleSet, allowed_move};
+use crate::search::Move;
+use crate::trace;
+
+// All AI games are played straight to the end, this stops ones that never end
+const TURN_LIMIT: usize = 10_000;
+
+// Answers that only show the player their own cards or advice about them, nothing happens at the table
+const LOOKS: [&str; 5] = ["s", "see", "suggest", "analyze", "a"];
+
+// Answers from the session, and a pause when they run out
+#[derive(Default)]
+struct Replaying<'a> {
+    answers: std::slice::Iter<'a, String>,
+    events: Vec<Event>,
+    paused: bool,
+    looking: bool, // The last answer was one of LOOKS, what comes until the next prompt is the reply to it
+    private: Vec<usize>, // Which of the events were
+}
+
+impl Frontend for Replaying<'_> {
+    fn event(&mut self, event: &Event) {
+        if self.looking {
+            self.private.push(self.events.len());
+        }
+        self.events.push(event.clone());
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.looking = false;
+        match prompt {
+            Prompt::Continue | Prompt::Exit => String::new(),
+            Prompt::Action { .. } | Prompt::Color { .. } | Prompt::PlayDrawn { .. } | Prompt::Challenge { .. } | Prompt::Menu { .. } => match self.answers.next() {
+                Some(answer) => {
+                    self.looking = LOOKS.contains(&answer.to_lowercase().as_str());
+                    answer.clone()
+                },
+                None => {
+                    self.paused = true;
+                    String::new()
+                },
+            },
+            // Setup never gets here with complete settings, and there's nothing to resume or rematch
+            Prompt::Resume | Prompt::Players | Prompt::AiPlayers | Prompt::Difficulty | Prompt::Rematch => {
+                self.paused = true;
+                String::new()
+            },
+        }
+    }
+
+    fn paused(&self) -> bool {
+        self.paused
+    }
+}
+
+#[derive(Clone)]
+pub struct Session {
+    table: Table, // As it was at the start of the current turn
+    current: Table, // Where the current turn is waiting, the hand in it is in the order the prompt numbers
+    answers: Vec<String>, // Given during the current turn
+    earlier: Vec<Json>, // From before the save this session was picked up from, as JSON since that's all a save keeps
+    events: Vec<Event>, // Up to the start of the current turn
+    turn_events: Vec<Event>, // The current turn up to where it's waiting
+    private: Vec<usize>, // By number, the events only the seat whose turn it was gets to see, see events_json_for
+    waiting: Option<Prompt>,
+    winner: Option<usize>,
+}
+
+// The same setup values as the command line, as JSON: {"players": 1, "ai_players": 2, "difficulty": "skilled",
+// "ai_mix": ["calm"], "names": ["Ana"], "rules": {"stacking": false}, "seed": "42", "handicaps": ["draw2", "none"]}
+// "secure": true shuffles with ChaCha20 like --secure-rng, and can't go with a seed
+pub fn settings_from_json(value: &Json) -> Result<Settings, String> {
+    let count = |key: &str| -> Result<Option<u8>, String> {
+        match value.get(key) {
+            None | Some(Json::Null) => Ok(None),
+            Some(n) => n.as_f64()
+                .filter(|n| n.fract() == 0.0 && (0.0..=255.0).contains(n))
+                .map(|n| Some(n as u8))
+                .ok_or(format!("{} should be a whole number from 0 to 255", key)),
+        }
+    };
+    let strings = |key: &str| -> Result<Vec<String>, String> {
+        match value.get(key) {
+            None => Ok(Vec::new()),
+            Some(list) => list.as_array()
+                .and_then(|items| items.iter().map(|i| i.as_str().map(String::from)).collect())
+                .ok_or(format!("{} should be a list of strings", key)),
+        }
+    };
+
+    let difficulty = match value.get("difficulty") {
+        None | Some(Json::Null) => None,
+        Some(d) => Some(d.as_str().ok_or("difficulty should be a string")?.parse::<Difficulty>()?),
+    };
+    let ai_mix = strings("ai_mix")?.iter().map(|d| d.parse()).collect::<Result<Vec<Difficulty>, _>>()?;
+    let handicaps = strings("handicaps")?.iter().map(|h| h.parse()).collect::<Result<Vec<Handicap>, _>>()?;
+    let rules = match value.get("rules") {
+        None => RuleSet::default(),
+        Some(rules) => RuleSet::from_json(rules).ok_or("rules should be an object like {\"stacking\": false}")?,
+    };
+    // Strings because JSON numbers can't hold every u64, small numbers are fine too
+    let seed = match value.get("seed") {
+        None | Some(Json::Null) => None,
+        Some(Json::Str(s)) => Some(s.trim().parse().map_err(|_| format!("{} is not a valid seed", s))?),
+        Some(Json::Number(n)) if n.fract() == 0.0 && *n >= 0.0 && *n < 9e15 => Some(*n as u64),
+        Some(_) => return Err(String::from("seed should be a string of digits")),
+    };
+    let secure = match value.get("secure") {
+        None | Some(Json::Null) => false,
+        Some(Json::Bool(secure)) => *secure,
+        Some(_) => return Err(String::from("secure should be true or false")),
+    };
+    if secure && seed.is_some() {
+        return Err(String::from("A seed can't go with secure, secure games aren't reproducible"));
+    }
+
+    Ok(Settings {
+        players: Some(count("players")?.ok_or("players is missing")?),
+        ai_players: Some(count("ai_players")?.unwrap_or(0)),
+        difficulty,
+        ai_mix,
+        names: strings("names")?,
+        ai_names: Vec::new(),
+        rules,
+        seed,
+        secure,
+        autoplay: false,
+        decks: count("decks")?,
+        first: 0,
+        handicaps,
+        stack: None,
+    })
+}
+
+impl Session {
+    // Deals and plays up to the first human prompt (or the end, with no humans)
+    pub fn start(settings: &Settings) -> Result<Session, String> {
+        let mut fe = Replaying::default();
+        let table = match setup(&mut fe, settings) {
+            Ok(Some(table)) => table,
+            Ok(None) => return Err(String::from("A game needs at least one player")),
+            Err(e) if e.is::<Paused>() => return Err(format!("The settings are missing something: {}", e)),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        // The deal's events go before whatever the first turns printed
+        let mut session = Session::from_table(table)?;
+        let dealt = fe.events.len();
+        session.events.splice(0..0, fe.events);
+        session.private.iter_mut().for_each(|i| *i += dealt);
+        Ok(session)
+    }
+
+    // Picks up a table dealt some other way, like from game notation, with no events before it
+    pub fn from_table(table: Table) -> Result<Session, String> {
+        Session::resume(table, Vec::new(), Vec::new())
+    }
+
+    // Picks a saved game back up, the events the save kept go first so the new ones are numbered on from them
+    pub fn resume(table: Table, earlier: Vec<Json>, private: Vec<usize>) -> Result<Session, String> {
+        let mut session = Session {
+            current: table.clone(),
+            table,
+            answers: Vec::new(),
+            earlier,
+            events: Vec::new(),
+            turn_events: Vec::new(),
+            private,
+            waiting: None,
+            winner: None,
+        };
+        session.advance()?;
+        Ok(session)
+    }
+
+    // Plays turns until one waits for an answer or somebody wins
+    fn advance(&mut self) -> Result<(), String> {
+        for _ in 0..TURN_LIMIT {
+            let mut table = self.table.clone();
+            let mut fe = Replaying { answers: self.answers.iter(), ..Replaying::default() };
+
+            // Traced once the turn is played for good, not every time it's played over to get to the next answer
+            let held = trace::enabled().then(trace::hold);
+            let result = play(&mut table, &mut fe, Some(1));
+            let mut events = fe.events;
+            if let (Some(held), Ok(_)) = (held, &result) {
+                held.release();
+            }
+            // The turn was played over from its start, so were the replies in it
+            let start = self.earlier.len() + self.events.len();
+            self.private.retain(|&i| i < start);
+            self.private.extend(fe.private.iter().map(|i| start + i));
+
+            match result {
+                Err(e) => match e.downcast_ref::<Paused>() {
+                    Some(Paused(prompt)) => {
+                        self.current = table;
+                        self.turn_events = events;
+                        self.waiting = Some(*prompt);
+                        return Ok(());
+                    },
+                    None => return Err(e.to_string()),
+                },
+                Ok(winner) => {
+                    self.current = table.clone();
+                    self.table = table;
+                    self.answers.clear();
+                    self.events.append(&mut events);
+                    self.turn_events.clear();
+                    self.waiting = None;
+                    if winner.is_some() {
+                        self.winner = winner;
+                        return Ok(());
+                    }
+                },
+            }
+        }
+        Err(format!("Nobody won in {} turns", TURN_LIMIT))
+    }
+
+    // An answer to the prompt the game is waiting on, exactly what a player would type
+    // Answers the game refuses show up as events and the same prompt comes back
+    pub fn act(&mut self, answer: &str) -> Result<(), String> {
+        if self.winner.is_some() {
+            return Err(String::from("The game is over"));
+        }
+        if self.waiting.is_none() {
+            return Err(String::from("Nobody is being asked anything"));
+        }
+        self.answers.push(answer.trim().to_string());
+        // An answer that ends the game without a winner, like a forfeit, isn't kept so the turn can still be played
+        let result = self.advance();
+        if result.is_err() {
+            self.answers.pop();
+        }
+        result
+    }
+
+    // Hands a human seat to the AI for the rest of the game. Whatever the seat is being asked right now the AI answers
+    pub fn take_over(&mut self, seat: usize, difficulty: Difficulty) -> Result<(), String> {
+        let index = seat.checked_sub(1).filter(|&i| i < self.table.players as usize).ok_or("That's not a human seat")?;
+        loop {
+            match self.waiting {
+                Some(Prompt::Action { player }) if player == seat => return self.act(&format!("takeover {}", difficulty_name(difficulty))),
+                Some(Prompt::Menu { player }) if player == seat => self.act("resume")?,
+                Some(Prompt::Color { player } | Prompt::PlayDrawn { player } | Prompt::Challenge { player }) if player == seat => {
+                    let answer = self.suggest(difficulty).ok_or("The AI had nothing to answer")?;
+                    self.act(&answer)?;
+                },
+                // Not this seat's turn, it only has to be marked for when it comes
+                _ => {
+                    take_over(&mut self.table, index, difficulty);
+                    take_over(&mut self.current, index, difficulty);
+                    return Ok(());
+                },
+            }
+        }
+    }
+
+    // Puts somebody new in a seat the AI is playing, from its next turn
+    pub fn join(&mut self, seat: usize, name: Option<&str>) -> Result<String, String> {
+        if self.winner.is_some() {
+            return Err(String::from("The game is over"));
+        }
+        let index = seat.checked_sub(1).ok_or("Seats are numbered from 1")?;
+        let message = join(&mut self.table, index, name)?;
+        join(&mut self.current, index, name)?;
+        Ok(message)
+    }
+
+    // Gives a seat the AI took over back to its player, from their next turn on
+    pub fn hand_back(&mut self, seat: usize) -> Option<String> {
+        let message = hand_back(&mut self.table, seat.checked_sub(1)?)?;
+        self.current.taken_over[seat - 1] = false;
+        Some(message)
+    }
+
+    pub fn waiting(&self) -> Option<Prompt> {
+        self.waiting
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    // Where the game is waiting, with the asked hand in prompt order
+    pub fn current(&self) -> &Table {
+        &self.current
+    }
+
+    // The table at the start of the turn being asked, which is what a save keeps
+    pub fn saved(&self) -> &Table {
+        &self.table
+    }
+
+    // A message for the whole table after whatever the turn has done so far. It isn't part of the game, so once the
+    // turn is played on it's gone again
+    pub fn notice(&mut self, message: &str) {
+        self.turn_events.push(Event::Notice(message.to_string()));
+    }
+
+    // Since this session started, the ones from before a resume are only kept as JSON
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter().chain(&self.turn_events)
+    }
+
+    // Every event of the game, the number the next one gets
+    pub fn event_count(&self) -> usize {
+        self.earlier.len() + self.events().count()
+    }
+
+    // The events up to the start of the turn being asked, the ones a save of saved() keeps, and which of them are private
+    pub fn saved_events(&self) -> (Vec<Json>, Vec<usize>) {
+        let events: Vec<Json> = self.earlier.iter().cloned().chain(self.events.iter().map(event_json)).collect();
+        let private = self.private.iter().copied().filter(|&i| i < events.len()).collect();
+        (events, private)
+    }
+
+    // What a screen needs to draw the table, with only the hand of the seat being asked shown
+    pub fn view(&self) -> Json {
+        let t = &self.current;
+        let seat = match self.waiting {
+            Some(Prompt::Action { player } | Prompt::Color { player } | Prompt::PlayDrawn { player } | Prompt::Challenge { player } | Prompt::Menu { player }) => Some(player),
+            _ => None,
+        };
+
+        Json::object(vec![
+            ("names", Json::Array(t.names.iter().map(|n| n.as_str().into()).collect())),
+            ("human_players", (t.players as usize).into()),
+            ("taken_over", Json::Array((1..=t.players as usize).filter(|&p| t.taken_over[p - 1]).map(Json::from).collect())),
+            ("ai_seats", Json::Array((1..=t.hands.len()).filter(|&p| t.is_ai(p - 1)).map(Json::from).collect())),
+            ("hand_sizes", Json::Array(t.hands.iter().map(|h| h.len().into()).collect())),
+            ("deck_size", t.deck.len().into()),
+            ("turn", t.game_state.player_number().into()),
+            ("last_played", card_json(&t.last_played)),
+            ("waiting", match self.waiting {
+                Some(prompt) => Json::object(vec![
+                    ("prompt", prompt.name().into()),
+                    ("player", seat.map_or(Json::Null, |p| p.into())),
+                ]),
+                None => Json::Null,
+            }),
+            ("hand", seat.map_or(Json::Null, |player| self.hand(player))),
+            ("winner", self.winner.map_or(Json::Null, |w| w.into())),
+            ("event_count", self.event_count().into()),
+        ])
+    }
+
+    // One seat's cards, with the index an action answer uses like a hand event
+    // Only the asked seat's hand is sure to be in prompt order, the others get sorted when their turn comes
+    pub fn hand(&self, seat: usize) -> Json {
+        match self.current.hands.get(seat.wrapping_sub(1)) {
+            Some(hand) => event_json(&Event::Hand(hand.clone())).get("cards").cloned().unwrap_or(Json::Null),
+            None => Json::Null,
+        }
+    }
+
+    // What one of the built in AIs would answer in the waiting player's place, for mixing them into experiments
+    // It plays from a copy of the game's AI generator, so asking doesn't change the game
+    pub fn suggest(&self, difficulty: Difficulty) -> Option<String> {
+        let t = &self.current;
+        let mut rand = t.ai_rand;
+        match self.waiting? {
+            Prompt::Action { player } => {
+                let hand = &t.hands[player - 1];
+                // Going down to one card, UNO is called with it
+                let call = if hand.len() == 2 { " uno" } else { "" };
+                // The Expert searches from the start of the turn, so only before anything was drawn
+                if difficulty == Difficulty::Expert && self.answers.is_empty()
+                    && let Some(mv) = expert_move(&self.table, rand.next_u64())
+                {
+                    let wild = |c: &UNOCard| matches!(c.special, SpecialCard::ColorChange | SpecialCard::PlusFour);
+                    return Some(match mv {
+                        Move::Play(card) => hand.iter().position(|c| *c == card || (wild(c) && c.special == card.special))
+                            .map_or(String::from("d"), |i| format!("{}{}", i + 1, call)),
+                        Move::Draw | Move::Keep => String::from("d"),
+                    });
+                }
+                let mut choice = get_move_ai(hand, t.last_played, difficulty, t.uno_detection_panic, Some(Neighbors::at(t)), &mut rand);
+                // Same as an AI seat, holding back isn't allowed under force play
+                if choice.is_none() && t.rules.force_play {
+                    choice = hand.iter().position(|c| allowed_move(*c, t.last_played));
+                }
+                Some(choice.map_or(String::from("d"), |i| format!("{}{}", i + 1, call)))
+            },
+            Prompt::Color { player } => Some(get_color(&pick_color_ai(&t.hands[player - 1], colors(t.rules.colors), difficulty, Some(Neighbors::at(t)), &mut rand)).to_lowercase()),
+            // The drawn card is the last one in the hand until the turn ends
+            Prompt::PlayDrawn { player } => {
+                let drawn = *t.hands[player - 1].last()?;
+                let play = play_drawn_ai(drawn, difficulty, t.uno_detection_panic, &mut rand);
+                Some(String::from(match (play, t.hands[player - 1].len()) {
+                    (true, 2) => "y uno",
+                    (true, _) => "y",
+                    (false, _) => "n",
+                }))
+            },
+            Prompt::Challenge { player } => {
+                let (against, color) = plus_four_from(t, player - 1)?;
+                let challenge = challenge_ai(&t.lacking[against], t.hands[against].len(), color, t.rules.colors as usize, t.rules.challenge_penalty, difficulty, &mut rand);
+                Some(String::from(if challenge { "y" } else { "n" }))
+            },
+            _ => None,
+        }
+    }
+
+    // Events from number `since` on, so a host can poll for what's new
+    pub fn events_json(&self, since: usize) -> Json {
+        let earlier = self.earlier.iter().skip(since).cloned();
+        Json::Array(earlier.chain(self.events().skip(since.saturating_sub(self.earlier.len())).map(event_json)).collect())
+    }
+
+    // The same events as one seat gets to see them, for hosts where the players can't see each other's screens. Other
+    // seats' cards are blanked out the way the view leaves out their hands, so are the replies to another seat looking
+    // at its hand or asking for advice, and the seed the whole deck could be worked out from goes. Every event keeps
+    // its number, blanked or not
+    pub fn events_json_for(&self, seat: usize, since: usize) -> Json {
+        let mut turn = 0;
+        let all = self.earlier.iter().cloned().chain(self.events().map(event_json));
+        Json::Array(all.enumerate().filter_map(|(i, mut event)| {
+            let name = event.get("event").and_then(Json::as_str).unwrap_or_default().to_string();
+            let player = event.get("player").and_then(Json::as_f64).map(|p| p as usize);
+            let hidden: &[&str] = match name.as_str() {
+                "game_start" => &["seed"],
+                "turn_start" => {
+                    turn = player.unwrap_or(0);
+                    &[]
+                },
+                _ if turn != seat && self.private.contains(&i) => &["message", "cards"],
+                "hand" | "illegal_move" if turn != seat => &["cards", "card"],
+                "drew" | "forced_draw" | "traded" if player != Some(seat) => &["card", "gave"],
+                _ => &[],
+            };
+            if let Json::Object(pairs) = &mut event {
+                pairs.retain(|(key, _)| name != "game_start" || !hidden.contains(&key.as_str()));
+                for (_, value) in pairs.iter_mut().filter(|(key, _)| hidden.contains(&key.as_str())) {
+                    *value = Json::Null;
+                }
+            }
+            (i >= since).then_some(event)
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(json: &str) -> Settings {
+        settings_from_json(&Json::parse(json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn plays_a_recorded_script_to_the_same_end() {
+        let mut session = Session::start(&settings(r#"{"players": 1, "ai_players": 1, "difficulty": "calm", "rules": {"hand-size": 3}, "seed": "10"}"#)).unwrap();
+        let script = include_str!("../../tests/scripts/calm_seed10.txt");
+        for answer in script.lines().filter(|l| !l.starts_with('#')) {
+            assert!(session.waiting().is_some());
+            session.act(answer).unwrap();
+        }
+        assert_eq!(session.winner(), Some(1));
+        assert_eq!(session.view().get("winner"), Some(&Json::from(1)));
+        assert!(session.act("1").is_err());
+    }
+
+    #[test]
+    fn a_refused_answer_asks_again() {
+        let mut session = Session::start(&settings(r#"{"players": 1, "ai_players": 1, "difficulty": "calm", "seed": 3}"#)).unwrap();
+        let before = session.events().count();
+        session.act("99").unwrap();
+        assert!(matches!(session.waiting(), Some(Prompt::Action { player: 1 })));
+        assert!(matches!(session.events().nth(before), Some(Event::InvalidInput(_))));
+        assert_eq!(session.view().get("hand").and_then(|h| h.as_array()).map(|h| h.len()), Some(7));
+    }
+
+    #[test]
+    fn all_ai_games_play_out_at_the_start() {
+        let session = Session::start(&settings(r#"{"players": 0, "ai_players": 3, "difficulty": "skilled", "seed": "9"}"#)).unwrap();
+        assert_eq!(session.winner(), Some(3));
+        assert!(session.waiting().is_none());
+    }
+
+    #[test]
+    fn suggestions_play_like_an_ai_seat() {
+        // Following the calm AI's advice in seat 1 has to give the same game as a calm AI sitting there
+        let mut session = Session::start(&settings(r#"{"players": 1, "ai_players": 2, "difficulty": "calm", "seed": "21"}"#)).unwrap();
+        while let Some(answer) = session.suggest(Difficulty::Calm) {
+            session.act(&answer).unwrap();
+        }
+        let ai_only = Session::start(&settings(r#"{"players": 0, "ai_players": 3, "difficulty": "calm", "seed": "21"}"#)).unwrap();
+        assert!(session.winner().is_some());
+        assert_eq!(session.view().get("hand_sizes"), ai_only.view().get("hand_sizes"));
+    }
+
+    #[test]
+    fn settings_have_to_be_complete() {
+        assert!(settings_from_json(&Json::parse(r#"{"ai_players": 1}"#).unwrap()).is_err());
+        assert!(Session::start(&settings(r#"{"players": 1, "ai_players": 1, "seed": 1}"#)).is_err());
+        assert!(settings(r#"{"players": 1, "secure": true}"#).secure);
+        assert!(settings_from_json(&Json::parse(r#"{"players": 1, "secure": true, "seed": "4"}"#).unwrap()).is_err());
+        assert!(settings_from_json(&Json::parse(r#"{"players": 1, "secure": "yes"}"#).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+// A stacked deal: the hands and the order of the deck written out instead of shuffled, for teaching a situation or
+// reproducing one. Same `key: value` lines as a puzzle, cards in the game notation's codes:
+//
+//     hand: R5 G2 W Y7 B+2 R8 G0    (one line for every seat, in order)
+//     hand: B3 Y3
+//     top: R2                       (W:B for a wild that was called blue)
+//     deck: R9 G3 Y1                (top first, more deck lines carry on where the last one stopped)
+//
+// Seats past the last hand line are dealt the usual way off the top of the deck, and the opening card is turned up
+// after them when there's no top line. A short deck is fine: once it runs out the pile is shuffled back in with the
+// game's seed like always, which is how back-to-back reshuffles get set up. Lines starting with # are comments
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cards::{Color, DeckShape, SpecialCard, UNOCard, build_deck_of, format_card_message};
+use crate::notation::{card_code, parse_card_code};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stack {
+    pub hands: Vec<Vec<UNOCard>>, // From seat 1, as many as were written
+    pub top: Option<UNOCard>,
+    pub deck: Vec<UNOCard>, // Top first
+}
+
+fn is_wild(card: &UNOCard) -> bool {
+    card.special == SpecialCard::ColorChange || card.special == SpecialCard::PlusFour
+}
+
+fn cards(text: &str) -> Result<Vec<UNOCard>, String> {
+    text.split_whitespace().map(parse_card_code).collect()
+}
+
+impl FromStr for Stack {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut stack = Stack::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let at = |e: String| format!("Line {}: {}", i + 1, e);
+            let (key, value) = line.split_once(':').ok_or(at(String::from("lines look like key: value")))?;
+            let value = value.trim();
+            match key.trim() {
+                "hand" => stack.hands.push(cards(value).map_err(at)?),
+                "top" if stack.top.is_some() => return Err(at(String::from("there's only one top card"))),
+                "top" => stack.top = Some(parse_card_code(value).map_err(at)?),
+                "deck" => stack.deck.extend(cards(value).map_err(at)?),
+                other => return Err(at(format!("{} isn't something a stacked deal has", other))),
+            }
+        }
+        if stack.hands.iter().any(|h| h.is_empty()) {
+            return Err(String::from("Every hand needs at least one card"));
+        }
+        if stack.hands.iter().flatten().chain(&stack.deck).any(|c| is_wild(c) && c.color != Color::NA) {
+            return Err(String::from("Only the top card can be a wild with a color, the others are just W and W+4"));
+        }
+        Ok(stack)
+    }
+}
+
+// The file form again, for replays to keep the deal they started from
+impl fmt::Display for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let codes = |cards: &[UNOCard]| cards.iter().map(card_code).collect::<Vec<_>>().join(" ");
+        for hand in &self.hands {
+            writeln!(f, "hand: {}", codes(hand))?;
+        }
+        if let Some(top) = &self.top {
+            writeln!(f, "top: {}", card_code(top))?;
+        }
+        writeln!(f, "deck: {}", codes(&self.deck))
+    }
+}
+
+impl Stack {
+    // Every card has to be one the rules' deck has, a purple card needs colors=5 and a 12 needs highest-number=12
+    pub fn check(&self, seats: usize, shape: DeckShape) -> Result<(), String> {
+        if self.hands.len() > seats {
+            return Err(format!("The stacked deal has {} hands for {} seats", self.hands.len(), seats));
+        }
+        let deck = build_deck_of(shape);
+        let top = self.top.map(|card| if is_wild(&card) { UNOCard { color: Color::NA, ..card } } else { card });
+        match self.hands.iter().flatten().chain(&self.deck).chain(&top).find(|card| !deck.contains(card)) {
+            Some(card) => Err(format!("{} isn't in a deck with these rules", format_card_message(card))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{card, cards as named};
+
+    #[test]
+    fn stacks_are_read_from_their_lines() {
+        let stack: Stack = "# Ana can't follow\nhand: R5 W\nhand: B3\ntop: W:G\ndeck: R9 G3\ndeck: Y1".parse().unwrap();
+        assert_eq!(stack.hands, vec![named(&["red 5", "wild"]), named(&["blue 3"])]);
+        assert_eq!(stack.top, Some(card("wild green")));
+        assert_eq!(stack.deck, named(&["red 9", "green 3", "yellow 1"]));
+        assert!(stack.check(2, DeckShape::default()).is_ok());
+        assert_eq!(stack.to_string().parse::<Stack>(), Ok(stack.clone()));
+        assert_eq!(stack.check(1, DeckShape::default()), Err(String::from("The stacked deal has 2 hands for 1 seats")));
+
+        assert_eq!("deck: P3".parse::<Stack>().unwrap().check(2, DeckShape::default()), Err(String::from("Purple 3 isn't in a deck with these rules")));
+        for bad in ["hand:", "hand: R5\ntop: R2\ntop: G2", "deck: W:R", "seat: R5", "hand R5"] {
+            assert!(bad.parse::<Stack>().is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+// A game with everything laid out by hand, for tests of the rules
+// Hands, the deck order and the rules are set up front, the human seats play from a script, and every event is kept
+//
+//     let mut game = TestGame::new(2)
+//         .hand(0, &["red 5", "blue +2"])
+//         .hand(1, &["green 3"])
+//         .top("red 1")
+//         .deck(&["yellow 7", "blue 9"])
+//         .actions(&["1"])
+//         .build();
+//     game.play_turns(1);
+//     assert_eq!(game.table().last_played, card("red 5"));
+
+use std::collections::VecDeque;
+
+use crate::cards::UNOCard;
+use crate::events::{Event, Frontend, Prompt};
+use crate::game::{Difficulty, Game, Table, default_name, play_with};
+use crate::plugins::RulePlugin;
+use crate::rng::{DeckRng, Randler};
+use crate::rules::{Handicap, RuleSet};
+
+// Parses a card description, panicking on typos since this is for tests
+pub fn card(description: &str) -> UNOCard {
+    description.parse().unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn cards(descriptions: &[&str]) -> Vec<UNOCard> {
+    descriptions.iter().map(|d| card(d)).collect()
+}
+
+// Answers the human seats from the script and remembers everything that happened
+// Besides what a player would type, "play <card>" plays that card from the hand wherever sorting put it, and
+// "trade <card>" trades it with the deck
+#[derive(Debug, Default)]
+pub struct ScriptedFrontend {
+    pub actions: VecDeque<String>,
+    pub events: Vec<Event>,
+    hand: Vec<UNOCard>, // The current human hand as last shown, kept sorted like the game keeps it
+}
+
+impl Frontend for ScriptedFrontend {
+    fn event(&mut self, event: &Event) {
+        match event {
+            Event::Hand(hand) => self.hand = hand.clone(),
+            Event::Drew { card: Some(card), .. } | Event::ForcedDraw { card: Some(card), .. } => {
+                self.hand.push(*card);
+                self.hand.sort();
+            },
+            Event::Played { ai: false, card, .. } => {
+                if let Some(index) = self.hand.iter().position(|c| c == card) {
+                    self.hand.remove(index);
+                }
+            },
+            Event::Traded { gave, got: Some(got), .. } => {
+                if let Some(index) = self.hand.iter().position(|c| c == gave) {
+                    self.hand[index] = *got;
+                    self.hand.sort();
+                }
+            },
+            _ => {},
+        }
+        self.events.push(event.clone());
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        let action = match prompt {
+            Prompt::Continue | Prompt::Exit => return String::new(),
+            Prompt::Action { .. } | Prompt::Color { .. } | Prompt::PlayDrawn { .. } | Prompt::Challenge { .. } | Prompt::Menu { .. } => self.actions.pop_front()
+                .unwrap_or_else(|| panic!("The script ran out on a {} prompt, events so far: {:#?}", prompt.name(), self.events)),
+            _ => panic!("A test game shouldn't ask for {}", prompt.name()),
+        };
+
+        let index = |description: &str| {
+            let wanted = card(description);
+            let index = self.hand.iter().position(|c| *c == wanted)
+                .unwrap_or_else(|| panic!("{} isn't in the hand {:?}", description, self.hand));
+            index + 1
+        };
+        // "play red 5 uno" calls UNO with it
+        if let Some(description) = action.strip_prefix("play ") {
+            match description.strip_suffix(" uno") {
+                Some(description) => format!("{} uno", index(description)),
+                None => index(description).to_string(),
+            }
+        } else if let Some(description) = action.strip_prefix("trade ") && description.parse::<usize>().is_err() {
+            format!("t {}", index(description))
+        } else {
+            action
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TestGame {
+    hands: Vec<Vec<UNOCard>>,
+    deck: Vec<UNOCard>, // Top first
+    top: UNOCard,
+    humans: usize,
+    difficulty: Difficulty,
+    rules: RuleSet,
+    seed: u64,
+    actions: Vec<String>,
+}
+
+impl TestGame {
+    // Every seat starts out human with an empty hand, on a red 0 and an empty deck
+    pub fn new(seats: usize) -> Self {
+        TestGame {
+            hands: vec![Vec::new(); seats],
+            deck: Vec::new(),
+            top: card("red 0"),
+            humans: seats,
+            difficulty: Difficulty::Calm,
+            rules: RuleSet::default(),
+            seed: 0,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn hand(mut self, seat: usize, hand: &[&str]) -> Self {
+        self.hands[seat] = cards(hand);
+        self
+    }
+
+    // The card everybody has to follow
+    pub fn top(mut self, top: &str) -> Self {
+        self.top = card(top);
+        self
+    }
+
+    // The draw pile, the first card is drawn first
+    pub fn deck(mut self, deck: &[&str]) -> Self {
+        self.deck = cards(deck);
+        self
+    }
+
+    // Seats from this one on are played by the AI
+    pub fn ai_from(mut self, seat: usize, difficulty: Difficulty) -> Self {
+        self.humans = seat;
+        self.difficulty = difficulty;
+        self
+    }
+
+    pub fn rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    // Only matters once the deck runs out and gets shuffled
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // What the human seats type, in order, like at the prompt ("1", "d", "red", ...) or "play <card>"
+    pub fn actions(mut self, actions: &[&str]) -> Self {
+        self.actions = actions.iter().map(|a| a.to_string()).collect();
+        self
+    }
+
+    pub fn build(self) -> Running {
+        let seats = self.hands.len();
+        let mut deck = self.deck;
+        deck.reverse();
+
+        let table = Table {
+            hands: self.hands,
+            deck,
+            discard: vec![self.top],
+            history: vec![self.top],
+            lacking: vec![Vec::new(); seats],
+            last_played: self.top,
+            game_state: Game::new(0, seats, 1),
+            add_queue: 0,
+            skipped: false,
+            uno_detection_panic: false,
+            players: self.humans as u8,
+            names: (1..=seats).map(default_name).collect(),
+            difficulties: vec![self.difficulty; seats],
+            rules: self.rules,
+            handicaps: vec![Handicap::default(); seats],
+            traded: vec![false; seats],
+            taken_over: vec![false; seats],
+            joined: vec![false; seats],
+            seed: Some(self.seed),
+            rand: DeckRng::Seeded(Randler::new(self.seed)),
+            ai_rand: Randler::stream(self.seed, 1),
+        };
+        Running { table, frontend: ScriptedFrontend { actions: self.actions.into(), ..ScriptedFrontend::default() }, plugins: Vec::new() }
+    }
+}
+
+// A built game, played a few turns at a time
+pub struct Running {
+    table: Table,
+    frontend: ScriptedFrontend,
+    plugins: Vec<Box<dyn RulePlugin>>,
+}
+
+impl Running {
+    // Rule plugins for every turn from here on, in the order they're added
+    pub fn plugin(mut self, plugin: impl RulePlugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    // Plays up to that many turns, the winner if somebody won along the way
+    pub fn play_turns(&mut self, turns: usize) -> Option<usize> {
+        self.try_turns(turns).unwrap_or_else(|e| panic!("The game failed: {}", e))
+    }
+
+    // The same, for games that are meant to stop with an error like a forfeit
+    pub fn try_turns(&mut self, turns: usize) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        play_with(&mut self.table, &mut self.frontend, Some(turns), &mut self.plugins)
+    }
+
+    pub fn play_to_end(&mut self) -> usize {
+        self.play_turns(usize::MAX).expect("Nobody won")
+    }
+
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    // For setting up things the builder doesn't cover, like an add queue or a reversed direction
+    pub fn table_mut(&mut self) -> &mut Table {
+        &mut self.table
+    }
+
+    pub fn hand(&self, seat: usize) -> &[UNOCard] {
+        &self.table.hands[seat]
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.frontend.events
+    }
+
+    // Events since the last call, so each turn can be checked on its own
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.frontend.events)
+    }
+
+    pub fn actions_left(&self) -> usize {
+        self.frontend.actions.len()
+    }
+}
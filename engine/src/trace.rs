@@ -0,0 +1,346 @@
+// Tracing: a span around every turn and an event for every action inside it, handed to whatever subscribers are
+// listening. Where log! is lines of prose for whoever is chasing a bug, these are records with fields, for a server
+// operator's tools as much as for a developer reading along
+//
+//     unoler::trace::subscribe(Box::new(Pretty::stderr()));
+//     unoler::trace::subscribe(Box::new(JsonLines::new(File::create("trace.jsonl")?)));
+//
+// --trace prints them to stderr and --trace-file writes them to a file, one JSON object per line. With nobody
+// subscribed a turn pays for one atomic load
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::events::{Event, Frontend, Prompt, event_json};
+use crate::game::Table;
+use crate::json::Json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Enter,
+    Exit,
+    Event,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Enter => "enter",
+            Kind::Exit => "exit",
+            Kind::Event => "event",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub kind: Kind,
+    pub span: u64, // The span itself for Enter and Exit, the one it happened in for an Event, 0 outside any
+    pub name: String,
+    pub fields: Vec<(String, Json)>,
+    pub depth: usize, // How many spans are open around it on its thread
+    pub at: Duration, // Since the first record
+}
+
+impl Record {
+    pub fn json(&self) -> Json {
+        Json::object(vec![
+            ("at", Json::Number(self.at.as_secs_f64())),
+            ("kind", self.kind.name().into()),
+            ("span", Json::Number(self.span as f64)),
+            ("name", self.name.as_str().into()),
+            ("depth", self.depth.into()),
+            ("fields", Json::Object(self.fields.clone())),
+        ])
+    }
+}
+
+pub trait Subscriber: Send {
+    fn record(&mut self, record: &Record);
+}
+
+// Closures subscribe too, for a quick look or a test
+impl<F: FnMut(&Record) + Send> Subscriber for F {
+    fn record(&mut self, record: &Record) {
+        self(record)
+    }
+}
+
+static ON: AtomicBool = AtomicBool::new(false);
+static NEXT_SPAN: AtomicU64 = AtomicU64::new(1);
+static SUBSCRIBERS: Mutex<Vec<Box<dyn Subscriber>>> = Mutex::new(Vec::new());
+static START: OnceLock<Instant> = OnceLock::new();
+
+// Each thread has its own spans, so games played side by side never nest in each other's turns
+#[derive(Default)]
+struct Local {
+    spans: Vec<u64>,
+    held: Option<Vec<Record>>,
+}
+
+thread_local! {
+    static LOCAL: RefCell<Local> = RefCell::new(Local::default());
+}
+
+pub fn subscribe(subscriber: Box<dyn Subscriber>) {
+    SUBSCRIBERS.lock().unwrap_or_else(|e| e.into_inner()).push(subscriber);
+    ON.store(true, Ordering::Relaxed);
+}
+
+// Checked before any fields get built
+pub fn enabled() -> bool {
+    ON.load(Ordering::Relaxed) || LOCAL.with(|l| l.borrow().held.is_some())
+}
+
+fn send(records: &[Record]) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap_or_else(|e| e.into_inner());
+    for record in records {
+        for subscriber in subscribers.iter_mut() {
+            subscriber.record(record);
+        }
+    }
+}
+
+fn emit(kind: Kind, span: Option<u64>, name: &str, fields: Vec<(String, Json)>) {
+    let at = START.get_or_init(Instant::now).elapsed();
+    let record = LOCAL.with(|l| {
+        let mut l = l.borrow_mut();
+        let span = span.unwrap_or_else(|| l.spans.last().copied().unwrap_or(0));
+        let record = Record { kind, span, name: name.to_string(), fields, depth: l.spans.len(), at };
+        match l.held.as_mut() {
+            Some(held) => {
+                held.push(record);
+                None
+            },
+            None => Some(record),
+        }
+    });
+    if let Some(record) = record {
+        send(&[record]);
+    }
+}
+
+fn owned(fields: Vec<(&str, Json)>) -> Vec<(String, Json)> {
+    fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+// Open until it's dropped, everything traced on this thread until then happens inside it
+pub struct Span {
+    id: u64,
+    name: &'static str,
+    opened: Instant,
+}
+
+pub fn span(name: &'static str, fields: impl FnOnce() -> Vec<(&'static str, Json)>) -> Option<Span> {
+    if !enabled() {
+        return None;
+    }
+    let id = NEXT_SPAN.fetch_add(1, Ordering::Relaxed);
+    emit(Kind::Enter, Some(id), name, owned(fields()));
+    LOCAL.with(|l| l.borrow_mut().spans.push(id));
+    Some(Span { id, name, opened: Instant::now() })
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        LOCAL.with(|l| {
+            let mut l = l.borrow_mut();
+            if let Some(i) = l.spans.iter().rposition(|s| *s == self.id) {
+                l.spans.truncate(i);
+            }
+        });
+        let micros = self.opened.elapsed().as_micros() as usize;
+        emit(Kind::Exit, Some(self.id), self.name, vec![(String::from("elapsed_us"), micros.into())]);
+    }
+}
+
+pub fn event(name: &str, fields: impl FnOnce() -> Vec<(&'static str, Json)>) {
+    if !enabled() {
+        return;
+    }
+    emit(Kind::Event, None, name, owned(fields()));
+}
+
+// A game event as an action, named and with the fields --json gives it
+pub fn action(event: &Event) {
+    if !enabled() {
+        return;
+    }
+    let (name, fields) = match event_json(event) {
+        Json::Object(mut pairs) => {
+            let name = match pairs.iter().position(|(k, _)| k == "event") {
+                Some(i) => pairs.remove(i).1.as_str().unwrap_or("event").to_string(),
+                None => String::from("event"),
+            };
+            (name, pairs)
+        },
+        _ => return,
+    };
+    emit(Kind::Event, None, &name, fields);
+}
+
+// Keeps this thread's records back instead of sending them, for a turn that might be played again. Sessions play a
+// turn over every time an answer comes in, and only the last time counts
+pub struct Held(());
+
+pub fn hold() -> Held {
+    LOCAL.with(|l| l.borrow_mut().held = Some(Vec::new()));
+    Held(())
+}
+
+impl Held {
+    // The records kept back so far, and nothing is held from now on
+    pub fn take(self) -> Vec<Record> {
+        LOCAL.with(|l| l.borrow_mut().held.take()).unwrap_or_default()
+    }
+
+    pub fn release(self) {
+        send(&self.take());
+    }
+}
+
+// Dropped without a release, the records were for a turn that gets played again
+impl Drop for Held {
+    fn drop(&mut self) {
+        LOCAL.with(|l| l.borrow_mut().held = None);
+    }
+}
+
+// Every event the game sends its frontend, traced on the way through
+pub struct Traced<'a>(pub &'a mut dyn Frontend);
+
+impl Frontend for Traced<'_> {
+    fn event(&mut self, event: &Event) {
+        action(event);
+        self.0.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.0.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.0.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<std::path::PathBuf, String> {
+        self.0.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.0.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        self.0.game_over(table, winner);
+    }
+
+    fn paused(&self) -> bool {
+        self.0.paused()
+    }
+}
+
+fn fields_text(fields: &[(String, Json)]) -> String {
+    fields.iter().map(|(k, v)| format!(" {}={}", k, v)).collect()
+}
+
+// For reading along: events indented under the turn they happened in
+//
+//     0.004s turn seat=2 ai=true cards=7 queued=0 deck=93
+//     0.004s   played player=2 ai=true card={"color":"red","special":"base","number":5,"name":"Red 5"}
+//     0.004s turn done in 31µs
+pub struct Pretty<W: Write + Send> {
+    out: W,
+}
+
+impl Pretty<io::Stderr> {
+    pub fn stderr() -> Self {
+        Pretty::new(io::stderr())
+    }
+}
+
+impl<W: Write + Send> Pretty<W> {
+    pub fn new(out: W) -> Self {
+        Pretty { out }
+    }
+}
+
+impl<W: Write + Send> Subscriber for Pretty<W> {
+    fn record(&mut self, record: &Record) {
+        let indent = "  ".repeat(record.depth);
+        let line = match record.kind {
+            Kind::Exit => {
+                let took = record.fields.iter().find(|(k, _)| k == "elapsed_us").and_then(|(_, v)| v.as_f64()).unwrap_or(0.0);
+                format!("{:8.3}s {}{} done in {}µs", record.at.as_secs_f64(), indent, record.name, took)
+            },
+            _ => format!("{:8.3}s {}{}{}", record.at.as_secs_f64(), indent, record.name, fields_text(&record.fields)),
+        };
+        // A closed pipe shouldn't take the game down with it
+        let _ = writeln!(self.out, "{}", line);
+    }
+}
+
+// For tools: every record as one line of JSON
+//
+//     {"at":0.004,"kind":"enter","span":3,"name":"turn","depth":0,"fields":{"seat":2,"ai":true,"cards":7,"queued":0,"deck":93}}
+pub struct JsonLines<W: Write + Send> {
+    out: W,
+}
+
+impl<W: Write + Send> JsonLines<W> {
+    pub fn new(out: W) -> Self {
+        JsonLines { out }
+    }
+}
+
+impl JsonLines<File> {
+    pub fn create(path: &std::path::Path) -> io::Result<Self> {
+        Ok(JsonLines::new(File::create(path)?))
+    }
+}
+
+impl<W: Write + Send> Subscriber for JsonLines<W> {
+    fn record(&mut self, record: &Record) {
+        let _ = writeln!(self.out, "{}", record.json());
+        let _ = self.out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_land_in_the_span_they_happen_in() {
+        let held = hold();
+        event("outside", Vec::new);
+        {
+            let _turn = span("turn", || vec![("seat", 2.into())]);
+            action(&Event::Uno { player: 2 });
+        }
+        let records = held.take();
+        let kinds: Vec<(Kind, &str)> = records.iter().map(|r| (r.kind, r.name.as_str())).collect();
+        assert_eq!(kinds, [(Kind::Event, "outside"), (Kind::Enter, "turn"), (Kind::Event, "uno"), (Kind::Exit, "turn")]);
+        assert_eq!(records[0].span, 0);
+        assert!(records[1].span != 0 && records[2].span == records[1].span && records[3].span == records[1].span);
+        assert_eq!(records[2].fields, [(String::from("player"), 2.into())]);
+        // Nothing held any more, and nobody subscribed in the tests
+        assert!(!enabled());
+
+        let mut out = Vec::new();
+        let mut pretty = Pretty::new(&mut out);
+        for record in &records {
+            pretty.record(record);
+        }
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().map(|l| l.split_once("s ").unwrap().1).collect();
+        assert_eq!(lines[..3], ["outside", "turn seat=2", "  uno player=2"]);
+        assert!(lines[3].starts_with("turn done in "));
+        let json = Json::parse(&records[1].json().to_string()).unwrap();
+        assert_eq!(json.get("kind").and_then(Json::as_str), Some("enter"));
+    }
+}
@@ -0,0 +1,97 @@
+// The browser build: cargo build --lib --release --target wasm32-unknown-unknown
+// There's no wasm-bindgen (no dependencies), so text crosses as UTF-8 in the module's memory. The host copies its
+// input into a buffer from unoler_alloc, and every call leaves a JSON answer that unoler_output points at,
+// unoler_output_len bytes long, until the next call. web/unoler.js wraps all of that up
+//
+// A browser has no OS random source, so games need a seed from the host (crypto.getRandomValues is fine)
+
+// The pointer rules are in the normal comments like everything else
+#![allow(clippy::missing_safety_doc)]
+
+use std::cell::RefCell;
+
+use crate::json::Json;
+use crate::session::{Session, settings_from_json};
+
+thread_local! {
+    static SESSION: RefCell<Option<Session>> = const { RefCell::new(None) };
+    static OUTPUT: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+// Errors come back as {"error": "..."}, everything else is whatever the call returns
+fn output(result: Result<Json, String>) -> usize {
+    let text = match result {
+        Ok(value) => value.to_string(),
+        Err(e) => Json::object(vec![("error", e.into())]).to_string(),
+    };
+    OUTPUT.with(|o| {
+        *o.borrow_mut() = text;
+        o.borrow().len()
+    })
+}
+
+// Takes back a buffer from unoler_alloc
+unsafe fn take_input(ptr: *mut u8, len: usize) -> Result<String, String> {
+    let bytes = unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) };
+    String::from_utf8(bytes.into_vec()).map_err(|_| String::from("The input isn't UTF-8"))
+}
+
+fn with_session(f: impl FnOnce(&mut Session) -> Result<Json, String>) -> Result<Json, String> {
+    SESSION.with(|s| match s.borrow_mut().as_mut() {
+        Some(session) => f(session),
+        None => Err(String::from("No game has been started")),
+    })
+}
+
+// A buffer for the host to write len bytes of input into, the call it's passed to frees it
+#[unsafe(no_mangle)]
+pub extern "C" fn unoler_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn unoler_output() -> *const u8 {
+    OUTPUT.with(|o| o.borrow().as_ptr())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn unoler_output_len() -> usize {
+    OUTPUT.with(|o| o.borrow().len())
+}
+
+// Settings JSON in (see session::settings_from_json), the view of the new game out
+// ptr and len have to come from unoler_alloc
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unoler_start(ptr: *mut u8, len: usize) -> usize {
+    let started = unsafe { take_input(ptr, len) }
+        .and_then(|text| Json::parse(&text))
+        .and_then(|json| settings_from_json(&json))
+        .and_then(|settings| Session::start(&settings));
+    output(started.map(|session| {
+        let view = session.view();
+        SESSION.with(|s| *s.borrow_mut() = Some(session));
+        view
+    }))
+}
+
+// What the player typed in, the view afterwards out
+// ptr and len have to come from unoler_alloc
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unoler_act(ptr: *mut u8, len: usize) -> usize {
+    let answer = unsafe { take_input(ptr, len) };
+    output(answer.and_then(|answer| with_session(|session| {
+        session.act(&answer)?;
+        Ok(session.view())
+    })))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn unoler_view() -> usize {
+    output(with_session(|session| Ok(session.view())))
+}
+
+// Every event from number `since` on, the same objects --json prints
+#[unsafe(no_mangle)]
+pub extern "C" fn unoler_events(since: usize) -> usize {
+    output(with_session(|session| Ok(session.events_json(since))))
+}
@@ -0,0 +1,52 @@
+// The stable API from the outside: only what the crate root re-exports, the way another program would use it
+// A change that breaks this breaks everybody building on the engine
+
+use unoler::{Difficulty, Event, Frontend, Json, Prompt, RuleSet, Session, Settings, Stack, UNOCard, play, setup, settings_from_json};
+
+#[test]
+fn a_session_plays_to_the_end_one_answer_at_a_time() {
+    let settings = settings_from_json(&Json::parse(r#"{"players": 1, "ai_players": 2, "difficulty": "calm", "seed": "6"}"#).unwrap()).unwrap();
+    let mut session = Session::start(&settings).unwrap();
+    // The human seat plays the way Skilled would
+    while session.winner().is_none() {
+        let answer = session.suggest(Difficulty::Skilled).unwrap();
+        session.act(&answer).unwrap();
+    }
+    assert!(session.events().any(|e| matches!(e, Event::GameStart { .. })));
+}
+
+// Every seat an AI, so nothing is ever asked but to go on
+struct Watching(Vec<Event>);
+
+impl Frontend for Watching {
+    fn event(&mut self, event: &Event) {
+        self.0.push(event.clone());
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        match prompt {
+            Prompt::Continue | Prompt::Exit => String::new(),
+            _ => panic!("Nobody should be asked {}", prompt.name()),
+        }
+    }
+}
+
+#[test]
+fn a_frontend_of_your_own_watches_a_stacked_deal() {
+    let stack: Stack = "hand: R5 R6\nhand: B3 G4\ntop: R2\ndeck: Y1 Y2 Y3 Y4".parse().unwrap();
+    let settings = Settings {
+        players: Some(0),
+        ai_players: Some(2),
+        difficulty: Some(Difficulty::Skilled),
+        rules: RuleSet { stacking: false, ..RuleSet::default() },
+        seed: Some(1),
+        stack: Some(stack),
+        ..Settings::default()
+    };
+    let mut fe = Watching(Vec::new());
+    let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+    let hand: &[UNOCard] = &table.hands[0];
+    assert_eq!(hand.len(), 2);
+    assert_eq!(play(&mut table, &mut fe, None).unwrap(), Some(1));
+    assert!(fe.0.iter().any(|e| matches!(e, Event::Played { player: 1, .. })));
+}
@@ -0,0 +1,1025 @@
+// The rules, played out on hand built tables
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use unoler::cards::{Color, DECK_SIZE, Hand, SpecialCard, UNOCard};
+use unoler::events::Event;
+use unoler::game::{Difficulty, Forfeited, Scores, Settings, Table, play, rematch, setup};
+use unoler::invariants;
+use unoler::plugins::{RulePlugin, StrictWildFour};
+use unoler::rules::{RuleSet, TurnState};
+use unoler::stack::Stack;
+use unoler::testing::{Running, ScriptedFrontend, TestGame, card, cards};
+
+const FILLER: [&str; 8] = ["yellow 1", "yellow 2", "yellow 3", "yellow 4", "yellow 5", "yellow 6", "yellow 7", "yellow 8"];
+
+fn count(events: &[Event], wanted: impl Fn(&Event) -> bool) -> usize {
+    events.iter().filter(|e| wanted(e)).count()
+}
+
+#[test]
+fn draw_twos_stack_onto_the_next_player() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red +2", "red 5"])
+        .hand(1, &["blue +2", "green 3"])
+        .top("red 1")
+        .deck(&FILLER)
+        .actions(&["play red +2", "play blue +2"])
+        .build();
+
+    game.play_turns(3);
+
+    assert_eq!(game.hand(0).len(), 5, "player 1 can't answer and takes all four");
+    assert_eq!(count(game.events(), |e| matches!(e, Event::ForcedDraw { player: 1, .. })), 4);
+    assert_eq!(game.table().add_queue, 0);
+    assert_eq!(game.table().last_played, card("blue +2"));
+}
+
+#[test]
+fn without_stacking_a_draw_two_is_taken_right_away() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red +2", "red 5"])
+        .hand(1, &["blue +2", "green 3"])
+        .top("red 1")
+        .deck(&FILLER)
+        .rules("official".parse().unwrap())
+        .actions(&["play red +2"])
+        .build();
+
+    game.play_turns(2);
+
+    assert_eq!(game.hand(1).len(), 4);
+    assert!(game.hand(1).contains(&card("blue +2")), "holding a +2 doesn't help");
+    assert_eq!(game.table().last_played, card("red +2"));
+}
+
+#[test]
+fn draw_fours_skip_even_with_a_counter_in_hand() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["wild +4", "red 5"])
+        .hand(1, &["blue +2", "green 1"])
+        .deck(&FILLER)
+        .actions(&["play wild +4", "green"])
+        .build();
+
+    game.play_turns(2);
+
+    assert_eq!(game.table().last_played.color, Color::Green);
+    assert_eq!(game.hand(1).len(), 6);
+    assert_eq!(count(game.events(), |e| matches!(e, Event::Skipped { player: 2 })), 1);
+}
+
+#[test]
+fn skips_pass_over_the_next_player() {
+    let mut game = TestGame::new(3)
+        .hand(0, &["red skip", "red 5"])
+        .hand(1, &["red 6", "red 7"])
+        .hand(2, &["red 8", "red 9"])
+        .actions(&["play red skip", "play red 8"])
+        .build();
+
+    game.play_turns(3);
+
+    assert_eq!(game.hand(1).len(), 2);
+    assert_eq!(game.table().last_played, card("red 8"));
+    assert_eq!(count(game.events(), |e| matches!(e, Event::Skipped { player: 2 })), 1);
+}
+
+#[test]
+fn reverses_turn_the_table_around() {
+    let mut game = TestGame::new(3)
+        .hand(0, &["red reverse", "red 5"])
+        .hand(1, &["red 6", "red 7"])
+        .hand(2, &["red 8", "red 9"])
+        .actions(&["play red reverse", "play red 8", "play red 6"])
+        .build();
+
+    game.play_turns(3);
+
+    let turns: Vec<usize> = game.events().iter()
+        .filter_map(|e| if let Event::TurnStart { player, .. } = e { Some(*player) } else { None })
+        .collect();
+    assert_eq!(turns, [1, 3, 2]);
+}
+
+#[test]
+fn reverses_are_skips_with_two_players() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red reverse", "red 5"])
+        .hand(1, &["red 6", "red 7"])
+        .actions(&["play red reverse", "play red 5"])
+        .build();
+
+    assert_eq!(game.play_turns(3), Some(1));
+    assert_eq!(game.hand(1).len(), 2);
+}
+
+#[test]
+fn an_empty_deck_is_refilled_from_the_discard_pile() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["green 5", "green 6"])
+        .hand(1, &["blue 1"])
+        .top("red 1")
+        .actions(&["d", "1"])
+        .build();
+    game.table_mut().discard = vec![card("red 7"), card("red 8"), card("red 1")];
+
+    game.play_turns(1);
+
+    assert_eq!(count(game.events(), |e| matches!(e, Event::Reshuffled { .. })), 1);
+    assert_eq!(game.table().deck.len(), 1);
+    assert_eq!(game.table().discard.len(), 2, "the old top card stays put");
+    assert_eq!(game.table().discard[0], card("red 1"));
+    assert!(matches!(game.table().last_played, c if c == card("red 7") || c == card("red 8")));
+}
+
+#[test]
+fn the_history_keeps_every_card_through_reshuffles() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 2", "green 6"])
+        .hand(1, &["green 2", "blue 1"])
+        .top("red 1")
+        .actions(&["play red 2", "play green 2", "d", "play green 6"])
+        .build();
+
+    game.play_turns(3);
+
+    assert!(matches!(game.events().iter().find(|e| matches!(e, Event::Reshuffled { .. })), Some(Event::Reshuffled { cards: 2 })));
+    assert_eq!(game.table().discard, cards(&["green 2", "green 6"]));
+    assert_eq!(game.table().history, cards(&["red 1", "red 2", "green 2", "green 6"]));
+}
+
+#[test]
+fn reshuffled_wilds_lose_their_color() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["blue 5"])
+        .hand(1, &["blue 1"])
+        .top("wild blue")
+        .actions(&["d", "play blue 5"])
+        .build();
+    game.table_mut().discard = vec![card("wild +4 red"), card("wild")];
+
+    game.play_turns(1);
+
+    assert!(game.events().contains(&Event::Drew { player: 1, card: Some(card("wild +4")) }));
+    assert_eq!(game.hand(0), [card("wild +4")]);
+}
+
+#[test]
+fn illegal_moves_are_refused_and_asked_again() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["blue 5", "red 2", "green 7"])
+        .top("red 1")
+        .hand(1, &["red 3"])
+        .actions(&["play blue 5", "4", "play red 2"])
+        .build();
+
+    game.play_turns(1);
+
+    assert_eq!(count(game.events(), |e| *e == Event::IllegalMove(card("blue 5"))), 1);
+    assert_eq!(count(game.events(), |e| matches!(e, Event::InvalidInput(_))), 1);
+    assert_eq!(game.table().last_played, card("red 2"));
+    assert_eq!(game.actions_left(), 0);
+}
+
+#[test]
+fn the_menu_pauses_the_turn_and_goes_back_to_it() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 5", "blue 6"])
+        .hand(1, &["red 3"])
+        .top("red 1")
+        .actions(&["menu", "rules", "settings palette contrast", "dance", "resume", "play red 5"])
+        .build();
+
+    game.play_turns(1);
+
+    let notices: Vec<&Event> = game.events().iter().filter(|e| matches!(e, Event::Notice(_) | Event::InvalidInput(_))).collect();
+    assert_eq!(notices, [
+        &Event::Notice(String::from("House rules: stacking on, force play off, 7 cards each, opening action off, draw one off")),
+        &Event::InvalidInput(String::from("Could not change that: nothing can be changed here")),
+        &Event::InvalidInput(String::from("Please enter save <slot>, rules, settings, forfeit or resume")),
+    ]);
+    assert_eq!(game.table().last_played, card("red 5"));
+    assert_eq!(game.actions_left(), 0);
+}
+
+#[test]
+fn forfeiting_ends_the_game_without_a_winner() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 5"])
+        .hand(1, &["red 3"])
+        .actions(&["m", "forfeit"])
+        .build();
+
+    let error = game.try_turns(1).unwrap_err();
+
+    assert!(matches!(error.downcast_ref::<Forfeited>(), Some(Forfeited(1))));
+    assert_eq!(game.events().last(), Some(&Event::Notice(String::from("Player #1 forfeits the game"))));
+    assert_eq!(game.hand(0), [card("red 5")]);
+}
+
+#[test]
+fn wilds_take_the_chosen_color() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["wild", "blue 5"])
+        .hand(1, &["red 3"])
+        .actions(&["play wild", "yellow"])
+        .build();
+
+    game.play_turns(1);
+
+    assert_eq!(game.table().last_played, card("wild yellow"));
+    assert_eq!(count(game.events(), |e| *e == Event::ColorChosen { player: 1, color: Color::Yellow }), 1);
+}
+
+#[test]
+fn force_play_refuses_draws_when_a_card_fits() {
+    let rules = RuleSet { force_play: true, ..RuleSet::default() };
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 5"])
+        .hand(1, &["red 3"])
+        .deck(&FILLER)
+        .rules(rules)
+        .actions(&["d", "play red 5"])
+        .build();
+
+    assert_eq!(game.play_turns(1), Some(1));
+    assert_eq!(game.table().deck.len(), FILLER.len());
+    assert_eq!(game.events().last(), Some(&Event::Win { player: 1 }));
+}
+
+#[test]
+fn under_draw_one_only_the_drawn_card_can_be_played() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["green 5", "blue 6"])
+        .hand(1, &["green 2", "yellow 3"])
+        .top("red 1")
+        .deck(&["red 4", "yellow 9", "red 7"])
+        .rules(RuleSet { draw_one: true, ..RuleSet::default() })
+        .actions(&["d", "y", "d", "d", "n"])
+        .build();
+
+    game.play_turns(3);
+
+    assert!(game.events().contains(&Event::Played { player: 1, ai: false, card: card("red 4") }));
+    assert_eq!(game.hand(1).len(), 3, "yellow 9 can't go on red 4, so nobody asks");
+    assert_eq!(game.hand(0), cards(&["green 5", "blue 6", "red 7"]), "red 7 was kept");
+    assert_eq!(game.table().last_played, card("red 4"));
+    assert_eq!(game.actions_left(), 0);
+}
+
+#[test]
+fn drawing_on_a_color_is_remembered_until_more_cards_come() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["green 5", "blue 6"])
+        .hand(1, &["red +2", "green 2"])
+        .top("red 1")
+        .deck(&["yellow 9", "yellow 2", "yellow 3"])
+        .rules(RuleSet { draw_one: true, ..RuleSet::default() })
+        .actions(&["d", "play red +2"])
+        .build();
+
+    game.play_turns(1);
+    assert_eq!(game.table().lacking, [vec![Color::Red], vec![]]);
+
+    game.play_turns(2);
+    assert_eq!(game.hand(0).len(), 5);
+    assert_eq!(game.table().lacking, [vec![], vec![]], "the +2 could have brought them red");
+}
+
+#[test]
+fn the_ai_keeps_a_drawn_wild_until_it_needs_it() {
+    let game = |difficulty| {
+        let mut game = TestGame::new(2)
+            .hand(0, &["green 5", "blue 6"])
+            .hand(1, &["green 2", "yellow 3"])
+            .top("red 1")
+            .deck(&["wild"])
+            .rules(RuleSet { draw_one: true, ..RuleSet::default() })
+            .ai_from(0, difficulty)
+            .build();
+        game.play_turns(1);
+        game.hand(0).len()
+    };
+    assert_eq!(game(Difficulty::Calm), 3);
+    assert_eq!(game(Difficulty::Aggressive), 2);
+}
+
+#[test]
+fn the_ai_follows_suit() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 5", "blue 5"])
+        .hand(1, &["green 3", "red 9", "blue 2"])
+        .ai_from(1, Difficulty::Calm)
+        .actions(&["play red 5"])
+        .build();
+
+    game.play_turns(2);
+
+    assert_eq!(game.table().last_played, card("red 9"));
+    assert_eq!(count(game.events(), |e| matches!(e, Event::Played { player: 2, ai: true, .. })), 1);
+}
+
+#[test]
+fn the_skilled_ai_sheds_points_when_somebody_is_on_uno() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 1", "blue 4"])
+        .hand(1, &["red 2", "red 9", "red +2", "wild"])
+        .top("red 5")
+        .ai_from(1, Difficulty::Skilled)
+        .actions(&["play red 1"])
+        .build();
+    game.table_mut().uno_detection_panic = true;
+
+    game.play_turns(2);
+
+    assert_eq!(game.table().last_played.special, SpecialCard::ColorChange);
+    assert_eq!(game.hand(1).points(), 31);
+}
+
+#[test]
+fn opening_skips_and_reverses_count_under_the_official_rules() {
+    let deal = |special: SpecialCard| (0..).find_map(|seed| {
+        let settings = Settings { players: Some(0), ai_players: Some(3), difficulty: Some(Difficulty::Calm), rules: "official".parse().unwrap(), seed: Some(seed), ..Settings::default() };
+        let mut fe = ScriptedFrontend::default();
+        let table = setup(&mut fe, &settings).unwrap().unwrap();
+        (table.last_played.special == special).then_some((table, fe))
+    }).unwrap();
+
+    let (mut table, mut fe) = deal(SpecialCard::Skip);
+    play(&mut table, &mut fe, Some(1)).unwrap();
+    assert!(fe.events.contains(&Event::Skipped { player: 1 }));
+
+    let (mut table, mut fe) = deal(SpecialCard::Reverse);
+    play(&mut table, &mut fe, Some(2)).unwrap();
+    let turns: Vec<usize> = fe.events.iter().filter_map(|e| if let Event::TurnStart { player, .. } = e { Some(*player) } else { None }).collect();
+    assert_eq!(turns, [3, 2], "the dealer goes first and play goes the other way");
+}
+
+#[test]
+fn asked_for_decks_have_to_hold_every_hand() {
+    let settings = |decks| Settings { players: Some(0), ai_players: Some(20), difficulty: Some(Difficulty::Calm), seed: Some(3), decks: Some(decks), ..Settings::default() };
+    let refused = setup(&mut ScriptedFrontend::default(), &settings(1)).err().unwrap().to_string();
+    assert!(refused.contains("need 141 cards, 1 deck only has 108. Ask for 2 decks"), "{}", refused);
+
+    let mut fe = ScriptedFrontend::default();
+    let table = setup(&mut fe, &settings(3)).unwrap().unwrap();
+    assert_eq!(table.deck.len() + table.discard.len() + 20 * 7, 3 * DECK_SIZE);
+    assert!(!fe.events.contains(&Event::NewDeck));
+}
+
+#[test]
+fn big_tables_are_dealt_from_several_decks() {
+    let settings = Settings { players: Some(0), ai_players: Some(40), difficulty: Some(Difficulty::Calm), seed: Some(3), ..Settings::default() };
+    let mut fe = ScriptedFrontend::default();
+    let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+    assert_eq!(table.deck.len() + table.discard.len() + 40 * 7, 3 * DECK_SIZE);
+    invariants::check(&table).unwrap();
+
+    assert!(play(&mut table, &mut fe, None).unwrap().is_some());
+    invariants::check(&table).unwrap();
+}
+
+#[test]
+fn rematches_keep_the_table_and_pass_the_deal_along() {
+    let mix = vec![Difficulty::Skilled, Difficulty::Calm, Difficulty::Aggressive];
+    let settings = Settings { players: Some(0), ai_players: Some(3), ai_mix: mix.clone(), seed: Some(5), ..Settings::default() };
+    let mut fe = ScriptedFrontend::default();
+    let mut scores = Scores::default();
+    let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+
+    for game in 1..=3 {
+        let winner = play(&mut table, &mut fe, None).unwrap();
+        let points = unoler::game::round_points(&table);
+        let before = scores.points.clone();
+        scores.record(&table, winner);
+        let winner = winner.unwrap();
+        assert_eq!(scores.points[winner - 1], before.get(winner - 1).unwrap_or(&0) + points);
+
+        let next = rematch(&mut table, &settings, scores.games);
+        assert_ne!(next.seed, table.seed, "every game is dealt from a new seed");
+        table = setup(&mut fe, &next).unwrap().unwrap();
+        assert_eq!(table.difficulties, mix);
+        assert_eq!(table.game_state.current_player, game % 3);
+        invariants::check(&table).unwrap();
+    }
+    assert_eq!(scores.games, 3);
+    assert_eq!(scores.lines(&table.names).len(), 4);
+}
+
+fn house_cards(cards: &[(&str, &str)]) -> RuleSet {
+    let overrides: Vec<(String, String)> = cards.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    RuleSet::build(None, &overrides).unwrap()
+}
+
+#[test]
+fn a_house_seven_swaps_with_the_smallest_hand() {
+    let mut game = TestGame::new(3)
+        .hand(0, &["red 7", "red 5", "blue 1"])
+        .hand(1, &["green 2", "green 3", "green 4", "green 6"])
+        .hand(2, &["red 8", "red 9"])
+        .top("red 1")
+        .rules(house_cards(&[("card-7", "swap")]))
+        .actions(&["play red 7"])
+        .build();
+
+    game.play_turns(1);
+
+    assert_eq!(game.hand(0), cards(&["red 8", "red 9"]));
+    assert_eq!(game.hand(2), cards(&["red 5", "blue 1"]));
+    assert_eq!(game.hand(1).len(), 4);
+}
+
+#[test]
+fn a_house_zero_passes_every_hand_along() {
+    let mut game = TestGame::new(3)
+        .hand(0, &["red 0", "red 5"])
+        .hand(1, &["green 2", "green 3"])
+        .hand(2, &["blue 8", "blue 9", "blue 1"])
+        .top("red 1")
+        .rules(house_cards(&[("card-0", "rotate")]))
+        .actions(&["play red 0"])
+        .build();
+
+    game.play_turns(1);
+
+    assert_eq!(game.hand(0), cards(&["blue 8", "blue 9", "blue 1"]));
+    assert_eq!(game.hand(1), cards(&["red 5"]));
+    assert_eq!(game.hand(2), cards(&["green 2", "green 3"]));
+}
+
+#[test]
+fn house_cards_can_pick_a_color_and_make_the_next_player_draw() {
+    let mut game = TestGame::new(3)
+        .hand(0, &["red 5", "red 6"])
+        .hand(1, &["green 2", "green 3"])
+        .hand(2, &["blue 8", "blue 9"])
+        .top("red 1")
+        .deck(&FILLER)
+        .rules(house_cards(&[("card-5", "color+draw2+skip2")]))
+        .actions(&["play red 5", "blue"])
+        .build();
+
+    game.play_turns(2);
+
+    assert_eq!(game.table().history[1], card("blue 5"), "the five counts as blue");
+    assert_eq!(game.hand(1).len(), 2, "skipped past without drawing");
+    assert_eq!(game.hand(2).len(), 4, "the second player skipped takes the two");
+    assert_eq!(count(game.events(), |e| matches!(e, Event::ForcedDraw { player: 3, .. })), 2);
+}
+
+#[test]
+fn house_card_games_keep_the_invariants() {
+    let tables = [
+        [("card-3", "draw2+skip2"), ("card-7", "swap")],
+        [("card-5", "reverse+color"), ("card-0", "rotate")],
+        [("card-5", "draw3+color"), ("card-9", "skip3")],
+    ];
+    for house in tables {
+        for seed in 1..=5 {
+            let settings = Settings { players: Some(0), ai_players: Some(4), difficulty: Some(Difficulty::Calm), seed: Some(seed), rules: house_cards(&house), ..Settings::default() };
+            let mut fe = ScriptedFrontend::default();
+            let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+            invariants::check(&table).unwrap();
+            // One turn at a time, the way --check-invariants looks at it
+            while play(&mut table, &mut fe, Some(1)).unwrap().is_none() {
+                invariants::check(&table).unwrap_or_else(|e| panic!("{:?} seed {}: {}", house, seed, e));
+            }
+        }
+    }
+}
+
+#[test]
+fn the_last_card_wins_before_any_hands_move() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 7"])
+        .hand(1, &["green 2", "green 3"])
+        .top("red 1")
+        .rules(house_cards(&[("card-7", "swap")]))
+        .actions(&["play red 7"])
+        .build();
+
+    assert_eq!(game.play_turns(1), Some(1));
+    assert_eq!(game.hand(1).len(), 2);
+}
+
+// Gives every player who starts a turn on a red card an extra card to draw, and counts the cards played
+struct RedTax(Rc<Cell<usize>>);
+
+impl RulePlugin for RedTax {
+    fn on_turn_start(&mut self, table: &Table, turn: &mut TurnState) {
+        if table.last_played.color == Color::Red {
+            turn.add_queue += 1;
+        }
+    }
+
+    fn on_card_played(&mut self, _table: &Table, _card: UNOCard, _turn: &mut TurnState) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[test]
+fn plugins_hear_about_every_turn() {
+    let played = Rc::new(Cell::new(0));
+    let mut game = TestGame::new(2)
+        .hand(0, &["blue 1", "red 5"])
+        .hand(1, &["red 1", "green 3"])
+        .top("blue 7")
+        .deck(&FILLER)
+        .actions(&["play blue 1", "play red 1"])
+        .build()
+        .plugin(RedTax(played.clone()));
+
+    game.play_turns(3);
+
+    assert_eq!(count(game.events(), |e| matches!(e, Event::ForcedDraw { .. })), 1, "only the red top cost anything");
+    assert_eq!(game.hand(0).len(), 2);
+    assert_eq!(played.get(), 2);
+}
+
+#[test]
+fn a_plugin_can_take_a_card_away() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["wild +4", "red 5", "blue 2"])
+        .hand(1, &["green 2", "green 3"])
+        .top("red 1")
+        .deck(&FILLER)
+        .actions(&["play wild +4", "play red 5"])
+        .build()
+        .plugin(StrictWildFour);
+
+    game.play_turns(1);
+
+    assert!(game.events().iter().any(|e| matches!(e, Event::IllegalMove(c) if *c == card("wild +4"))));
+    assert_eq!(game.table().last_played, card("red 5"));
+}
+
+#[test]
+fn a_seat_handicapped_out_of_stacking_takes_the_draw() {
+    let mut game = TestGame::new(3)
+        .hand(0, &["red +2", "red 5"])
+        .hand(1, &["blue +2", "green 3"])
+        .hand(2, &["yellow 9"])
+        .top("red 1")
+        .deck(&FILLER)
+        .actions(&["play red +2"])
+        .build();
+    game.table_mut().handicaps[1] = "no-stack".parse().unwrap();
+
+    game.play_turns(2);
+
+    assert_eq!(game.hand(1).len(), 4, "stacking is on, but not for seat 2");
+    assert_eq!(game.table().add_queue, 0);
+}
+
+#[test]
+fn a_seat_handicapped_with_force_play_has_to_play() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 5", "green 3"])
+        .hand(1, &["yellow 9"])
+        .top("red 1")
+        .deck(&FILLER)
+        .actions(&["d", "play red 5"])
+        .build();
+    game.table_mut().handicaps[0] = "force-play".parse().unwrap();
+
+    game.play_turns(1);
+
+    assert!(game.events().iter().any(|e| matches!(e, Event::InvalidInput(m) if m.contains("force play"))));
+    assert_eq!(game.hand(0), cards(&["green 3"]));
+}
+
+#[test]
+fn handicapped_seats_are_dealt_extra_cards() {
+    let settings = Settings {
+        players: Some(0),
+        ai_players: Some(3),
+        difficulty: Some(Difficulty::Calm),
+        seed: Some(8),
+        handicaps: vec![Default::default(), "draw3".parse().unwrap()],
+        ..Settings::default()
+    };
+    let plain = setup(&mut ScriptedFrontend::default(), &Settings { handicaps: Vec::new(), ..settings.clone() }).unwrap().unwrap();
+    let table = setup(&mut ScriptedFrontend::default(), &settings).unwrap().unwrap();
+
+    assert_eq!(table.hands.iter().map(|h| h.len()).collect::<Vec<_>>(), [7, 10, 7]);
+    assert_eq!(table.hands[0], plain.hands[0], "the others are dealt what they would have been");
+    assert_eq!(table.hands[1][..7], plain.hands[1][..]);
+    invariants::check(&table).unwrap();
+}
+
+#[test]
+fn handicaps_count_toward_the_decks_a_big_table_needs() {
+    let draw10 = "draw10".parse().unwrap();
+    let settings = Settings {
+        players: Some(0),
+        ai_players: Some(12),
+        difficulty: Some(Difficulty::Calm),
+        seed: Some(1),
+        handicaps: vec![draw10; 4],
+        ..Settings::default()
+    };
+    let mut fe = ScriptedFrontend::default();
+    let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+    assert_eq!(table.deck.len() + table.discard.len() + 12 * 7 + 4 * 10, 2 * DECK_SIZE);
+    invariants::check(&table).unwrap();
+
+    assert!(play(&mut table, &mut fe, None).unwrap().is_some());
+}
+
+#[test]
+fn trading_swaps_a_card_for_the_top_of_the_deck_once_a_game() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["green 5", "blue 6"])
+        .hand(1, &["red 7", "yellow 3"])
+        .top("red 1")
+        .deck(&["yellow 9", "red 4"])
+        .rules(RuleSet { trade: true, ..RuleSet::default() })
+        .actions(&["trade blue 6", "play red 7", "t 1", "d", "d", "play red 4"])
+        .build();
+
+    game.play_turns(1);
+    assert!(game.events().contains(&Event::Traded { player: 1, gave: card("blue 6"), got: Some(card("yellow 9")) }));
+    assert_eq!(game.table().deck.last(), Some(&card("blue 6")), "the card given is drawn next");
+    assert_eq!(game.table().traded, [true, false]);
+
+    game.play_turns(2);
+    assert!(game.events().iter().any(|e| matches!(e, Event::InvalidInput(m) if m.contains("already traded"))));
+    assert_eq!(game.hand(0).len(), 3);
+    assert!(game.hand(0).contains(&card("yellow 9")) && game.hand(0).contains(&card("blue 6")));
+    assert_eq!(game.table().last_played, card("red 4"));
+    assert_eq!(game.actions_left(), 0);
+}
+
+#[test]
+fn the_ai_takes_over_a_seat_with_its_hand_where_it_sits() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["green 5", "blue 6"])
+        .hand(1, &["red 7", "yellow 3"])
+        .top("red 1")
+        .deck(&FILLER)
+        .actions(&["takeover grumpy", "takeover", "play yellow 3"])
+        .build();
+
+    game.play_turns(1);
+    assert!(game.events().iter().any(|e| matches!(e, Event::InvalidInput(m) if m.contains("grumpy"))));
+    assert!(game.events().contains(&Event::Notice(String::from("The skilled AI plays Player #1's seat from here"))));
+    assert_eq!(game.table().taken_over, [true, false]);
+    assert_eq!(game.table().difficulties[0], Difficulty::Skilled);
+    assert!(game.events().contains(&Event::Drew { player: 1, card: None }), "it had nothing to play and drew");
+    assert!(game.hand(0).contains(&card("green 5")) && game.hand(0).contains(&card("blue 6")), "from the same hand");
+    assert_eq!(game.table().game_state.current_player, 1, "play goes on from the same seat");
+
+    game.play_turns(2);
+    assert_eq!(game.actions_left(), 0, "the seat is never asked again");
+    assert_eq!(count(game.events(), |e| matches!(e, Event::TurnStart { player: 1, ai: true, .. })), 1);
+}
+
+#[test]
+fn somebody_late_joins_an_ai_seat_with_its_hand() {
+    let mut game = TestGame::new(3)
+        .hand(0, &["green 5", "blue 6"])
+        .hand(1, &["red 7", "yellow 3"])
+        .hand(2, &["red 8", "green 9"])
+        .top("red 1")
+        .deck(&FILLER)
+        .ai_from(1, Difficulty::Calm)
+        .actions(&["join 1", "join 4", "join 3 Maria Lopez", "play red 5", "play red 8"])
+        .build();
+    game.table_mut().hands[0].push(card("red 5"));
+
+    game.play_turns(1);
+    assert!(game.events().iter().any(|e| matches!(e, Event::InvalidInput(m) if m == "Player #1 is already playing seat 1")));
+    assert!(game.events().iter().any(|e| matches!(e, Event::InvalidInput(m) if m.starts_with("There's no seat 4"))));
+    assert!(game.events().contains(&Event::Notice(String::from("Maria Lopez sits down at seat 3 and plays Player #3's hand from its next turn"))));
+    assert_eq!(game.table().joined, [false, false, true]);
+    assert!(!game.table().is_ai(2) && game.table().is_ai(1));
+
+    game.play_turns(2);
+    assert_eq!(game.table().names[2], "Maria Lopez");
+    assert!(game.events().iter().any(|e| matches!(e, Event::TurnStart { player: 3, ai: false, .. })));
+    assert!(game.events().contains(&Event::Hand(cards(&["red 8", "green 9"]))), "her hand is the AI's");
+    assert_eq!(game.actions_left(), 0);
+}
+
+#[test]
+fn trading_is_refused_without_the_rule() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 5", "blue 6"])
+        .hand(1, &["yellow 3"])
+        .deck(&FILLER)
+        .actions(&["trade blue 6", "play red 5"])
+        .build();
+
+    game.play_turns(1);
+
+    assert!(game.events().iter().any(|e| matches!(e, Event::InvalidInput(m) if m.contains("off for this game"))));
+    assert_eq!(game.hand(0), cards(&["blue 6"]));
+}
+
+#[test]
+fn an_ai_with_nothing_to_play_trades_instead_of_drawing() {
+    let game = |difficulty| {
+        let mut game = TestGame::new(2)
+            .hand(0, &["green 5", "green 7", "blue 6"])
+            .hand(1, &["red 8", "red 9"])
+            .top("red 1")
+            .deck(&["yellow 9", "red 4"])
+            .rules(RuleSet { trade: true, ..RuleSet::default() })
+            .ai_from(0, difficulty)
+            .build();
+        game.play_turns(1);
+        game
+    };
+
+    let skilled = game(Difficulty::Skilled);
+    assert!(skilled.events().contains(&Event::Traded { player: 1, gave: card("blue 6"), got: None }), "the color it holds least of");
+    assert!(skilled.hand(0).contains(&card("yellow 9")));
+    assert_eq!(skilled.hand(0).len(), 3);
+
+    let calm = game(Difficulty::Calm);
+    assert_eq!(count(calm.events(), |e| matches!(e, Event::Traded { .. })), 0);
+    assert_eq!(count(calm.events(), |e| matches!(e, Event::Drew { .. })), 2);
+}
+
+#[test]
+fn an_illegal_play_can_cost_cards() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["blue 6", "red 5"])
+        .hand(1, &["yellow 3"])
+        .top("red 1")
+        .deck(&FILLER)
+        .rules(RuleSet { illegal_penalty: 2, ..RuleSet::default() })
+        .actions(&["play blue 6", "play red 5"])
+        .build();
+
+    game.play_turns(1);
+
+    assert_eq!(count(game.events(), |e| matches!(e, Event::ForcedDraw { player: 1, .. })), 2);
+    assert_eq!(game.hand(0), cards(&["yellow 1", "yellow 2", "blue 6"]), "and then it's still their turn");
+    assert_eq!(game.table().last_played, card("red 5"));
+}
+
+#[test]
+fn a_forgotten_uno_can_cost_cards() {
+    let game = |rules: RuleSet, action: &str| {
+        let mut game = TestGame::new(2)
+            .hand(0, &["red 5", "red 6"])
+            .hand(1, &["yellow 3", "blue 4"])
+            .top("red 1")
+            .deck(&FILLER)
+            .rules(rules)
+            .actions(&[action])
+            .build();
+        game.play_turns(1);
+        game
+    };
+    let penalty = RuleSet { uno_penalty: 4, ..RuleSet::default() };
+
+    let forgot = game(penalty, "play red 5");
+    assert_eq!(count(forgot.events(), |e| matches!(e, Event::ForcedDraw { player: 1, .. })), 4);
+    assert_eq!(count(forgot.events(), |e| matches!(e, Event::Uno { .. })), 0);
+    assert_eq!(forgot.hand(0).len(), 5);
+
+    let called = game(penalty, "play red 5 uno");
+    assert!(called.events().contains(&Event::Uno { player: 1 }));
+    assert_eq!(called.hand(0), cards(&["red 6"]));
+    // Without the rule it's called for them, with the word or without
+    assert_eq!(game(RuleSet::default(), "play red 5").hand(0), cards(&["red 6"]));
+    assert_eq!(game(RuleSet::default(), "play red 5 uno").events(), called.events());
+}
+
+#[test]
+fn the_ai_never_forgets_uno() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 3", "blue 4", "green 2"])
+        .hand(1, &["red 5", "red 6"])
+        .top("red 9")
+        .deck(&FILLER)
+        .rules(RuleSet { uno_penalty: 4, ..RuleSet::default() })
+        .actions(&["play red 3"])
+        .ai_from(1, Difficulty::Skilled)
+        .build();
+    game.play_turns(2);
+    assert!(game.events().contains(&Event::Uno { player: 2 }));
+    assert_eq!(game.hand(1).len(), 1);
+}
+
+#[test]
+fn a_plus_four_can_be_challenged() {
+    let game = |first: &[&str], answer: &str, then: &[&str]| {
+        let actions = [&["play wild +4", "blue"][..], &[answer], then].concat();
+        let mut game = TestGame::new(2)
+            .hand(0, first)
+            .hand(1, &["blue 7", "green 2"])
+            .top("red 1")
+            .deck(&FILLER)
+            .rules(RuleSet { challenge_penalty: 2, ..RuleSet::default() })
+            .actions(&actions)
+            .build();
+        game.play_turns(2);
+        assert_eq!(game.actions_left(), 0);
+        game
+    };
+
+    // Holding a red card, the +4 was a bluff and comes back to them, the challenger plays on
+    let caught = game(&["wild +4", "red 5", "green 9"], "y", &["play blue 7"]);
+    assert!(caught.events().contains(&Event::Challenge { player: 2, against: 1, bluff: true }));
+    assert_eq!(caught.hand(0).len(), 6);
+    assert_eq!(caught.hand(1), cards(&["green 2"]));
+    assert_eq!(caught.table().last_played, card("blue 7"));
+
+    // Nothing red, so the challenger takes the four and the penalty
+    let wrong = game(&["wild +4", "blue 5", "green 9"], "y", &[]);
+    assert!(wrong.events().contains(&Event::Challenge { player: 2, against: 1, bluff: false }));
+    assert_eq!(count(wrong.events(), |e| matches!(e, Event::ForcedDraw { player: 2, .. })), 6);
+    assert_eq!(wrong.hand(0).len(), 2);
+
+    let taken = game(&["wild +4", "red 5", "green 9"], "n", &[]);
+    assert_eq!(count(taken.events(), |e| matches!(e, Event::Challenge { .. })), 0);
+    assert_eq!(taken.hand(1).len(), 6);
+}
+
+#[test]
+fn the_ai_challenges_by_what_it_has_seen() {
+    let game = |difficulty: Difficulty, lacking: Vec<Color>| {
+        let mut game = TestGame::new(2)
+            .hand(0, &["wild +4", "red 5", "green 9", "yellow 4", "blue 2"])
+            .hand(1, &["blue 7", "green 2"])
+            .top("red 1")
+            .deck(&FILLER)
+            .rules(RuleSet { challenge_penalty: 2, ..RuleSet::default() })
+            .actions(&["play wild +4", "blue"])
+            .ai_from(1, difficulty)
+            .build();
+        game.table_mut().lacking[0] = lacking;
+        game.play_turns(2);
+        game
+    };
+    let challenged = |game: &Running| count(game.events(), |e| matches!(e, Event::Challenge { .. }));
+
+    assert_eq!(challenged(&game(Difficulty::Skilled, Vec::new())), 1, "four cards left, one is likely red");
+    assert_eq!(challenged(&game(Difficulty::Skilled, vec![Color::Red])), 0, "they drew on red before");
+    assert_eq!(challenged(&game(Difficulty::Calm, Vec::new())), 0);
+}
+
+#[test]
+fn analyzing_guesses_colors_without_changing_the_game() {
+    let game = |actions: &[&str]| {
+        let mut game = TestGame::new(3)
+            .hand(0, &["red 5", "blue 6"])
+            .hand(1, &["red 8", "red 9"])
+            .hand(2, &["blue 2", "green 4"])
+            .top("red 1")
+            .deck(&FILLER)
+            .actions(actions)
+            .ai_from(1, Difficulty::Skilled)
+            .build();
+        game.table_mut().lacking[2] = vec![Color::Red];
+        game.play_turns(3);
+        game
+    };
+
+    let analyzed = game(&["analyze", "play red 5"]);
+    let notices: Vec<&str> = analyzed.events().iter().filter_map(|e| match e { Event::Notice(m) => Some(m.as_str()), _ => None }).collect();
+    assert_eq!(notices.len(), 2, "{:?}", notices);
+    assert!(notices[0].starts_with("Player #2 (2 cards): red "), "{}", notices[0]);
+    assert!(notices[1].contains("red 0%"), "they drew on red: {}", notices[1]);
+
+    let plain = game(&["play red 5"]);
+    let without = |events: &[Event]| events.iter().filter(|e| !matches!(e, Event::Notice(_))).cloned().collect::<Vec<_>>();
+    assert_eq!(without(analyzed.events()), without(plain.events()));
+}
+
+#[test]
+fn suggestions_rank_the_legal_plays() {
+    let game = |actions: &[&str]| {
+        let mut game = TestGame::new(2)
+            .hand(0, &["red 5", "red +2", "blue 6"])
+            .hand(1, &["red 9"])
+            .top("red 1")
+            .deck(&FILLER)
+            .actions(actions)
+            .ai_from(1, Difficulty::Calm)
+            .build();
+        game.play_turns(2);
+        game
+    };
+
+    let suggested = game(&["suggest", "play red +2"]);
+    let notices: Vec<&str> = suggested.events().iter().filter_map(|e| match e { Event::Notice(m) => Some(m.as_str()), _ => None }).collect();
+    assert_eq!(notices.len(), 3, "the header and both red cards: {:?}", notices);
+    assert!(notices[1].ends_with("Red Draw 2: aggressive"), "stopping their last card comes first: {}", notices[1]);
+    assert!(notices[2].ends_with("Red 5: risky"), "{}", notices[2]);
+
+    let plain = game(&["play red +2"]);
+    let without = |events: &[Event]| events.iter().filter(|e| !matches!(e, Event::Notice(_))).cloned().collect::<Vec<_>>();
+    assert_eq!(without(suggested.events()), without(plain.events()), "asking doesn't change the game");
+}
+
+#[test]
+fn cards_can_be_played_by_name() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 5", "wild +4", "blue 6"])
+        .hand(1, &["red 9", "yellow 3", "yellow 4"])
+        .top("red 1")
+        .deck(&FILLER)
+        .actions(&["green 9", "Red 5", "wild draw 4", "blue"])
+        .ai_from(1, Difficulty::Calm)
+        .build();
+    game.play_turns(3);
+
+    assert_eq!(count(game.events(), |e| matches!(e, Event::InvalidInput(_))), 1, "a card that isn't in the hand");
+    assert_eq!(game.hand(0), cards(&["blue 6"]));
+    assert_eq!(game.table().last_played, card("wild +4 blue"));
+}
+
+#[test]
+fn six_color_decks_play_out() {
+    let rules = RuleSet { colors: 6, ..RuleSet::default() };
+    let settings = Settings { players: Some(0), ai_players: Some(8), difficulty: Some(Difficulty::Skilled), seed: Some(5), rules, ..Settings::default() };
+    let mut fe = ScriptedFrontend::default();
+    let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+    assert_eq!(table.deck.len() + table.discard.len() + 8 * 7, 162);
+    invariants::check(&table).unwrap();
+
+    assert!(play(&mut table, &mut fe, None).unwrap().is_some());
+    invariants::check(&table).unwrap();
+    assert!(fe.events.iter().any(|e| matches!(e, Event::Played { card, .. } if card.color == Color::Purple)));
+}
+
+#[test]
+fn only_the_decks_colors_can_be_called() {
+    let mut game = TestGame::new(2)
+        .hand(0, &["wild", "red 5"])
+        .hand(1, &["blue 9", "blue 8"])
+        .deck(&FILLER)
+        .actions(&["play wild", "purple", "green"])
+        .build();
+    game.play_turns(1);
+    assert_eq!(count(game.events(), |e| matches!(e, Event::InvalidInput(_))), 1);
+    assert_eq!(game.table().last_played, card("wild green"));
+
+    let mut six = TestGame::new(2)
+        .hand(0, &["wild", "red 5"])
+        .hand(1, &["blue 9", "blue 8"])
+        .deck(&FILLER)
+        .rules(RuleSet { colors: 6, ..RuleSet::default() })
+        .actions(&["play wild", "purple"])
+        .build();
+    six.play_turns(1);
+    assert_eq!(six.table().last_played, card("wild purple"));
+}
+
+#[test]
+fn numbers_past_nine_play_out() {
+    let rules = RuleSet { highest_number: 12, ..RuleSet::default() };
+    let settings = Settings { players: Some(0), ai_players: Some(4), difficulty: Some(Difficulty::Expert), seed: Some(8), rules, ..Settings::default() };
+    let mut fe = ScriptedFrontend::default();
+    let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+    assert_eq!(table.deck.len() + table.discard.len() + 4 * 7, 132);
+    invariants::check(&table).unwrap();
+
+    assert!(play(&mut table, &mut fe, None).unwrap().is_some());
+    invariants::check(&table).unwrap();
+    assert!(fe.events.iter().any(|e| matches!(e, Event::Played { card, .. } if card.number > 9)));
+
+    let mut game = TestGame::new(2)
+        .hand(0, &["red 12", "blue 5"])
+        .hand(1, &["blue 9", "blue 8"])
+        .top("green 12")
+        .deck(&FILLER)
+        .rules(rules)
+        .actions(&["play red 12"])
+        .build();
+    game.play_turns(1);
+    assert_eq!(game.table().last_played, card("red 12"));
+}
+
+#[test]
+fn stacked_deals_skip_the_shuffle() {
+    // Nobody can follow the top card and the deck is two cards, so the pile goes back in again and again
+    let stack: Stack = "hand: B5 G7 B+2\nhand: Y3 G4\ntop: W:R\ndeck: R9 R8".parse().unwrap();
+    let settings = Settings { players: Some(0), ai_players: Some(2), difficulty: Some(Difficulty::Calm), seed: Some(3), stack: Some(stack), ..Settings::default() };
+    let mut fe = ScriptedFrontend::default();
+    let mut table = setup(&mut fe, &settings).unwrap().unwrap();
+    assert_eq!(table.hands, [cards(&["blue 5", "green 7", "blue +2"]), cards(&["yellow 3", "green 4"])]);
+    assert_eq!(table.last_played, card("wild red"));
+    assert_eq!(table.discard, cards(&["wild"]));
+    assert_eq!(table.deck, cards(&["red 8", "red 9"]));
+
+    let mut again = ScriptedFrontend::default();
+    let mut replayed = setup(&mut again, &settings).unwrap().unwrap();
+    for _ in 0..12 {
+        play(&mut table, &mut fe, Some(1)).unwrap();
+        play(&mut replayed, &mut again, Some(1)).unwrap();
+    }
+    assert!(count(&fe.events, |e| matches!(e, Event::Reshuffled { .. })) >= 2);
+    assert_eq!(fe.events, again.events);
+
+    // A third seat is dealt off the deck, which hasn't got seven cards
+    let short = Settings { ai_players: Some(3), ..settings };
+    let error = setup(&mut ScriptedFrontend::default(), &short).unwrap_err();
+    assert_eq!(error.to_string(), "The stacked deck ran out during the deal, it needs more cards");
+}
@@ -0,0 +1,42 @@
+/* Plays one human seat against two AIs by trying answers in a circle until one is taken, to show the C API
+ *   cargo build -p unoler-engine --release --features ffi
+ *   cc examples/ffi.c -Iinclude -Ltarget/release -lunoler -o ffi && LD_LIBRARY_PATH=target/release ./ffi */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "unoler.h"
+
+int main(void) {
+    /* Refused answers just get the question asked again, so cards are tried in order, then a color, then a draw */
+    const char *answers[] = {"1", "2", "3", "4", "5", "6", "7", "8", "9", "red", "d"};
+    char *error = NULL;
+    UnolerGame *game = unoler_game_new("{\"players\": 1, \"ai_players\": 2, \"difficulty\": \"calm\", \"seed\": \"7\"}", &error);
+    if (!game) {
+        fprintf(stderr, "Could not start: %s\n", error);
+        unoler_string_free(error);
+        return 1;
+    }
+
+    for (int i = 0; i < 100000; i++) {
+        char *view = unoler_game_view(game);
+        int over = strstr(view, "\"winner\":null") == NULL;
+        if (over) {
+            printf("%s\n", view);
+        }
+        unoler_string_free(view);
+        if (over) {
+            break;
+        }
+
+        /* A real client would parse the view and pick a card */
+        if (unoler_game_act(game, answers[i % 11], &error) != 0) {
+            fprintf(stderr, "Refused: %s\n", error);
+            unoler_string_free(error);
+            break;
+        }
+    }
+
+    unoler_game_free(game);
+    return 0;
+}
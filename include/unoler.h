@@ -0,0 +1,44 @@
+/* The UNOler engine as a C library, built with: cargo build -p unoler-engine --release --features ffi
+ * Link against target/release/libunoler.so (unoler.dll, libunoler.dylib).
+ *
+ * Everything is JSON or plain text in UTF-8 C strings. Strings returned by the library belong to the caller
+ * and are freed with unoler_string_free, games with unoler_game_free. See engine/src/ffi.rs for the details. */
+
+#ifndef UNOLER_H
+#define UNOLER_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct UnolerGame UnolerGame;
+
+/* Deals a game from settings JSON such as {"players": 1, "ai_players": 2, "difficulty": "skilled", "seed": "42"}
+ * and plays up to the first question for a human. NULL on failure, with the reason in *error if error isn't NULL. */
+UnolerGame *unoler_game_new(const char *settings, char **error);
+
+/* The table as JSON: whose turn it is, the card to match, hand sizes, what is being asked of whom,
+ * that player's hand with the indexes unoler_game_act takes, and the winner. */
+char *unoler_game_view(const UnolerGame *game);
+
+/* A JSON array of every event from number `since` on, the same objects --json prints. */
+char *unoler_game_events(const UnolerGame *game, size_t since);
+
+/* Answers the question in the view with what the player would have typed: "3", "d", "red", ...
+ * 0 when the game took it (refused moves show up as events), -1 with the reason in *error otherwise. */
+int unoler_game_act(UnolerGame *game, const char *answer, char **error);
+
+/* What a built in AI ("calm", "aggressive", "skilled", "random" or "expert") would answer in the asked player's place, ready for
+ * unoler_game_act. Asking doesn't change the game. NULL when nobody is being asked, or with the reason in *error. */
+char *unoler_game_suggest(const UnolerGame *game, const char *difficulty, char **error);
+
+void unoler_game_free(UnolerGame *game);
+void unoler_string_free(char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
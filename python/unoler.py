@@ -0,0 +1,149 @@
+# Python bindings for the engine, through the C API with ctypes so there's nothing to compile but the library
+#   cargo build -p unoler-engine --release --features ffi
+#   python3 python/unoler.py    (plays a few AI games as an example)
+# The library is looked for in UNOLER_LIB first, then in target/release next to this folder
+
+import ctypes
+import json
+import os
+import sys
+from pathlib import Path
+
+DIFFICULTIES = ("calm", "aggressive", "skilled", "random", "expert")
+
+
+class UnolerError(Exception):
+    pass
+
+
+def _find_library():
+    if os.environ.get("UNOLER_LIB"):
+        return os.environ["UNOLER_LIB"]
+    names = {"win32": "unoler.dll", "darwin": "libunoler.dylib"}
+    path = Path(__file__).resolve().parent.parent / "target" / "release" / names.get(sys.platform, "libunoler.so")
+    if not path.exists():
+        raise UnolerError(f"{path} doesn't exist, build it with: cargo build -p unoler-engine --release --features ffi")
+    return str(path)
+
+
+_lib = None
+
+
+# Loaded on first use, so importing works without the library around
+def _load():
+    global _lib
+    if _lib is None:
+        lib = ctypes.CDLL(_find_library())
+        game, text, error = ctypes.c_void_p, ctypes.c_char_p, ctypes.POINTER(ctypes.c_void_p)
+        # Returned strings are void pointers so they can be handed back to unoler_string_free
+        lib.unoler_game_new.argtypes, lib.unoler_game_new.restype = [text, error], game
+        lib.unoler_game_view.argtypes, lib.unoler_game_view.restype = [game], ctypes.c_void_p
+        lib.unoler_game_events.argtypes, lib.unoler_game_events.restype = [game, ctypes.c_size_t], ctypes.c_void_p
+        lib.unoler_game_act.argtypes, lib.unoler_game_act.restype = [game, text, error], ctypes.c_int
+        lib.unoler_game_suggest.argtypes, lib.unoler_game_suggest.restype = [game, text, error], ctypes.c_void_p
+        lib.unoler_game_free.argtypes, lib.unoler_game_free.restype = [game], None
+        lib.unoler_string_free.argtypes, lib.unoler_string_free.restype = [ctypes.c_void_p], None
+        _lib = lib
+    return _lib
+
+
+# Copies a string out of the library and frees it
+def _take(pointer):
+    if not pointer:
+        return None
+    try:
+        return ctypes.string_at(pointer).decode("utf-8")
+    finally:
+        _lib.unoler_string_free(pointer)
+
+
+class Game:
+    # Same settings as the browser build and the C API:
+    #   Game(players=1, ai_players=2, difficulty="skilled", seed=42, rules={"stacking": False})
+    # Plays up to the first question for a human, so an AI only game is over as soon as it's made
+    def __init__(self, **settings):
+        lib = _load()
+        if "seed" in settings and settings["seed"] is not None:
+            settings["seed"] = str(settings["seed"])
+        error = ctypes.c_void_p()
+        self._game = lib.unoler_game_new(json.dumps(settings).encode("utf-8"), ctypes.byref(error))
+        if not self._game:
+            raise UnolerError(_take(error.value))
+
+    def _handle(self):
+        if not self._game:
+            raise UnolerError("The game was closed")
+        return self._game
+
+    # The table: turn, last_played, hand_sizes, waiting ({"prompt", "player"}), hand, winner, ...
+    def view(self):
+        return json.loads(_take(_lib.unoler_game_view(self._handle())))
+
+    # Every event from number `since` on, the same objects --json prints
+    def events(self, since=0):
+        return json.loads(_take(_lib.unoler_game_events(self._handle(), since)))
+
+    # What the asked player would have typed: "3", "d", "red", ... Refused moves show up as events and the
+    # same question comes back, anything the game can't take at all raises
+    def act(self, answer):
+        error = ctypes.c_void_p()
+        if _lib.unoler_game_act(self._handle(), str(answer).encode("utf-8"), ctypes.byref(error)) != 0:
+            raise UnolerError(_take(error.value))
+
+    # What a built in AI would answer in the asked player's place, None when nobody is being asked
+    def suggest(self, difficulty="skilled"):
+        error = ctypes.c_void_p()
+        answer = _take(_lib.unoler_game_suggest(self._handle(), difficulty.encode("utf-8"), ctypes.byref(error)))
+        if error.value:
+            raise UnolerError(_take(error.value))
+        return answer
+
+    @property
+    def waiting(self):
+        return self.view()["waiting"]
+
+    @property
+    def winner(self):
+        return self.view()["winner"]
+
+    def close(self):
+        if self._game:
+            _lib.unoler_game_free(self._game)
+            self._game = None
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *_):
+        self.close()
+
+    def __del__(self):
+        # The library can be gone already at interpreter shutdown
+        if _lib is not None:
+            self.close()
+
+
+# Plays `games` games with one seat per entry in `seats`, each entry a built in AI difficulty or a function
+# called as policy(game) that returns an answer, and returns the winning seat (1 based) of each game
+# Seeds go up from `seed`, so the same call gives the same results
+def simulate(games, seats, seed=0, rules=None):
+    winners = []
+    for n in range(games):
+        settings = {"players": len(seats), "ai_players": 0, "difficulty": "calm", "seed": seed + n}
+        if rules:
+            settings["rules"] = rules
+        with Game(**settings) as game:
+            while True:
+                view = game.view()
+                if view["winner"] is not None:
+                    winners.append(view["winner"])
+                    break
+                seat = seats[view["waiting"]["player"] - 1]
+                game.act(seat(game) if callable(seat) else game.suggest(seat))
+    return winners
+
+
+if __name__ == "__main__":
+    results = simulate(200, ["skilled", "calm", "random"], seed=1)
+    for seat, name in enumerate(["skilled", "calm", "random"], 1):
+        print(f"Seat {seat} ({name}): won {results.count(seat)} of {len(results)}")
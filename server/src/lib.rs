@@ -0,0 +1,1006 @@
+// `UNOler serve` and `unoler-server`: games over HTTP with JSON bodies, so anything with an HTTP library can play
+//   POST /games                  settings JSON like the browser build's, gives {"id", "seats"}
+//   POST /games/<id>/join        takes the next free human seat, gives {"seat", "token"}. {"seat": 3, "name": n} sits
+//                                down at a seat the AI is playing instead, with its hand, from its next turn
+//   GET  /games/<id>?token=t     the table, with the hand of the token's seat
+//   GET  /games/<id>/events?since=n&token=t
+//                                the events from number n on, as the token's seat sees them
+//   POST /games/<id>/action      {"token": t, "answer": "3"} (or any action --json reads), from the seat being asked
+//   POST /games/<id>/reclaim     {"token": t}, takes back a seat the AI took over, from the next turn
+//   GET  /metrics                how the server is doing, in Prometheus' text format
+// With --idle-timeout the AI takes over a joined seat that has been asked for longer than that, found out on the next
+// request for the game. A player can hand their seat over themselves too, with the answer "takeover"
+// The same calls are also there as the Twirp service in proto/unoler.proto, under /twirp/unoler.Unoler/
+// Connections are read and answered on a pool of --threads workers, so a slow client only holds up its own. The
+// requests themselves are handled one at a time under a lock: turns are quick and it keeps every game in one place
+// SIGINT and SIGTERM (Ctrl-C on windows) stop it gently: every game gets a notice and stops taking moves, clients get a
+// few seconds to see it, and the games still being played are saved to --games-dir and picked up on the next start.
+// A second signal skips the wait
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use unoler::events::{Event, Prompt, answer_from_json};
+use unoler::game::Difficulty;
+use unoler::json::Json;
+use unoler::persist::{table_from_json, table_to_json};
+use unoler::pool::Pool;
+use unoler::rng::{ChaChaRng, Randler, Rng};
+use unoler::session::{Session, settings_from_json};
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+// Nothing the API takes comes close, anything bigger is somebody sending junk
+const MAX_BODY: usize = 64 * 1024;
+// The same for the request line, each header and how many headers there are
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+// How long clients get to see the shutdown notice, and how often the accept loop looks for a signal
+const GRACE: Duration = Duration::from_secs(3);
+const POLL: Duration = Duration::from_millis(50);
+
+// Connections waiting for a worker, past that a new one is turned away with a 503
+const QUEUE_PER_THREAD: usize = 4;
+
+// Bigger tables than anyone plays, each game is dealt and its AI turns played under the lock every request waits on
+const MAX_SEATS: usize = 10;
+const MAX_HAND: u8 = 15;
+const MAX_DECKS: u8 = 4;
+// Games still being played, a finished one doesn't count
+const MAX_GAMES: usize = 100;
+
+const STOPPING_NOTICE: &str = "The server is shutting down. This game is saved and carries on when it's back";
+
+// Signals caught so far. The handlers may only touch an atomic, the accept loop does the rest
+static SIGNALS: AtomicU8 = AtomicU8::new(0);
+
+// SIGINT and SIGTERM have the same numbers on Linux, macOS and the BSDs
+#[cfg(unix)]
+const SIGINT: i32 = 2;
+#[cfg(unix)]
+const SIGTERM: i32 = 15;
+
+#[cfg(unix)]
+unsafe extern "C" {
+    unsafe fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+}
+
+#[cfg(unix)]
+extern "C" fn on_signal(_: i32) {
+    SIGNALS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    unsafe fn SetConsoleCtrlHandler(handler: extern "system" fn(u32) -> i32, add: i32) -> i32;
+}
+
+// Ctrl-C, Ctrl-Break and closing the console, handled so windows doesn't end the process itself
+#[cfg(windows)]
+extern "system" fn on_ctrl(_: u32) -> i32 {
+    SIGNALS.fetch_add(1, Ordering::Relaxed);
+    1
+}
+
+fn catch_signals() {
+    #[cfg(unix)]
+    unsafe {
+        signal(SIGINT, on_signal);
+        signal(SIGTERM, on_signal);
+    }
+    #[cfg(windows)]
+    unsafe {
+        SetConsoleCtrlHandler(on_ctrl, 1);
+    }
+}
+
+struct Game {
+    session: Session,
+    tokens: Vec<(usize, String)>, // The seat and token of everybody who joined
+    asked: Instant, // When the waiting seat last heard from
+}
+
+// Counted since the server started, Prometheus works out the rates
+#[derive(Default)]
+struct Metrics {
+    requests: u64,
+    errors: BTreeMap<u16, u64>, // By status
+    games: u64,
+    turns: u64,
+    reshuffles: u64,
+}
+
+impl Metrics {
+    fn tally<'a>(&mut self, events: impl Iterator<Item = &'a Event>) {
+        for event in events {
+            match event {
+                Event::TurnStart { .. } => self.turns += 1,
+                Event::Reshuffled { .. } => self.reshuffles += 1,
+                _ => {},
+            }
+        }
+    }
+}
+
+pub struct Server {
+    games: HashMap<usize, Game>,
+    next_id: usize,
+    tokens: ChaChaRng, // Keyed from the OS, a token says nothing about the ones handed out after it
+    seeds: Option<Randler>, // With --seed, what the games made without a seed of their own are dealt from
+    idle: Option<Duration>, // How long a seat gets to answer before the AI takes over, None to wait forever
+    metrics: Metrics,
+    started: Instant,
+    stopping: bool, // Shutting down, nothing changes any more
+}
+
+type Response = (u16, Json);
+
+fn error(status: u16, message: &str) -> Response {
+    (status, Json::object(vec![("error", message.into())]))
+}
+
+// The value of `key` in a query string like since=3&token=ab
+fn query_value<'a>(query: &'a str, key: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| pair.split_once('=').filter(|(k, _)| *k == key).map(|(_, v)| v))
+}
+
+impl Server {
+    pub fn new(seed: Option<u64>, idle: Option<Duration>) -> io::Result<Self> {
+        Ok(Server {
+            games: HashMap::new(),
+            next_id: 1,
+            tokens: ChaChaRng::from_os()?,
+            seeds: seed.map(Randler::new),
+            idle,
+            metrics: Metrics::default(),
+            started: Instant::now(),
+            stopping: false,
+        })
+    }
+
+    // Everything but the sockets, so it can be tested without any
+    pub fn handle(&mut self, method: &str, target: &str, body: &str) -> Response {
+        let response = self.route(method, target, body);
+        self.count(response.0);
+        response
+    }
+
+    fn count(&mut self, status: u16) {
+        self.metrics.requests += 1;
+        if status >= 400 {
+            *self.metrics.errors.entry(status).or_default() += 1;
+        }
+    }
+
+    fn route(&mut self, method: &str, target: &str, body: &str) -> Response {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        if let Some(rpc) = path.strip_prefix("/twirp/unoler.Unoler/") {
+            return if method == "POST" { self.twirp(rpc, body) } else { twirp_error(404, "Twirp calls are POSTs") };
+        }
+        if self.stopping && method == "POST" {
+            return error(503, "The server is shutting down, games carry on when it's back");
+        }
+        let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+        let body = if body.trim().is_empty() { Ok(Json::object(vec![])) } else { Json::parse(body) };
+        let Ok(body) = body else { return error(400, "The body isn't valid JSON") };
+
+        match (method, parts.as_slice()) {
+            ("POST", ["games"]) => self.create(&body),
+            (_, ["games"]) => error(405, "Use POST to create a game"),
+            (_, ["games", id, rest @ ..]) => {
+                let Some(game) = id.parse().ok().and_then(|id: usize| self.games.get_mut(&id)) else {
+                    return error(404, "There's no game with that id");
+                };
+                let before = game.session.events().count();
+                // Stopping games stay the way they'll be saved
+                if let Some(idle) = self.idle && !self.stopping {
+                    take_over_idle(game, idle);
+                }
+                let response = match (method, rest) {
+                    ("GET", []) => view(game, query_value(query, "token")),
+                    // Only for the players, as their seat sees them
+                    ("GET", ["events"]) => match (seat_of(game, query_value(query, "token")), query_value(query, "since").unwrap_or("0").parse()) {
+                        (None, _) => error(403, "Join the game and send the token you got"),
+                        (Some(seat), Ok(since)) => (200, game.session.events_json_for(seat, since)),
+                        (_, Err(_)) => error(400, "since should be a number"),
+                    },
+                    ("POST", ["join"]) => join(game, &body, &mut self.tokens),
+                    ("POST", ["action"]) => action(game, &body),
+                    ("POST", ["reclaim"]) => reclaim(game, &body),
+                    (_, [] | ["events"] | ["join"] | ["action"] | ["reclaim"]) => error(405, "That's not the method for this"),
+                    _ => error(404, "Unknown path"),
+                };
+                self.metrics.tally(game.session.events().skip(before));
+                response
+            },
+            _ => error(404, "Unknown path"),
+        }
+    }
+
+    // Every call turns into the plain route it stands for, with the answer and any error in Twirp's shape
+    fn twirp(&mut self, rpc: &str, body: &str) -> Response {
+        let Ok(body) = Json::parse(if body.trim().is_empty() { "{}" } else { body }) else {
+            return twirp_error(400, "The body isn't valid JSON");
+        };
+        let body = snake_case_keys(&body);
+        let id = body.get("game_id").and_then(|id| id.as_f64()).unwrap_or(0.0);
+        let text = |key: &str| body.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+
+        let (status, json) = match rpc {
+            "CreateGame" => {
+                // Protobuf leaves out zeros and empty strings, which mean the same as not saying
+                let Json::Object(pairs) = &body else { return twirp_error(400, "The body should be an object") };
+                let mut settings: Vec<(String, Json)> = pairs.iter().filter(|(_, v)| *v != Json::from("")).cloned().collect();
+                if !settings.iter().any(|(key, _)| key == "players") {
+                    settings.push((String::from("players"), 0.into()));
+                }
+                self.route("POST", "/games", &Json::Object(settings).to_string())
+            },
+            "JoinGame" => {
+                let mut seat = vec![];
+                if let Some(number) = body.get("seat").and_then(|s| s.as_f64()).filter(|&s| s > 0.0) {
+                    seat.push(("seat", Json::Number(number)));
+                }
+                if !text("name").is_empty() {
+                    seat.push(("name", text("name").into()));
+                }
+                self.route("POST", &format!("/games/{}/join", id), &Json::object(seat).to_string())
+            },
+            "GetView" => match text("token").as_str() {
+                "" => self.route("GET", &format!("/games/{}", id), ""),
+                token => self.route("GET", &format!("/games/{}?token={}", id, token), ""),
+            },
+            "StreamEvents" => {
+                let since = body.get("since").and_then(|s| s.as_f64()).unwrap_or(0.0);
+                match self.route("GET", &format!("/games/{}/events?since={}&token={}", id, since, text("token")), "") {
+                    (200, events) => (200, Json::object(vec![("events", events)])),
+                    failed => failed,
+                }
+            },
+            "SubmitAction" => {
+                let action = Json::object(vec![("token", text("token").into()), ("answer", text("answer").into())]);
+                self.route("POST", &format!("/games/{}/action", id), &action.to_string())
+            },
+            "ReclaimSeat" => {
+                let reclaim = Json::object(vec![("token", text("token").into())]);
+                self.route("POST", &format!("/games/{}/reclaim", id), &reclaim.to_string())
+            },
+            _ => return twirp_error(404, &format!("{} isn't a call of unoler.Unoler", rpc)),
+        };
+        if status < 300 {
+            return (status, json);
+        }
+        let message = json.get("error").and_then(|e| e.as_str()).unwrap_or("Something went wrong").to_string();
+        twirp_error(status, &message)
+    }
+
+    // Every metric with its help and type lines, counters end in _total
+    pub fn metrics(&self) -> String {
+        let playing: Vec<&Game> = self.games.values().filter(|g| g.session.winner().is_none()).collect();
+        // Joined seats the AI isn't playing for anybody who went quiet
+        let clients = playing.iter()
+            .map(|g| g.tokens.iter().filter(|(seat, _)| !g.session.current().taken_over.get(seat - 1).copied().unwrap_or(false)).count())
+            .sum::<usize>();
+        let m = &self.metrics;
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, f64)>| {
+            let _ = writeln!(out, "# HELP unoler_{} {}\n# TYPE unoler_{} {}", name, help, name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "unoler_{}{} {}", name, labels, value);
+            }
+        };
+        metric("games_active", "gauge", "Games still being played", vec![(String::new(), playing.len() as f64)]);
+        metric("clients_connected", "gauge", "Seats in those games held by somebody who joined", vec![(String::new(), clients as f64)]);
+        metric("games_total", "counter", "Games created", vec![(String::new(), m.games as f64)]);
+        metric("turns_total", "counter", "Turns played in every game", vec![(String::new(), m.turns as f64)]);
+        metric("reshuffles_total", "counter", "Times a discard pile went back into the deck", vec![(String::new(), m.reshuffles as f64)]);
+        metric("requests_total", "counter", "Requests answered, not counting these", vec![(String::new(), m.requests as f64)]);
+        let errors = m.errors.iter().map(|(status, n)| (format!("{{status=\"{}\"}}", status), *n as f64)).collect();
+        metric("errors_total", "counter", "Requests answered with an error, by status", errors);
+        metric("uptime_seconds", "gauge", "Seconds since the server started", vec![(String::new(), self.started.elapsed().as_secs_f64())]);
+        out
+    }
+
+    // Every game still being played is told and takes no more moves, watching it still works
+    pub fn stop(&mut self) {
+        self.stopping = true;
+        for game in self.games.values_mut().filter(|g| g.session.winner().is_none()) {
+            game.session.notice(STOPPING_NOTICE);
+        }
+    }
+
+    // Each game still being played as game-<id>.json, a save slot with the seats' tokens next to the table. The
+    // table is the one at the start of the turn being asked, whatever was answered in it so far is asked again. The
+    // events up to it are kept too, so a host polling /events?since= carries on where it was
+    pub fn save_games(&self, dir: &Path) -> io::Result<usize> {
+        let mut saved = 0;
+        for (id, game) in self.games.iter().filter(|(_, g)| g.session.winner().is_none()) {
+            let tokens = game.tokens.iter().map(|(seat, token)| Json::object(vec![("seat", (*seat).into()), ("token", token.as_str().into())]));
+            let (events, private) = game.session.saved_events();
+            let save = Json::object(vec![
+                ("table", table_to_json(game.session.saved())),
+                ("events_at_snapshot", events.len().into()),
+                ("events", Json::Array(events)),
+                ("private", Json::Array(private.into_iter().map(Json::from).collect())),
+                ("id", (*id).into()),
+                ("tokens", Json::Array(tokens.collect())),
+            ]);
+            fs::create_dir_all(dir)?;
+            fs::write(dir.join(format!("game-{}.json", id)), save.to_string())?;
+            saved += 1;
+        }
+        Ok(saved)
+    }
+
+    // Picks the games save_games left back up, under the same ids and tokens. The files go once they're read, the
+    // next shutdown writes them again
+    pub fn load_games(&mut self, dir: &Path) -> io::Result<usize> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let mut loaded = 0;
+        for entry in entries {
+            let path = entry?.path();
+            let named = path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("game-") && n.ends_with(".json"));
+            if !named {
+                continue;
+            }
+            let game = fs::read_to_string(&path).ok().and_then(|text| Json::parse(&text).ok()).and_then(|save| {
+                let id = save.get("id")?.as_f64()? as usize;
+                let mut events = save.get("events")?.as_array()?.to_vec();
+                events.truncate(save.get("events_at_snapshot")?.as_f64()? as usize);
+                // Saves from before the events were kept per seat have no private ones
+                let private = match save.get("private") {
+                    Some(private) => private.as_array()?.iter().map(|i| i.as_f64().map(|i| i as usize)).collect::<Option<_>>()?,
+                    None => Vec::new(),
+                };
+                let session = Session::resume(table_from_json(save.get("table")?)?, events, private).ok()?;
+                let tokens = save.get("tokens")?.as_array()?.iter()
+                    .map(|t| Some((t.get("seat")?.as_f64()? as usize, t.get("token")?.as_str()?.to_string())))
+                    .collect::<Option<Vec<_>>>()?;
+                Some((id, Game { session, tokens, asked: Instant::now() }))
+            });
+            let Some((id, game)) = game else {
+                eprintln!("Could not pick up the saved game {}, it's left where it is", path.display());
+                continue;
+            };
+            fs::remove_file(&path)?;
+            self.next_id = self.next_id.max(id + 1);
+            self.games.insert(id, game);
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    fn create(&mut self, body: &Json) -> Response {
+        // A game nobody asked to play back from a seed is shuffled so nobody can work out the deck, unless the whole
+        // server was started from one
+        let settings = settings_from_json(body).map(|mut settings| {
+            if settings.seed.is_none() && body.get("secure").is_none() {
+                match &mut self.seeds {
+                    Some(seeds) => settings.seed = Some(seeds.rand()),
+                    None => settings.secure = true,
+                }
+            }
+            settings
+        });
+        let settings = settings.and_then(|settings| {
+            let seats = settings.players.unwrap_or(0) as usize + settings.ai_players.unwrap_or(0) as usize;
+            if seats > MAX_SEATS {
+                Err(format!("A game here seats at most {}, not {}", MAX_SEATS, seats))
+            } else if settings.rules.hand_size > MAX_HAND {
+                Err(format!("Hands here are at most {} cards, not {}", MAX_HAND, settings.rules.hand_size))
+            } else if settings.decks.is_some_and(|decks| decks > MAX_DECKS) {
+                Err(format!("A game here is dealt from at most {} decks", MAX_DECKS))
+            } else {
+                Ok(settings)
+            }
+        });
+        if self.games.values().filter(|g| g.session.winner().is_none()).count() >= MAX_GAMES {
+            return error(503, &format!("There are {} games going already, try again once one is over", MAX_GAMES));
+        }
+        let session = match settings.and_then(|settings| Session::start(&settings)) {
+            Ok(session) => session,
+            Err(e) => return error(400, &e),
+        };
+        let humans = session.view().get("human_players").and_then(|h| h.as_f64()).unwrap_or(0.0) as usize;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.metrics.games += 1;
+        self.metrics.tally(session.events());
+        self.games.insert(id, Game { session, tokens: Vec::new(), asked: Instant::now() });
+
+        (201, Json::object(vec![
+            ("id", id.into()),
+            ("seats", Json::Array((1..=humans).map(Json::from).collect())),
+        ]))
+    }
+}
+
+// Twirp errors have a code name next to the message, and it picks the status
+fn twirp_error(status: u16, message: &str) -> Response {
+    let (status, code) = match status {
+        400 => (400, "invalid_argument"),
+        403 => (403, "permission_denied"),
+        404 => (404, "bad_route"),
+        409 => (412, "failed_precondition"),
+        503 => (503, "unavailable"),
+        _ => (500, "internal"),
+    };
+    (status, Json::object(vec![("code", code.into()), ("msg", message.into())]))
+}
+
+// Protobuf JSON clients send lowerCamelCase names by default, aiPlayers for ai_players
+fn snake_case_keys(value: &Json) -> Json {
+    match value {
+        Json::Object(pairs) => Json::Object(pairs.iter().map(|(key, value)| {
+            let mut snake = String::new();
+            for c in key.chars() {
+                if c.is_ascii_uppercase() {
+                    snake.push('_');
+                }
+                snake.push(c.to_ascii_lowercase());
+            }
+            (snake, snake_case_keys(value))
+        }).collect()),
+        Json::Array(items) => Json::Array(items.iter().map(snake_case_keys).collect()),
+        other => other.clone(),
+    }
+}
+
+fn seat_of(game: &Game, token: Option<&str>) -> Option<usize> {
+    let token = token?;
+    game.tokens.iter().find(|(_, t)| t == token).map(|(seat, _)| *seat)
+}
+
+fn join(game: &mut Game, body: &Json, rand: &mut ChaChaRng) -> Response {
+    let seat = match body.get("seat").map(|s| s.as_f64()) {
+        // Late, at a seat the AI has been playing. Whoever had it before, if anybody, can't answer for it any more
+        Some(Some(seat)) => {
+            let seat = seat as usize;
+            if let Err(e) = game.session.join(seat, body.get("name").and_then(|n| n.as_str())) {
+                return error(409, &e);
+            }
+            game.tokens.retain(|(s, _)| *s != seat);
+            seat
+        },
+        Some(None) => return error(400, "seat should be a number"),
+        None => {
+            let humans = game.session.view().get("human_players").and_then(|h| h.as_f64()).unwrap_or(0.0) as usize;
+            match (1..=humans).find(|seat| !game.tokens.iter().any(|(s, _)| s == seat)) {
+                Some(seat) => seat,
+                None => return error(409, "Every human seat is taken"),
+            }
+        },
+    };
+    let token = format!("{:016x}{:016x}", rand.next_u64(), rand.next_u64());
+    game.tokens.push((seat, token.clone()));
+    (200, Json::object(vec![("seat", seat.into()), ("token", token.into())]))
+}
+
+// The shared view, with the hand swapped for the caller's own, nobody else's is shown
+fn view(game: &Game, token: Option<&str>) -> Response {
+    let seat = seat_of(game, token);
+    if token.is_some() && seat.is_none() {
+        return error(403, "That token isn't for this game");
+    }
+    let mut view = game.session.view();
+    if let Json::Object(pairs) = &mut view {
+        for (key, value) in pairs.iter_mut() {
+            if key == "hand" {
+                *value = seat.map_or(Json::Null, |s| game.session.hand(s));
+            }
+        }
+        pairs.push((String::from("seat"), seat.map_or(Json::Null, Json::from)));
+    }
+    (200, view)
+}
+
+fn action(game: &mut Game, body: &Json) -> Response {
+    let Some(seat) = seat_of(game, body.get("token").and_then(|t| t.as_str())) else {
+        return error(403, "Join the game and send the token you got");
+    };
+    let asked = match game.session.waiting() {
+        Some(Prompt::Action { player } | Prompt::Color { player } | Prompt::PlayDrawn { player } | Prompt::Challenge { player } | Prompt::Menu { player }) => player,
+        _ => return error(409, "Nobody is being asked anything"),
+    };
+    if asked != seat {
+        return error(409, "It's not your turn");
+    }
+    let Some(answer) = body.get("answer").and_then(answer_from_json) else {
+        return error(400, "answer is missing, send what you'd type like \"3\", \"d\" or \"red\"");
+    };
+    if answer.starts_with("save") {
+        return error(400, "Games on the server can't be saved");
+    }
+
+    // Refused moves come back as events, with the same question asked again
+    let before = game.session.event_count();
+    game.asked = Instant::now();
+    match game.session.act(&answer) {
+        Ok(()) => (200, Json::object(vec![("events", game.session.events_json_for(seat, before))])),
+        Err(e) => error(409, &e),
+    }
+}
+
+// Seats nobody has joined yet keep waiting, there's nobody to have gone quiet
+fn take_over_idle(game: &mut Game, idle: Duration) {
+    let asked = match game.session.waiting() {
+        Some(Prompt::Action { player } | Prompt::Color { player } | Prompt::PlayDrawn { player } | Prompt::Challenge { player } | Prompt::Menu { player }) => player,
+        _ => return,
+    };
+    if !game.tokens.iter().any(|(seat, _)| *seat == asked) || game.asked.elapsed() < idle {
+        return;
+    }
+    game.asked = Instant::now();
+    if let Err(e) = game.session.take_over(asked, Difficulty::Skilled) {
+        eprintln!("Could not take over seat {}: {}", asked, e);
+    }
+}
+
+fn reclaim(game: &mut Game, body: &Json) -> Response {
+    let Some(seat) = seat_of(game, body.get("token").and_then(|t| t.as_str())) else {
+        return error(403, "Join the game and send the token you got");
+    };
+    match game.session.hand_back(seat) {
+        Some(message) => (200, Json::object(vec![("message", message.into())])),
+        None => error(409, "The AI isn't playing your seat"),
+    }
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        412 => "Precondition Failed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
+
+// The request line and the body, the only headers that matter are the body's length
+fn read_request(stream: &mut impl Read) -> Result<(String, String, String), Response> {
+    let mut reader = BufReader::new(stream);
+    let line = read_line(&mut reader).map_err(|_| error(400, "Couldn't read the request"))?
+        .ok_or_else(|| error(400, "The request line is too long"))?;
+    let mut words = line.split_whitespace();
+    let (Some(method), Some(target)) = (words.next(), words.next()) else {
+        return Err(error(400, "That's not an HTTP request"));
+    };
+    let (method, target) = (method.to_string(), target.to_string());
+
+    let mut length = 0;
+    for headers in 0.. {
+        let header = read_line(&mut reader).map_err(|_| error(400, "Couldn't read the headers"))?
+            .ok_or_else(|| error(431, "A header is too long"))?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers == MAX_HEADERS {
+            return Err(error(431, "There are too many headers"));
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse().map_err(|_| error(400, "Content-Length isn't a number"))?;
+        }
+    }
+    if length > MAX_BODY {
+        return Err(error(413, "The body is too big"));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|_| error(400, "The body was cut short"))?;
+    let body = String::from_utf8(body).map_err(|_| error(400, "The body isn't UTF-8"))?;
+    Ok((method, target, body))
+}
+
+// One line of at most MAX_LINE bytes, None if it goes on past that. Empty at the end of the stream
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    let read = reader.take(MAX_LINE as u64 + 1).read_line(&mut line)?;
+    Ok((read <= MAX_LINE || line.ends_with('\n')).then_some(line))
+}
+
+fn respond(stream: &mut impl Write, (status, body): Response) -> io::Result<()> {
+    respond_with(stream, status, "application/json", &body.to_string())
+}
+
+fn respond_with(stream: &mut impl Write, status: u16, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        status, status_text(status), content_type, body.len(), body,
+    )?;
+    stream.flush()
+}
+
+fn lock(server: &Mutex<Server>) -> MutexGuard<'_, Server> {
+    server.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Reading and writing happen on the worker, only handling the request holds the lock
+fn serve_one(server: &Mutex<Server>, mut stream: TcpStream) -> io::Result<()> {
+    // A client that connects and says nothing only holds up its own worker
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let response = match read_request(&mut stream) {
+        // Plain text, the one answer that isn't JSON
+        Ok((method, target, _)) if method == "GET" && target == "/metrics" => {
+            let metrics = lock(server).metrics();
+            return respond_with(&mut stream, 200, "text/plain; version=0.0.4", &metrics);
+        },
+        Ok((method, target, body)) => lock(server).handle(&method, &target, &body),
+        Err(response) => {
+            lock(server).count(response.0);
+            response
+        },
+    };
+    respond(&mut stream, response)
+}
+
+pub fn run(port: u16, seed: Option<u64>, idle: Option<Duration>, games_dir: &Path, threads: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    // Accepting without blocking, so a signal is noticed between connections
+    listener.set_nonblocking(true)?;
+
+    let server = Arc::new(Mutex::new(Server::new(seed, idle)?));
+    let loaded = lock(&server).load_games(games_dir)?;
+    if loaded > 0 {
+        println!("Picked up {} saved game{} from {}", loaded, if loaded == 1 { "" } else { "s" }, games_dir.display());
+    }
+    catch_signals();
+    let pool = Pool::new(threads, threads * QUEUE_PER_THREAD);
+    let threads = pool.threads();
+    println!("Serving games on http://{} with {} worker thread{}", listener.local_addr()?, threads, if threads == 1 { "" } else { "s" });
+
+    let mut stopped: Option<Instant> = None;
+    loop {
+        let signals = SIGNALS.load(Ordering::Relaxed);
+        if signals > 0 && stopped.is_none() {
+            println!("Shutting down, the games carry on next time");
+            lock(&server).stop();
+            stopped = Some(Instant::now());
+        }
+        if stopped.is_some_and(|at| at.elapsed() >= GRACE || signals > 1) {
+            break;
+        }
+        match listener.accept() {
+            Ok((stream, _)) => {
+                // One bad connection is the client's problem, not the server's
+                let busy = stream.set_nonblocking(false).and_then(|_| stream.try_clone());
+                let shared = Arc::clone(&server);
+                let queued = pool.try_submit(move || {
+                    if let Err(e) = serve_one(&shared, stream) {
+                        eprintln!("Connection failed: {}", e);
+                    }
+                });
+                // Every worker busy and the queue full, this one is told so instead of waiting in line
+                if queued.is_err() {
+                    lock(&server).count(503);
+                    if let Ok(mut busy) = busy {
+                        let _ = respond(&mut busy, error(503, "The server is busy, try again in a moment"));
+                    }
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(POLL),
+            Err(e) => eprintln!("Connection failed: {}", e),
+        }
+    }
+    drop(listener);
+    // The requests already taken in are answered before anything is saved
+    drop(pool);
+
+    let saved = lock(&server).save_games(games_dir)?;
+    println!("Saved {} game{} to {}", saved, if saved == 1 { "" } else { "s" }, games_dir.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::rng::DeckRng;
+
+    fn ok(response: Response) -> Json {
+        assert!(response.0 < 300, "{:?}", response);
+        response.1
+    }
+
+    fn text(json: &Json, key: &str) -> String {
+        json.get(key).and_then(|v| v.as_str()).unwrap().to_string()
+    }
+
+    #[test]
+    fn two_players_take_turns() {
+        let mut server = Server::new(Some(1), None).unwrap();
+        let game = ok(server.handle("POST", "/games", r#"{"players": 2, "ai_players": 0, "difficulty": "calm", "seed": "4"}"#));
+        assert_eq!(game.get("id"), Some(&Json::from(1)));
+
+        let one = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        let two = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        assert_eq!(server.handle("POST", "/games/1/join", "").0, 409);
+
+        // Only your own hand shows, and only the seat being asked can answer
+        let view = ok(server.handle("GET", &format!("/games/1?token={}", two), ""));
+        assert_eq!(view.get("seat"), Some(&Json::from(2)));
+        assert_eq!(view.get("hand").and_then(|h| h.as_array()).map(|h| h.len()), Some(7));
+        assert_eq!(ok(server.handle("GET", "/games/1", "")).get("hand"), Some(&Json::Null));
+
+        let draw = |token: &str| format!(r#"{{"token": "{}", "answer": {{"action": "draw"}}}}"#, token);
+        assert_eq!(server.handle("POST", "/games/1/action", &draw(&two)).0, 409);
+        let drew = ok(server.handle("POST", "/games/1/action", &draw(&one)));
+        assert!(drew.get("events").and_then(|e| e.as_array()).is_some_and(|e| !e.is_empty()));
+        let events = |server: &mut Server, token: &str| ok(server.handle("GET", &format!("/games/1/events?since=0&token={}", token), ""));
+        assert!(events(&mut server, &one).to_string().contains("\"drew\""));
+        assert_eq!(server.handle("GET", "/games/1/events?since=0", "").0, 403, "only the players follow the game");
+    }
+
+    #[test]
+    fn the_events_only_show_a_seat_what_it_can_see() {
+        let mut server = Server::new(Some(1), None).unwrap();
+        ok(server.handle("POST", "/games", r#"{"players": 2, "ai_players": 0, "difficulty": "calm", "seed": "4"}"#));
+        let one = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        let two = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        let answer = |answer: &str| format!(r#"{{"token": "{}", "answer": "{}"}}"#, one, answer);
+        ok(server.handle("POST", "/games/1/action", &answer("suggest")));
+        let asked = ok(server.handle("POST", "/games/1/action", &answer("d"))).get("events").cloned().unwrap();
+        let events = |server: &mut Server, token: &str| ok(server.handle("GET", &format!("/games/1/events?since=0&token={}", token), "")).as_array().unwrap().to_vec();
+        let (mine, theirs) = (events(&mut server, &one), events(&mut server, &two));
+        assert_eq!(mine.len(), theirs.len(), "every event keeps its number");
+
+        let named = |events: &[Json], name: &str| events.iter().filter(|e| e.get("event").and_then(Json::as_str) == Some(name)).cloned().collect::<Vec<_>>();
+        assert!(named(&theirs, "game_start")[0].get("seed").is_none());
+        assert!(named(&mine, "game_start")[0].get("seed").is_none(), "not even the players get the seed");
+        assert!(named(&mine, "hand")[0].get("cards").and_then(Json::as_array).is_some());
+        assert_eq!(named(&theirs, "hand")[0].get("cards"), Some(&Json::Null));
+        let drew = |events: &[Json]| named(events, "drew").into_iter().find(|e| e.get("player") == Some(&Json::from(1))).unwrap();
+        assert!(drew(&mine).get("card").is_some_and(|c| c.get("color").is_some()));
+        assert_eq!(drew(&theirs).get("card"), Some(&Json::Null));
+        // Seat 1's advice is about its own cards
+        let advice = |events: &[Json]| named(events, "notice").into_iter().find(|e| e.get("message") == Some(&Json::from("Suggested, best first:"))).is_some();
+        assert!(advice(&mine) && !advice(&theirs));
+        assert_eq!(asked, Json::Array(mine[mine.len() - asked.as_array().unwrap().len()..].to_vec()));
+    }
+
+    #[test]
+    fn the_ai_takes_over_a_seat_that_went_quiet_until_it_comes_back() {
+        // Nobody gets any time at all, so every request finds whoever is asked idle
+        let mut server = Server::new(Some(1), Some(Duration::ZERO)).unwrap();
+        ok(server.handle("POST", "/games", r#"{"players": 2, "ai_players": 0, "difficulty": "calm", "seed": "4"}"#));
+        let taken = |server: &mut Server| ok(server.handle("GET", "/games/1", "")).get("taken_over").cloned();
+        assert_eq!(taken(&mut server), Some(Json::Array(Vec::new())), "seats nobody joined wait");
+
+        let one = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        assert_eq!(taken(&mut server), Some(Json::Array(vec![1.into()])));
+        let events = ok(server.handle("GET", &format!("/games/1/events?since=0&token={}", one), ""));
+        assert!(events.to_string().contains("The skilled AI plays Player #1's seat from here"));
+
+        let reclaim = format!(r#"{{"token": "{}"}}"#, one);
+        assert_eq!(text(&ok(server.handle("POST", "/games/1/reclaim", &reclaim)), "message"), "Player #1 is back in their seat");
+        assert_eq!(server.handle("POST", "/games/1/reclaim", &reclaim).0, 409, "it's already theirs");
+        assert_eq!(server.handle("POST", "/games/1/reclaim", r#"{"token": "nope"}"#).0, 403);
+    }
+
+    #[test]
+    fn a_late_arrival_sits_down_at_an_ai_seat() {
+        let mut server = Server::new(Some(1), None).unwrap();
+        ok(server.handle("POST", "/games", r#"{"players": 1, "ai_players": 2, "difficulty": "calm", "seed": "4"}"#));
+        let one = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        assert_eq!(ok(server.handle("GET", "/games/1", "")).get("ai_seats"), Some(&Json::Array(vec![2.into(), 3.into()])));
+
+        assert_eq!(server.handle("POST", "/games/1/join", r#"{"seat": 1}"#).0, 409, "somebody is playing it");
+        let joined = ok(server.handle("POST", "/games/1/join", r#"{"seat": 3, "name": "Ana"}"#));
+        assert_eq!(joined.get("seat"), Some(&Json::from(3)));
+        let view = ok(server.handle("GET", &format!("/games/1?token={}", text(&joined, "token")), ""));
+        assert_eq!(view.get("ai_seats"), Some(&Json::Array(vec![2.into()])));
+        assert_eq!(view.get("names").and_then(|n| n.as_array()).map(|n| n[2].clone()), Some(Json::from("Ana")));
+        assert_eq!(view.get("hand").and_then(|h| h.as_array()).map(|h| h.len()), Some(7), "the AI's hand is hers");
+
+        // With the first seat handed to the AI too, the game goes round until she's the one asked
+        ok(server.handle("POST", "/games/1/action", &format!(r#"{{"token": "{}", "answer": {{"action": "takeover"}}}}"#, one)));
+        assert_eq!(ok(server.handle("GET", "/games/1", "")).get("turn"), Some(&Json::from(3)));
+    }
+
+    #[test]
+    fn games_without_a_seed_are_shuffled_securely() {
+        let mut server = Server::new(None, None).unwrap();
+        ok(server.handle("POST", "/games", r#"{"players": 1, "ai_players": 1, "difficulty": "calm"}"#));
+        ok(server.handle("POST", "/games", r#"{"players": 1, "ai_players": 1, "difficulty": "calm", "seed": "4"}"#));
+        ok(server.handle("POST", "/games", r#"{"players": 1, "ai_players": 1, "difficulty": "calm", "secure": false}"#));
+        let rand = |id| &server.games[&id].session.saved().rand;
+        assert!(matches!(rand(1), DeckRng::Secure(_)));
+        assert!(matches!(rand(2), DeckRng::Seeded(_)));
+        assert!(matches!(rand(3), DeckRng::Seeded(_)));
+        assert_eq!(server.games[&1].session.saved().seed, None);
+
+        // A server started from a seed deals the same games again
+        let seeds = || {
+            let mut server = Server::new(Some(9), None).unwrap();
+            ok(server.handle("POST", "/games", r#"{"players": 1, "ai_players": 1, "difficulty": "calm"}"#));
+            server.games[&1].session.saved().seed
+        };
+        assert!(seeds().is_some());
+        assert_eq!(seeds(), seeds());
+    }
+
+    #[test]
+    fn seat_tokens_give_nothing_away_about_the_next_one() {
+        let mut server = Server::new(Some(1), None).unwrap();
+        ok(server.handle("POST", "/games", r#"{"players": 2, "ai_players": 0, "difficulty": "calm", "seed": "4"}"#));
+        let first = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        let second = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        assert_ne!(first, second);
+
+        // Undo xorshift64*'s multiply on the first token's last half and step the generator on, like somebody going
+        // after the next seat's token would
+        let multiplier: u64 = 0x2545F4914F6CDD1D;
+        let inverse = (0..6).fold(multiplier, |inv, _| inv.wrapping_mul(2u64.wrapping_sub(multiplier.wrapping_mul(inv))));
+        assert_eq!(multiplier.wrapping_mul(inverse), 1);
+        let last = u64::from_str_radix(&first[16..], 16).unwrap();
+        let guessed = format!("{:016x}", Randler::from_state(last.wrapping_mul(inverse)).rand());
+        assert_ne!(&second[..16], guessed, "the next token follows from the last one");
+    }
+
+    #[test]
+    fn only_so_many_games_go_at_once() {
+        let mut server = Server::new(Some(1), None).unwrap();
+        let game = r#"{"players": 1, "ai_players": 1, "difficulty": "calm", "seed": "4"}"#;
+        for _ in 0..MAX_GAMES {
+            ok(server.handle("POST", "/games", game));
+        }
+        assert_eq!(server.handle("POST", "/games", game).0, 503);
+        // A finished game makes room
+        let token = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        ok(server.handle("POST", "/games/1/action", &format!(r#"{{"token": "{}", "answer": "takeover"}}"#, token)));
+        assert!(server.games[&1].session.winner().is_some());
+        ok(server.handle("POST", "/games", game));
+    }
+
+    #[test]
+    fn bad_requests_get_errors() {
+        let mut server = Server::new(Some(1), None).unwrap();
+        assert_eq!(server.handle("POST", "/games", "{").0, 400);
+        assert_eq!(server.handle("POST", "/games", r#"{"ai_players": 2}"#).0, 400);
+        // Tables too big to deal and play under the lock
+        assert_eq!(server.handle("POST", "/games", r#"{"players": 255, "ai_players": 255}"#).0, 400);
+        assert_eq!(server.handle("POST", "/games", r#"{"players": 1, "ai_players": 10}"#).0, 400);
+        assert_eq!(server.handle("POST", "/games", r#"{"players": 2, "rules": {"hand-size": 30}}"#).0, 400);
+        assert_eq!(server.handle("POST", "/games", r#"{"players": 2, "decks": 200}"#).0, 400);
+        assert!(server.games.is_empty());
+        assert_eq!(server.handle("GET", "/games", "").0, 405);
+        assert_eq!(server.handle("GET", "/games/7", "").0, 404);
+        ok(server.handle("POST", "/games", r#"{"players": 1, "ai_players": 1, "difficulty": "calm"}"#));
+        assert_eq!(server.handle("GET", "/games/1?token=nope", "").0, 403);
+        assert_eq!(server.handle("POST", "/games/1/action", r#"{"answer": "1"}"#).0, 403);
+        assert_eq!(server.handle("GET", "/games/1/events?since=0", "").0, 403);
+        let token = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        assert_eq!(server.handle("GET", &format!("/games/1/events?since=x&token={}", token), "").0, 400);
+    }
+
+    #[test]
+    fn the_twirp_service_plays_too() {
+        let mut server = Server::new(Some(1), None).unwrap();
+        let call = |server: &mut Server, rpc: &str, body: &str| server.handle("POST", &format!("/twirp/unoler.Unoler/{}", rpc), body);
+
+        let game = ok(call(&mut server, "CreateGame", r#"{"players": 1, "aiPlayers": 1, "difficulty": "calm", "rules": {"handSize": 4}, "seed": "5"}"#));
+        assert_eq!(game.get("id"), Some(&Json::from(1)));
+        let token = text(&ok(call(&mut server, "JoinGame", r#"{"gameId": 1}"#)), "token");
+
+        let view = ok(call(&mut server, "GetView", &format!(r#"{{"gameId": 1, "token": "{}"}}"#, token)));
+        assert_eq!(view.get("hand").and_then(|h| h.as_array()).map(|h| h.len()), Some(4));
+        ok(call(&mut server, "SubmitAction", &format!(r#"{{"gameId": 1, "token": "{}", "answer": "d"}}"#, token)));
+        let events = ok(call(&mut server, "StreamEvents", &format!(r#"{{"gameId": 1, "token": "{}"}}"#, token)));
+        assert!(events.get("events").and_then(|e| e.as_array()).is_some());
+        assert_eq!(call(&mut server, "StreamEvents", r#"{"gameId": 1}"#).0, 403);
+
+        // An AI only game, with the zero players protobuf leaves out
+        ok(call(&mut server, "CreateGame", r#"{"aiPlayers": 2, "difficulty": "skilled", "seed": ""}"#));
+
+        let (status, error) = call(&mut server, "SubmitAction", r#"{"gameId": 1, "token": "nope", "answer": "d"}"#);
+        assert_eq!((status, text(&error, "code")), (403, String::from("permission_denied")));
+        assert_eq!(call(&mut server, "Shuffle", "{}").0, 404);
+        assert_eq!(server.handle("GET", "/twirp/unoler.Unoler/GetView", "").0, 404);
+    }
+
+    #[test]
+    fn metrics_count_games_turns_and_errors() {
+        let mut server = Server::new(Some(1), None).unwrap();
+        ok(server.handle("POST", "/games", r#"{"players": 1, "ai_players": 1, "difficulty": "calm", "seed": "4"}"#));
+        ok(server.handle("POST", "/games/1/join", ""));
+        ok(server.handle("POST", "/games", r#"{"players": 0, "ai_players": 2, "difficulty": "calm", "seed": "4"}"#));
+        assert_eq!(server.handle("GET", "/games/9", "").0, 404);
+        assert_eq!(server.handle("GET", "/twirp/unoler.Unoler/GetView", "").0, 404);
+
+        let metrics = server.metrics();
+        let value = |name: &str| metrics.lines().find_map(|l| l.strip_prefix(name)?.strip_prefix(' ')).unwrap().to_string();
+        // The AI only game is over as soon as it's made
+        assert_eq!(value("unoler_games_active"), "1");
+        assert_eq!(value("unoler_games_total"), "2");
+        assert_eq!(value("unoler_clients_connected"), "1");
+        assert!(value("unoler_turns_total").parse::<u64>().unwrap() > 2);
+        assert_eq!(value("unoler_requests_total"), "5");
+        assert_eq!(value("unoler_errors_total{status=\"404\"}"), "2");
+        assert!(metrics.contains("# TYPE unoler_reshuffles_total counter\n"));
+    }
+
+    #[test]
+    fn stopping_saves_the_games_for_the_next_start() {
+        let dir = std::env::temp_dir().join(format!("unoler-server-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut server = Server::new(Some(1), None).unwrap();
+        ok(server.handle("POST", "/games", r#"{"players": 1, "ai_players": 1, "difficulty": "calm", "seed": "4"}"#));
+        let token = text(&ok(server.handle("POST", "/games/1/join", "")), "token");
+        ok(server.handle("POST", "/games", r#"{"players": 0, "ai_players": 2, "difficulty": "calm", "seed": "4"}"#));
+        ok(server.handle("POST", "/games", r#"{"players": 2, "ai_players": 0, "difficulty": "calm", "seed": "5"}"#));
+        let hand = ok(server.handle("GET", &format!("/games/1?token={}", token), "")).get("hand").cloned();
+        let events = ok(server.handle("GET", &format!("/games/1/events?since=0&token={}", token), ""));
+
+        server.stop();
+        assert!(ok(server.handle("GET", &format!("/games/1/events?since=0&token={}", token), "")).to_string().contains(STOPPING_NOTICE));
+        // Nobody's seat is played for them any more, however long they've been quiet
+        server.idle = Some(Duration::ZERO);
+        let view = ok(server.handle("GET", "/games/1", ""));
+        assert_eq!(view.get("taken_over"), Some(&Json::Array(Vec::new())));
+        let draw = format!(r#"{{"token": "{}", "answer": "d"}}"#, token);
+        assert_eq!(server.handle("POST", "/games/1/action", &draw).0, 503);
+        assert_eq!(server.handle("POST", "/games", "{}").0, 503);
+        // Twirp calls are all POSTs, only the ones that change something are turned away
+        ok(server.handle("POST", "/twirp/unoler.Unoler/GetView", r#"{"gameId": 1}"#));
+        let (status, error) = server.handle("POST", "/twirp/unoler.Unoler/SubmitAction", &format!(r#"{{"gameId": 1, "token": "{}", "answer": "d"}}"#, token));
+        assert_eq!((status, text(&error, "code")), (503, String::from("unavailable")));
+        // The AI only game was over already
+        assert_eq!(server.save_games(&dir).unwrap(), 2);
+
+        let mut next = Server::new(Some(2), None).unwrap();
+        assert_eq!(next.load_games(&dir).unwrap(), 2);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        let view = ok(next.handle("GET", &format!("/games/1?token={}", token), ""));
+        assert_eq!(view.get("hand").cloned(), hand);
+        // The events carry on with the numbers they had
+        assert_eq!(ok(next.handle("GET", &format!("/games/1/events?since=0&token={}", token), "")), events);
+        let count = events.as_array().unwrap().len();
+        assert_eq!(view.get("event_count"), Some(&Json::from(count)));
+        let drew = ok(next.handle("POST", "/games/1/action", &draw));
+        assert_eq!(ok(next.handle("GET", &format!("/games/1/events?since={}&token={}", count, token), "")), drew.get("events").cloned().unwrap());
+        assert_eq!(ok(next.handle("POST", "/games", r#"{"players": 1, "difficulty": "calm"}"#)).get("id"), Some(&Json::from(4)));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reads_a_request_with_a_body() {
+        let raw = "POST /games HTTP/1.1\r\nHost: x\r\ncontent-length: 13\r\n\r\n{\"players\":1}";
+        let (method, target, body) = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!((method.as_str(), target.as_str(), body.as_str()), ("POST", "/games", "{\"players\":1}"));
+
+        let huge = format!("POST /games HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1);
+        assert_eq!(read_request(&mut huge.as_bytes()).unwrap_err().0, 413);
+
+        // Lines that go on and on, and headers that never end, are turned away before they're all read
+        let long_target = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(read_request(&mut long_target.as_bytes()).unwrap_err().0, 400);
+        let long_header = format!("GET / HTTP/1.1\r\nX-Junk: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(read_request(&mut long_header.as_bytes()).unwrap_err().0, 431);
+        let many = format!("GET / HTTP/1.1\r\n{}\r\n", "X-Junk: a\r\n".repeat(MAX_HEADERS + 1));
+        assert_eq!(read_request(&mut many.as_bytes()).unwrap_err().0, 431);
+        let enough = format!("GET / HTTP/1.1\r\n{}\r\n", "X-Junk: a\r\n".repeat(MAX_HEADERS));
+        assert!(read_request(&mut enough.as_bytes()).is_ok());
+    }
+}
@@ -0,0 +1,91 @@
+// `unoler-server`: the host on its own, for machines that only serve games. Takes the flags `UNOler serve` does
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use unoler::persist::data_dir;
+use unoler::pool::default_threads;
+use unoler::trace;
+
+const USAGE: &str = "Usage: unoler-server [options]
+
+Options:
+  --port <n>             The port to listen on, 7878 by default
+  --idle-timeout <secs>  The AI takes over a seat that hasn't answered in that long
+  --seed <n>             Seed the shuffles of games made without a seed, so a session can be run again. Without it
+                         they're shuffled securely
+  --games-dir <path>     Where games still being played are saved on shutdown and picked up on start, server/ in
+                         UNOler's data folder by default
+  --threads <n>          Worker threads answering requests, one per core by default
+  --trace                Trace every turn of every game to stderr
+  --trace-file <path>    Write the trace to this file as one JSON object per line
+  -h, --help             Show this message";
+
+struct Options {
+    port: u16,
+    idle: Option<Duration>,
+    seed: Option<u64>,
+    trace: bool,
+    trace_file: Option<PathBuf>,
+    games_dir: Option<PathBuf>,
+    threads: Option<usize>,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options { port: unoler_server::DEFAULT_PORT, idle: None, seed: None, trace: false, trace_file: None, games_dir: None, threads: None };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || inline.clone().or_else(|| args.next()).ok_or(format!("{} needs a value", flag));
+        match flag.as_str() {
+            "--port" => {
+                let port = value()?;
+                options.port = port.parse().map_err(|_| format!("{} is not a valid port", port))?;
+            },
+            "--idle-timeout" => {
+                let secs = value()?;
+                options.idle = Some(Duration::from_secs(secs.parse().map_err(|_| format!("{} is not a valid number of seconds", secs))?));
+            },
+            "--seed" => {
+                let seed = value()?;
+                options.seed = Some(seed.parse().map_err(|_| format!("{} is not a valid seed", seed))?);
+            },
+            "--games-dir" => options.games_dir = Some(PathBuf::from(value()?)),
+            "--threads" => {
+                let threads = value()?;
+                options.threads = Some(threads.parse().ok().filter(|&n| n > 0).ok_or(format!("{} is not a valid number of threads", threads))?);
+            },
+            "--trace" => options.trace = true,
+            "--trace-file" => options.trace_file = Some(PathBuf::from(value()?)),
+            "-h" | "--help" => return Ok(None),
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    Ok(Some(options))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return Ok(());
+        },
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        },
+    };
+    if options.trace {
+        trace::subscribe(Box::new(trace::Pretty::stderr()));
+    }
+    if let Some(path) = &options.trace_file {
+        trace::subscribe(Box::new(trace::JsonLines::create(path).map_err(|e| format!("Could not open the trace file {}: {}", path.display(), e))?));
+    }
+    let games_dir = options.games_dir.unwrap_or_else(|| data_dir().join("server"));
+    let threads = options.threads.unwrap_or_else(default_threads);
+    unoler_server::run(options.port, options.seed, options.idle, &games_dir, threads)
+}
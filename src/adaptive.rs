@@ -0,0 +1,159 @@
+// --difficulty adaptive: the AIs get harder while you keep winning and easier while you keep losing
+// The levels go from all Random to all Skilled, with the steps in between mixing two difficulties across the AI
+// seats. After every finished game with a human at the table the result goes into adaptive.json next to the saves,
+// and once there are a few results the win rate is held against a fair share of the table: well above it moves a
+// level up, well below it a level down, and either way the record starts over at the new level
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use unoler::game::Difficulty::{self, Aggressive, Calm, Random, Skilled};
+use unoler::game::difficulty_name;
+use unoler::json::Json;
+
+use crate::save;
+
+const LEVELS: [&[Difficulty]; 7] = [&[Random], &[Random, Calm], &[Calm], &[Calm, Aggressive], &[Aggressive], &[Aggressive, Skilled], &[Skilled]];
+const START: usize = 2; // All Calm, like a quick game
+const WINDOW: usize = 6; // Results kept at a level
+const ENOUGH: usize = 3; // Results before the level can move
+
+#[derive(Debug, Clone, PartialEq)]
+struct Game {
+    won: bool,
+    seats: usize, // Everybody, so a win against five AIs counts for more than one against a single AI
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adaptive {
+    level: usize,
+    recent: Vec<Game>,
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Adaptive { level: START, recent: Vec::new() }
+    }
+}
+
+impl Adaptive {
+    // The AI seats take these in turn, like --ai-mix
+    pub fn mix(&self) -> Vec<Difficulty> {
+        LEVELS[self.level].to_vec()
+    }
+
+    pub fn describe(&self) -> String {
+        let names: Vec<&str> = LEVELS[self.level].iter().map(|d| difficulty_name(*d)).collect();
+        format!("Adaptive AIs, level {} of {}: {}", self.level + 1, LEVELS.len(), names.join(" and "))
+    }
+
+    // Says so when the level moved
+    pub fn record(&mut self, won: bool, seats: usize) -> Option<String> {
+        self.recent.push(Game { won, seats: seats.max(2) });
+        if self.recent.len() > WINDOW {
+            self.recent.remove(0);
+        }
+        if self.recent.len() < ENOUGH {
+            return None;
+        }
+        // Winning your seat's share of the games is even, half again as much is too easy and half of it too hard
+        let wins = self.recent.iter().filter(|g| g.won).count() as f64;
+        let fair: f64 = self.recent.iter().map(|g| 1.0 / g.seats as f64).sum();
+        let level = if wins > fair * 1.5 && self.level + 1 < LEVELS.len() {
+            self.level + 1
+        } else if wins < fair * 0.5 && self.level > 0 {
+            self.level - 1
+        } else {
+            return None;
+        };
+        let harder = level > self.level;
+        self.level = level;
+        self.recent.clear();
+        Some(String::from(if harder { "You're on a roll, the AIs get harder next game" } else { "Tough run, the AIs get easier next game" }))
+    }
+
+    fn to_json(&self) -> Json {
+        let recent = self.recent.iter().map(|g| Json::object(vec![("won", g.won.into()), ("seats", g.seats.into())])).collect();
+        Json::object(vec![("level", self.level.into()), ("recent", Json::Array(recent))])
+    }
+
+    fn from_json(value: &Json) -> Option<Adaptive> {
+        let level = value.get("level")?.as_f64().filter(|n| *n >= 0.0)? as usize;
+        let recent = value.get("recent")?.as_array()?.iter()
+            .map(|g| Some(Game { won: g.get("won")?.as_bool()?, seats: g.get("seats")?.as_f64()? as usize }))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Adaptive { level: level.min(LEVELS.len() - 1), recent })
+    }
+}
+
+pub fn path() -> PathBuf {
+    save::data_dir().join("adaptive.json")
+}
+
+// No file yet, or one that doesn't read back, starts at the beginning: it's only a difficulty
+pub fn load(path: &Path) -> Adaptive {
+    fs::read_to_string(path).ok()
+        .and_then(|text| Json::parse(&text).ok())
+        .and_then(|value| Adaptive::from_json(&value))
+        .unwrap_or_default()
+}
+
+pub fn store(path: &Path, adaptive: &Adaptive) -> io::Result<()> {
+    fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    fs::write(path, adaptive.to_json().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winning_moves_up_and_losing_moves_down() {
+        let mut adaptive = Adaptive::default();
+        assert_eq!(adaptive.mix(), [Calm]);
+        assert_eq!(adaptive.record(true, 4), None, "not enough games yet");
+        assert_eq!(adaptive.record(true, 4), None);
+        assert!(adaptive.record(false, 4).unwrap().contains("harder"), "two of three against three AIs is plenty");
+        assert_eq!(adaptive.mix(), [Calm, Aggressive]);
+        assert!(adaptive.recent.is_empty());
+        for _ in 0..2 {
+            assert_eq!(adaptive.record(false, 4), None);
+        }
+        assert!(adaptive.record(false, 4).unwrap().contains("easier"));
+        assert_eq!(adaptive.level, START);
+        // One in four against three AIs is fair, it stays put
+        for won in [true, false, false, false, false, true] {
+            assert_eq!(adaptive.record(won, 4), None);
+        }
+        assert_eq!(adaptive.recent.len(), WINDOW);
+    }
+
+    #[test]
+    fn the_ends_of_the_ladder_hold() {
+        let mut adaptive = Adaptive { level: LEVELS.len() - 1, recent: Vec::new() };
+        for _ in 0..10 {
+            assert_eq!(adaptive.record(true, 2), None);
+        }
+        assert_eq!(adaptive.mix(), [Skilled]);
+        let mut adaptive = Adaptive { level: 0, recent: Vec::new() };
+        for _ in 0..10 {
+            assert_eq!(adaptive.record(false, 2), None);
+        }
+        assert_eq!(adaptive.mix(), [Random]);
+    }
+
+    #[test]
+    fn the_record_is_kept_between_games() {
+        let path = std::env::temp_dir().join(format!("unoler-adaptive-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(load(&path), Adaptive::default());
+        let mut adaptive = Adaptive { level: 5, recent: Vec::new() };
+        adaptive.record(true, 3);
+        store(&path, &adaptive).unwrap();
+        assert_eq!(load(&path), adaptive);
+        fs::write(&path, "{\"level\": \"high\"}").unwrap();
+        assert_eq!(load(&path), Adaptive::default());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+// A compact binary form of a Table, for the per-turn autosave snapshot and anything else that ships whole tables around
+// Every card is one byte (color in the high nibble, number or kind in the low one), every list of cards or names
+// starts with its length as a LEB128 varint, and the rest is flags and fixed width numbers, little endian
+// It carries exactly what table_to_json does, a secure generator's key included: it isn't written, a fresh one comes on load
+
+use unoler::cards::{ALL_COLORS, Color, MAX_NUMBER, SpecialCard, UNOCard};
+use unoler::effects::Effect;
+use unoler::game::{Difficulty, Game, Table};
+use unoler::rng::{ChaChaRng, DeckRng, Randler};
+use unoler::rules::{Handicap, RuleSet};
+
+const MAGIC: &[u8; 4] = b"UNO\x09"; // The last byte is the version
+
+// The extra colors come after NA so every card byte of a standard deck stays what it was
+const COLORS: [Color; 7] = [Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::NA, Color::Purple, Color::Orange];
+const DIFFICULTIES: [Difficulty; 5] = [Difficulty::Calm, Difficulty::Aggressive, Difficulty::Skilled, Difficulty::Random, Difficulty::Expert];
+const KINDS: [SpecialCard; 5] = [SpecialCard::Skip, SpecialCard::Reverse, SpecialCard::PlusTwo, SpecialCard::ColorChange, SpecialCard::PlusFour];
+
+// Numbers past 9 set the top bit, with what's past 10 in the low nibble
+fn card_byte(card: &UNOCard) -> u8 {
+    let color = COLORS.iter().position(|c| *c == card.color).unwrap_or(4) as u8;
+    let kind = match card.special {
+        SpecialCard::Base if card.number > 9 => 0x80 | (card.number.min(MAX_NUMBER) - 10) as u8,
+        SpecialCard::Base => card.number.max(0) as u8,
+        special => 10 + KINDS.iter().position(|k| *k == special).unwrap_or(0) as u8,
+    };
+    color << 4 | kind
+}
+
+fn card_from_byte(byte: u8) -> Option<UNOCard> {
+    let color = *COLORS.get((byte >> 4 & 0x07) as usize)?;
+    match (byte & 0x80 != 0, byte & 0x0f) {
+        (true, n) if 10 + n as i8 <= MAX_NUMBER => Some(UNOCard::new(color, SpecialCard::Base, 10 + n as i8)),
+        (true, _) => None,
+        (false, n @ 0..=9) => Some(UNOCard::new(color, SpecialCard::Base, n as i8)),
+        (false, kind) => Some(UNOCard::new(color, *KINDS.get(kind as usize - 10)?, -1)),
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.0.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.0.push(n as u8);
+    }
+
+    fn u64(&mut self, n: u64) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn cards(&mut self, cards: &[UNOCard]) {
+        self.varint(cards.len() as u64);
+        self.0.extend(cards.iter().map(card_byte));
+    }
+
+    fn text(&mut self, text: &str) {
+        self.varint(text.len() as u64);
+        self.0.extend_from_slice(text.as_bytes());
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        if count > self.0.len() {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            n |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return Some(n);
+            }
+        }
+        None
+    }
+
+    // A length can't be more than what's left, so a broken one fails here instead of allocating forever
+    fn len(&mut self) -> Option<usize> {
+        Some(self.varint()? as usize).filter(|n| *n <= self.0.len())
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    fn cards(&mut self) -> Option<Vec<UNOCard>> {
+        let len = self.len()?;
+        self.bytes(len)?.iter().map(|b| card_from_byte(*b)).collect()
+    }
+
+    fn text(&mut self) -> Option<String> {
+        let len = self.len()?;
+        String::from_utf8(self.bytes(len)?.to_vec()).ok()
+    }
+}
+
+pub fn encode(t: &Table) -> Vec<u8> {
+    let mut w = Writer(MAGIC.to_vec());
+    w.varint(t.hands.len() as u64);
+    for (i, hand) in t.hands.iter().enumerate() {
+        w.cards(hand);
+        w.text(&t.names[i]);
+        w.0.push(DIFFICULTIES.iter().position(|d| *d == t.difficulties[i]).unwrap_or(0) as u8);
+        // The colors they're lacking as bits, in ALL_COLORS order
+        w.0.push(t.lacking[i].iter().filter_map(|c| ALL_COLORS.iter().position(|k| k == c)).fold(0, |bits, k| bits | 1 << k));
+        // The handicap's extra cards, then its no stacking and force play, whether they've traded, whether the AI took
+        // the seat over and whether somebody joined it as bits
+        let handicap = t.handicaps[i];
+        let bits = handicap.no_stacking as u8 | (handicap.force_play as u8) << 1 | (t.traded[i] as u8) << 2
+            | (t.taken_over[i] as u8) << 3 | (t.joined[i] as u8) << 4;
+        w.0.extend([handicap.extra_cards, bits]);
+    }
+    w.cards(&t.deck);
+    w.cards(&t.discard);
+    w.cards(&t.history);
+    w.0.push(card_byte(&t.last_played));
+
+    w.varint(t.game_state.current_player as u64);
+    let flags = [t.game_state.direction < 0, t.skipped, t.uno_detection_panic, t.rules.stacking, t.rules.force_play, t.seed.is_some(), t.rules.opening_action, t.rules.draw_one];
+    w.0.push(flags.iter().enumerate().fold(0, |bits, (i, set)| bits | (*set as u8) << i));
+    w.varint(t.add_queue as u64);
+    w.0.push(t.players);
+    w.0.push(t.rules.hand_size);
+    w.0.push(t.rules.trade as u8);
+    w.0.push(t.rules.illegal_penalty);
+    w.0.push(t.rules.uno_penalty);
+    w.0.push(t.rules.challenge_penalty);
+    w.0.push(t.rules.colors);
+    w.0.push(t.rules.highest_number as u8);
+    // House cards are the number, the draw and skip counts and a byte of flags each
+    w.varint(t.rules.house_cards().count() as u64);
+    for (n, effect) in t.rules.house_cards() {
+        let flags = [effect.reverse, effect.choose_color, effect.swap, effect.rotate];
+        w.0.extend([n as u8, effect.draw, effect.skip, flags.iter().enumerate().fold(0, |bits, (i, set)| bits | (*set as u8) << i)]);
+    }
+    if let Some(seed) = t.seed {
+        w.u64(seed);
+    }
+    match &t.rand {
+        DeckRng::Seeded(rand) => {
+            w.0.push(0);
+            w.u64(rand.state());
+        },
+        DeckRng::Secure(_) => w.0.push(1),
+    }
+    w.u64(t.ai_rand.state());
+    w.0
+}
+
+pub fn decode(bytes: &[u8]) -> Option<Table> {
+    let mut r = Reader(bytes.strip_prefix(MAGIC)?);
+    let seats = r.len()?;
+    let (mut hands, mut names, mut difficulties, mut lacking) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let (mut handicaps, mut traded, mut taken_over, mut joined) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for _ in 0..seats {
+        hands.push(r.cards()?);
+        names.push(r.text()?);
+        difficulties.push(*DIFFICULTIES.get(r.byte()? as usize)?);
+        let bits = r.byte()?;
+        if bits >> ALL_COLORS.len() != 0 {
+            return None;
+        }
+        lacking.push((0..ALL_COLORS.len()).filter(|k| bits & 1 << k != 0).map(|k| ALL_COLORS[k]).collect());
+        let (extra_cards, bits) = (r.byte()?, r.byte()?);
+        if bits >> 5 != 0 {
+            return None;
+        }
+        handicaps.push(Handicap { extra_cards, no_stacking: bits & 1 != 0, force_play: bits & 2 != 0 });
+        traded.push(bits & 4 != 0);
+        taken_over.push(bits & 8 != 0);
+        joined.push(bits & 16 != 0);
+    }
+    let deck = r.cards()?;
+    let discard = r.cards()?;
+    let history = r.cards()?;
+    let last_played = card_from_byte(r.byte()?)?;
+
+    // Same checks as a JSON save, a broken turn order would panic right away
+    let current_player = r.varint()? as usize;
+    if hands.is_empty() || current_player >= hands.len() {
+        return None;
+    }
+    let flags = r.byte()?;
+    let flag = |i: u8| flags & (1 << i) != 0;
+    let add_queue = u32::try_from(r.varint()?).ok()?;
+    let players = r.byte()?;
+    let hand_size = r.byte()?;
+    let trade = match r.byte()? {
+        0 => false,
+        1 => true,
+        _ => return None,
+    };
+    let illegal_penalty = r.byte()?;
+    let (uno_penalty, challenge_penalty) = (r.byte()?, r.byte()?);
+    let colors = r.byte()?;
+    if !(4..=6).contains(&colors) {
+        return None;
+    }
+    let highest_number = r.byte()? as i8;
+    if !(9..=MAX_NUMBER).contains(&highest_number) {
+        return None;
+    }
+    let mut cards = RuleSet::default().cards;
+    for _ in 0..r.len()? {
+        let (n, draw, skip, bits) = (r.byte()? as usize, r.byte()?, r.byte()?, r.byte()?);
+        if n > MAX_NUMBER as usize || bits >> 4 != 0 {
+            return None;
+        }
+        let set = |i: u8| bits & (1 << i) != 0;
+        cards[n] = Effect { draw, skip, reverse: set(0), choose_color: set(1), swap: set(2), rotate: set(3) };
+    }
+    let seed = if flag(5) { Some(r.u64()?) } else { None };
+    let rand = match r.byte()? {
+        0 => DeckRng::Seeded(Randler::from_state(r.u64()?)),
+        1 => DeckRng::Secure(ChaChaRng::from_os().ok()?),
+        _ => return None,
+    };
+    let ai_rand = Randler::from_state(r.u64()?);
+    if !r.0.is_empty() {
+        return None;
+    }
+
+    Some(Table {
+        game_state: Game::new(current_player, hands.len(), if flag(0) { -1 } else { 1 }),
+        hands,
+        deck,
+        discard,
+        history,
+        lacking,
+        last_played,
+        add_queue,
+        skipped: flag(1),
+        uno_detection_panic: flag(2),
+        players,
+        names,
+        difficulties,
+        handicaps,
+        traded,
+        taken_over,
+        joined,
+        rules: RuleSet { stacking: flag(3), force_play: flag(4), hand_size, opening_action: flag(6), draw_one: flag(7), trade, illegal_penalty, uno_penalty, challenge_penalty, colors, highest_number, cards },
+        seed,
+        rand,
+        ai_rand,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::persist::table_to_json;
+    use unoler::cards::{DeckShape, build_deck_of};
+    use unoler::game::{Settings, play};
+    use unoler::testing::ScriptedFrontend;
+
+    #[test]
+    fn every_card_is_one_byte_and_back() {
+        let mut cards = build_deck_of(DeckShape { colors: 6, highest: MAX_NUMBER });
+        cards.extend(COLORS.iter().map(|&color| UNOCard::new(color, SpecialCard::PlusFour, -1)));
+        for card in cards {
+            assert_eq!(card_from_byte(card_byte(&card)), Some(card));
+        }
+        assert_eq!(card_from_byte(0x0f), None);
+        assert_eq!(card_from_byte(0x70), None);
+        assert_eq!(card_from_byte(0x83), None);
+    }
+
+    #[test]
+    fn tables_round_trip_like_the_json_form() {
+        let settings = Settings {
+            players: Some(0),
+            ai_players: Some(3),
+            difficulty: Some(Difficulty::Skilled),
+            seed: Some(12),
+            rules: RuleSet::build(None, &[("force-play", "on"), ("trade", "on"), ("illegal-penalty", "2"), ("uno-penalty", "4"), ("challenge-penalty", "2"), ("colors", "6"), ("highest-number", "11"), ("card-7", "swap+skip2"), ("card-11", "draw2")]
+                .map(|(key, value)| (key.to_string(), value.to_string()))).unwrap(),
+            handicaps: vec![Handicap::default(), "draw1+no-stack+force-play".parse().unwrap()],
+            ..Settings::default()
+        };
+        let mut fe = ScriptedFrontend::default();
+        let mut table = unoler::game::setup(&mut fe, &settings).unwrap().unwrap();
+        // Somebody who joined late and has since handed the seat back over, so it stays the AI's
+        (table.taken_over[1], table.taken_over[2], table.joined[1]) = (true, true, true);
+        for _ in 0..40 {
+            let bytes = encode(&table);
+            let back = decode(&bytes).unwrap();
+            assert_eq!(table_to_json(&back), table_to_json(&table));
+            assert!(bytes.len() * 3 < table_to_json(&table).to_string().len());
+            if play(&mut table, &mut fe, Some(1)).unwrap().is_some() {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn damaged_bytes_are_refused() {
+        let settings = Settings { players: Some(0), ai_players: Some(2), difficulty: Some(Difficulty::Calm), seed: Some(1), ..Settings::default() };
+        let table = unoler::game::setup(&mut ScriptedFrontend::default(), &settings).unwrap().unwrap();
+        let bytes = encode(&table);
+        for cut in 0..bytes.len() {
+            assert!(decode(&bytes[..cut]).is_none(), "{} of {} bytes decoded", cut, bytes.len());
+        }
+        assert!(decode(&[bytes.as_slice(), &[0]].concat()).is_none());
+        assert!(decode(b"UNO\x06").is_none());
+    }
+}
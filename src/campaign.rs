@@ -0,0 +1,228 @@
+// `UNOler campaign`: one player against a run of tables that get harder, ending at a boss table of three Skilled AIs
+// Each `campaign` plays the next table, winning it moves you on and losing means playing it again next time
+// Progress is kept in campaign.json next to the saves, `campaign reset` starts over
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use unoler::effects::Effect;
+use unoler::events::{Event, Frontend};
+use unoler::game::Difficulty::{self, Aggressive, Calm, Skilled};
+use unoler::game::{Forfeited, Settings, difficulty_name, play, setup};
+use unoler::json::Json;
+use unoler::rules::{HOUSE_CARDS, RuleSet};
+
+use crate::save;
+
+pub struct Stage {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub opponents: &'static [Difficulty],
+    pub rules: RuleSet,
+}
+
+pub const STAGES: [Stage; 6] = [
+    Stage {
+        name: "The kitchen table",
+        about: "One calm player to warm up against",
+        opponents: &[Calm],
+        rules: RuleSet { stacking: true, force_play: false, hand_size: 7, opening_action: false, draw_one: false, trade: false, illegal_penalty: 0, uno_penalty: 0, challenge_penalty: 0, colors: 4, highest_number: 9, cards: [Effect::NONE; HOUSE_CARDS] },
+    },
+    Stage {
+        name: "Family night",
+        about: "Two calm players and no stacking to hide behind",
+        opponents: &[Calm, Calm],
+        rules: RuleSet { stacking: false, force_play: false, hand_size: 7, opening_action: false, draw_one: false, trade: false, illegal_penalty: 0, uno_penalty: 0, challenge_penalty: 0, colors: 4, highest_number: 9, cards: [Effect::NONE; HOUSE_CARDS] },
+    },
+    Stage {
+        name: "The card shark",
+        about: "An aggressive player joins, and you have to play whenever you can",
+        opponents: &[Aggressive, Calm],
+        rules: RuleSet { stacking: false, force_play: true, hand_size: 7, opening_action: false, draw_one: false, trade: false, illegal_penalty: 0, uno_penalty: 0, challenge_penalty: 0, colors: 4, highest_number: 9, cards: [Effect::NONE; HOUSE_CARDS] },
+    },
+    Stage {
+        name: "Pile on",
+        about: "Two aggressive players with stacking back on",
+        opponents: &[Aggressive, Aggressive],
+        rules: RuleSet { stacking: true, force_play: true, hand_size: 7, opening_action: false, draw_one: false, trade: false, illegal_penalty: 0, uno_penalty: 0, challenge_penalty: 0, colors: 4, highest_number: 9, cards: [Effect::NONE; HOUSE_CARDS] },
+    },
+    Stage {
+        name: "The back room",
+        about: "A skilled player leads two aggressive ones, and everyone starts with more cards",
+        opponents: &[Skilled, Aggressive, Aggressive],
+        rules: RuleSet { stacking: true, force_play: true, hand_size: 9, opening_action: false, draw_one: false, trade: false, illegal_penalty: 0, uno_penalty: 0, challenge_penalty: 0, colors: 4, highest_number: 9, cards: [Effect::NONE; HOUSE_CARDS] },
+    },
+    Stage {
+        name: "The boss table",
+        about: "Three skilled players. Beat them and the campaign is yours",
+        opponents: &[Skilled, Skilled, Skilled],
+        rules: RuleSet { stacking: true, force_play: true, hand_size: 9, opening_action: false, draw_one: false, trade: false, illegal_penalty: 0, uno_penalty: 0, challenge_penalty: 0, colors: 4, highest_number: 9, cards: [Effect::NONE; HOUSE_CARDS] },
+    },
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Progress {
+    pub stage: usize, // The next table to play, STAGES.len() once the boss table is beaten
+    pub losses: usize,
+}
+
+impl Progress {
+    pub fn finished(&self) -> bool {
+        self.stage >= STAGES.len()
+    }
+
+    fn to_json(self) -> Json {
+        Json::object(vec![("stage", self.stage.into()), ("losses", self.losses.into())])
+    }
+
+    fn from_json(value: &Json) -> Option<Progress> {
+        let count = |key: &str| value.get(key)?.as_f64().filter(|n| *n >= 0.0).map(|n| n as usize);
+        Some(Progress { stage: count("stage")?.min(STAGES.len()), losses: count("losses").unwrap_or(0) })
+    }
+
+    // Moves on after a win, and says how it went
+    pub fn record(&mut self, won: bool) -> String {
+        let stage = &STAGES[self.stage];
+        if !won {
+            self.losses += 1;
+            return format!("{} beat you this time. Run campaign again for another try", stage.name);
+        }
+        self.stage += 1;
+        match STAGES.get(self.stage) {
+            Some(next) => format!("Cleared {}! Next up: {}", stage.name, next.name),
+            None => format!("You beat {} and finished the campaign, losing {} games on the way!", stage.name, self.losses),
+        }
+    }
+}
+
+pub fn progress_path() -> PathBuf {
+    save::data_dir().join("campaign.json")
+}
+
+// No file yet is a fresh campaign
+pub fn load(path: &Path) -> io::Result<Progress> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Progress::default()),
+        Err(e) => return Err(e),
+    };
+    Json::parse(&text).ok()
+        .and_then(|value| Progress::from_json(&value))
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData, format!("{} is damaged, campaign reset starts over", path.display())))
+}
+
+pub fn store(path: &Path, progress: Progress) -> io::Result<()> {
+    fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    fs::write(path, progress.to_json().to_string())
+}
+
+pub fn reset(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn describe(stage: &Stage) -> String {
+    let opponents: Vec<&str> = stage.opponents.iter().map(|d| difficulty_name(*d)).collect();
+    let rules = stage.rules;
+    format!(
+        "{}. Opponents: {}. Stacking {}, force play {}, {} cards each",
+        stage.about,
+        opponents.join(", "),
+        if rules.stacking { "on" } else { "off" },
+        if rules.force_play { "on" } else { "off" },
+        rules.hand_size,
+    )
+}
+
+pub fn status(progress: Progress) {
+    for (i, stage) in STAGES.iter().enumerate() {
+        let mark = if i < progress.stage { "x" } else if i == progress.stage { ">" } else { " " };
+        println!("[{}] {}. {}: {}", mark, i + 1, stage.name, describe(stage));
+    }
+    if progress.finished() {
+        println!("Campaign finished with {} losses along the way", progress.losses);
+    } else if progress.losses > 0 {
+        println!("{} games lost so far", progress.losses);
+    }
+}
+
+// Plays the next table and saves how it went. Only a finished game counts, quitting halfway changes nothing
+pub fn play_next(path: &Path, fe: &mut dyn Frontend, names: Vec<String>, seed: Option<u64>, secure: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut progress = load(path)?;
+    if progress.finished() {
+        fe.event(&Event::Notice(String::from("You've already beaten the boss table. campaign reset starts over")));
+        return Ok(());
+    }
+    let stage = &STAGES[progress.stage];
+    fe.event(&Event::Notice(format!("Table {} of {}: {}", progress.stage + 1, STAGES.len(), stage.name)));
+    fe.event(&Event::Notice(describe(stage)));
+
+    let settings = Settings {
+        players: Some(1),
+        ai_players: Some(stage.opponents.len() as u8),
+        difficulty: None,
+        ai_mix: stage.opponents.to_vec(),
+        names,
+        ai_names: Vec::new(),
+        rules: stage.rules,
+        seed,
+        secure,
+        autoplay: false,
+        decks: None,
+        first: 0,
+        handicaps: Vec::new(),
+        stack: None,
+    };
+    let Some(mut table) = setup(fe, &settings)? else { return Ok(()) };
+    // Giving up counts the same as losing the table
+    let won = match play(&mut table, fe, None) {
+        Err(e) if e.is::<Forfeited>() => Some(false),
+        result => result?.map(|winner| winner == 1),
+    };
+    if let Some(won) = won {
+        fe.event(&Event::Notice(progress.record(won)));
+        store(path, progress)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_get_harder_up_to_the_boss() {
+        for pair in STAGES.windows(2) {
+            assert!(pair[0].opponents.len() <= pair[1].opponents.len(), "{} has more opponents than {}", pair[0].name, pair[1].name);
+        }
+        assert_eq!(STAGES[STAGES.len() - 1].opponents, &[Skilled, Skilled, Skilled]);
+    }
+
+    #[test]
+    fn wins_move_on_and_losses_stay() {
+        let mut progress = Progress::default();
+        assert!(progress.record(false).contains("another try"));
+        assert_eq!(progress, Progress { stage: 0, losses: 1 });
+        for _ in 0..STAGES.len() - 1 {
+            assert!(progress.record(true).contains("Next up"));
+        }
+        assert!(progress.record(true).contains("finished the campaign"));
+        assert!(progress.finished());
+    }
+
+    #[test]
+    fn progress_is_kept_between_runs() {
+        let path = std::env::temp_dir().join(format!("unoler-campaign-{}.json", std::process::id()));
+        reset(&path).unwrap();
+        assert_eq!(load(&path).unwrap(), Progress::default());
+        store(&path, Progress { stage: 3, losses: 4 }).unwrap();
+        assert_eq!(load(&path).unwrap(), Progress { stage: 3, losses: 4 });
+        fs::write(&path, "{\"stage\": \"two\"}").unwrap();
+        assert!(load(&path).is_err());
+        reset(&path).unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,198 @@
+// AI table talk: now and then an AI seat says something about what just happened, in the voice of its difficulty.
+// Calm is polite, Aggressive gloats, Skilled explains itself and Random doesn't know either. It only ever adds notices
+// to the log, so the game, the AIs and the recordings never hear about it. --quiet-ai (or table_talk = false under
+// [display]) turns it off
+
+use std::path::PathBuf;
+
+use unoler::cards::SpecialCard;
+use unoler::events::{Event, Frontend, Prompt};
+use unoler::game::{Difficulty, Table};
+use unoler::rng::Randler;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Moment {
+    Stacked, // A +2 or +4 on top of a stack
+    DrawCard,
+    Skip,
+    Reverse,
+    Wild,
+    Uno,
+    Won,
+    Lost, // A human won
+}
+
+fn lines(difficulty: Difficulty, moment: Moment) -> &'static [&'static str] {
+    use Difficulty::*;
+    use Moment::*;
+    match (difficulty, moment) {
+        (Calm, Stacked) => &["Stacking that, sorry!", "I'll just pass this along, sorry."],
+        (Calm, DrawCard) => &["Sorry about that one.", "Nothing personal!"],
+        (Calm, Skip) => &["Sorry, you'll have to sit this one out.", "Skipping you, hope that's okay."],
+        (Calm, Reverse) => &["Let's go the other way for a bit.", "Turning it around."],
+        (Calm, Wild) => &["A change of color, I think.", "Let's try something different."],
+        (Calm, Uno) => &["Oh, one card left. UNO!", "UNO, I think?"],
+        (Calm, Won) => &["Good game, everyone!", "Oh, I won? Thanks for playing!"],
+        (Calm, Lost) => &["Well played!", "Good game, you earned that."],
+        (Aggressive, Stacked) => &["Stacking that +2, sorry! Not sorry.", "Right back at you!", "The pile grows!"],
+        (Aggressive, DrawCard) => &["Draw up!", "Take that!", "Start drawing."],
+        (Aggressive, Skip) => &["Skipped! Sit down.", "Nope, not your turn."],
+        (Aggressive, Reverse) => &["Back the other way, suckers!", "Reverse!"],
+        (Aggressive, Wild) => &["My color now.", "Deal with this."],
+        (Aggressive, Uno) => &["UNO! Try and stop me.", "UNO! You're all done."],
+        (Aggressive, Won) => &["Too easy.", "Was there ever any doubt?"],
+        (Aggressive, Lost) => &["Beginner's luck.", "Rematch. Now."],
+        (Skilled, Stacked) => &["Stacking was the only sensible play.", "Passing the stack along, as planned."],
+        (Skilled, DrawCard) => &["That should slow you down.", "Card count matters."],
+        (Skilled, Skip) => &["A skip, right on time.", "Tempo."],
+        (Skilled, Reverse) => &["Reversing, it's better for me this way.", "The order favors me now."],
+        (Skilled, Wild) => &["I held that wild for exactly this.", "That color is thin in your hands, I'd guess."],
+        (Skilled, Uno) => &["UNO. Right on schedule.", "UNO, as planned."],
+        (Skilled, Won) => &["As calculated.", "The numbers worked out."],
+        (Skilled, Lost) => &["Well played, I'll study that one.", "Interesting. Good game."],
+        (Expert, Stacked) => &["Stacking. I ran the numbers on not stacking.", "Every line I looked at had this stack in it."],
+        (Expert, DrawCard) => &["In most of the hands I can picture you, that hurts.", "You'll want those cards, trust me."],
+        (Expert, Skip) => &["Skipping you came out ahead in every line.", "You were one move from trouble for me."],
+        (Expert, Reverse) => &["Reversing, the other direction looks better from here.", "I'd rather face the other neighbor."],
+        (Expert, Wild) => &["Nobody's shown that color, so that's the color.", "I played out a few futures, this one wins."],
+        (Expert, Uno) => &["UNO. I saw this coming a few turns ago.", "UNO, the search liked this."],
+        (Expert, Won) => &["That was the likeliest ending.", "Good game. It went about how I figured."],
+        (Expert, Lost) => &["I didn't see that deal coming. Well played.", "Not a line I gave you much chance in. Good game."],
+        (Random, Stacked) => &["More cards for everyone!", "Is this how stacking works?"],
+        (Random, DrawCard) => &["Did I mean to do that?", "Surprise!"],
+        (Random, Skip) => &["Eeny, meeny, skippy!", "Skip! Or was it?"],
+        (Random, Reverse) => &["Wheee, backwards!", "Round and round we go."],
+        (Random, Wild) => &["I picked that color with my eyes closed.", "Colors!"],
+        (Random, Uno) => &["UNO? UNO!", "Wait, UNO!"],
+        (Random, Won) => &["I won? How?", "No idea what happened, but yay!"],
+        (Random, Lost) => &["Was I even playing?", "Good game, I think!"],
+    }
+}
+
+// What happened at the table that's worth a word, and which seat says it
+fn moment(event: &Event, stack: u32, humans: usize) -> Option<(usize, Moment)> {
+    match event {
+        Event::Played { player, ai: true, card } => {
+            let moment = match card.special {
+                SpecialCard::PlusTwo | SpecialCard::PlusFour if stack > 0 => Moment::Stacked,
+                SpecialCard::PlusTwo | SpecialCard::PlusFour => Moment::DrawCard,
+                SpecialCard::Skip => Moment::Skip,
+                SpecialCard::Reverse => Moment::Reverse,
+                SpecialCard::ColorChange => Moment::Wild,
+                SpecialCard::Base => return None,
+            };
+            Some((*player, moment))
+        },
+        Event::Uno { player } if *player > humans => Some((*player, Moment::Uno)),
+        Event::Win { player } if *player > humans => Some((*player, Moment::Won)),
+        // The first AI seat speaks for the table
+        Event::Win { .. } => Some((humans + 1, Moment::Lost)),
+        _ => None,
+    }
+}
+
+pub struct TableTalk {
+    inner: Box<dyn Frontend>,
+    names: Vec<String>,
+    difficulties: Vec<Difficulty>,
+    humans: usize,
+    stack: u32, // What was waiting to be drawn when the turn started, a draw card on top of it is stacking
+    rand: Randler, // Its own, so talking doesn't change a seeded game
+    at_uno: Vec<usize>, // UNO is called every turn with one card, it's only worth saying the first time
+}
+
+impl TableTalk {
+    pub fn new(inner: Box<dyn Frontend>) -> Self {
+        TableTalk { inner, names: Vec::new(), difficulties: Vec::new(), humans: 0, stack: 0, rand: Randler::new(0), at_uno: Vec::new() }
+    }
+
+    fn say(&mut self, player: usize, moment: Moment) {
+        let (Some(name), Some(&difficulty)) = (self.names.get(player.wrapping_sub(1)), self.difficulties.get(player.wrapping_sub(1))) else { return };
+        // Plays only get a word every other time or so, the big moments always do
+        let always = matches!(moment, Moment::Uno | Moment::Won | Moment::Lost);
+        if !always && self.rand.rand().is_multiple_of(2) {
+            return;
+        }
+        let options = lines(difficulty, moment);
+        let line = options[(self.rand.rand() % options.len() as u64) as usize];
+        let notice = Event::Notice(format!("{}: \"{}\"", name, line));
+        self.inner.event(&notice);
+    }
+}
+
+impl Frontend for TableTalk {
+    fn event(&mut self, event: &Event) {
+        self.inner.event(event);
+        match event {
+            Event::Drew { player, .. } | Event::ForcedDraw { player, .. } => self.at_uno.retain(|p| p != player),
+            Event::Uno { player } if self.at_uno.contains(player) => return,
+            Event::Uno { player } => self.at_uno.push(*player),
+            _ => {},
+        }
+        if let Some((player, moment)) = moment(event, self.stack, self.humans) {
+            self.say(player, moment);
+        }
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        if self.names.is_empty() {
+            self.rand = Randler::new(table.seed.unwrap_or(0) ^ 0x7a1c);
+        }
+        self.names = table.names.clone();
+        self.difficulties = table.difficulties.clone();
+        self.humans = table.players as usize;
+        self.stack = table.add_queue;
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        self.inner.game_over(table, winner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::cards::UNOCard;
+    use unoler::testing::card;
+
+    const MOMENTS: [Moment; 8] = [Moment::Stacked, Moment::DrawCard, Moment::Skip, Moment::Reverse, Moment::Wild, Moment::Uno, Moment::Won, Moment::Lost];
+
+    fn played(card: UNOCard, stack: u32) -> Option<Moment> {
+        moment(&Event::Played { player: 2, ai: true, card }, stack, 1).map(|(_, m)| m)
+    }
+
+    #[test]
+    fn every_personality_has_something_to_say() {
+        for difficulty in [Difficulty::Calm, Difficulty::Aggressive, Difficulty::Skilled, Difficulty::Random, Difficulty::Expert] {
+            for moment in MOMENTS {
+                assert!(!lines(difficulty, moment).is_empty(), "{:?} {:?}", difficulty, moment);
+            }
+        }
+    }
+
+    #[test]
+    fn the_table_reacts_to_the_big_plays() {
+        assert_eq!(played(card("red +2"), 0), Some(Moment::DrawCard));
+        assert_eq!(played(card("red +2"), 2), Some(Moment::Stacked));
+        assert_eq!(played(card("wild"), 0), Some(Moment::Wild));
+        assert_eq!(played(card("red 5"), 0), None);
+        assert_eq!(moment(&Event::Played { player: 1, ai: false, card: card("red +2") }, 0, 1), None, "humans talk for themselves");
+        assert_eq!(moment(&Event::Uno { player: 3 }, 0, 1), Some((3, Moment::Uno)));
+        assert_eq!(moment(&Event::Uno { player: 1 }, 0, 1), None);
+        assert_eq!(moment(&Event::Win { player: 1 }, 0, 1), Some((2, Moment::Lost)));
+        assert_eq!(moment(&Event::Win { player: 1 }, 0, 0), Some((1, Moment::Won)), "no humans, the winner gloats");
+    }
+}
@@ -0,0 +1,487 @@
+// Command line flags, parsed by hand since the project has no dependencies
+
+use std::path::PathBuf;
+
+use unoler::events::{Animation, Palette};
+use unoler::game::Difficulty;
+use unoler::rules::{Handicap, RuleSet};
+
+use crate::graphics::Graphics;
+use crate::spectate::Speed;
+use crate::tournament::Format;
+
+pub const USAGE: &str = "Usage: UNOler [options] [command]
+
+Commands:
+  quick                   Start a game of you against three AIs right away, without any setup questions
+  saves list              List the saved games
+  saves resume <slot>     Continue a saved game
+  saves delete <slot>     Delete a saved game
+  simulate                Play AI only games without showing them and print win rates, average turns and
+                          reshuffles. Uses --games (default 1000), --players (seats, default 4), --ai-mix, --seed
+                          and the rule flags
+  bench-ai                Play every AI difficulty against every other and print a table of win rates. Uses
+                          --games (per pairing, default 2000), --seed and the rule flags
+  exhibition              Play the same ten seeded AI only games every time and print each one's seed, seats,
+                          winner and turns. Save the table and diff it after engine changes. Uses the rule flags
+  tournament              Play every AI in --ai-mix (all of them without it) against every other one heads-up
+                          and print the standings. Uses --games (per pairing, default 1000), --seed and the rule
+                          flags. With --bracket the people in --names play too, one game a match
+  spectate                Watch one AI only game told a turn at a time. Uses --speed, --players (seats, default
+                          4), --ai-mix or --difficulty, --seed and the rule flags
+  replay verify <path>    Play a game recorded with --record again and check that nothing about it changed
+  replay review <path>    Go over the human turns of a recorded game next to what the Expert AI would have done
+  replay odds <path>      Print every seat's chance of winning at each turn of a recorded game
+  notation export <path>  Print a game recorded with --record as game notation (see the README)
+  notation play <path>    Play a game in game notation on this version and check every move and the result
+  puzzle list             List the built in endgame puzzles
+  puzzle <name|path>      Play a built in puzzle or one from a file: win from a set position within a few turns
+  practice list           List the practice scenarios
+  practice <scenario>     Drill one kind of decision: the same spot dealt fresh and played out against the AIs,
+                          then gone over next to the Expert. Uses --games (default 5), --difficulty and --seed
+  campaign                Play the next table of the campaign against harder and harder AIs
+  campaign status         Show how far into the campaign you are
+  campaign reset          Start the campaign over
+  stats                   Sum up the games kept by [stats] enabled = true in the config file, with the ratings
+  stats clear             Delete the kept games and ratings
+  serve                   Host games over an HTTP JSON API on localhost, on --port (default 7878)
+
+Options:
+  --players <n>          Number of human players
+  --ai-players <n>       Number of AI players
+  --difficulty <name>    AI difficulty (calm, aggressive, skilled, random, or expert), or adaptive to have the AIs get
+                         harder or easier between games depending on how you've been doing
+  --ai-mix <a,b,...>     Difficulties for the AI seats in turn, like skilled,calm
+  --names <a,b,...>      Names for the human players, in seat order
+  --rules <preset>       Start from a rule preset: house (the default) or official
+  --stacking=<on|off>    Whether +2 and +4 can be stacked instead of drawn
+  --force-play=<on|off>  Whether you have to play a card when you can instead of drawing
+  --hand-size=<n>        How many cards everybody starts with
+  --decks <n>            Shuffle this many decks together instead of enough for the table, as long as every hand fits
+  --stack <path>         Deal the hands and deck order written in this file instead of shuffling, for teaching a
+                         position or reproducing a bug (hand:, top: and deck: lines in game record codes)
+  --draw-one=<on|off>    Whether drawing ends your turn unless you play the card you drew
+  --opening-action=<on|off>
+                         Whether a Skip or Reverse turned up as the first card skips or turns the table around
+  --trade=<on|off>       Whether everybody gets one turn a game to trade a card for the top of the deck (t <number>)
+  --illegal-penalty=<n>  Cards drawn for trying to play a card that doesn't fit, 0 (the default) to only refuse it
+  --uno-penalty=<n>      Cards drawn for going down to one card without calling UNO (play it with 3 uno), 0 (the
+                         default) calls it for you
+  --challenge-penalty=<n>
+                         Let a +4 be challenged as a bluff, with this many cards on top for a challenge that's
+                         wrong. 0 (the default) is no challenging
+  --colors=<n>           Colors in the deck, 4 (the default) or 5 and 6 for purple and orange cards at big tables
+  --highest-number=<n>   The highest number card, 9 (the default) up to 12 for 0-12 decks
+  --card-<0-12>=<effect> Give a number card a house effect, primitives joined with +: draw<n>, skip, skip<n>,
+                         reverse, color, swap or rotate (--card-7=swap --card-0=rotate is the 7-0 game)
+  --handicap <seat>:<handicap>
+                         Handicap one seat, by number from 1, with draw<n> (more cards at every deal), no-stack
+                         or force-play joined with +, like --handicap 1:draw2+no-stack. Can be given again
+  --profile <name>       Play with a saved rule profile instead of the config file's rules
+  --save-profile <name>  Save the rules from the other flags and the config file as a profile and exit
+  --config <path>        Read defaults from this file instead of the usual config.toml
+  --batch                Never ask anything: setup comes from flags and the config file, human seats are
+                         played by the AI (or by --script), and one JSON result line is printed at the end
+  --script <path>        Play the human seats from a file of answers, one per line, in --batch mode (implied).
+                         Stops with an error if an answer is refused or the script runs out
+  --no-color             Don't color the cards (also off when NO_COLOR is set or output isn't a terminal)
+  --palette <name>       Card colors: standard, colorblind (Okabe-Ito, safe for red-green color blindness) or
+                         contrast. Colored cards also start with their color letter, [R] Red 5
+  --animation <speed>    How fast played cards slide in and your first hand is dealt: off, slow, normal (the
+                         default on a terminal) or fast
+  --graphics <mode>      Also show the top card and your hand as pictures: auto (when the terminal is known to
+                         show them), kitty, iterm, sixel or off (the default)
+  --quiet-ai             Keep the AIs from chatting about what they play
+  --bracket <format>     Play the tournament as a knockout or a swiss bracket between --names and --ai-mix
+  --speed <speed>        How long spectate shows every turn: slow, normal (the default), fast, a number of seconds,
+                         or instant to only print how the game ended
+  --fast-forward         Don't stop after every AI turn, show what the AIs did when it's a human's turn again
+                         (ff or step at any press enter prompt switches during the game)
+  --auto-move            Play your turn for you when there's only one thing you can do: draw when nothing can be
+                         played, or play the one card you can under force play
+  --review               After the game, go over your turns next to what the Expert AI would have done and point
+                         out the usual mistakes (drawing with a card to play, a wild spent early, not stacking)
+  --hand-strength        Sum up your hand on your turn: how many cards you can play, how many colors you can
+                         follow and the points you'd give away
+  --win-odds             Estimate every seat's chance of winning from quick playouts: each turn when only AIs
+                         play (spectate included), after the game when people do
+  --tui                  Pick cards with the arrow keys and enter instead of typing their number (d draws, h hints,
+                         : types a command) or by clicking them. Line mode is used anyway when the terminal can't do it
+  --turn-timer <seconds> In --tui mode, draw for you when your turn takes longer than this, with the seconds left
+                         counting down under your hand
+  --json                 Emit events and prompts as JSON lines, read actions as JSON
+  --socket <path>        Speak the --json protocol on a Unix socket at this path instead, for a GUI. Waits for
+                         one connection before the game starts
+  --seed <n>             Use a fixed random seed so the game can be reproduced
+  --secure-rng           Shuffle with a ChaCha20 generator keyed from the OS, for games where
+                         nobody should be able to predict the deck (no seed, can't be replayed)
+  --check-invariants     Check after every turn that no cards appeared or vanished and the table makes sense,
+                         and crash with the details if not (for catching rule bugs)
+  --debug                On your turn, peek-deck <n>, give P<seat> <card>, set-top <card> and dump-state look
+                         at or change the table, for reproducing rule bugs
+  --repl <path>          Read debug REPL queries from this file or named pipe and answer them on stderr, breaking
+                         in between turns whenever a line comes in (state.hands[2], events.last(10),
+                         allowed_move(red 5, blue 5), next, continue)
+  --port <n>             The port for serve
+  --idle-timeout <secs>  For serve, the AI takes over a seat that hasn't answered in that long
+  --games-dir <path>     For serve, where games still being played are saved on shutdown and picked up on start,
+                         server/ in the data folder by default
+  --threads <n>          Worker threads for simulate, bench-ai, tournament and serve, one per core by default
+  --fuzz-parse <n>       Feed n random inputs to every parser of user input and report any that panic
+  --record <path>        Write a replay of the game to this file when it ends, for replay verify
+  --transcript <path>    Write the game to this file as plain sentences, a line a turn, for sharing
+  --on-event <command>   Run this shell command with the game and feed it every event as a line of JSON
+  --on-game-end <command>
+                         Run this shell command after every game with the result as JSON on its stdin, for a
+                         club's own scoring
+  --dump-deal            Print the shuffled deck and the hands it deals for the seed, without playing
+  -v, --verbose          Log what the engine is doing to stderr: -v for reshuffles, -vv for every turn and AI
+                         decision too, -vvv for each random pick as well
+  --log-file <path>      Write the log to this file instead of stderr
+  --trace                Trace every turn and what happened in it to stderr, with the fields of each action
+  --trace-file <path>    Write the trace to this file as one JSON object per line
+  -h, --help             Show this message
+
+Environment:
+  UNOLER_SEED, UNOLER_RULES, UNOLER_PLAYERS, UNOLER_AI_PLAYERS, UNOLER_DIFFICULTY and UNOLER_CONFIG work like
+  the matching flags, which win over them. UNOLER_RULES takes a preset and overrides: official,hand-size=5
+  NO_COLOR or UNOLER_NO_COLOR turn off colors like --no-color";
+
+// What to do once the flags are sorted out
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Command {
+    #[default]
+    Play,
+    Quick,
+    SavesList,
+    SavesResume(String),
+    SavesDelete(String),
+    Simulate,
+    BenchAi,
+    Exhibition,
+    Spectate,
+    Tournament,
+    ReplayVerify(PathBuf),
+    ReplayReview(PathBuf),
+    ReplayOdds(PathBuf),
+    NotationExport(PathBuf),
+    NotationPlay(PathBuf),
+    PuzzleList,
+    Puzzle(String),
+    PracticeList,
+    Practice(String),
+    Campaign,
+    CampaignStatus,
+    CampaignReset,
+    Stats,
+    StatsClear,
+    Serve,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub command: Command,
+    pub players: Option<u8>,
+    pub ai_players: Option<u8>,
+    pub difficulty: Option<Difficulty>,
+    pub adaptive: bool, // --difficulty adaptive, the difficulty comes from adaptive.json
+    pub ai_mix: Vec<Difficulty>,
+    pub names: Vec<String>,
+    pub rules: Option<String>,
+    pub rule_overrides: Vec<(String, String)>,
+    pub profile: Option<String>,
+    pub save_profile: Option<String>,
+    pub config: Option<PathBuf>,
+    pub batch: bool,
+    pub script: Option<PathBuf>,
+    pub json: bool,
+    pub socket: Option<PathBuf>,
+    pub no_color: bool,
+    pub palette: Option<Palette>,
+    pub animation: Option<Animation>,
+    pub graphics: Option<String>,
+    pub quiet_ai: bool,
+    pub speed: Option<Speed>,
+    pub bracket: Option<Format>,
+    pub fast_forward: bool,
+    pub auto_move: bool,
+    pub hand_strength: bool,
+    pub win_odds: bool,
+    pub review: bool,
+    pub tui: bool,
+    pub turn_timer: Option<u8>,
+    pub verbosity: u8,
+    pub log_file: Option<PathBuf>,
+    pub trace: bool,
+    pub trace_file: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub secure_rng: bool,
+    pub record: Option<PathBuf>,
+    pub transcript: Option<PathBuf>,
+    pub handicaps: Vec<Handicap>, // By seat
+    pub on_event: Option<String>,
+    pub on_game_end: Option<String>,
+    pub dump_deal: bool,
+    pub check_invariants: bool,
+    pub debug: bool,
+    pub repl: Option<PathBuf>,
+    pub fuzz_parse: Option<usize>,
+    pub games: Option<usize>,
+    pub decks: Option<u8>,
+    pub stack: Option<PathBuf>,
+    pub port: Option<u16>,
+    pub idle_timeout: Option<u64>, // Seconds
+    pub games_dir: Option<PathBuf>,
+    pub threads: Option<usize>,
+    pub help: bool,
+}
+
+// Splits "--flag=value" into the flag and its value, otherwise takes the next argument
+fn flag_value(flag: &str, inline: Option<&str>, rest: &mut impl Iterator<Item = String>) -> Result<String, String> {
+    match inline {
+        Some(value) => Ok(value.to_string()),
+        None => rest.next().ok_or(format!("{} needs a value", flag)),
+    }
+}
+
+fn parse_command(words: &[String]) -> Result<Command, String> {
+    let words: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+    match words.as_slice() {
+        [] => Ok(Command::Play),
+        ["quick"] => Ok(Command::Quick),
+        ["saves", "list"] => Ok(Command::SavesList),
+        ["saves", "resume", slot] => Ok(Command::SavesResume(slot.to_string())),
+        ["saves", "delete", slot] => Ok(Command::SavesDelete(slot.to_string())),
+        ["simulate"] => Ok(Command::Simulate),
+        ["bench-ai"] => Ok(Command::BenchAi),
+        ["exhibition"] => Ok(Command::Exhibition),
+        ["spectate"] => Ok(Command::Spectate),
+        ["tournament"] => Ok(Command::Tournament),
+        ["serve"] => Ok(Command::Serve),
+        ["replay", "verify", path] => Ok(Command::ReplayVerify(PathBuf::from(path))),
+        ["replay", "review", path] => Ok(Command::ReplayReview(PathBuf::from(path))),
+        ["replay", "odds", path] => Ok(Command::ReplayOdds(PathBuf::from(path))),
+        ["replay", ..] => Err(String::from("replay needs verify <path>")),
+        ["notation", "export", path] => Ok(Command::NotationExport(PathBuf::from(path))),
+        ["notation", "play", path] => Ok(Command::NotationPlay(PathBuf::from(path))),
+        ["notation", ..] => Err(String::from("notation needs export <path> or play <path>")),
+        ["puzzle", "list"] => Ok(Command::PuzzleList),
+        ["puzzle", name] => Ok(Command::Puzzle(name.to_string())),
+        ["puzzle", ..] => Err(String::from("puzzle needs list or the name or path of a puzzle")),
+        ["practice", "list"] => Ok(Command::PracticeList),
+        ["practice", name] => Ok(Command::Practice(name.to_string())),
+        ["practice", ..] => Err(String::from("practice needs list or the name of a scenario")),
+        ["campaign"] => Ok(Command::Campaign),
+        ["campaign", "status"] => Ok(Command::CampaignStatus),
+        ["campaign", "reset"] => Ok(Command::CampaignReset),
+        ["campaign", ..] => Err(String::from("campaign needs nothing, status or reset")),
+        ["stats"] => Ok(Command::Stats),
+        ["stats", "clear"] => Ok(Command::StatsClear),
+        ["stats", ..] => Err(String::from("stats needs nothing or clear")),
+        ["saves", ..] => Err(String::from("saves needs list, resume <slot> or delete <slot>")),
+        _ => Err(format!("Unknown command {}", words.join(" "))),
+    }
+}
+
+// Environment variables fill in whatever the flags left unset, for wrappers and harnesses that can't touch files
+fn apply_env(options: &mut Options, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+    let var = |name: &str| var(name).filter(|v| !v.trim().is_empty());
+
+    if options.seed.is_none() && !options.secure_rng && let Some(seed) = var("UNOLER_SEED") {
+        options.seed = Some(seed.trim().parse().map_err(|_| format!("UNOLER_SEED: {} is not a valid seed", seed))?);
+    }
+    if options.players.is_none() && let Some(players) = var("UNOLER_PLAYERS") {
+        options.players = Some(players.trim().parse().map_err(|_| format!("UNOLER_PLAYERS: {} is not a valid player count", players))?);
+    }
+    if options.ai_players.is_none() && let Some(ai_players) = var("UNOLER_AI_PLAYERS") {
+        options.ai_players = Some(ai_players.trim().parse().map_err(|_| format!("UNOLER_AI_PLAYERS: {} is not a valid player count", ai_players))?);
+    }
+    if options.difficulty.is_none() && !options.adaptive && let Some(difficulty) = var("UNOLER_DIFFICULTY") {
+        if difficulty.trim().eq_ignore_ascii_case("adaptive") {
+            options.adaptive = true;
+        } else {
+            options.difficulty = Some(difficulty.parse().map_err(|e| format!("UNOLER_DIFFICULTY: {}", e))?);
+        }
+    }
+    // NO_COLOR is the usual convention (https://no-color.org), UNOLER_NO_COLOR only affects this game
+    if var("NO_COLOR").is_some() || var("UNOLER_NO_COLOR").is_some() {
+        options.no_color = true;
+    }
+    if options.config.is_none() && let Some(config) = var("UNOLER_CONFIG") {
+        options.config = Some(PathBuf::from(config));
+    }
+
+    // The overrides count as if they were typed before the flags, so a flag for the same rule still wins
+    if let Some(rules) = var("UNOLER_RULES") {
+        let mut overrides = Vec::new();
+        for item in rules.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            match item.split_once('=') {
+                Some((key, value)) => overrides.push((key.trim().to_string(), value.trim().to_string())),
+                None if options.rules.is_none() && options.profile.is_none() => options.rules = Some(item.to_string()),
+                None => {},
+            }
+        }
+        overrides.append(&mut options.rule_overrides);
+        options.rule_overrides = overrides;
+    }
+
+    Ok(())
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>, env: impl Fn(&str) -> Option<String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    let mut words = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+
+        match flag.as_str() {
+            "--players" | "--ai-players" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                let count = Some(value.parse().map_err(|_| format!("{} is not a valid player count", value))?);
+                if flag == "--players" { options.players = count } else { options.ai_players = count }
+            },
+            "--difficulty" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                if value.trim().eq_ignore_ascii_case("adaptive") {
+                    options.adaptive = true;
+                } else {
+                    options.difficulty = Some(value.parse()?);
+                }
+            },
+            "--ai-mix" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.ai_mix = value.split(',').map(|d| d.trim().parse()).collect::<Result<_, _>>()?;
+            },
+            "--names" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.names = value.split(',').map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect();
+            },
+            "--rules" => options.rules = Some(flag_value(&flag, inline.as_deref(), &mut args)?),
+            _ if flag.starts_with("--") && RuleSet::NAMES.contains(&&flag[2..]) => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.rule_overrides.push((flag[2..].to_string(), value));
+            },
+            "--handicap" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                let (seat, handicap) = value.split_once(':').ok_or(format!("--handicap takes a seat and a handicap, like 1:draw2, not {}", value))?;
+                let seat: usize = seat.trim().parse().ok().filter(|s| (1..=255).contains(s)).ok_or(format!("{} is not a seat number", seat))?;
+                if options.handicaps.len() < seat {
+                    options.handicaps.resize(seat, Handicap::default());
+                }
+                options.handicaps[seat - 1] = handicap.parse()?;
+            },
+            "--profile" => options.profile = Some(flag_value(&flag, inline.as_deref(), &mut args)?),
+            "--save-profile" => options.save_profile = Some(flag_value(&flag, inline.as_deref(), &mut args)?),
+            "--config" => options.config = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "--batch" => options.batch = true,
+            "--script" => options.script = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "--json" => options.json = true,
+            "--socket" => options.socket = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "--no-color" => options.no_color = true,
+            "--palette" => options.palette = Some(flag_value(&flag, inline.as_deref(), &mut args)?.parse()?),
+            "--animation" => options.animation = Some(flag_value(&flag, inline.as_deref(), &mut args)?.parse()?),
+            "--graphics" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                Graphics::from_setting(&value)?;
+                options.graphics = Some(value);
+            },
+            "--quiet-ai" => options.quiet_ai = true,
+            "--speed" => options.speed = Some(flag_value(&flag, inline.as_deref(), &mut args)?.parse()?),
+            "--bracket" => options.bracket = Some(flag_value(&flag, inline.as_deref(), &mut args)?.parse()?),
+            "--fast-forward" => options.fast_forward = true,
+            "--auto-move" => options.auto_move = true,
+            "--hand-strength" => options.hand_strength = true,
+            "--win-odds" => options.win_odds = true,
+            "--review" => options.review = true,
+            "--tui" => options.tui = true,
+            "--turn-timer" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.turn_timer = Some(value.parse().map_err(|_| format!("{} is not a valid number of seconds", value))?);
+            },
+            "--seed" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.seed = Some(value.parse().map_err(|_| format!("{} is not a valid seed", value))?);
+            },
+            "--secure-rng" => options.secure_rng = true,
+            "--record" => options.record = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "--transcript" => options.transcript = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "--on-event" => options.on_event = Some(flag_value(&flag, inline.as_deref(), &mut args)?),
+            "--on-game-end" => options.on_game_end = Some(flag_value(&flag, inline.as_deref(), &mut args)?),
+            "--dump-deal" => options.dump_deal = true,
+            "--check-invariants" => options.check_invariants = true,
+            "--debug" => options.debug = true,
+            "--repl" => options.repl = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "--fuzz-parse" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.fuzz_parse = Some(value.parse().map_err(|_| format!("{} is not a valid number of inputs", value))?);
+            },
+            "--decks" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.decks = Some(value.parse().map_err(|_| format!("{} is not a valid number of decks", value))?);
+            },
+            "--stack" => options.stack = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "--games" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.games = Some(value.parse().map_err(|_| format!("{} is not a valid number of games", value))?);
+            },
+            "--threads" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.threads = Some(value.parse().ok().filter(|&n| n > 0).ok_or(format!("{} is not a valid number of threads", value))?);
+            },
+            "--port" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.port = Some(value.parse().map_err(|_| format!("{} is not a valid port", value))?);
+            },
+            "--idle-timeout" => {
+                let value = flag_value(&flag, inline.as_deref(), &mut args)?;
+                options.idle_timeout = Some(value.parse().map_err(|_| format!("{} is not a valid number of seconds", value))?);
+            },
+            "--games-dir" => options.games_dir = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "--verbose" => options.verbosity = options.verbosity.saturating_add(1),
+            // -v, -vv, -vvv
+            _ if flag.strip_prefix('-').is_some_and(|vs| !vs.is_empty() && vs.chars().all(|c| c == 'v')) => {
+                options.verbosity = options.verbosity.saturating_add((flag.len() - 1).min(255) as u8);
+            },
+            "--log-file" => options.log_file = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "--trace" => options.trace = true,
+            "--trace-file" => options.trace_file = Some(PathBuf::from(flag_value(&flag, inline.as_deref(), &mut args)?)),
+            "-h" | "--help" => options.help = true,
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+            _ => words.push(arg),
+        }
+    }
+
+    if options.seed.is_some() && options.secure_rng {
+        return Err(String::from("--seed can't be combined with --secure-rng, secure games aren't reproducible"));
+    }
+
+    if options.socket.is_some() && options.batch {
+        return Err(String::from("--socket is for a GUI playing along, it can't be combined with --batch"));
+    }
+
+    if options.stack.is_some() && options.decks.is_some() {
+        return Err(String::from("--stack brings its own deck, it can't be combined with --decks"));
+    }
+
+    if options.rules.is_some() && options.profile.is_some() {
+        return Err(String::from("--rules and --profile both pick the starting rules, use one of them"));
+    }
+
+    apply_env(&mut options, env)?;
+
+    // Bad rules are usage errors, so check them now rather than when the game starts
+    RuleSet::build(options.rules.as_deref(), &options.rule_overrides)?;
+
+    // A script only makes sense without anybody at the keyboard
+    if options.script.is_some() {
+        options.batch = true;
+    }
+
+    options.command = parse_command(&words)?;
+    Ok(options)
+}
@@ -0,0 +1,514 @@
+// Defaults from ~/.config/unoler/config.toml (%APPDATA%\unoler\config.toml on windows)
+// so regulars don't answer the same setup questions every launch. Command line flags win over the file.
+// Rule profiles are small files of their own in the profiles folder next to it.
+//
+// Only the slice of TOML a config file needs is understood: [tables] and [dotted.tables], key = value, comments,
+// strings, integers, floats, booleans and arrays. It's parsed into the same Json values the rest of the game uses.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use unoler::cards::Aliases;
+use unoler::events::{Animation, Look, Palette};
+use unoler::game::Difficulty;
+use unoler::json::Json;
+use unoler::rules::{RuleSet, json_rule_value};
+
+use crate::graphics::Graphics;
+use crate::save;
+
+// Anything left as None gets asked at setup like before
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub players: Option<u8>,
+    pub ai_players: Option<u8>,
+    pub difficulty: Option<Difficulty>,
+    pub adaptive: bool, // difficulty = "adaptive"
+    pub names: Vec<String>,
+    pub decks: Option<u8>,
+    pub rules_preset: Option<String>,
+    pub rule_overrides: Vec<(String, String)>,
+    pub color: Option<bool>,
+    pub palette: Option<Palette>,
+    pub animation: Option<Animation>,
+    pub graphics: Option<String>,
+    pub table_talk: Option<bool>,
+    pub fast_forward: Option<bool>,
+    pub auto_move: Option<bool>,
+    pub hand_strength: Option<bool>,
+    pub tui: Option<bool>,
+    pub turn_timer: Option<u8>,
+    pub looks: Vec<(String, Look)>, // [player.<name>] tables, by name
+    pub aliases: Aliases, // [aliases], card = "what the group calls it"
+    pub stats: bool, // Opt in only, so there's no flag to turn it on by accident
+    pub warnings: Vec<String>, // Printed by load, kept here so the fuzzer doesn't flood the terminal
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    let var = |name: &str| env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+    let base = if cfg!(windows) {
+        var("APPDATA")
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|h| h.join(".config")))
+    };
+
+    base.map(|b| b.join("unoler").join("config.toml"))
+}
+
+fn profile_path(name: &str) -> Result<PathBuf, String> {
+    save::check_name(name, "profile").map_err(|e| e.to_string())?;
+    let dir = config_path().and_then(|p| p.parent().map(Path::to_path_buf)).ok_or("Could not find the config folder")?;
+    Ok(dir.join("profiles").join(format!("{}.toml", name)))
+}
+
+// Written as a [rules] table, so a profile can be read the same way as the config file
+pub fn save_profile(name: &str, rules: RuleSet) -> Result<PathBuf, String> {
+    let path = profile_path(name)?;
+    let mut text = String::from("[rules]\n");
+    if let Json::Object(pairs) = rules.to_json() {
+        for (key, value) in pairs {
+            text.push_str(&format!("{} = {}\n", key, value));
+        }
+    }
+
+    let write = || -> io::Result<()> {
+        fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+        fs::write(&path, text)
+    };
+    write().map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+pub fn load_profile(name: &str) -> Result<RuleSet, String> {
+    let path = profile_path(name)?;
+    let text = fs::read_to_string(&path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => format!("There is no rule profile named {}", name),
+        _ => format!("{}: {}", path.display(), e),
+    })?;
+
+    let profile = parse_toml(&text)
+        .and_then(|toml| config_from_toml(&toml))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    for warning in &profile.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    RuleSet::build(profile.rules_preset.as_deref(), &profile.rule_overrides)
+}
+
+// A missing default file is fine, a missing file that was asked for by --config isn't
+pub fn load(explicit: Option<&Path>) -> Result<Config, String> {
+    let path = match explicit {
+        Some(path) => path.to_path_buf(),
+        None => match config_path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        },
+    };
+
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && explicit.is_none() => return Ok(Config::default()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+
+    let config = parse_toml(&text)
+        .and_then(|toml| config_from_toml(&toml))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    for warning in &config.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    Ok(config)
+}
+
+fn small_number(value: &Json, key: &str) -> Result<u8, String> {
+    value.as_f64()
+        .filter(|n| n.fract() == 0.0 && (0.0..=255.0).contains(n))
+        .map(|n| n as u8)
+        .ok_or(format!("{} should be a whole number from 0 to 255", key))
+}
+
+pub fn config_from_toml(toml: &Json) -> Result<Config, String> {
+    let mut config = Config::default();
+
+    let Json::Object(tables) = toml else { return Ok(config) };
+    for (table, values) in tables {
+        let Json::Object(values) = values else {
+            return Err(format!("{} should be a [table]", table));
+        };
+        if let Some(name) = table.strip_prefix("player.") {
+            let look = player_look(table, values, &mut config.warnings)?;
+            config.looks.push((name.to_string(), look));
+            continue;
+        }
+        for (key, value) in values {
+            match (table.as_str(), key.as_str()) {
+                ("game", "players") => config.players = Some(small_number(value, "game.players")?),
+                ("game", "ai_players") => config.ai_players = Some(small_number(value, "game.ai_players")?),
+                ("game", "decks") => config.decks = Some(small_number(value, "game.decks")?),
+                ("game", "difficulty") => {
+                    let name = value.as_str().ok_or("game.difficulty should be a string")?;
+                    if name.trim().eq_ignore_ascii_case("adaptive") {
+                        config.adaptive = true;
+                    } else {
+                        config.difficulty = Some(name.parse()?);
+                    }
+                },
+                ("game", "names") => {
+                    config.names = value.as_array()
+                        .and_then(|names| names.iter().map(|n| n.as_str().map(String::from)).collect())
+                        .ok_or("game.names should be a list of strings")?;
+                },
+                ("display", "color") => config.color = Some(value.as_bool().ok_or("display.color should be true or false")?),
+                ("display", "palette") => config.palette = Some(value.as_str().ok_or("display.palette should be a string")?.parse()?),
+                ("display", "animation") => {
+                    config.animation = Some(value.as_str().ok_or("display.animation should be a string")?.parse()?);
+                },
+                ("display", "graphics") => {
+                    let setting = value.as_str().ok_or("display.graphics should be a string")?;
+                    Graphics::from_setting(setting)?;
+                    config.graphics = Some(setting.to_string());
+                },
+                ("display", "table_talk") => config.table_talk = Some(value.as_bool().ok_or("display.table_talk should be true or false")?),
+                ("display", "fast_forward") => {
+                    config.fast_forward = Some(value.as_bool().ok_or("display.fast_forward should be true or false")?);
+                },
+                ("display", "tui") => config.tui = Some(value.as_bool().ok_or("display.tui should be true or false")?),
+                ("display", "turn_timer") => config.turn_timer = Some(small_number(value, "display.turn_timer")?),
+                ("display", "auto_move") => config.auto_move = Some(value.as_bool().ok_or("display.auto_move should be true or false")?),
+                ("display", "hand_strength") => {
+                    config.hand_strength = Some(value.as_bool().ok_or("display.hand_strength should be true or false")?);
+                },
+                ("aliases", card) => {
+                    let alias = value.as_str().ok_or(format!("aliases.{} should be a string", card))?;
+                    config.aliases.add(card, alias).map_err(|e| format!("aliases.{}: {}", card, e))?;
+                },
+                ("stats", "enabled") => config.stats = value.as_bool().ok_or("stats.enabled should be true or false")?,
+                ("rules", "preset") => {
+                    let preset = value.as_str().ok_or("rules.preset should be a string")?;
+                    preset.parse::<RuleSet>()?;
+                    config.rules_preset = Some(preset.to_string());
+                },
+                ("rules", rule) if RuleSet::NAMES.contains(&rule.replace('_', "-").as_str()) => {
+                    let value = json_rule_value(value).ok_or(format!("rules.{} should be a boolean or a number", rule))?;
+                    RuleSet::default().set(rule, &value)?;
+                    config.rule_overrides.push((rule.to_string(), value));
+                },
+                // Newer or misspelled keys shouldn't stop the game, but they shouldn't vanish silently either
+                _ => config.warnings.push(format!("unknown config key {}.{}", table, key)),
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+// An avatar is what goes in front of the name, so it has to stay short and can't carry escape codes
+fn player_look(table: &str, values: &[(String, Json)], warnings: &mut Vec<String>) -> Result<Look, String> {
+    let mut look = Look::default();
+    for (key, value) in values {
+        match key.as_str() {
+            "avatar" => {
+                let avatar = value.as_str().map(str::trim)
+                    .filter(|a| (1..=8).contains(&a.chars().count()) && !a.chars().any(char::is_control))
+                    .ok_or(format!("{}.avatar should be an emoji or a few initials", table))?;
+                look.avatar = Some(avatar.to_string());
+            },
+            "color" => look.accent = Some(value.as_str().ok_or(format!("{}.color should be a string", table))?.parse()?),
+            _ => warnings.push(format!("unknown config key {}.{}", table, key)),
+        }
+    }
+    Ok(look)
+}
+
+// Everything in the file ends up in a table named after its [header], [player.Maria] in one named player.Maria
+// Keys before the first header go in a table with an empty name
+pub fn parse_toml(text: &str) -> Result<Json, String> {
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0, line: 1, depth: 0 };
+    let mut tables: Vec<(String, Json)> = vec![(String::new(), Json::Object(Vec::new()))];
+    let mut current = 0;
+
+    loop {
+        parser.skip_blank();
+        match parser.peek() {
+            None => break,
+            Some('[') => {
+                parser.pos += 1;
+                let mut name = parser.key()?;
+                parser.skip_spaces();
+                while parser.peek() == Some('.') {
+                    parser.pos += 1;
+                    name = format!("{}.{}", name, parser.key()?);
+                    parser.skip_spaces();
+                }
+                parser.expect(']')?;
+                parser.end_of_line()?;
+
+                if tables.iter().any(|(t, _)| *t == name) {
+                    return Err(parser.error(&format!("[{}] is defined twice", name)));
+                }
+                tables.push((name, Json::Object(Vec::new())));
+                current = tables.len() - 1;
+            },
+            Some(_) => {
+                let key = parser.key()?;
+                parser.skip_spaces();
+                parser.expect('=')?;
+                let value = parser.value()?;
+                parser.end_of_line()?;
+
+                let Json::Object(pairs) = &mut tables[current].1 else { unreachable!() };
+                if pairs.iter().any(|(k, _)| *k == key) {
+                    return Err(parser.error(&format!("{} is defined twice", key)));
+                }
+                pairs.push((key, value));
+            },
+        }
+    }
+
+    // Drop the unnamed table if nothing was put in it
+    if let Json::Object(pairs) = &tables[0].1 && pairs.is_empty() {
+        tables.remove(0);
+    }
+    Ok(Json::Object(tables))
+}
+
+// Config files never nest arrays this deep, the limit just keeps "[[[[..." from overflowing the stack
+const MAX_DEPTH: usize = 32;
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", self.line, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(' ' | '\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), Some('\n') | None) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    // Spaces, comments and newlines, for between statements and inside arrays
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') => { self.line += 1; self.pos += 1; },
+                Some('\r') => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        self.skip_comment();
+        match self.peek() {
+            None | Some('\n') | Some('\r') => Ok(()),
+            Some(c) => Err(self.error(&format!("unexpected '{}' after value", c))),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a key"));
+                }
+                Ok(self.chars[start..self.pos].iter().collect())
+            },
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') => Ok(Json::Str(self.basic_string()?)),
+            Some('\'') => Ok(Json::Str(self.literal_string()?)),
+            Some('[') => {
+                if self.depth >= MAX_DEPTH {
+                    return Err(self.error("arrays are nested too deep"));
+                }
+                self.depth += 1;
+                let array = self.array();
+                self.depth -= 1;
+                array
+            },
+            Some('t') | Some('f') => {
+                let word = self.word();
+                match word.as_str() {
+                    "true" => Ok(Json::Bool(true)),
+                    "false" => Ok(Json::Bool(false)),
+                    _ => Err(self.error(&format!("unknown value {}", word))),
+                }
+            },
+            Some(c) if c == '+' || c == '-' || c.is_ascii_digit() => {
+                let word = self.word();
+                word.replace('_', "").parse::<f64>()
+                    .map(Json::Number)
+                    .map_err(|_| self.error(&format!("{} is not a number", word)))
+            },
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    // Bare words end at anything that can't be part of a number or boolean
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '+' || c == '-' || c == '.' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Json::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {},
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            match c {
+                '\'' => {
+                    let s = self.chars[start..self.pos].iter().collect();
+                    self.pos += 1;
+                    return Ok(s);
+                },
+                '\n' => break,
+                _ => self.pos += 1,
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.peek() else { return Err(self.error("unterminated string")) };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\n' => return Err(self.error("unterminated string")),
+                '\\' => {
+                    let Some(e) = self.peek() else { return Err(self.error("unterminated string")) };
+                    self.pos += 1;
+                    match e {
+                        '"' => out.push('"'),
+                        '\\' => out.push('\\'),
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'u' | 'U' => {
+                            let len = if e == 'u' { 4 } else { 8 };
+                            let hex: String = self.chars.iter().skip(self.pos).take(len).collect();
+                            self.pos += len;
+                            let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+                                .ok_or(self.error(&format!("bad escape \\{}{}", e, hex)))?;
+                            out.push(c);
+                        },
+                        _ => return Err(self.error(&format!("bad escape \\{}", e))),
+                    }
+                },
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::events::Accent;
+
+    #[test]
+    fn players_can_pick_how_they_look() {
+        let text = "[player.Maria]\navatar = \"🦊\"\ncolor = \"green\"\n\n[player.\"Ana María\"]\navatar = \"AM\"\nshoe = 9\n";
+        let config = config_from_toml(&parse_toml(text).unwrap()).unwrap();
+        assert_eq!(config.looks, [
+            (String::from("Maria"), Look { avatar: Some(String::from("🦊")), accent: Some(Accent::Green) }),
+            (String::from("Ana María"), Look { avatar: Some(String::from("AM")), accent: None }),
+        ]);
+        assert_eq!(config.warnings, ["unknown config key player.Ana María.shoe"]);
+
+        let bad = |text: &str| config_from_toml(&parse_toml(text).unwrap()).is_err();
+        assert!(bad("[player.Bo]\navatar = \"\\u001b[31m\""), "escape codes");
+        assert!(bad("[player.Bo]\navatar = \"nine letters\""));
+        assert!(bad("[player.Bo]\ncolor = \"plaid\""));
+    }
+
+    #[test]
+    fn aliases_come_from_their_own_table() {
+        let text = "[aliases]\n\"+4\" = \"the nuke\"\n\"red 7\" = \"lucky\"\n";
+        let config = config_from_toml(&parse_toml(text).unwrap()).unwrap();
+        assert_eq!(config.aliases.name(&"wild +4 red".parse().unwrap()), "the nuke (Red)");
+        assert_eq!(config.aliases.expand("lucky"), "red 7");
+
+        let bad = |text: &str| config_from_toml(&parse_toml(text).unwrap()).is_err();
+        assert!(bad("[aliases]\n\"+4\" = 4"));
+        assert!(bad("[aliases]\n\"+5\" = \"the nuke\""));
+        assert!(bad("[aliases]\nskip = \"blue 2\""));
+    }
+}
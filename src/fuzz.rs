@@ -0,0 +1,143 @@
+// `--fuzz-parse`: throws random and mangled text at everything that reads user input and makes sure none of it panics
+// The command line, card names, colors, difficulties, yes/no answers, rules, JSON actions and the TOML config
+// There's no fuzzing crate to lean on, so inputs come from the game's own generator, which keeps runs reproducible
+
+use std::panic::{self, AssertUnwindSafe};
+
+use unoler::cards::{Color, UNOCard};
+use unoler::events::answer_from_json;
+use unoler::game::{Difficulty, YesNo};
+use unoler::json::Json;
+use unoler::rng::{Randler, Rng};
+use unoler::rules::RuleSet;
+
+use crate::{cli, config};
+
+// Real inputs to start mangling from, random bytes alone rarely get past the first character
+const SAMPLES: [&str; 16] = [
+    "red 5",
+    "Wild Draw 4 (Blue)",
+    "yellow +2",
+    "skilled",
+    "yes",
+    "official",
+    "--seed=42 --players 2 --ai-players 1",
+    "saves resume friday --rules official --hand-size=5",
+    "simulate --games 10 --ai-mix skilled,calm",
+    "{\"action\":\"play\",\"card\":2}",
+    "{\"value\":{\"value\":\"red\"}}",
+    "[1, -2.5e3, \"\\u00e9\\ud83c\\udccf\", true, null, {}]",
+    "[game]\nplayers = 2\nnames = [\"Maria\", 'Kostas']\n",
+    "[rules]\npreset = \"official\"\nhand_size = 1_0 # ten\nstacking = false\n",
+    "\"a\\tb\" = \"\\U0001F0CF\"\n[display]\ncolor = true",
+    "[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[[]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]]",
+];
+
+// Bytes that mean something to one parser or another
+const SPECIAL: &[u8] = b" \t\n\r\"'\\[]{}():,=#-+._0123456789eEuU";
+
+fn random_input(rand: &mut Randler) -> String {
+    let mut bytes: Vec<u8> = rand.choose(&SAMPLES).map(|s| s.as_bytes().to_vec()).unwrap_or_default();
+
+    match rand.gen_range(0, 3) {
+        // Pure noise
+        Some(0) => {
+            let len = rand.gen_range(0, 64).unwrap_or(0);
+            bytes = (0..len).map(|_| rand.next_u64() as u8).collect();
+        },
+        // Very deep nesting, for anything recursive
+        Some(1) if rand.rand_bool(0.1) => {
+            let depth = rand.gen_range(100, 5000).unwrap_or(100) as usize;
+            bytes = [vec![b'['; depth], vec![b'{'; depth]].concat();
+        },
+        // A few edits to a real input
+        _ => {
+            for _ in 0..rand.gen_range(1, 8).unwrap_or(1) {
+                let at = rand.gen_range(0, bytes.len() as u64).unwrap_or(0) as usize;
+                let byte = if rand.rand_bool(0.7) { *rand.choose(SPECIAL).unwrap_or(&b' ') } else { rand.next_u64() as u8 };
+                let last = bytes.len().saturating_sub(1);
+                match rand.gen_range(0, 3) {
+                    Some(0) if !bytes.is_empty() => { bytes.remove(at.min(last)); },
+                    Some(1) if !bytes.is_empty() => bytes[at.min(last)] = byte,
+                    _ => bytes.insert(at.min(bytes.len()), byte),
+                }
+            }
+        },
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// Every parser gets the same input, results are thrown away since only panics count
+fn parse_everything(input: &str) {
+    let words = input.split_whitespace().map(String::from).collect::<Vec<_>>();
+    let _ = cli::parse_args(words, |name| name.starts_with("UNOLER_").then(|| input.to_string()));
+
+    let _ = input.parse::<UNOCard>();
+    let _ = input.parse::<Color>();
+    let _ = input.parse::<Difficulty>();
+    let _ = input.parse::<YesNo>();
+    let _ = input.parse::<RuleSet>();
+    if let Some((key, value)) = input.split_once('=') {
+        let _ = RuleSet::default().set(key, value);
+    }
+
+    if let Ok(json) = Json::parse(input) {
+        let _ = answer_from_json(&json);
+        let _ = json.to_string();
+    }
+    if let Ok(toml) = config::parse_toml(input) {
+        let _ = config::config_from_toml(&toml);
+    }
+}
+
+// Returns the inputs that made something panic
+pub fn run(iterations: usize, seed: u64) -> Vec<String> {
+    let mut rand = Randler::new(seed);
+    let mut failures = Vec::new();
+
+    // The default hook would print every panic, they get listed at the end instead
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    for _ in 0..iterations {
+        let input = random_input(&mut rand);
+        if panic::catch_unwind(AssertUnwindSafe(|| parse_everything(&input))).is_err() {
+            failures.push(input);
+        }
+    }
+    panic::set_hook(hook);
+
+    failures
+}
+
+pub fn report(iterations: usize, seed: u64) -> bool {
+    let failures = run(iterations, seed);
+    if failures.is_empty() {
+        println!("Fuzzed the parsers with {} inputs (seed {}), nothing panicked", iterations, seed);
+        return true;
+    }
+
+    println!("{} of {} inputs (seed {}) made a parser panic:", failures.len(), iterations, seed);
+    for input in failures.iter().take(20) {
+        println!("  {:?}", input);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsers_never_panic() {
+        let failures = run(20_000, 0xF022);
+        assert!(failures.is_empty(), "panicked on {:?}", &failures[..failures.len().min(10)]);
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let deep = "[".repeat(100_000);
+        assert!(Json::parse(&deep).is_err());
+        assert!(config::parse_toml(&format!("a = {}", deep)).is_err());
+    }
+}
@@ -0,0 +1,384 @@
+// --graphics: pictures of the top card and the hand for terminals that can show images, drawn here pixel by pixel
+// (a colored card, a white oval and the number or symbol in a blocky font) and sent with whichever protocol the
+// terminal speaks. The text stays as it is underneath, so a terminal that turns out not to understand the pictures
+// still has everything, and anything that isn't one of these terminals only gets the text
+
+use std::env;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use unoler::cards::{Color, SpecialCard, UNOCard};
+use unoler::events::{Event, Frontend, Prompt};
+use unoler::game::Table;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Graphics {
+    Kitty, // The kitty graphics protocol, raw pixels
+    Iterm, // iTerm2's inline images (WezTerm speaks it too), a whole image file
+    Sixel, // The old DEC one, xterm -ti vt340, foot, mlterm and others
+}
+
+impl Graphics {
+    // What the environment says about the terminal, None when there's nothing to go by
+    pub fn detect() -> Option<Graphics> {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        detect_from(&var("TERM"), &var("TERM_PROGRAM"), env::var_os("KITTY_WINDOW_ID").is_some())
+    }
+
+    // --graphics kitty, iterm or sixel picks one, auto detects and off (or a terminal nobody recognizes) is text only
+    pub fn from_setting(setting: &str) -> Result<Option<Graphics>, String> {
+        match setting.trim().to_lowercase().as_str() {
+            "auto" => Ok(Graphics::detect()),
+            "off" | "none" => Ok(None),
+            "kitty" => Ok(Some(Graphics::Kitty)),
+            "iterm" | "iterm2" => Ok(Some(Graphics::Iterm)),
+            "sixel" => Ok(Some(Graphics::Sixel)),
+            _ => Err(format!("Unknown graphics setting {} (try auto, kitty, iterm, sixel or off)", setting)),
+        }
+    }
+
+    // The escape sequence that shows an image, sized in cells where the protocol can
+    fn encode(self, image: &Image) -> String {
+        let cols = image.width.div_ceil(CELL_WIDTH);
+        let rows = image.height.div_ceil(CELL_HEIGHT);
+        match self {
+            Graphics::Kitty => {
+                let rgb: Vec<u8> = image.pixels.iter().flatten().copied().collect();
+                let data = base64(&rgb);
+                let chunks: Vec<&str> = data.as_bytes().chunks(4096).map(|c| std::str::from_utf8(c).unwrap_or("")).collect();
+                let mut out = String::new();
+                for (i, chunk) in chunks.iter().enumerate() {
+                    let more = u8::from(i + 1 < chunks.len());
+                    if i == 0 {
+                        out += &format!("\x1b_Ga=T,f=24,s={},v={},c={},r={},m={};{}\x1b\\", image.width, image.height, cols, rows, more, chunk);
+                    } else {
+                        out += &format!("\x1b_Gm={};{}\x1b\\", more, chunk);
+                    }
+                }
+                out
+            },
+            Graphics::Iterm => {
+                let file = bmp(image);
+                format!("\x1b]1337;File=inline=1;size={};width={};height={}:{}\x07", file.len(), cols, rows, base64(&file))
+            },
+            Graphics::Sixel => sixel(image),
+        }
+    }
+}
+
+fn detect_from(term: &str, program: &str, kitty_window: bool) -> Option<Graphics> {
+    if kitty_window || term == "xterm-kitty" || program == "ghostty" {
+        Some(Graphics::Kitty)
+    } else if program == "iTerm.app" || program == "WezTerm" {
+        Some(Graphics::Iterm)
+    } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+        Some(Graphics::Sixel)
+    } else {
+        None
+    }
+}
+
+// A guess at the pixels in a terminal cell, only for telling kitty and iTerm2 how many cells to take, sixel
+// images come out at their real size
+const CELL_WIDTH: usize = 8;
+const CELL_HEIGHT: usize = 16;
+
+const CARD_WIDTH: usize = 40;
+const CARD_HEIGHT: usize = 60;
+const GAP: usize = 4; // Between cards in a hand
+const SCALE: usize = 3; // Font pixels to image pixels
+
+type Rgb = [u8; 3];
+
+const WHITE: Rgb = [255, 255, 255];
+const INK: Rgb = [30, 30, 30]; // Wild cards and the space between cards
+
+fn rgb(color: Color) -> Rgb {
+    match color {
+        Color::Red => [215, 38, 61],
+        Color::Green => [46, 147, 60],
+        Color::Yellow => [242, 193, 78],
+        Color::Blue => [27, 101, 166],
+        Color::Purple => [123, 63, 160],
+        Color::Orange => [240, 128, 30],
+        Color::NA => INK,
+    }
+}
+
+// Blocky glyphs, five rows each, with s for skip, r for reverse and w for wild
+const GLYPHS: [(char, [&str; 5]); 15] = [
+    ('0', ["###", "#.#", "#.#", "#.#", "###"]),
+    ('1', [".#.", "##.", ".#.", ".#.", "###"]),
+    ('2', ["###", "..#", "###", "#..", "###"]),
+    ('3', ["###", "..#", "###", "..#", "###"]),
+    ('4', ["#.#", "#.#", "###", "..#", "..#"]),
+    ('5', ["###", "#..", "###", "..#", "###"]),
+    ('6', ["###", "#..", "###", "#.#", "###"]),
+    ('7', ["###", "..#", "..#", "..#", "..#"]),
+    ('8', ["###", "#.#", "###", "#.#", "###"]),
+    ('9', ["###", "#.#", "###", "..#", "###"]),
+    ('+', ["...", ".#.", "###", ".#.", "..."]),
+    ('s', [".###.", "#..##", "#.#.#", "##..#", ".###."]),
+    ('r', ["...#.", "#####", ".....", "#####", ".#..."]),
+    ('w', ["#...#", "#...#", "#.#.#", "##.##", "#...#"]),
+    (' ', [".", ".", ".", ".", "."]),
+];
+
+fn label(card: &UNOCard) -> String {
+    match card.special {
+        SpecialCard::Base => card.number.to_string(),
+        SpecialCard::Skip => String::from("s"),
+        SpecialCard::Reverse => String::from("r"),
+        SpecialCard::PlusTwo => String::from("+ 2"),
+        SpecialCard::ColorChange => String::from("w"),
+        SpecialCard::PlusFour => String::from("+ 4"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>, // Row by row from the top
+}
+
+impl Image {
+    fn new(width: usize, height: usize, fill: Rgb) -> Image {
+        Image { width, height, pixels: vec![fill; width * height] }
+    }
+
+    fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    fn paste(&mut self, other: &Image, left: usize) {
+        for y in 0..other.height {
+            for x in 0..other.width {
+                self.set(left + x, y, other.get(x, y));
+            }
+        }
+    }
+}
+
+fn card_image(card: &UNOCard) -> Image {
+    let mut image = Image::new(CARD_WIDTH, CARD_HEIGHT, WHITE);
+    let fill = rgb(card.color);
+    for y in 2..CARD_HEIGHT - 2 {
+        for x in 2..CARD_WIDTH - 2 {
+            image.set(x, y, fill);
+        }
+    }
+    // The oval in the middle, tilted cards are too much for a 40 pixel wide picture
+    let (cx, cy, rx, ry) = (CARD_WIDTH as f64 / 2.0, CARD_HEIGHT as f64 / 2.0, 15.0, 21.0);
+    for y in 0..CARD_HEIGHT {
+        for x in 0..CARD_WIDTH {
+            let (dx, dy) = ((x as f64 + 0.5 - cx) / rx, (y as f64 + 0.5 - cy) / ry);
+            if dx * dx + dy * dy <= 1.0 {
+                image.set(x, y, WHITE);
+            }
+        }
+    }
+    let glyphs: Vec<&[&str; 5]> = label(card).chars().filter_map(|c| GLYPHS.iter().find(|(g, _)| *g == c).map(|(_, rows)| rows)).collect();
+    let width: usize = glyphs.iter().map(|g| g[0].len() * SCALE).sum();
+    let (mut left, top) = ((CARD_WIDTH - width) / 2, (CARD_HEIGHT - 5 * SCALE) / 2);
+    for glyph in glyphs {
+        for (row, line) in glyph.iter().enumerate() {
+            for (col, _) in line.chars().enumerate().filter(|(_, c)| *c == '#') {
+                for (sx, sy) in (0..SCALE).flat_map(|sx| (0..SCALE).map(move |sy| (sx, sy))) {
+                    image.set(left + col * SCALE + sx, top + row * SCALE + sy, fill);
+                }
+            }
+        }
+        left += glyph[0].len() * SCALE;
+    }
+    image
+}
+
+fn hand_image(hand: &[UNOCard]) -> Image {
+    let width = (hand.len() * (CARD_WIDTH + GAP)).saturating_sub(GAP).max(1);
+    let mut image = Image::new(width, CARD_HEIGHT, INK);
+    for (i, card) in hand.iter().enumerate() {
+        image.paste(&card_image(card), i * (CARD_WIDTH + GAP));
+    }
+    image
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            out.push(if i <= chunk.len() { ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char } else { '=' });
+        }
+    }
+    out
+}
+
+// An uncompressed 24 bit BMP, the simplest file every image viewer opens. Rows go bottom up and are padded to 4 bytes
+fn bmp(image: &Image) -> Vec<u8> {
+    let row = (image.width * 3).div_ceil(4) * 4;
+    let size = 54 + row * image.height;
+    let mut out = Vec::with_capacity(size);
+    out.extend_from_slice(b"BM");
+    for n in [size as u32, 0, 54, 40, image.width as u32, image.height as u32] {
+        out.extend_from_slice(&n.to_le_bytes());
+    }
+    out.extend_from_slice(&1u16.to_le_bytes()); // Planes
+    out.extend_from_slice(&24u16.to_le_bytes());
+    out.extend_from_slice(&[0; 24]); // No compression and the rest left to the viewer
+    for y in (0..image.height).rev() {
+        for x in 0..image.width {
+            let [r, g, b] = image.get(x, y);
+            out.extend_from_slice(&[b, g, r]);
+        }
+        out.resize(out.len() + row - image.width * 3, 0);
+    }
+    out
+}
+
+// Sixels are six pixel tall columns, one character each. Every band of six rows goes over once per color with that
+// color's pixels as bits, $ goes back to the start of the band and - on to the next one. Runs of the same character
+// are shortened to !count
+fn sixel(image: &Image) -> String {
+    let mut palette: Vec<Rgb> = Vec::new();
+    for pixel in &image.pixels {
+        if !palette.contains(pixel) {
+            palette.push(*pixel);
+        }
+    }
+    let mut out = String::from("\x1bPq");
+    for (i, [r, g, b]) in palette.iter().enumerate() {
+        let percent = |c: &u8| *c as usize * 100 / 255;
+        out += &format!("#{};2;{};{};{}", i, percent(r), percent(g), percent(b));
+    }
+    for band in (0..image.height).step_by(6) {
+        for (i, color) in palette.iter().enumerate() {
+            let columns: Vec<u8> = (0..image.width)
+                .map(|x| (0..6).filter(|dy| band + dy < image.height && image.get(x, band + dy) == *color).fold(0, |bits, dy| bits | 1 << dy))
+                .collect();
+            if columns.iter().all(|bits| *bits == 0) {
+                continue;
+            }
+            out += &format!("#{}", i);
+            let mut x = 0;
+            while x < columns.len() {
+                let run = columns[x..].iter().take_while(|bits| **bits == columns[x]).count();
+                let c = (63 + columns[x]) as char;
+                if run > 3 { out += &format!("!{}{}", run, c) } else { out.extend(std::iter::repeat_n(c, run)) }
+                x += run;
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out + "\x1b\\"
+}
+
+// Sits in front of the real frontend and adds the pictures after the lines they go with: the top card when a human's
+// turn starts or they ask to see it again, and the hand before it's listed
+pub struct Pictures {
+    inner: Box<dyn Frontend>,
+    graphics: Graphics,
+}
+
+impl Pictures {
+    pub fn new(inner: Box<dyn Frontend>, graphics: Graphics) -> Self {
+        Pictures { inner, graphics }
+    }
+
+    fn show(&self, image: &Image) {
+        println!("{}", self.graphics.encode(image));
+        io::stdout().flush().expect("Failed to flush terminal.");
+    }
+}
+
+impl Frontend for Pictures {
+    fn event(&mut self, event: &Event) {
+        if let Event::Hand(hand) = event {
+            self.show(&hand_image(hand));
+        }
+        self.inner.event(event);
+        match event {
+            Event::TurnStart { ai: false, last_played, .. } | Event::LastCard(last_played) => self.show(&card_image(last_played)),
+            _ => {},
+        }
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        self.inner.game_over(table, winner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::testing::{card, cards};
+
+    #[test]
+    fn terminals_are_told_apart_by_their_environment() {
+        assert_eq!(detect_from("xterm-kitty", "", false), Some(Graphics::Kitty));
+        assert_eq!(detect_from("xterm-256color", "", true), Some(Graphics::Kitty));
+        assert_eq!(detect_from("xterm-256color", "iTerm.app", false), Some(Graphics::Iterm));
+        assert_eq!(detect_from("foot", "", false), Some(Graphics::Sixel));
+        assert_eq!(detect_from("xterm-256color", "Apple_Terminal", false), None);
+        assert_eq!(Graphics::from_setting("off"), Ok(None));
+        assert!(Graphics::from_setting("ascii art").is_err());
+    }
+
+    #[test]
+    fn cards_are_drawn_in_their_color() {
+        let red = card_image(&card("red 7"));
+        assert_eq!((red.width, red.height), (CARD_WIDTH, CARD_HEIGHT));
+        assert_eq!(red.get(0, 0), WHITE, "the border");
+        assert_eq!(red.get(4, 4), rgb(Color::Red));
+        assert_eq!(red.get(CARD_WIDTH / 2, 6), rgb(Color::Red), "above the oval");
+        assert_eq!(red.get(22, 36), rgb(Color::Red), "the bottom of the 7 in the oval");
+        assert_ne!(red, card_image(&card("red 1")));
+        let hand = hand_image(&cards(&["red 5", "blue skip", "wild +4"]));
+        assert_eq!(hand.width, 3 * CARD_WIDTH + 2 * GAP);
+        assert_eq!(hand.get(CARD_WIDTH + GAP + 4, 4), rgb(Color::Blue));
+        assert_eq!(hand.get(CARD_WIDTH + 1, 4), INK);
+    }
+
+    #[test]
+    fn images_go_out_in_each_protocol() {
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"Man"), "TWFu");
+        let image = card_image(&card("green 2"));
+        let file = bmp(&image);
+        assert_eq!(&file[..2], b"BM");
+        assert_eq!(file.len(), 54 + 120 * 60);
+        assert_eq!(u32::from_le_bytes(file[2..6].try_into().unwrap()) as usize, file.len());
+        let kitty = Graphics::Kitty.encode(&image);
+        assert!(kitty.starts_with("\x1b_Ga=T,f=24,s=40,v=60,c=5,r=4,m=1;"));
+        assert!(kitty.ends_with("\x1b\\") && kitty.contains("\x1b_Gm=0;"), "7200 bytes don't fit in one chunk");
+        let sixel = Graphics::Sixel.encode(&image);
+        assert!(sixel.starts_with("\x1bPq#0;2;100;100;100#1;2;18;57;23"));
+        assert_eq!(sixel.matches('-').count(), 10, "one band per six rows");
+        assert!(Graphics::Iterm.encode(&image).starts_with(&format!("\x1b]1337;File=inline=1;size={};", file.len())));
+    }
+}
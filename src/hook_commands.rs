@@ -0,0 +1,111 @@
+// --on-event and --on-game-end: the scoring hooks (unoler::hooks) as outside programs, for clubs that keep their
+// ladder in a script of their own
+// The event command starts with the game and reads one JSON event a line until its stdin closes. What it prints is
+// thrown away so it can't get in the middle of the game. The game end command runs once per game with the result on
+// stdin and its output shown, so it can print the standings
+
+use std::io::Write;
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use unoler::events::Frontend;
+use unoler::hooks::Hooks;
+use unoler::json::Json;
+
+fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+    shell.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(command);
+    shell
+}
+
+// The running event command, which stops being written to the first time it can't take a line
+struct EventCommand {
+    command: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl EventCommand {
+    fn send(&mut self, event: &Json) {
+        let Some(stdin) = self.stdin.as_mut() else { return };
+        if let Err(e) = writeln!(stdin, "{}", event) {
+            eprintln!("{} stopped taking events: {}", self.command, e);
+            self.stdin = None;
+        }
+    }
+}
+
+// Waits for it to read the rest once the game is over, so nothing is still being scored after UNOler exits
+impl Drop for EventCommand {
+    fn drop(&mut self) {
+        self.stdin = None;
+        let _ = self.child.wait();
+    }
+}
+
+fn game_end(command: &str, result: &Json) {
+    let run = || -> std::io::Result<std::process::ExitStatus> {
+        let mut child = shell(command).stdin(Stdio::piped()).spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{}", result)?;
+        }
+        child.wait()
+    };
+    match run() {
+        Ok(status) if status.success() => {},
+        Ok(status) => eprintln!("{} failed ({})", command, status),
+        Err(e) => eprintln!("Could not run {}: {}", command, e),
+    }
+}
+
+// The frontend with whichever commands were asked for, or as it was without either
+pub fn attach(inner: Box<dyn Frontend>, on_event: Option<&str>, on_game_end: Option<&str>) -> Result<Box<dyn Frontend>, String> {
+    if on_event.is_none() && on_game_end.is_none() {
+        return Ok(inner);
+    }
+    let mut hooks = Hooks::new(inner);
+    if let Some(command) = on_event {
+        let mut child = shell(command).stdin(Stdio::piped()).stdout(Stdio::null()).spawn()
+            .map_err(|e| format!("Could not run {}: {}", command, e))?;
+        let stdin = child.stdin.take();
+        let mut events = EventCommand { command: command.to_string(), child, stdin };
+        hooks = hooks.on_event(move |event| events.send(event));
+    }
+    if let Some(command) = on_game_end {
+        let command = command.to_string();
+        hooks = hooks.on_game_end(move |result| game_end(&command, result));
+    }
+    Ok(Box::new(hooks))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use unoler::game::{Difficulty, Settings, play, setup};
+    use unoler::testing::ScriptedFrontend;
+
+    #[test]
+    fn commands_get_the_events_and_the_result() {
+        let dir = std::env::temp_dir().join(format!("unoler-hooks-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (events, result) = (dir.join("events"), dir.join("result"));
+        let mut fe = attach(
+            Box::new(ScriptedFrontend::default()),
+            Some(&format!("cat > {}", events.display())),
+            Some(&format!("cat > {}", result.display())),
+        ).unwrap();
+
+        let settings = Settings { players: Some(0), ai_players: Some(2), difficulty: Some(Difficulty::Calm), seed: Some(4), ..Settings::default() };
+        let mut table = setup(fe.as_mut(), &settings).unwrap().unwrap();
+        let winner = play(&mut table, fe.as_mut(), None).unwrap();
+        fe.game_over(&table, winner);
+        drop(fe);
+
+        let events = std::fs::read_to_string(&events).unwrap();
+        assert!(events.lines().all(|line| Json::parse(line).is_ok()));
+        assert!(events.lines().last().unwrap().contains("\"win\""));
+        let result = Json::parse(&std::fs::read_to_string(&result).unwrap()).unwrap();
+        assert_eq!(result.get("winner"), Some(&Json::from(winner.unwrap())));
+        assert_eq!(result.get("seed").and_then(|s| s.as_str()), Some("4"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
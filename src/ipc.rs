@@ -0,0 +1,88 @@
+// --socket: the --json protocol on a local socket instead of stdin and stdout, for a GUI in its own process
+// The game waits for one connection, then plays over it until somebody wins or the GUI hangs up
+// std only has Unix domain sockets, so Windows named pipes aren't there yet
+
+use std::path::Path;
+
+use unoler::events::JsonFrontend;
+
+#[cfg(unix)]
+pub fn accept(path: &Path) -> Result<JsonFrontend, String> {
+    use std::fs;
+    use std::io::BufReader;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    let failed = |e: std::io::Error| format!("{}: {}", path.display(), e);
+
+    // A socket left behind by an earlier game would stop the bind, anything else there is somebody's file
+    if let Ok(meta) = fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            return Err(format!("{} already exists and isn't a socket", path.display()));
+        }
+        fs::remove_file(path).map_err(failed)?;
+    }
+
+    let listener = UnixListener::bind(path).map_err(failed)?;
+    eprintln!("Waiting for a connection on {}", path.display());
+    let (stream, _) = listener.accept().map_err(failed)?;
+    // Nobody else can connect once the game has started
+    drop(listener);
+    let _ = fs::remove_file(path);
+
+    let input = stream.try_clone().map_err(failed)?;
+    Ok(JsonFrontend::new(Box::new(BufReader::new(input)), Box::new(stream)))
+}
+
+#[cfg(not(unix))]
+pub fn accept(path: &Path) -> Result<JsonFrontend, String> {
+    Err(format!("Can't listen on {}, --socket only works on Unix systems for now", path.display()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use std::thread;
+    use std::time::Duration;
+
+    use unoler::events::{Frontend, Prompt};
+
+    #[test]
+    fn talks_json_over_the_socket() {
+        let path = std::env::temp_dir().join(format!("unoler-test-{}.sock", std::process::id()));
+        let client_path = path.clone();
+        let client = thread::spawn(move || {
+            // The game side has to bind first, so keep trying for a bit
+            let mut stream = None;
+            for _ in 0..100 {
+                stream = UnixStream::connect(&client_path).ok();
+                if stream.is_some() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+            let stream = stream.expect("Couldn't connect");
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            (&stream).write_all(b"{\"action\": \"draw\"}\n").unwrap();
+            line
+        });
+
+        let mut fe = accept(&path).unwrap();
+        assert!(!path.exists());
+        assert_eq!(fe.prompt(&Prompt::Action { player: 1 }), "d");
+        assert!(client.join().unwrap().contains("\"prompt\":\"action\""));
+    }
+
+    #[test]
+    fn leaves_other_files_alone() {
+        let path = std::env::temp_dir().join(format!("unoler-test-{}.txt", std::process::id()));
+        std::fs::write(&path, "keep me").unwrap();
+        assert!(accept(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
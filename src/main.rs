@@ -1,915 +1,701 @@
-use std::io::{self, Write, Result, Error, ErrorKind};
-use std::str::FromStr;
-use std::fmt::Display;
-
-fn input<T, E>(message: &str, error: &str) -> T 
-where 
-    T: FromStr<Err = E>,
-    E: Display,
-    
-{
-    let mut u_input = String::new();
-    
-    loop {
-    
-        print!("{}: ", message);
-        io::stdout().flush().expect("Failed to flush terminal.");
-        
-        
-        let n = io::stdin().read_line(&mut u_input).expect("Input failed.");
-        
-        if n == 0 {
-            println!("0 Bytes read. Please retry the program or ensure EOF hasn't reached.");
-        }
-        
-        match u_input.trim().parse::<T>() {
-            Ok(val) => return val,
-            Err(e) => println!("Error: {} ({})", error, e),
-        }
-        
-        u_input.clear();
-    
-    }
-    
-}
-
-// For a random number generator on windows
-#[cfg(windows)]
-#[link(name = "bcrypt")]
-unsafe extern "system" {
-    unsafe fn BCryptGenRandom(
-        hAlgorithm: *mut core::ffi::c_void,
-        pbBuffer: *mut u8,
-        cbBuffer: u32,
-        dwFlags: u32,
-    ) -> i32;
-}
-
-#[derive(Debug, Clone, Copy)]
-struct Randler {
-    seed: u64
-}
-
-// For good practice, add default
-impl Default for Randler {
-    fn default() -> Self {
-        // Bro if you went out of your way to use a custom OS not derived from Linux or Windows AND are compiling this project? That's all on you
-        Self::urandom_seed_init().expect("Failed to seed from /dev/urandom or bcryptprimitives. Use new(seed) instead.")
-    }
-}
-
-impl Randler {
-    // Creates a new instance based on a seed
-    pub fn new(seed: u64) -> Self {
-        if seed == 0 {
-            println!("Warning! A seed of 0 will result in every random value being the same.");
-        }
-        Randler { seed: seed }
-    }
-
-    // Gets a seed based off of urandom
-    #[cfg(unix)]
-    fn get_base_random_udev() -> Result<u64> {
-        use std::fs::File;
-        use std::io::Read;
-    
-        let mut file = File::open("/dev/urandom")?;
-        
-        let mut buffer = [0u8; 8];
-        file.read_exact(&mut buffer)?;
-        
-        let random_num = u64::from_ne_bytes(buffer);
-        
-        if random_num == 0 {
-            return Ok(1);
-        }
-    
-        Ok(random_num)
-    }
-
-    // Gets a random seed based off of bcryptprimitives
-    #[cfg(windows)]
-    pub fn get_base_random_udev() -> Result<u64> {
-        let mut buf = [0u8; 8];
-        const BCRYPT_USE_SYSTEM_PREFERRED_RNG: u32 = 0x00000002;
-
-        let status = unsafe {
-            BCryptGenRandom(
-                core::ptr::null_mut(),
-                buf.as_mut_ptr(),
-                buf.len() as u32,
-                BCRYPT_USE_SYSTEM_PREFERRED_RNG,
-            )
-        };
-
-        // NTSTATUS: success is >= 0
-        if status < 0 {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("BCryptGenRandom failed with status {status:#x}"),
-            ));
-        }
-
-        let value = u64::from_le_bytes(buf);
-
-        // Nonzero check
-        if value == 0 {
-            return Ok(1);
-        }
-
-        Ok(value)
-    }
-
-    // Automatically instantiates an instance based off of the seed
-    pub fn urandom_seed_init() -> Result<Self> {
-        Ok(Self::new(Randler::get_base_random_udev()?))
-    }
-
-    // Creates a random number based on Xorshift64
-    pub fn rand(&mut self) -> u64 {
-        let mut x = self.seed;
-        
-        x ^= x << 12;
-        x ^= x >> 25;
-        x ^= x << 27;
-        
-        self.seed = x;
-        x
-    }
-    
-    // Creates a random number within a defined range
-    pub fn rand_range(&mut self, min: u64, max: u64) -> Option<u64>  {
-        // Ensure input is proper
-        if min > max {
-            println!("Max should not be smaller than min");
-            return None;
-        }
-
-        // Ensure integer overflow doesn't occur
-        if min == 0 && max == u64::MAX { return Some(self.rand()); }
-
-        // Normalize
-        let range = max - min + 1;
-        let limit = u64::MAX - (u64::MAX % range);
-
-        // Prevent modulo bias
-        let mut x = self.rand();
-        while x >= limit {
-            x = self.rand();
-        }
-        
-        let ranged = (x % range) + min;
-        
-        Some(ranged)
-    }
-}
-
-// Colors for the cards
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
-enum Color {
-    Red,
-    Green,
-    Yellow,
-    Blue,
-    NA
-}
-
-// So the user can input a color
-impl FromStr for Color {
-    type Err = String;
-    
-    fn from_str(s: &str) -> std::result::Result< Self, Self::Err > {
-        let sl = s.to_lowercase();
-        match sl.as_str() {
-            "red" => Ok(Color::Red),
-            "green" => Ok(Color::Green),
-            "yellow" => Ok(Color::Yellow),
-            "blue" => Ok(Color::Blue),
-            _ => Err( format!("{} is not an UNO standard color", s) ),
-        }
-    }
-}
-
-// Special cards for the cards
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd)]
-enum SpecialCard {
-    PlusFour,
-    ColorChange,
-    PlusTwo,
-    Skip,
-    Reverse,
-    Base
-}
-// One full card
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-struct UNOCard {
-    color: Color,
-    special: SpecialCard,
-    number: i8,
-}
-
-impl UNOCard {
-    fn new(color: Color, special: SpecialCard, number: i8) -> Self {
-        UNOCard { color: color, special: special, number: number }
-    }
-}
-
-
-#[derive(Debug, Copy, Clone)]
-enum Difficulty {
-    Calm,
-    Aggressive,
-    Skilled
-}
-
-impl FromStr for Difficulty {
-    type Err = String;
-    
-    fn from_str(s: &str) -> std::result::Result< Self, Self::Err > {
-        let sl = s.to_lowercase();
-        match sl.as_str() {
-            "calm" => Ok(Difficulty::Calm),
-            "aggressive" => Ok(Difficulty::Aggressive),
-            "skilled" => Ok(Difficulty::Skilled),
-            _ => Err( format!("{} is not an avaliable difficulty", s) ),
+mod adaptive;
+mod binary;
+mod campaign;
+mod chat;
+mod cli;
+mod config;
+mod fuzz;
+mod graphics;
+mod hook_commands;
+mod ipc;
+mod practice;
+mod puzzle;
+mod ratings;
+mod replay;
+mod save;
+mod sim;
+mod spectate;
+mod stats;
+mod term;
+mod thinking;
+mod tournament;
+mod transcript;
+mod tui;
+
+use cli::Command;
+use unoler::cards::{UNOCard, decks_for, format_card_message, shuffled_decks};
+use unoler::events::{Animation, BatchFrontend, Event, Frontend, HandStrength, JsonFrontend, OnlyMove, Prompt, TextFrontend, labels};
+use unoler::game::{Difficulty, Forfeited, Scores, Settings, YesNo, game_start_event, input, play, rematch, round_table, setup};
+use unoler::invariants;
+use unoler::review::Review;
+use unoler::log::Level;
+use unoler::notation::Notation;
+use unoler::odds::WinOdds;
+use unoler::rng::Randler;
+use unoler::rules::RuleSet;
+use unoler::stack::Stack;
+use unoler::trace;
+
+// For checking shuffles and reproducing dealing bugs: deals like a real game would and prints everything instead of playing
+fn dump_deal(fe: &mut dyn Frontend, settings: &Settings) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let Some(table) = setup(fe, settings)? else { return Ok(()) };
+    let seed = table.seed.ok_or("Secure games can't be dumped, they have no seed")?;
+    
+    let list = |cards: &[UNOCard]| cards.iter().map(format_card_message).collect::<Vec<_>>().join(", ");
+    
+    // Same generator and the same first shuffle as setup, so this is the order the hands were dealt from
+    // A stacked deal wasn't shuffled, what's left of its deck is the draw pile at the end
+    if settings.stack.is_none() {
+        let shape = table.rules.deck();
+        let extra_cards = table.handicaps.iter().map(|h| h.extra_cards as usize).sum();
+        let decks = settings.decks.map_or(decks_for(table.hands.len(), table.rules.hand_size, extra_cards, shape), usize::from);
+        let deck = shuffled_decks(&mut Randler::new(seed), decks, shape);
+        println!("Shuffled deck for seed {} ({} cards, top first):", seed, deck.len());
+        for (i, card) in deck.iter().rev().enumerate() {
+            println!("{:>4}. {}", i + 1, format_card_message(card));
         }
     }
-}
-
-// Current game state, handling turns and reverses
-#[derive(Debug, Copy, Clone)]
-struct Game {
-    current_player: i8,
-    max_players: i8,
-    direction: i8,
-}
-
-impl Game {
-    fn new(c: i8, m: i8, d: i8) -> Self {
-        Self { current_player: c, max_players: m, direction: d }
-    }
-    
-    fn next_turn(&mut self) {
-        self.current_player = (self.current_player + self.direction).rem_euclid(self.max_players);
-    }
     
-    fn reverse(&mut self) {
-        self.direction *= -1;
-    }
-    
-    fn player_number(&self) -> i8 {
-        self.current_player + 1
+    println!("\nDeals:");
+    for (name, hand) in table.names.iter().zip(&table.hands) {
+        println!("  {}: {}", name, list(hand));
     }
+    println!("\nOpening card: {}", format_card_message(&table.last_played));
+    let mut draw_pile = table.deck.clone();
+    draw_pile.reverse();
+    println!("Draw pile ({} cards, top first): {}", draw_pile.len(), list(&draw_pile));
+    Ok(())
 }
 
-// Gets the name of a color from the enum
-fn get_color(color: &Color) -> String {
-    match color {
-        Color::Red => return String::from("Red"),
-        Color::Blue => return String::from("Blue"),
-        Color::Yellow => return String::from("Yellow"),
-        Color::Green => return String::from("Green"),
-        Color::NA => return String::from("None"),
-    }
-}
+fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
 
-// Formats the card message to be displayed to the user
-fn format_card_message(card: &UNOCard) -> String {
-    let color_str = get_color(&card.color);
-    match card.special {
-        SpecialCard::PlusFour => format!("Wild Draw 4 ({})", color_str),
-        SpecialCard::ColorChange => format!("Wild Card ({})", color_str),
-        SpecialCard::PlusTwo => format!("{} Draw 2", color_str),
-        SpecialCard::Skip => format!("{} Skip", color_str),
-        SpecialCard::Reverse => format!("{} Reverse", color_str),
-        SpecialCard::Base => format!("{} {}", color_str, card.number),
+    let options = match cli::parse_args(std::env::args().skip(1), |name| std::env::var(name).ok()) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        },
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
     }
-}
-
-// Builds a full standard deck of UNO cards
-fn build_deck() -> Vec<UNOCard> {
-    let mut deck = Vec::with_capacity(108);
-    let colors = [Color::Red, Color::Green, Color::Yellow, Color::Blue];
-    let specials = [SpecialCard::Reverse, SpecialCard::Skip, SpecialCard::PlusTwo];
-
-    for &color in &colors {
-        for n in 0..=9 {
-            let count = if n == 0 { 1 } else { 2 };
-            for _ in 0..count {
-                deck.push(UNOCard::new(color, SpecialCard::Base, n));
-            }
-        }
-        for &spec in &specials {
-            for _ in 0..2 {
-                deck.push(UNOCard::new(color, spec, -1));
-            }
+    unoler::log::set_level(Level::from_verbosity(options.verbosity));
+    if let Some(path) = &options.log_file {
+        match std::fs::File::create(path) {
+            Ok(file) => unoler::log::log_to(file),
+            Err(e) => {
+                eprintln!("Could not open the log file {}: {}", path.display(), e);
+                std::process::exit(2);
+            },
         }
     }
-    for _ in 0..4 {
-        deck.push(UNOCard::new(Color::NA, SpecialCard::ColorChange, -1));
-        deck.push(UNOCard::new(Color::NA, SpecialCard::PlusFour, -1));
+    if options.trace {
+        trace::subscribe(Box::new(trace::Pretty::stderr()));
     }
-    deck
-}
-
-// Shuffles the deck
-fn shuffle(deck: &mut Vec<UNOCard>, rand: &mut Randler) {
-    let n = deck.len();
-    for i in (1..n).rev() {
-        if let Some(j) = rand.rand_range(0, i as u64) {
-            deck.swap(i, j as usize);
+    if let Some(path) = &options.trace_file {
+        match trace::JsonLines::create(path) {
+            Ok(file) => trace::subscribe(Box::new(file)),
+            Err(e) => {
+                eprintln!("Could not open the trace file {}: {}", path.display(), e);
+                std::process::exit(2);
+            },
         }
     }
-}
-
-// Checks if a move is legal
-fn allowed_move( card_chosen: UNOCard, last_card: UNOCard ) -> bool {
-    // Not doing Color == NA yet to ensure color choice shenanigans don't occur
-    if card_chosen.special == SpecialCard::ColorChange || card_chosen.special == SpecialCard::PlusFour {
-        return true; // I'm going to ignore the "you need no playable cards to play draw 4" because screw that rule
-    } 
-    
-    // Special cards on the same special card works
-    if card_chosen.special == last_card.special && card_chosen.special != SpecialCard::Base {
-        return true;
+    if let Some(threads) = options.threads {
+        sim::set_threads(threads);
     }
-    
-    // Default color-or-number match
-    if (card_chosen.special == SpecialCard::Base && card_chosen.number == last_card.number) || card_chosen.color == last_card.color {
-        return true;
+    if options.check_invariants {
+        invariants::enable();
     }
-    
-    false // Can't play it!
-    
-}
-
-// So that if the first drawn card is wild, rnadomly choose a color, but I have to use the random number generator
-// So it maps the number to a color
-fn color_from_number(num: u8) -> Result<Color> {
-    match num {
-        0 => Ok(Color::Red),
-        1 => Ok(Color::Green),
-        2 => Ok(Color::Yellow),
-        3 => Ok(Color::Blue),
-        4 => Ok(Color::NA),
-        _ => Err( Error::new( ErrorKind::Other, "Could not find corresponding color from the given number" ) ),
+    if options.debug {
+        unoler::debug::enable();
     }
-}
-
-// Builds a new deck and shuffles it
-fn refresh_deck(deck:&mut  Vec<UNOCard>, random:&mut Randler) {
-    *deck = build_deck();
-    shuffle(deck, random);
-}
-
-// Checks if there are any plus fours or plus twos in the hand
-fn check_countercards(hand: &Vec<UNOCard>) -> bool {
-    hand.iter().any(|u: &UNOCard| u.special == SpecialCard::PlusFour || u.special == SpecialCard::PlusTwo)
-}
-
-// Ensures the deck is full
-// If there is a discard pile, a new deck is made from the discard pile and shuffled
-// If there is no discard pile, an entirely new deck is made and shuffled
-fn ensure_deck_full(deck: &mut Vec<UNOCard>, discard: &mut Vec<UNOCard>, rand: &mut Randler) {
-    if deck.is_empty() {
-        if discard.len() > 1 {
-            println!("Deck empty. Using discard pile...");
-            
-            discard.iter_mut().for_each(|c| {
-                if c.special == SpecialCard::ColorChange || c.special == SpecialCard::PlusFour {
-                    c.color = Color::NA;
-                }
-            });
-            
-            let top = discard.pop().unwrap();
-            deck.append(discard);
-            shuffle(deck, rand);
-            discard.push(top);
-        } else {
-            println!("Deck empty. Using new deck...");
-            refresh_deck(deck, rand);
-        }
+    if let Some(iterations) = options.fuzz_parse {
+        let seed = match options.seed {
+            Some(seed) => seed,
+            None => Randler::get_base_random_udev()?,
+        };
+        std::process::exit(if fuzz::report(iterations, seed) { 0 } else { 1 });
     }
-}
-
-// Clears the terminal, but you might just want to enable ANSI escape codes
-// If you are on windows, you should probably run the following command in your terminal:
-// reg add HKCU\Console /v VirtualTerminalLevel /t REG_DWORD /d 1
-fn clear_terminal() {
-    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
-    use std::io::Write;
-    std::io::stdout().flush().unwrap();
-}
 
-// This is for the AI players
-fn get_move_ai(hand: &Vec<UNOCard>, last_played: UNOCard, difficulty: Difficulty, uno: bool) -> Option<usize> {
-    
-    // To adhere to the +2 stacking force
-    if last_played.special == SpecialCard::PlusTwo && check_countercards(hand) {
-        if let Some(idx) = hand.iter().position(|c| {
-            c.special == SpecialCard::PlusTwo ||
-            c.special == SpecialCard::PlusFour
-        }) {
-            return Some(idx);
-        }
-    }
-    
-    match difficulty {
-        // Saves special cards for last
-        Difficulty::Calm => {
-            if let Some(idx) = hand.iter().position(
-                |c| { c.special == SpecialCard::Base && allowed_move(*c, last_played) }
-            ) {
-                return Some(idx);
+    // Save management doesn't need a game at all
+    match &options.command {
+        Command::SavesList => {
+            let slots = save::list_slots()?;
+            if slots.is_empty() {
+                println!("No saved games in {}", save::data_dir().join("saves").display());
             }
-            
-            if let Some(idx) = hand.iter().position(|c| {
-                c.special != SpecialCard::Base &&
-                c.special != SpecialCard::PlusFour &&
-                c.special != SpecialCard::ColorChange &&
-                allowed_move(*c, last_played)
-            }) {
-                return Some(idx);
-            }
-            
-            if let Some(idx) = hand.iter().position(|c| {
-                c.special == SpecialCard::PlusFour || c.special == SpecialCard::ColorChange
-            }) {
-                return Some(idx);
+            for (slot, summary) in slots {
+                println!("{}: {}", slot, summary);
             }
+            return Ok(());
         },
-        
-        // Uses disruption cards immediately
-        Difficulty::Aggressive => {
-            if let Some(idx) = hand.iter().position(|c| {
-                c.special == SpecialCard::PlusFour
-            }) {
-                return Some(idx);
+        Command::SavesDelete(slot) => {
+            if let Err(e) = save::delete_slot(slot) {
+                eprintln!("Could not delete save {}: {}", slot, e);
+                std::process::exit(1);
             }
-            
-            if let Some(idx) = hand.iter().position(|c| {
-                (c.special == SpecialCard::PlusTwo ||
-                c.special == SpecialCard::Skip ||
-                c.special == SpecialCard::Reverse) &&
-                allowed_move(*c, last_played)
-            }) {
-                return Some(idx);
+            println!("Deleted save {}", slot);
+            return Ok(());
+        },
+        Command::ReplayVerify(path) => {
+            match replay::verify(path) {
+                Ok(events) => println!("{}: all {} events and the final table match", path.display(), events),
+                Err(e) => {
+                    eprintln!("Could not verify the replay: {}", e);
+                    std::process::exit(2);
+                },
             }
-            
-            if let Some(idx) = hand.iter().position(|c| {
-                (c.special == SpecialCard::ColorChange || c.special == SpecialCard::Base) &&
-                allowed_move(*c, last_played)
-            }) {
-                return Some(idx);
+            return Ok(());
+        },
+        Command::ReplayReview(path) => {
+            match replay::review(path) {
+                Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+                Err(e) => {
+                    eprintln!("Could not review the replay: {}", e);
+                    std::process::exit(2);
+                },
             }
+            return Ok(());
         },
-        
-        // "I lost to this AI twice"
-        //                  - Alexandros3015, February 24th, 2026
-        // Ts one is impossible without a god hand
-        Difficulty::Skilled => {
-            if uno {
-                if let Some(idx) = hand.iter().position(|c| {
-                    c.special != SpecialCard::Base &&
-                    allowed_move(*c, last_played)
-                }) {
-                    return Some(idx);
-                }
+        Command::ReplayOdds(path) => {
+            match replay::odds(path) {
+                Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+                Err(e) => {
+                    eprintln!("Could not play the replay back: {}", e);
+                    std::process::exit(2);
+                },
             }
-        
-        
-            let (reds, blues, yellows, greens) = count_color(hand);
-            
-            if reds > blues && reds > yellows && reds > greens {
-                if let Some(idx) = hand.iter().position(|c| {
-                    c.color == Color::Red &&
-                    c.special == SpecialCard::Base &&
-                    allowed_move(*c, last_played)
-                }) {
-                    return Some(idx);
-                }
-                
-                if let Some(idx) = hand.iter().position(|c| {
-                    c.color == Color::Red &&
-                    allowed_move(*c, last_played)
-                }) {
-                    return Some(idx);
-                }
+            return Ok(());
+        },
+        Command::NotationExport(path) => {
+            match replay::export(path) {
+                Ok(notation) => print!("{}", notation),
+                Err(e) => {
+                    eprintln!("Could not export the replay: {}", e);
+                    std::process::exit(2);
+                },
             }
-            else if blues > yellows && blues > greens {
-                if let Some(idx) = hand.iter().position(|c| {
-                    c.color == Color::Blue &&
-                    c.special == SpecialCard::Base &&
-                    allowed_move(*c, last_played)
-                }) {
-                    return Some(idx);
-                }
-                
-                if let Some(idx) = hand.iter().position(|c| {
-                    c.color == Color::Blue &&
-                    allowed_move(*c, last_played)
-                }) {
-                    return Some(idx);
-                }
+            return Ok(());
+        },
+        Command::NotationPlay(path) => {
+            let read = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| text.parse::<Notation>())
+                .and_then(|notation| notation.replay().map(|session| (notation, session)));
+            match read {
+                Ok((notation, session)) => {
+                    let result = session.winner().map_or(String::from("nobody won"), |w| format!("{} won", session.current().names[w - 1]));
+                    println!("{}: all {} turns play the same, {}", path.display(), notation.turns.len(), result);
+                },
+                Err(e) => {
+                    eprintln!("{}: {}", path.display(), e);
+                    std::process::exit(1);
+                },
             }
-            else if yellows > greens {
-                if let Some(idx) = hand.iter().position(|c| {
-                    c.color == Color::Yellow &&
-                    c.special == SpecialCard::Base &&
-                    allowed_move(*c, last_played)
-                }) {
-                    return Some(idx);
-                }
-                
-                if let Some(idx) = hand.iter().position(|c| {
-                    c.color == Color::Yellow &&
-                    allowed_move(*c, last_played)
-                }) {
-                    return Some(idx);
-                }
+            return Ok(());
+        },
+        Command::CampaignStatus => {
+            match campaign::load(&campaign::progress_path()) {
+                Ok(progress) => campaign::status(progress),
+                Err(e) => {
+                    eprintln!("Could not read the campaign: {}", e);
+                    std::process::exit(1);
+                },
             }
-            
-            else if greens > 0 {
-                if let Some(idx) = hand.iter().position(|c| {
-                    c.color == Color::Green &&
-                    c.special == SpecialCard::Base &&
-                    allowed_move(*c, last_played)
-                }) {
-                    return Some(idx);
-                }
-                
-                if let Some(idx) = hand.iter().position(|c| {
-                    c.color == Color::Green &&
-                    allowed_move(*c, last_played)
-                }) {
-                    return Some(idx);
-                }
+            return Ok(());
+        },
+        Command::CampaignReset => {
+            if let Err(e) = campaign::reset(&campaign::progress_path()) {
+                eprintln!("Could not reset the campaign: {}", e);
+                std::process::exit(1);
             }
-            
-            if let Some(idx) = hand.iter().position(|c| {
-                c.special == SpecialCard::Base &&
-                allowed_move(*c, last_played)
-            }) {
-                return Some(idx);
+            println!("The campaign starts over from the first table");
+            return Ok(());
+        },
+        Command::Stats => {
+            match stats::load(&stats::stats_path()) {
+                Ok(records) => {
+                    let lines = stats::summary(&records).into_iter().chain(ratings::load(&ratings::path()).lines());
+                    for line in lines.chain(thinking::load(&thinking::path()).lines()) {
+                        println!("{}", line);
+                    }
+                },
+                Err(e) => {
+                    eprintln!("Could not read the stats: {}", e);
+                    std::process::exit(1);
+                },
             }
-            
-            if let Some(idx) = hand.iter().position(|c| {
-                c.special == SpecialCard::ColorChange ||
-                c.special == SpecialCard::PlusFour
-            }) {
-                return Some(idx);
+            return Ok(());
+        },
+        Command::StatsClear => {
+            let cleared = stats::clear(&stats::stats_path()).and_then(|_| ratings::clear(&ratings::path()));
+            if let Err(e) = cleared.and_then(|_| thinking::clear(&thinking::path())) {
+                eprintln!("Could not delete the stats: {}", e);
+                std::process::exit(1);
             }
-            
-            
-            
+            println!("Deleted the recorded games, ratings and thinking times");
+            return Ok(());
         },
-        
+        Command::PuzzleList => {
+            puzzle::list();
+            return Ok(());
+        },
+        Command::PracticeList => {
+            practice::list();
+            return Ok(());
+        },
+        Command::Serve => {
+            let idle = options.idle_timeout.map(std::time::Duration::from_secs);
+            let games_dir = options.games_dir.clone().unwrap_or_else(|| save::data_dir().join("server"));
+            let threads = options.threads.unwrap_or_else(unoler::pool::default_threads);
+            return unoler_server::run(options.port.unwrap_or(unoler_server::DEFAULT_PORT), options.seed, idle, &games_dir, threads);
+        },
+        Command::Play | Command::Quick | Command::SavesResume(_) | Command::Simulate | Command::BenchAi | Command::Exhibition | Command::Spectate | Command::Tournament | Command::Puzzle(_) | Command::Practice(_) | Command::Campaign => {},
     }
-    
-    // Draw
-    None
-}
-
-fn count_color(hand: &Vec<UNOCard>) -> (usize, usize, usize, usize) {
-    // Counts all colors
-    let reds: usize = hand
-        .iter()
-        .filter(|&card| card.color == Color::Red)
-        .count();
 
-    let blues: usize = hand
-        .iter()
-        .filter(|&card| card.color == Color::Blue)
-        .count();
-        
-    let yellows: usize = hand
-        .iter()
-        .filter(|&card| card.color == Color::Yellow)
-        .count();
-        
-    let greens: usize = hand
-        .iter()
-        .filter(|&card| card.color == Color::Green)
-        .count();
-        
-    (reds, blues, yellows, greens)
-}
+    // The config file fills in what would be asked at setup, flags override it
+    let config = match config::load(options.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Could not load the config file: {}", e);
+            std::process::exit(2);
+        },
+    };
+    // A --rules preset or a --profile replaces the config file's rules entirely, single overrides stack on top
+    let rules = if let Some(profile) = &options.profile {
+        config::load_profile(profile).and_then(|mut rules| {
+            for (key, value) in &options.rule_overrides {
+                rules.set(key, value)?;
+            }
+            Ok(rules)
+        })
+    } else if let Some(preset) = &options.rules {
+        RuleSet::build(Some(preset), &options.rule_overrides)
+    } else {
+        RuleSet::build(config.rules_preset.as_deref(), &[config.rule_overrides.clone(), options.rule_overrides.clone()].concat())
+    };
+    let rules = match rules {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("Could not set up the rules: {}", e);
+            std::process::exit(2);
+        },
+    };
+    if let Some(name) = &options.save_profile {
+        match config::save_profile(name, rules) {
+            Ok(path) => println!("Saved the rules as {} in {}", name, path.display()),
+            Err(e) => {
+                eprintln!("Could not save the rule profile: {}", e);
+                std::process::exit(1);
+            },
+        }
+        return Ok(());
+    }
+    // A difficulty on the command line beats an adaptive one in the config file and the other way around
+    let mut adaptive = (options.adaptive || (config.adaptive && options.difficulty.is_none())).then(|| adaptive::load(&adaptive::path()));
+    let adaptive_mix = adaptive.as_ref().map(|a| a.mix()).filter(|_| options.ai_mix.is_empty());
+    // quick is always you against three AIs, and answers the difficulty question itself if the config file doesn't
+    let quick = options.command == Command::Quick;
+    let stack = match &options.stack {
+        Some(path) => match std::fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|text| text.parse::<Stack>()) {
+            Ok(stack) => Some(stack),
+            Err(e) => {
+                eprintln!("Could not read the stacked deal in {}: {}", path.display(), e);
+                std::process::exit(2);
+            },
+        },
+        None => None,
+    };
+    let settings = Settings {
+        players: options.players.or(if quick { Some(1) } else { config.players }),
+        ai_players: options.ai_players.or(if quick { Some(3) } else { config.ai_players }),
+        difficulty: options.difficulty
+            .or(adaptive_mix.as_ref().map(|mix| mix[0]))
+            .or(config.difficulty)
+            .or(if quick { Some(Difficulty::Calm) } else { None }),
+        ai_mix: adaptive_mix.clone().unwrap_or(options.ai_mix.clone()),
+        names: if options.names.is_empty() { config.names } else { options.names.clone() },
+        ai_names: Vec::new(),
+        rules,
+        seed: options.seed,
+        secure: options.secure_rng,
+        autoplay: options.batch && options.script.is_none(),
+        decks: options.decks.or(config.decks),
+        first: 0,
+        handicaps: options.handicaps.clone(),
+        stack,
+    };
+
+    if options.command == Command::BenchAi {
+        let seed = match settings.seed {
+            Some(seed) => seed,
+            None => Randler::get_base_random_udev()?,
+        };
+        // Self-play is what the difficulties' ratings come from, kept like the rest of the stats
+        let mut ratings = config.stats.then(|| ratings::load(&ratings::path()));
+        sim::bench(options.games.unwrap_or(2000), settings.rules, seed, sim::DEFAULT_TURN_LIMIT, ratings.as_mut())?;
+        if let Some(ratings) = ratings
+            && let Err(e) = ratings::store(&ratings::path(), &ratings)
+        {
+            eprintln!("Could not save the ratings: {}", e);
+        }
+        return Ok(());
+    }
 
-// Gets the most common color on the deck
-fn get_common_color(hand: &Vec<UNOCard>, rand: &mut Randler) -> Color {
+    if options.command == Command::Exhibition {
+        return sim::exhibit(settings.rules, sim::DEFAULT_TURN_LIMIT);
+    }
 
-    let (reds, blues, yellows, greens) = count_color(hand);
+    if options.command == Command::Simulate {
+        let sim = sim::Simulation {
+            games: options.games.unwrap_or(1000),
+            seats: settings.players.unwrap_or(4) as usize + settings.ai_players.unwrap_or(0) as usize,
+            mix: if settings.ai_mix.is_empty() { vec![settings.difficulty.unwrap_or(Difficulty::Calm)] } else { settings.ai_mix.clone() },
+            rules: settings.rules,
+            seed: match settings.seed {
+                Some(seed) => seed,
+                None => Randler::get_base_random_udev()?,
+            },
+            turn_limit: sim::DEFAULT_TURN_LIMIT,
+        };
+        return sim::report(&sim);
+    }
+
+    if options.command == Command::Tournament && options.bracket.is_none() {
+        // Without --ai-mix every difficulty enters
+        let mix = if options.ai_mix.is_empty() { sim::DIFFICULTIES.to_vec() } else { options.ai_mix.clone() };
+        let entrants = match tournament::entrants(&mix) {
+            Ok(entrants) => entrants,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            },
+        };
+        let tournament = tournament::Tournament {
+            games: options.games.unwrap_or(1000),
+            rules: settings.rules,
+            seed: match settings.seed {
+                Some(seed) => seed,
+                None => Randler::get_base_random_udev()?,
+            },
+            turn_limit: sim::DEFAULT_TURN_LIMIT,
+        };
+        return tournament::report(&entrants, &tournament);
+    }
+
+    if options.command == Command::Spectate {
+        let seats = settings.players.unwrap_or(4) as usize + settings.ai_players.unwrap_or(0) as usize;
+        let mix = if settings.ai_mix.is_empty() { vec![settings.difficulty.unwrap_or(Difficulty::Calm)] } else { settings.ai_mix.clone() };
+        // Named seats are only kept for the ones setup thinks are people, so they all are and the AI plays them
+        let settings = Settings {
+            players: Some(seats as u8),
+            ai_players: Some(0),
+            difficulty: Some(mix[0]),
+            names: spectate::seat_names(&mix, seats),
+            ai_mix: mix,
+            autoplay: true,
+            ..settings
+        };
+        let speed = options.speed.unwrap_or_default();
+        let text = TextFrontend::new(!options.no_color && config.color != Some(false), false)
+            .palette(options.palette.or(config.palette).unwrap_or_default())
+            .looks(config.looks.clone())
+            .aliases(config.aliases.clone());
+        let commentary: Box<dyn Frontend> = Box::new(spectate::Commentary::new(text, speed));
+        let fe: Box<dyn Frontend> = if options.quiet_ai || config.table_talk == Some(false) { commentary } else { Box::new(chat::TableTalk::new(commentary)) };
+        let mut fe: Box<dyn Frontend> = if options.win_odds { Box::new(WinOdds::new(fe)) } else { fe };
+        return spectate::run(&settings, fe.as_mut(), speed, sim::DEFAULT_TURN_LIMIT);
+    }
+
+    // A broken puzzle should say so before anything waits for a player
+    let puzzle = match &options.command {
+        Command::Puzzle(name) => match puzzle::find(name) {
+            Ok(puzzle) => Some(puzzle),
+            Err(e) => {
+                eprintln!("Could not load the puzzle: {}", e);
+                std::process::exit(2);
+            },
+        },
+        _ => None,
+    };
+    let practice = match &options.command {
+        Command::Practice(name) => match practice::find(name) {
+            Ok(scenario) => Some(scenario),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            },
+        },
+        _ => None,
+    };
+
+    // --json swaps the terminal frontend for one JSON object per line, for wrappers and test harnesses
+    let inner: Box<dyn Frontend> = if options.batch {
+        let script = match &options.script {
+            Some(path) => match std::fs::read_to_string(path) {
+                Ok(text) => text.lines().enumerate()
+                    .map(|(i, line)| (i + 1, line.trim().to_string()))
+                    .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+                    .collect(),
+                Err(e) => {
+                    eprintln!("Could not read the script {}: {}", path.display(), e);
+                    std::process::exit(2);
+                },
+            },
+            None => Vec::new(),
+        };
+        let events: Option<Box<dyn Frontend>> = if options.json { Some(Box::new(JsonFrontend::stdio())) } else { None };
+        Box::new(BatchFrontend::new(events, script))
+    } else if let Some(path) = &options.socket {
+        match ipc::accept(path) {
+            Ok(fe) => Box::new(fe),
+            Err(e) => {
+                eprintln!("Could not open the socket: {}", e);
+                std::process::exit(2);
+            },
+        }
+    } else if options.json {
+        Box::new(JsonFrontend::stdio())
+    } else {
+        let text = TextFrontend::new(!options.no_color && config.color != Some(false), options.fast_forward || config.fast_forward == Some(true));
+        let text = text.palette(options.palette.or(config.palette).unwrap_or_default())
+            .animation(options.animation.or(config.animation).unwrap_or(Animation::Normal))
+            .looks(config.looks.clone())
+            .aliases(config.aliases.clone());
+        // Line mode is what's left when the terminal can't do keys
+        let screen: Box<dyn Frontend> = if (options.tui || config.tui == Some(true)) && term::available() {
+            Box::new(tui::Tui::new(text).turn_timer(options.turn_timer.or(config.turn_timer).unwrap_or(0)))
+        } else {
+            Box::new(text)
+        };
+        // Both settings were checked when they were read, and pictures only go to a terminal
+        let setting = options.graphics.as_ref().or(config.graphics.as_ref());
+        let graphics = setting.and_then(|s| graphics::Graphics::from_setting(s).ok().flatten());
+        let screen: Box<dyn Frontend> = match graphics.filter(|_| std::io::IsTerminal::is_terminal(&std::io::stdout())) {
+            Some(graphics) => Box::new(graphics::Pictures::new(screen, graphics)),
+            None => screen,
+        };
+        if options.quiet_ai || config.table_talk == Some(false) { screen } else { Box::new(chat::TableTalk::new(screen)) }
+    };
+    // Under anything that answers for the player, so only answers somebody gave are timed
+    let inner: Box<dyn Frontend> = if options.batch { inner } else { Box::new(thinking::Thinking::new(inner, config.stats.then(thinking::path))) };
+    // Scripts are recordings of every answer, so batch runs never answer for the player
+    let inner: Box<dyn Frontend> = if !options.batch && (options.auto_move || config.auto_move == Some(true)) {
+        Box::new(OnlyMove::new(inner))
+    } else {
+        inner
+    };
+    let inner: Box<dyn Frontend> = if options.hand_strength || config.hand_strength == Some(true) {
+        Box::new(HandStrength::new(inner))
+    } else {
+        inner
+    };
+    let inner: Box<dyn Frontend> = if options.review { Box::new(Review::new(inner)) } else { inner };
+    let inner: Box<dyn Frontend> = if options.win_odds { Box::new(WinOdds::new(inner)) } else { inner };
+    let inner = match hook_commands::attach(inner, options.on_event.as_deref(), options.on_game_end.as_deref()) {
+        Ok(inner) => inner,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        },
+    };
+    let inner: Box<dyn Frontend> = match &options.transcript {
+        Some(path) => match transcript::Transcript::new(inner, path) {
+            Ok(transcript) => Box::new(transcript),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            },
+        },
+        None => inner,
+    };
+    let inner: Box<dyn Frontend> = match &options.repl {
+        Some(path) => Box::new(unoler::repl::Repl::new(inner, unoler::repl::listen(path), Box::new(std::io::stderr()))),
+        None => inner,
+    };
+    // Every event is logged so a crash can dump it along with the game
+    let mut recording = replay::Recording::new(Box::new(save::Recorder::new(inner)), options.record.is_some());
+    let fe: &mut dyn Frontend = &mut recording;
     
-    // Returns the most common color
-    if reds > blues && reds > yellows && reds > greens {
-        return Color::Red;
-    }
-    else if blues > yellows && blues >  greens {
-        return Color::Blue;
-    } else if yellows > greens {
-        return Color::Yellow;
-    } else if greens > 0 {
-        return Color::Green;
+    // Batch runs shouldn't leave a recovery file for the next interactive game, and puzzles, drills and campaign games
+    // aren't games to resume on their own
+    if !options.batch && puzzle.is_none() && practice.is_none() && options.command != Command::Campaign && options.command != Command::Tournament {
+        save::install_panic_hook();
     }
     
-    // If there is no common color, return a random color
-    color_from_number( rand.rand_range(0, 3).unwrap_or(0) as u8 ).unwrap_or(Color::Red)
-}
+    if options.dump_deal {
+        return dump_deal(fe, &settings);
+    }
 
-fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    if let Some(puzzle) = &puzzle {
+        puzzle::play_puzzle(puzzle, fe)?;
+        let _: String = input(fe, Prompt::Exit, "Error")?;
+        return Ok(());
+    }
 
-    
-    let players: u8 = input("How many players?", "Please enter a proper number that is not too big.");
-    let ai_players: u8 = input("How many AI players?", "Please enter a proper number that is not too big.");
-    let total_players: u8 = players + ai_players;
-    
-    
-    let difficulty: Difficulty;
-    if ai_players > 0 {
-        difficulty = input("What AI difficulty? (calm, aggressive, or skilled)", "Please enter a proper difficulty");
-    } else {difficulty = Difficulty::Calm}
-    
-    let mut rand = Randler::default();
-    
-    // Warnings
-    if total_players == 0 {
-        println!("ZERO PLAYERS?? Without a doubt. Right away sir!");
-        println!("Player 0 wins? Is this the outcome you desire?");
+    if let Some(scenario) = practice {
+        let seed = match settings.seed {
+            Some(seed) => seed,
+            None => Randler::get_base_random_udev()?,
+        };
+        let drills = options.games.unwrap_or(practice::DEFAULT_DRILLS);
+        practice::run(scenario, drills, settings.rules, settings.difficulty.unwrap_or(Difficulty::Skilled), seed, fe)?;
+        let _: String = input(fe, Prompt::Exit, "Error")?;
         return Ok(());
     }
-    if total_players == 1 {
-        println!("Sure bro, one player");
-    } else if total_players == 2 {
-        println!("WARNING: Reverse cards now count as skip cards!");
-    } else if total_players > 10 {
-        println!("WARNING: Playing with this many players may cause unexpected behavior!");
+
+    if let Some(format) = options.bracket.filter(|_| options.command == Command::Tournament) {
+        let mut bracket = match tournament::Bracket::new(format, tournament::field(&settings.names, &options.ai_mix)) {
+            Ok(bracket) => bracket,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            },
+        };
+        let seed = match settings.seed {
+            Some(seed) => seed,
+            None => Randler::get_base_random_udev()?,
+        };
+        tournament::play_bracket(&mut bracket, fe, &settings, seed)?;
+        let _: String = input(fe, Prompt::Exit, "Error")?;
+        return Ok(());
     }
-    
-    let mut game: Vec<Vec<UNOCard>> = Vec::new(); // All decks
-    
-    let mut deck = build_deck(); // The deck
 
-    shuffle(&mut deck, &mut rand);
-    
-    // Give seven cards to each player
-    for _ in 0..total_players {
-        let mut temp: Vec<UNOCard> = Vec::new();
-        for _ in 0..7 {
-            if deck.is_empty() {
-                println!("Cards expended. Using new deck.");
-                refresh_deck(&mut deck, &mut rand);
-                        
-            }
-        
-            temp.push( deck.pop().ok_or("Error, out of cards")? );
-        }
-        game.push(temp);
+    if options.command == Command::Campaign {
+        campaign::play_next(&campaign::progress_path(), fe, settings.names.clone(), settings.seed, settings.secure)?;
+        let _: String = input(fe, Prompt::Exit, "Error")?;
+        return Ok(());
     }
     
-    // Game time:
-    
-    // The initial card
-    let mut last_played: UNOCard = deck.pop().ok_or("Error, out of cards")?; // Promise this'll be the last unsafe thing done with popping
+    let resumed = if let Command::SavesResume(slot) = &options.command {
+        match save::load_slot(slot) {
+            Ok(table) => Some((table, format!("save {}", slot))),
+            Err(e) => {
+                eprintln!("Could not resume save {}: {}", slot, e);
+                std::process::exit(1);
+            },
+        }
+    } else if options.batch || quick {
+        // Batch runs never pick up somebody's crashed game, and quick games don't ask about it
+        None
+    } else {
+        match save::load_recovery() {
+            Some(table) => {
+                let YesNo(resume) = input(fe, Prompt::Resume, "Please enter y or n")?;
+                save::clear_recovery();
+                if resume { Some((table, String::from("the crashed game"))) } else { None }
+            },
+            None => None,
+        }
+    };
     
-    if last_played.color == Color::NA {
-        last_played.color = color_from_number( rand.rand_range(0, 3).ok_or("Error with randomization")? as u8 )?;
+    // A replay starts from the deal, so it can't pick up halfway
+    if resumed.is_some() && options.record.is_some() {
+        fe.event(&Event::Notice(String::from("Resumed games can't be recorded, --record is ignored")));
     }
-    
-    println!("\n------------\n");
-    
-    let mut game_state = Game::new(0, total_players as i8,1); // The game state
-    let mut add_queue: u32 = 0; // The queue for adding cards to the next player
-    let mut getting_added_to: bool; // Whether or not the player is getting cards added to them
-    let mut skipped: bool = false; // Whether or not the player has been skipped
-    let mut discard: Vec<UNOCard> = Vec::new(); // The discard pile
-    let mut uno_detection_panic: bool = false;
-    
-    loop {
-        getting_added_to = true;
-        
-        let current_idx = game_state.player_number() - 1;
-
-        let player_hand = &mut game[current_idx as usize]; // The player's hand
-        
-        let is_ai: bool = current_idx >= players as i8;
+    let mut resumed_game = resumed.is_some();
+    let record = options.record.as_ref().filter(|_| !resumed_game);
 
-        player_hand.sort();
-        
-        println!("\nPlayer #{}'s turn!", game_state.player_number());
-        println!("Last card played: {}\n", format_card_message(&last_played));
-        
-        if is_ai { println!("AI player!"); }
-        
-        if !is_ai {
-        
-            for (index, item) in player_hand.iter().enumerate() {
-                println!("{}. {}", index + 1,format_card_message(item));
+    let mut table = match resumed {
+        Some((table, from)) => {
+            let current = &table.names[table.game_state.current_player];
+            fe.event(&Event::Notice(format!("Resuming {} at {}'s turn", from, current)));
+            fe.event(&game_start_event(&table));
+            Some(table)
+        },
+        None => {
+            save::reset_log();
+            if let Some(adaptive) = adaptive.as_ref().filter(|_| adaptive_mix.is_some()) {
+                fe.event(&Event::Notice(adaptive.describe()));
+            }
+            // Settings that can't work are the player's to fix, not a crash
+            match setup(fe, &settings) {
+                Ok(table) => table,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(2);
+                },
             }
-            println!("Type \"d\" or \"draw\" to draw a card");
-            println!("Type \"s\" or \"see\" to see the last played card and your hand again");
+        },
+    };
+    
+    // Rematches keep adding up the points, each game on its own still goes into the stats
+    let mut scores = Scores::default();
+    while let Some(mut t) = table.take() {
+        // Resumed games started somewhere else and batch runs are experiments, neither say much about how people play
+        let keep_stats = config.stats && !options.batch && !resumed_game;
+        let turns_before = recording.turns();
+        let fe: &mut dyn Frontend = &mut recording;
+        // Nobody wins a forfeited game, and like an unfinished one it stays out of the stats and ratings
+        let winner = match play(&mut t, fe, None) {
+            Err(e) if e.is::<Forfeited>() => None,
+            result => result?,
+        };
+        if let Some(winner) = winner.filter(|_| keep_stats)
+            && let Err(e) = stats::append(&stats::stats_path(), &stats::GameRecord::new(&t, winner, recording.turns() - turns_before))
+        {
+            eprintln!("Could not save the stats: {}", e);
         }
-        
-        let countercards = check_countercards(player_hand);
-        let mut answer: String;
-        let card_selected: Option<UNOCard>;
-        loop {
-            // If the player cannot counter the current plus two and the adding queue is not empty, then add the cards to the player
-            if !countercards && add_queue > 0 {
-                card_selected = None;
-                getting_added_to = false;
-                for _ in 0..add_queue {
-                    
-                    ensure_deck_full(&mut deck, &mut discard, &mut rand);
-                    
-                    let drawed: UNOCard = deck.pop().ok_or("Error, out of cards")?;
-                    player_hand.push(drawed);
-                    
-                    if is_ai {
-                        println!("Force drawing");
-                    }
-                    else {
-                        println!("Force drawing: {}", format_card_message(&drawed));
-                    }
-                }
-                
-                add_queue = 0;
-                skipped = false;
-                break;
-            // If the player has been skipped, then skip the card
-            } else if skipped {
-                println!("You have been skipped!");
-                skipped = false;
-                card_selected = None;
-                break;
+        if let Some(winner) = winner.filter(|_| keep_stats) {
+            let mut ratings = ratings::load(&ratings::path());
+            ratings.record(&ratings::seats(&t), winner);
+            if let Err(e) = ratings::store(&ratings::path(), &ratings) {
+                eprintln!("Could not save the ratings: {}", e);
             }
-            
-            player_hand.sort();
-            
-            if is_ai {
-                let ai_move: Option<usize> = get_move_ai(player_hand, last_played, difficulty, uno_detection_panic);
-                
-                if let Some( play_move ) = ai_move {
-                    card_selected = Some(player_hand[play_move]);
-                    discard.push( card_selected.unwrap() );
-                    player_hand.remove(play_move);
-                    
-                    println!("AI card selected: {}", format_card_message(&card_selected.unwrap()));
-                    break;
-                }
-                else {
-                    ensure_deck_full(&mut deck, &mut discard, &mut rand);
-                    
-                    let drawed: UNOCard = deck.pop().ok_or("Error, out of cards")?;
-                    player_hand.push(drawed);
-                    println!("AI drew a card");
-                }
+        }
+        // Only games somebody actually played move the level
+        if let Some(adaptive) = adaptive.as_mut().filter(|_| !options.batch && !resumed_game && t.players > 0)
+            && let Some(winner) = winner
+        {
+            if let Some(message) = adaptive.record(winner <= t.players as usize, t.hands.len()) {
+                recording.event(&Event::Notice(message));
             }
-            else {
-        
-    
-                println!("What would you like to play (or draw)?");
-                answer = input("Enter", "Please enter a card that you have!");
-                
-                answer = answer.to_lowercase();
-                
-                // If the player wants to draw a card, then draw a card
-                if answer == "draw" || answer == "d" {
-                
-                    if player_hand.len() == 1 && uno_detection_panic {
-                        uno_detection_panic = false;
-                    }
-                
-                    ensure_deck_full(&mut deck, &mut discard, &mut rand);
-                    
-                    let drawed: UNOCard = deck.pop().ok_or("Error, out of cards")?;
-                    player_hand.push(drawed);
-                    println!("Drawed card: {}\n", format_card_message(&drawed));
-                // Display the last played card and the player's hand
-                } else if answer == "s" || answer == "see" {
-                    
-                    println!("Last card played: {}\n", format_card_message(&last_played));
-                    for (index, item) in player_hand.iter().enumerate() {
-                        println!("{}. {}", index + 1,format_card_message(item));
-                    }
-    
-                    println!("Type \"d\" or \"draw\" to draw a card");
-                    println!("Type \"s\" or \"see\" to see the last played card and your hand again");
-                    continue;
-                }
-                // Parse the answer
-                let Ok(answer_usize) = answer.trim().parse::<usize>() else {
-                    continue; 
-                };
-                
-                // Ensure the answer is within the bounds of the player's hand
-                if answer_usize <= 0 {
-                    println!("Please enter a card that you can use");
-                    continue;
-                }
-                
-                let answer_usize = (answer_usize -1) as usize; // Zero indexing fix
-                
-                // Check if the card is valid
-                if answer_usize >= player_hand.len() {
-                    println!("Please enter a card that you have!\n");
-                } else if !allowed_move(player_hand[answer_usize], last_played) {
-                    println!("Playing a {} is not allowed. Pick another card or draw.\n", format_card_message(&player_hand[answer_usize]));
-                } 
-                // If the card is valid, then play it
-                else {
-                    card_selected = Some(player_hand[answer_usize]);
-                    discard.push( card_selected.unwrap() );
-                    player_hand.remove(answer_usize);
-                    println!("Card selected: {}", format_card_message(&card_selected.unwrap()));
-                    break;
-                }
+            if let Err(e) = adaptive::store(&adaptive::path(), adaptive) {
+                eprintln!("Could not save the adaptive difficulty: {}", e);
             }
         }
-        
-        // We're gonna do some spins on the rules here 
-        // So for one +4s CANNOT be countererd, but they can be played on a +2
-        // Adding cards will only work if you have a skip card, if that is the case then you are immune until you play 
-        // If not, you're drawing right now
-        if let Some(card) = card_selected {
-            last_played = card;
-
-            match card.special {
-                SpecialCard::PlusFour => {
-                
-                    if is_ai {
-                        last_played.color = get_common_color(player_hand, &mut rand);
-                    }
-                    else {
-                        let chosen_color: Color = input("Enter color", "Please enter an UNO color");
-                        last_played.color = chosen_color;
-                    }
-                    
-                    add_queue += 4;
-                    getting_added_to = false;
-                    skipped = true;
-                },
-                SpecialCard::PlusTwo => {
-                    add_queue += 2;
-                    getting_added_to = false;
-                },
-                SpecialCard::ColorChange => {
-                    if is_ai {
-                        last_played.color = get_common_color(player_hand, &mut rand);
-                    }
-                    else {
-                        let chosen_color: Color = input("Enter color", "Please enter an UNO color");
-                        last_played.color = chosen_color;
-                    }
-                },
-                SpecialCard::Skip => skipped = true,
-                SpecialCard::Reverse => {
-                    if total_players == 2 {
-                        skipped = true;
-                    } else {
-                        game_state.reverse();
-                    }
-                },
-                SpecialCard::Base => {},
-            }
+        // A replay starts from one deal, so only the first game of a series is recorded
+        if let Some(path) = record.filter(|_| scores.games == 0)
+            && let Err(e) = replay::write(path, &recording, &t, &settings)
+        {
+            eprintln!("Could not write the replay: {}", e);
+        }
+        recording.game_over(&t, winner);
+        // After the replay is written, the scores aren't part of the game
+        scores.record(&t, winner);
+        if options.batch {
+            break;
         }
-                
-        
-        // If the player has a countercard but decided not to use it, then they draw at the end of the turn
-        if getting_added_to && countercards && add_queue > 0 {
-            for _ in 0..add_queue {
-                
-                ensure_deck_full(&mut deck, &mut discard, &mut rand);
-                let drawed: UNOCard = deck.pop().ok_or("Error, out of cards")?;
-                player_hand.push(drawed);
-                println!("Force drawing: {}", format_card_message(&drawed));
+        if let Some(winner) = winner {
+            for line in round_table(&t, &labels(&config.looks, &t.names), winner) {
+                recording.event(&Event::Notice(line));
             }
-            
-            add_queue = 0;
-            skipped = false;
         }
-        
-        // UNO!
-        if player_hand.len() == 1 {
-            uno_detection_panic = true;
-            println!("UNO");
+        if scores.games > 1 {
+            for line in scores.lines(&labels(&config.looks, &t.names)) {
+                recording.event(&Event::Notice(line));
+            }
         }
-        
-        // Exit the loop if a player has won (no cards left)
-        if player_hand.len() == 0 {
-            println!("Player #{} wins!", game_state.player_number());
+
+        // Anything but a yes goes on to the exit prompt like a single game always did, so input that runs out still ends
+        let fe: &mut dyn Frontend = &mut recording;
+        let answer: String = input(fe, Prompt::Rematch, "Error")?;
+        if !answer.parse::<YesNo>().is_ok_and(|YesNo(yes)| yes) {
             break;
         }
-        
-        // Clear the terminal and move to the next turn
-        let _: String = input("Press enter to continue...", "Error");
-        clear_terminal();
-        
-        
-        game_state.next_turn();
+        let next = rematch(&mut t, &settings, scores.games);
+        let seats = t.hands.len();
+        fe.event(&Event::Notice(format!("Game {}, {} deals", scores.games + 1, t.names[(scores.games + seats - 1) % seats])));
+        save::reset_log();
+        resumed_game = false;
+        table = setup(fe, &next)?;
     }
+    let fe: &mut dyn Frontend = &mut recording;
     
     // Exit the game
-    let _: String = input("Press enter to exit...", "Error");
+    let _: String = input(fe, Prompt::Exit, "Error")?;
 
     Ok(())
 }
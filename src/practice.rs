@@ -0,0 +1,248 @@
+// `UNOler practice <scenario>`: the same kind of spot dealt again and again with fresh cards, to drill one decision
+// Each drill is played out against the AIs and then gone over next to the Expert, like --review does
+//
+//     uno-under-four    Two cards left, stacking on and a +4 coming at you: pass it on or take it
+//     one-color-left    The next player is on UNO and only one of your colors follows the top card
+//
+// --games is how many drills (5 by default), --difficulty how the AIs play and --seed deals the same drills again
+
+use std::path::PathBuf;
+
+use unoler::cards::{Color, SpecialCard, UNOCard, colors, format_card_message, shuffled_deck};
+use unoler::events::{Event, Frontend, Prompt};
+use unoler::game::{Difficulty, Forfeited, Table, play};
+use unoler::review::Reviewer;
+use unoler::rng::{Randler, Rng};
+use unoler::rules::RuleSet;
+
+use crate::puzzle::{self, Puzzle};
+
+pub const DEFAULT_DRILLS: usize = 5;
+
+pub struct Scenario {
+    pub slug: &'static str,
+    pub about: &'static str,
+    stacking: bool, // Turned on whatever the rules say, for the drills about stacking
+    deal: fn(&mut Dealer) -> Option<Deal>,
+}
+
+pub const SCENARIOS: [Scenario; 2] = [
+    Scenario { slug: "uno-under-four", about: "Two cards left, stacking on and a +4 coming at you: pass it on or take it", stacking: true, deal: uno_under_four },
+    Scenario { slug: "one-color-left", about: "The next player is on UNO and only one of your colors follows the top card", stacking: false, deal: one_color_left },
+];
+
+// What a scenario deals, the rest of the deck goes under it
+struct Deal {
+    you: Vec<UNOCard>,
+    opponents: Vec<Vec<UNOCard>>,
+    top: UNOCard,
+    queued: u32, // Cards waiting for you to draw or pass on
+}
+
+// Cards come off one shuffled deck, so nothing in a drill is dealt twice
+struct Dealer {
+    deck: Vec<UNOCard>,
+    rand: Randler,
+    colors: &'static [Color],
+}
+
+fn is_wild(card: &UNOCard) -> bool {
+    card.special == SpecialCard::ColorChange || card.special == SpecialCard::PlusFour
+}
+
+impl Dealer {
+    fn take(&mut self, wanted: impl Fn(&UNOCard) -> bool) -> Option<UNOCard> {
+        let i = self.deck.iter().position(wanted)?;
+        Some(self.deck.remove(i))
+    }
+
+    fn hand(&mut self, cards: usize, wanted: impl Fn(&UNOCard) -> bool) -> Option<Vec<UNOCard>> {
+        (0..cards).map(|_| self.take(&wanted)).collect()
+    }
+
+    fn between(&mut self, min: usize, max: usize) -> usize {
+        self.rand.gen_range(min as u64, max as u64).unwrap_or(min as u64) as usize
+    }
+
+    fn color(&mut self) -> Color {
+        *self.rand.choose(self.colors).unwrap_or(&Color::Red)
+    }
+}
+
+fn uno_under_four(d: &mut Dealer) -> Option<Deal> {
+    let called = d.color();
+    let mut you = vec![d.take(|c| c.special == SpecialCard::PlusFour)?];
+    you.push(d.take(|c| !is_wild(c) && c.special != SpecialCard::PlusTwo)?);
+    let opponents = (0..d.between(1, 3)).map(|_| {
+        let cards = d.between(3, 6);
+        d.hand(cards, |_| true)
+    }).collect::<Option<_>>()?;
+    let top = UNOCard { color: called, ..d.take(|c| c.special == SpecialCard::PlusFour)? };
+    Some(Deal { you, opponents, top, queued: 4 })
+}
+
+fn one_color_left(d: &mut Dealer) -> Option<Deal> {
+    let top = d.take(|c| c.special == SpecialCard::Base)?;
+    // No number matches either, so the top card's color really is the only way to follow it
+    let other = |c: &UNOCard| c.color != top.color && !is_wild(c) && !(c.special == SpecialCard::Base && c.number == top.number);
+    let matching = d.between(1, 2);
+    let mut you = d.hand(matching, |c| c.color == top.color && !is_wild(c))?;
+    let rest = d.between(3, 4);
+    you.extend(d.hand(rest, other)?);
+    if d.rand.rand_bool(0.5) {
+        you.push(d.take(|c| c.special == SpecialCard::ColorChange)?);
+    }
+    let mut opponents = vec![d.hand(1, |c| !is_wild(c))?];
+    for _ in 0..d.between(0, 2) {
+        let cards = d.between(3, 6);
+        opponents.push(d.hand(cards, |_| true)?);
+    }
+    Some(Deal { you, opponents, top, queued: 0 })
+}
+
+pub fn find(name: &str) -> Result<&'static Scenario, String> {
+    SCENARIOS.iter().find(|s| s.slug == name).ok_or_else(|| {
+        let names = SCENARIOS.iter().map(|s| s.slug).collect::<Vec<_>>().join(", ");
+        format!("{} isn't a practice scenario, there's {}", name, names)
+    })
+}
+
+pub fn list() {
+    for scenario in &SCENARIOS {
+        println!("{:<16} {}", scenario.slug, scenario.about);
+    }
+}
+
+// A whole table for one drill, you in seat 1 and about to move
+fn table(scenario: &Scenario, rules: RuleSet, difficulty: Difficulty, rand: &mut Randler) -> Result<Table, Box<dyn std::error::Error>> {
+    let rules = RuleSet { stacking: rules.stacking || scenario.stacking, ..rules };
+    let mut dealer = Dealer { deck: shuffled_deck(rand, rules.deck()), rand: Randler::new(rand.next_u64()), colors: colors(rules.colors) };
+    let deal = (scenario.deal)(&mut dealer).ok_or("The deck ran out dealing the drill")?;
+    let puzzle = Puzzle {
+        name: scenario.slug.to_string(),
+        about: scenario.about.to_string(),
+        goal: 1,
+        difficulty,
+        rules,
+        you: deal.you,
+        opponents: deal.opponents,
+        top: deal.top,
+        deck: dealer.deck,
+    };
+    let mut t = puzzle::table(&puzzle)?;
+    t.add_queue = deal.queued;
+    Ok(t)
+}
+
+// Hands every turn of the drill to a reviewer on its way through
+struct Drill<'a> {
+    inner: &'a mut dyn Frontend,
+    reviewer: Reviewer,
+}
+
+impl Frontend for Drill<'_> {
+    fn event(&mut self, event: &Event) {
+        self.reviewer.event(event);
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.reviewer.turn_start(table);
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, _slot: &str) -> Result<PathBuf, String> {
+        Err(String::from("Drills aren't saved, there's a new one along in a moment"))
+    }
+
+    fn paused(&self) -> bool {
+        self.inner.paused()
+    }
+}
+
+// Plays the drills one after another, the number won out of those played
+pub fn run(scenario: &Scenario, drills: usize, rules: RuleSet, difficulty: Difficulty, seed: u64, fe: &mut dyn Frontend) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let mut rand = Randler::new(seed);
+    let mut won = 0;
+    for drill in 1..=drills {
+        let mut t = table(scenario, rules, difficulty, &mut rand)?;
+        fe.event(&Event::Notice(format!("Drill {} of {}: {}", drill, drills, scenario.about)));
+        for (i, hand) in t.hands.iter().enumerate().skip(1) {
+            fe.event(&Event::Notice(format!("{} holds {} card{}", t.names[i], hand.len(), if hand.len() == 1 { "" } else { "s" })));
+        }
+        let waiting = if t.add_queue > 0 { format!(", with {} cards waiting for you", t.add_queue) } else { String::new() };
+        fe.event(&Event::Notice(format!("The top card is {}{}", format_card_message(&t.last_played), waiting)));
+
+        let mut watched = Drill { inner: &mut *fe, reviewer: Reviewer::default() };
+        let winner = match play(&mut t, &mut watched, None) {
+            Err(e) if e.is::<Forfeited>() => {
+                fe.event(&Event::Notice(format!("Practice over, {} of {} drills won", won, drill - 1)));
+                return Ok((won, drill - 1));
+            },
+            winner => winner?,
+        };
+        for line in watched.reviewer.report() {
+            fe.event(&Event::Notice(line));
+        }
+        match winner {
+            Some(1) => {
+                won += 1;
+                fe.event(&Event::Notice(String::from("You went out first!")));
+            },
+            Some(winner) => fe.event(&Event::Notice(format!("{} went out first", t.names[winner - 1]))),
+            None => {},
+        }
+    }
+    fe.event(&Event::Notice(format!("Practice over, {} of {} drills won", won, drills)));
+    Ok((won, drills))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::invariants;
+    use unoler::plugins::legal_moves;
+    use unoler::testing::ScriptedFrontend;
+
+    #[test]
+    fn drills_deal_the_spot_they_promise() {
+        let mut rand = Randler::new(4);
+        for _ in 0..50 {
+            let t = table(find("uno-under-four").unwrap(), RuleSet::default(), Difficulty::Calm, &mut rand).unwrap();
+            assert!(t.rules.stacking);
+            assert_eq!((t.hands[0].len(), t.add_queue, t.last_played.special), (2, 4, SpecialCard::PlusFour));
+            assert!(t.hands[0].iter().any(|c| c.special == SpecialCard::PlusFour));
+            invariants::check(&t).unwrap();
+
+            let t = table(find("one-color-left").unwrap(), RuleSet::default(), Difficulty::Calm, &mut rand).unwrap();
+            assert_eq!(t.hands[1].len(), 1);
+            let follows: Vec<Color> = legal_moves(&t.hands[0], t.last_played, &[]).into_iter()
+                .map(|i| t.hands[0][i])
+                .filter(|c| !is_wild(c))
+                .map(|c| c.color)
+                .collect();
+            assert!(!follows.is_empty() && follows.iter().all(|c| *c == t.last_played.color));
+            invariants::check(&t).unwrap();
+        }
+        assert!(find("three-wilds").is_err());
+    }
+
+    #[test]
+    fn drills_play_out_and_get_counted() {
+        // Whatever answer fits the prompt, the ones that don't are asked again
+        let mut fe = ScriptedFrontend::default();
+        let answers: Vec<String> = (1..=30).map(|n| n.to_string()).chain(["red", "n", "d"].map(String::from)).collect();
+        fe.actions = answers.iter().cycle().take(20000).cloned().collect();
+        let scenario = find("one-color-left").unwrap();
+        let (won, played) = run(scenario, 3, RuleSet::default(), Difficulty::Skilled, 9, &mut fe).unwrap();
+        assert!(won <= played && played == 3);
+        let notices: Vec<&String> = fe.events.iter().filter_map(|e| match e { Event::Notice(n) => Some(n), _ => None }).collect();
+        assert!(notices.iter().any(|n| n.starts_with("Drill 3 of 3: ")));
+        assert_eq!(notices.iter().filter(|n| n.starts_with("Review of Player #1's turns")).count(), 3);
+        assert!(notices.last().unwrap().starts_with("Practice over, "));
+    }
+}
@@ -0,0 +1,269 @@
+// `UNOler puzzle <name or file>`: an endgame set up by hand, won within a number of your own turns
+// A puzzle file is `key: value` lines, cards in the game notation's codes (R5, GS, B+2, W, W+4, W:Y on top):
+//
+//     name: Color choice
+//     about: Pick the color they can't answer
+//     goal: 2               (your turns to win in)
+//     you: W Y5
+//     opponent: B7          (one line for every opponent, in seat order after you)
+//     top: R2
+//     deck: R9 G3 Y1        (top first)
+//     difficulty: calm      (how the opponents play, calm if left out)
+//     rules: stacking=on force-play=off
+//
+// You only see how many cards the others hold. Lines starting with # are comments
+
+use std::fs;
+
+use unoler::cards::{Color, SpecialCard, UNOCard, format_card_message};
+use unoler::events::{Event, Frontend, Prompt};
+use unoler::game::{Difficulty, Forfeited, Game, Settings, Table, play, setup};
+use unoler::notation::parse_card_code;
+use unoler::rules::{RuleSet, TurnState};
+
+// Puzzles are fixed positions, so the odd reshuffle should always come out the same too
+const SEED: u64 = 0;
+
+pub const BUILT_IN: [(&str, &str); 4] = [
+    ("color-choice", include_str!("../puzzles/color-choice.txt")),
+    ("skip-ahead", include_str!("../puzzles/skip-ahead.txt")),
+    ("hold-the-four", include_str!("../puzzles/hold-the-four.txt")),
+    ("long-way-round", include_str!("../puzzles/long-way-round.txt")),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Puzzle {
+    pub name: String,
+    pub about: String,
+    pub goal: usize,
+    pub difficulty: Difficulty,
+    pub rules: RuleSet,
+    pub you: Vec<UNOCard>,
+    pub opponents: Vec<Vec<UNOCard>>,
+    pub top: UNOCard,
+    pub deck: Vec<UNOCard>, // Top first
+}
+
+fn cards(text: &str) -> Result<Vec<UNOCard>, String> {
+    text.split_whitespace().map(parse_card_code).collect()
+}
+
+pub fn parse(text: &str) -> Result<Puzzle, String> {
+    let mut name = None;
+    let mut about = String::new();
+    let mut goal = None;
+    let mut difficulty = Difficulty::Calm;
+    let mut rules = RuleSet::default();
+    let mut you = None;
+    let mut opponents = Vec::new();
+    let mut top = None;
+    let mut deck = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let at = |e: String| format!("Line {}: {}", i + 1, e);
+        let (key, value) = line.split_once(':').ok_or(at(String::from("lines look like key: value")))?;
+        let value = value.trim();
+        match key.trim() {
+            "name" => name = Some(value.to_string()),
+            "about" => about = value.to_string(),
+            "goal" => goal = Some(value.parse().ok().filter(|g| *g > 0).ok_or(at(format!("{} is not a number of turns", value)))?),
+            "difficulty" => difficulty = value.parse().map_err(at)?,
+            "rules" => {
+                for rule in value.split_whitespace() {
+                    let (key, value) = rule.split_once('=').ok_or(at(format!("{} should look like stacking=on", rule)))?;
+                    rules.set(key, value).map_err(at)?;
+                }
+            },
+            "you" => you = Some(cards(value).map_err(at)?),
+            "opponent" => opponents.push(cards(value).map_err(at)?),
+            "top" => top = Some(parse_card_code(value).map_err(at)?),
+            "deck" => deck = cards(value).map_err(at)?,
+            other => return Err(at(format!("{} isn't something a puzzle has", other))),
+        }
+    }
+
+    let top = top.ok_or("The puzzle needs a top card")?;
+    let wild = top.special == SpecialCard::ColorChange || top.special == SpecialCard::PlusFour;
+    if wild && top.color == Color::NA {
+        return Err(String::from("A wild on top needs the color it was given, like W:R"));
+    }
+    let you = you.filter(|h| !h.is_empty()).ok_or("The puzzle needs your hand")?;
+    if opponents.is_empty() || opponents.iter().any(|h| h.is_empty()) {
+        return Err(String::from("The puzzle needs opponents, each with at least one card"));
+    }
+    Ok(Puzzle {
+        name: name.ok_or("The puzzle needs a name")?,
+        about,
+        goal: goal.ok_or("The puzzle needs a goal")?,
+        difficulty,
+        rules,
+        you,
+        opponents,
+        top,
+        deck,
+    })
+}
+
+// A built in puzzle by name, or a file
+pub fn find(name: &str) -> Result<Puzzle, String> {
+    match BUILT_IN.iter().find(|(slug, _)| *slug == name) {
+        Some((_, text)) => parse(text),
+        None => {
+            let text = fs::read_to_string(name)
+                .map_err(|e| format!("{} isn't a built in puzzle or a file you can read: {}", name, e))?;
+            parse(&text).map_err(|e| format!("{}: {}", name, e))
+        },
+    }
+}
+
+pub fn list() {
+    for (slug, text) in BUILT_IN {
+        match parse(text) {
+            Ok(puzzle) => println!("{:<16} {} (win in {}): {}", slug, puzzle.name, puzzle.goal, puzzle.about),
+            Err(e) => println!("{:<16} broken: {}", slug, e),
+        }
+    }
+}
+
+// Setup's seed line would be about a deal that gets thrown away, the puzzle introduces itself instead
+struct Quiet;
+
+impl Frontend for Quiet {
+    fn event(&mut self, _event: &Event) {}
+
+    fn prompt(&mut self, _prompt: &Prompt) -> String {
+        String::new()
+    }
+}
+
+// You in seat 1 and the opponents after, holding exactly the puzzle's cards
+pub fn table(puzzle: &Puzzle) -> Result<Table, Box<dyn std::error::Error>> {
+    let settings = Settings {
+        players: Some(1),
+        ai_players: Some(puzzle.opponents.len() as u8),
+        difficulty: Some(puzzle.difficulty),
+        rules: puzzle.rules,
+        seed: Some(SEED),
+        ..Settings::default()
+    };
+    let mut table = setup(&mut Quiet, &settings)?.ok_or("The puzzle has no players")?;
+
+    let mut opening = puzzle.top;
+    if opening.special == SpecialCard::ColorChange || opening.special == SpecialCard::PlusFour {
+        opening.color = Color::NA;
+    }
+    table.hands = std::iter::once(puzzle.you.clone()).chain(puzzle.opponents.iter().cloned()).collect();
+    table.deck = puzzle.deck.iter().rev().copied().collect();
+    table.discard = vec![opening];
+    table.history = vec![puzzle.top];
+    table.last_played = puzzle.top;
+    // The top card was played by somebody before you, it doesn't act again
+    table.set_turn_state(TurnState { game: Game::new(0, table.hands.len(), 1), add_queue: 0, skipped: false });
+    Ok(table)
+}
+
+// Plays it out one turn at a time so your turns can be counted, true if you won in time
+pub fn play_puzzle(puzzle: &Puzzle, fe: &mut dyn Frontend) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut t = table(puzzle)?;
+    fe.event(&Event::Notice(format!("{}: {}", puzzle.name, puzzle.about)));
+    for (i, hand) in t.hands.iter().enumerate().skip(1) {
+        fe.event(&Event::Notice(format!("{} holds {} cards", t.names[i], hand.len())));
+    }
+    fe.event(&Event::Notice(format!("The top card is {}. Win within {} of your turns!", format_card_message(&t.last_played), puzzle.goal)));
+
+    let mut turns = 0;
+    loop {
+        if t.game_state.player_number() == 1 {
+            if turns == puzzle.goal {
+                fe.event(&Event::Notice(format!("Out of turns, the puzzle wanted a win in {}. Try again!", puzzle.goal)));
+                return Ok(false);
+            }
+            turns += 1;
+            fe.event(&Event::Notice(format!("Your turn {} of {}", turns, puzzle.goal)));
+        }
+        let played = match play(&mut t, fe, Some(1)) {
+            Err(e) if e.is::<Forfeited>() => {
+                fe.event(&Event::Notice(String::from("Given up, try again!")));
+                return Ok(false);
+            },
+            played => played?,
+        };
+        match played {
+            Some(1) => {
+                fe.event(&Event::Notice(format!("Solved in {} of {} turns!", turns, puzzle.goal)));
+                return Ok(true);
+            },
+            Some(winner) => {
+                fe.event(&Event::Notice(format!("{} got rid of their cards first. Try again!", t.names[winner - 1])));
+                return Ok(false);
+            },
+            None => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::rules::allowed_move;
+    use unoler::session::Session;
+
+    // Tries every legal answer the human has, drawing at most a few times a turn, and counts how the games end
+    fn search(session: &Session, goal: usize, draws: usize, wins: &mut usize, losses: &mut usize) {
+        let turns = session.events().filter(|e| matches!(e, Event::TurnStart { player: 1, .. })).count();
+        let answers: Vec<String> = match session.waiting() {
+            _ if session.winner() == Some(1) => return *wins += 1,
+            _ if session.winner().is_some() || turns > goal => return *losses += 1,
+            Some(Prompt::Action { .. }) => {
+                let t = session.current();
+                let mut answers: Vec<String> = (0..t.hands[0].len())
+                    .filter(|&i| allowed_move(t.hands[0][i], t.last_played))
+                    .map(|i| (i + 1).to_string())
+                    .collect();
+                if draws < 2 {
+                    answers.push(String::from("d"));
+                }
+                answers
+            },
+            Some(Prompt::Color { .. }) => ["red", "green", "yellow", "blue"].map(String::from).to_vec(),
+            Some(Prompt::PlayDrawn { .. } | Prompt::Challenge { .. }) => ["y", "n"].map(String::from).to_vec(),
+            _ => return *losses += 1,
+        };
+        for answer in answers {
+            let mut next = session.clone();
+            next.act(&answer).unwrap();
+            let draws = if answer == "d" { draws + 1 } else { 0 };
+            search(&next, goal, draws, wins, losses);
+        }
+    }
+
+    fn outcomes(puzzle: &Puzzle) -> (usize, usize) {
+        let session = Session::from_table(table(puzzle).unwrap()).unwrap();
+        let (mut wins, mut losses) = (0, 0);
+        search(&session, puzzle.goal, 0, &mut wins, &mut losses);
+        (wins, losses)
+    }
+
+    #[test]
+    fn built_in_puzzles_can_be_solved_and_failed() {
+        for (slug, text) in BUILT_IN {
+            let puzzle = parse(text).unwrap_or_else(|e| panic!("{}: {}", slug, e));
+            let (wins, losses) = outcomes(&puzzle);
+            assert!(wins > 0, "{} can't be solved", slug);
+            assert!(losses > 0, "{} can't be failed", slug);
+        }
+    }
+
+    #[test]
+    fn puzzles_need_the_important_parts() {
+        assert!(parse("name: x\ngoal: 1\nyou: R5\ntop: R1").unwrap_err().contains("opponents"));
+        assert!(parse("name: x\ngoal: 1\nyou: R5\nopponent: G1\ntop: W").unwrap_err().contains("color"));
+        assert!(parse("name: x\ngoal: 0\nyou: R5\nopponent: G1\ntop: R1").unwrap_err().contains("turns"));
+        assert!(parse("name: x\ngoal: 1\nyou: R5\nopponent: G1\ntop: R1\nsize: 3").is_err());
+        assert!(find("no-such-puzzle").is_err());
+    }
+}
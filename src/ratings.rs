@@ -0,0 +1,213 @@
+// Elo style ratings, kept in ratings.json next to the saves whenever [stats] is enabled
+// Every AI difficulty has one, and so does every human by the name they play under. A game is scored as the winner
+// beating each of the other seats, with the usual K split between them so a win at a full table isn't worth five wins.
+// Seats that are the same player (two Calm AIs, say) don't play each other. `UNOler bench-ai` feeds its games in too,
+// which is what gives the difficulties their numbers; `UNOler stats` shows them and `UNOler stats clear` drops them
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use unoler::game::{Difficulty, Table, difficulty_name};
+use unoler::json::Json;
+
+use crate::save;
+
+const START: f64 = 1500.0;
+const K: f64 = 32.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Player {
+    Ai(Difficulty),
+    Human(String),
+}
+
+impl Player {
+    fn key(&self) -> (bool, &str) {
+        match self {
+            Player::Ai(difficulty) => (true, difficulty_name(*difficulty)),
+            Player::Human(name) => (false, name),
+        }
+    }
+}
+
+// Everybody at a table, in seat order
+pub fn seats(t: &Table) -> Vec<Player> {
+    let humans = t.players as usize;
+    t.names.iter().zip(&t.difficulties).enumerate()
+        .map(|(seat, (name, difficulty))| if seat < humans { Player::Human(name.clone()) } else { Player::Ai(*difficulty) })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rating {
+    ai: bool,
+    name: String,
+    rating: f64,
+    games: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ratings {
+    players: Vec<Rating>,
+}
+
+// How often a is expected to beat b
+fn expected(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+impl Ratings {
+    fn find(&mut self, player: &Player) -> usize {
+        let (ai, name) = player.key();
+        match self.players.iter().position(|r| r.ai == ai && r.name == name) {
+            Some(index) => index,
+            None => {
+                self.players.push(Rating { ai, name: name.to_string(), rating: START, games: 0 });
+                self.players.len() - 1
+            },
+        }
+    }
+
+    // winner is a seat from 1 like everywhere else
+    pub fn record(&mut self, seats: &[Player], winner: usize) {
+        let Some(won) = seats.get(winner.wrapping_sub(1)) else { return };
+        let mut players: Vec<usize> = seats.iter().map(|p| self.find(p)).collect();
+        let won = self.find(won);
+        players.sort_unstable();
+        players.dedup();
+        if players.len() < 2 {
+            return;
+        }
+
+        // Everything is worked out from the ratings before the game, then applied
+        let k = K / (seats.len() - 1) as f64;
+        let mut changes = vec![0.0; self.players.len()];
+        for &lost in players.iter().filter(|p| **p != won) {
+            let change = k * (1.0 - expected(self.players[won].rating, self.players[lost].rating));
+            changes[won] += change;
+            changes[lost] -= change;
+        }
+        for index in players {
+            self.players[index].rating += changes[index];
+            self.players[index].games += 1;
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        if self.players.is_empty() {
+            return Vec::new();
+        }
+        let mut players: Vec<&Rating> = self.players.iter().collect();
+        players.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        let mut lines = vec![String::new(), format!("Ratings (everybody starts at {})", START)];
+        for r in players {
+            let name = if r.ai { format!("{} AI", r.name) } else { r.name.clone() };
+            lines.push(format!("  {:<20} {:>5.0}  {} game{}", name, r.rating, r.games, if r.games == 1 { "" } else { "s" }));
+        }
+        lines
+    }
+
+    fn to_json(&self) -> Json {
+        Json::Array(self.players.iter().map(|r| Json::object(vec![
+            ("ai", r.ai.into()),
+            ("name", r.name.as_str().into()),
+            ("rating", Json::Number(r.rating)),
+            ("games", r.games.into()),
+        ])).collect())
+    }
+
+    fn from_json(value: &Json) -> Option<Ratings> {
+        let players = value.as_array()?.iter()
+            .map(|r| Some(Rating {
+                ai: r.get("ai")?.as_bool()?,
+                name: r.get("name")?.as_str()?.to_string(),
+                rating: r.get("rating")?.as_f64()?,
+                games: r.get("games")?.as_f64()? as usize,
+            }))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Ratings { players })
+    }
+}
+
+pub fn path() -> PathBuf {
+    save::data_dir().join("ratings.json")
+}
+
+// A file that doesn't read back starts everybody over, same as no file
+pub fn load(path: &Path) -> Ratings {
+    fs::read_to_string(path).ok()
+        .and_then(|text| Json::parse(&text).ok())
+        .and_then(|value| Ratings::from_json(&value))
+        .unwrap_or_default()
+}
+
+pub fn store(path: &Path, ratings: &Ratings) -> io::Result<()> {
+    fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    fs::write(path, ratings.to_json().to_string())
+}
+
+pub fn clear(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn human(name: &str) -> Player {
+        Player::Human(String::from(name))
+    }
+
+    impl Ratings {
+        fn rating(&self, player: &Player) -> Option<f64> {
+            let (ai, name) = player.key();
+            self.players.iter().find(|r| r.ai == ai && r.name == name).map(|r| r.rating)
+        }
+    }
+
+    #[test]
+    fn winners_take_points_from_the_table() {
+        let mut ratings = Ratings::default();
+        let seats = [human("Maria"), Player::Ai(Difficulty::Calm), Player::Ai(Difficulty::Skilled)];
+        ratings.record(&seats, 1);
+        assert_eq!(ratings.rating(&human("Maria")), Some(START + K / 2.0), "half of K from each of two even players");
+        assert_eq!(ratings.rating(&Player::Ai(Difficulty::Calm)), Some(START - K / 4.0));
+        // An upset is worth more than beating somebody weaker
+        let before = ratings.rating(&Player::Ai(Difficulty::Calm)).unwrap();
+        ratings.record(&seats[..2], 2);
+        let upset = ratings.rating(&Player::Ai(Difficulty::Calm)).unwrap() - before;
+        assert!(upset > K / 2.0, "{}", upset);
+        let total: f64 = ratings.players.iter().map(|r| r.rating - START).sum();
+        assert!(total.abs() < 1e-9, "points only move around");
+    }
+
+    #[test]
+    fn the_same_player_twice_doesnt_count() {
+        let mut ratings = Ratings::default();
+        ratings.record(&[Player::Ai(Difficulty::Calm), Player::Ai(Difficulty::Calm)], 1);
+        assert_eq!(ratings.rating(&Player::Ai(Difficulty::Calm)), Some(START));
+        assert_eq!(ratings.players[0].games, 0);
+        ratings.record(&[Player::Ai(Difficulty::Calm), Player::Ai(Difficulty::Calm), Player::Ai(Difficulty::Random)], 2);
+        assert_eq!(ratings.players[0].games, 1);
+        assert!(ratings.rating(&Player::Ai(Difficulty::Calm)).unwrap() > START);
+        assert_eq!(ratings.rating(&human("Calm")), None, "humans and AIs are kept apart");
+    }
+
+    #[test]
+    fn ratings_are_kept_between_games() {
+        let path = std::env::temp_dir().join(format!("unoler-ratings-{}.json", std::process::id()));
+        clear(&path).unwrap();
+        assert_eq!(load(&path), Ratings::default());
+        let mut ratings = Ratings::default();
+        ratings.record(&[human("Sam"), Player::Ai(Difficulty::Aggressive)], 2);
+        store(&path, &ratings).unwrap();
+        assert_eq!(load(&path), ratings);
+        assert!(ratings.lines()[2].starts_with("  aggressive AI"), "{:?}", ratings.lines());
+        clear(&path).unwrap();
+        assert_eq!(load(&path), Ratings::default());
+    }
+}
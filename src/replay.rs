@@ -0,0 +1,291 @@
+// Replays: the settings, every answer typed and every event of a finished game, written by --record
+// `replay verify` plays the answers again on the current engine and checks that the same events come out and the
+// table ends up the same, so a change to the rules can't quietly change how a recorded game goes
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use unoler::events::{Event, Frontend, Prompt, event_json};
+use unoler::game::{Difficulty, Settings, Table, difficulty_name, play, setup};
+use unoler::json::Json;
+use unoler::notation::Notation;
+use unoler::odds::Tracker;
+use unoler::review::Reviewer;
+use unoler::rules::RuleSet;
+
+use unoler::persist::table_to_json;
+
+// Bumped whenever the file layout changes, old replays are refused instead of misread
+const VERSION: usize = 1;
+
+// Forwards everything while keeping what a replay needs, or just forwards when nothing is being recorded
+pub struct Recording {
+    inner: Box<dyn Frontend>,
+    enabled: bool,
+    answers: Vec<String>,
+    events: Vec<Json>,
+    turns: usize, // Counted even when nothing is recorded, for the stats
+}
+
+impl Recording {
+    pub fn new(inner: Box<dyn Frontend>, enabled: bool) -> Self {
+        Recording { inner, enabled, answers: Vec::new(), events: Vec::new(), turns: 0 }
+    }
+
+    pub fn turns(&self) -> usize {
+        self.turns
+    }
+}
+
+impl Frontend for Recording {
+    fn event(&mut self, event: &Event) {
+        if let Event::TurnStart { .. } = event {
+            self.turns += 1;
+        }
+        if self.enabled {
+            self.events.push(event_json(event));
+        }
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        let answer = self.inner.prompt(prompt);
+        // Setup answers end up in the settings, and the rest don't change the game
+        if self.enabled && let Prompt::Action { .. } | Prompt::Color { .. } | Prompt::PlayDrawn { .. } | Prompt::Challenge { .. } | Prompt::Menu { .. } = prompt {
+            self.answers.push(answer.clone());
+        }
+        answer
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        self.inner.game_over(table, winner);
+    }
+}
+
+// Enough to deal the same game again, taken from the table since setup may have asked for some of it
+// The number of decks is only there when it was asked for, otherwise setup works it out again, and a stacked deal
+// is kept in its file form
+fn settings_json(t: &Table, dealt: &Settings) -> Result<Json, String> {
+    let seed = t.seed.ok_or("Secure games can't be recorded, they have no seed")?;
+    let mut settings = vec![
+        ("seed", seed.to_string().into()),
+        ("players", (t.players as usize).into()),
+        ("ai_players", (t.hands.len() - t.players as usize).into()),
+        ("names", Json::Array(t.names.iter().map(|n| n.as_str().into()).collect())),
+        ("difficulties", Json::Array(t.difficulties.iter().map(|d| difficulty_name(*d).into()).collect())),
+        ("rules", t.rules.to_json()),
+    ];
+    if let Some(decks) = dealt.decks {
+        settings.push(("decks", (decks as usize).into()));
+    }
+    if let Some(stack) = &dealt.stack {
+        settings.push(("stack", stack.to_string().into()));
+    }
+    if t.handicaps.iter().any(|h| !h.is_none()) {
+        settings.push(("handicaps", Json::Array(t.handicaps.iter().map(|h| h.to_string().into()).collect())));
+    }
+    Ok(Json::object(settings))
+}
+
+fn settings_from_json(value: &Json) -> Option<Settings> {
+    let players = value.get("players")?.as_f64()? as u8;
+    let difficulties = value.get("difficulties")?.as_array()?.iter()
+        .map(|d| d.as_str()?.parse().ok())
+        .collect::<Option<Vec<Difficulty>>>()?;
+
+    let names: Vec<String> = value.get("names")?.as_array()?.iter().map(|n| n.as_str().map(String::from)).collect::<Option<_>>()?;
+
+    // Setup hands the first AI difficulty to the human seats as well, so the mix reproduces all of them
+    Some(Settings {
+        players: Some(players),
+        ai_players: Some(value.get("ai_players")?.as_f64()? as u8),
+        difficulty: Some(*difficulties.first().unwrap_or(&Difficulty::Calm)),
+        ai_mix: difficulties.get(players as usize..).unwrap_or_default().to_vec(),
+        names: names.clone(),
+        ai_names: names.get(players as usize..).unwrap_or_default().to_vec(),
+        rules: RuleSet::from_json(value.get("rules")?)?,
+        seed: Some(value.get("seed")?.as_str()?.parse().ok()?),
+        secure: false,
+        // Setup only names the AI seats when nobody is human
+        autoplay: players == 0,
+        decks: match value.get("decks") {
+            Some(decks) => Some(decks.as_f64()? as u8),
+            None => None,
+        },
+        first: 0, // Only the first game of a rematch series is recorded
+        handicaps: match value.get("handicaps") {
+            Some(handicaps) => handicaps.as_array()?.iter().map(|h| h.as_str()?.parse().ok()).collect::<Option<_>>()?,
+            None => Vec::new(),
+        },
+        stack: match value.get("stack") {
+            Some(stack) => Some(stack.as_str()?.parse().ok()?),
+            None => None,
+        },
+    })
+}
+
+// One event per line, so two versions of a replay diff nicely
+pub fn write(path: &Path, recording: &Recording, t: &Table, dealt: &Settings) -> Result<(), String> {
+    let settings = settings_json(t, dealt)?;
+    let answers = Json::Array(recording.answers.iter().map(|a| a.as_str().into()).collect());
+    let events = recording.events.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(",\n");
+    let text = format!(
+        "{{\"version\":{},\"settings\":{},\"answers\":{},\"final\":{},\"events\":[\n{}\n]}}\n",
+        VERSION, settings, answers, table_to_json(t), events,
+    );
+
+    fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Feeds the recorded answers back and stops at the first event that doesn't match
+struct Playback {
+    answers: std::vec::IntoIter<String>,
+    expected: Vec<Json>,
+    seen: usize,
+    events: Vec<Event>,
+    reviewer: Reviewer,
+    odds: Option<Tracker>, // Only for replay odds, the rollouts take a while
+}
+
+impl Playback {
+    fn diverged(&self, message: &str) -> ! {
+        println!("The replay diverged at event {}: {}", self.seen + 1, message);
+        io::stdout().flush().expect("Failed to flush terminal.");
+        std::process::exit(1);
+    }
+}
+
+impl Frontend for Playback {
+    fn event(&mut self, event: &Event) {
+        let got = event_json(event);
+        match self.expected.get(self.seen) {
+            Some(expected) if *expected == got => {},
+            Some(expected) => self.diverged(&format!("expected\n  {}\nbut got\n  {}", expected, got)),
+            None => self.diverged(&format!("the recording ended, but the game went on with\n  {}", got)),
+        }
+        self.seen += 1;
+        self.events.push(event.clone());
+        self.reviewer.event(event);
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.reviewer.turn_start(table);
+        if let Some(odds) = self.odds.as_mut() {
+            odds.turn_start(table);
+        }
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        match prompt {
+            Prompt::Continue | Prompt::Exit => String::new(),
+            Prompt::Action { .. } | Prompt::Color { .. } | Prompt::PlayDrawn { .. } | Prompt::Challenge { .. } | Prompt::Menu { .. } => match self.answers.next() {
+                Some(answer) => answer,
+                None => self.diverged(&format!("the game asked for another {} but every recorded answer was used", prompt.name())),
+            },
+            _ => self.diverged(&format!("the game asked for {}, which the settings should have answered", prompt.name())),
+        }
+    }
+}
+
+// Which parts of two tables differ, by key
+// Only what the recording has is compared, so keys added to the format since (a new rule, say) don't count
+fn changed_keys(expected: &Json, got: &Json) -> Vec<String> {
+    match (expected, got) {
+        (Json::Object(expected), Json::Object(_)) => expected.iter()
+            .flat_map(|(key, value)| match (value, got.get(key)) {
+                (Json::Object(_), Some(inner @ Json::Object(_))) => {
+                    changed_keys(value, inner).into_iter().map(|inner_key| format!("{}.{}", key, inner_key)).collect()
+                },
+                (_, got) if got == Some(value) => Vec::new(),
+                _ => vec![key.clone()],
+            })
+            .collect(),
+        _ => vec![String::from("everything")],
+    }
+}
+
+// What a replay that still plays the same leaves behind
+struct Played {
+    dealt: Table,
+    events: Vec<Event>,
+    reviewer: Reviewer,
+    odds: Option<Tracker>,
+}
+
+// The replay played again, or what went wrong with the file. A replay that doesn't match exits from inside the playback
+fn play_back(path: &Path, odds: bool) -> Result<Played, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let replay = Json::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let damaged = || format!("{} is not a replay file", path.display());
+
+    match replay.get("version").and_then(|v| v.as_f64()) {
+        Some(v) if v as usize == VERSION => {},
+        Some(v) => return Err(format!("{} is a version {} replay, this build reads version {}", path.display(), v, VERSION)),
+        None => return Err(damaged()),
+    }
+    let settings = replay.get("settings").and_then(settings_from_json).ok_or_else(damaged)?;
+    let answers = replay.get("answers").and_then(|a| a.as_array()).ok_or_else(damaged)?.iter()
+        .map(|a| a.as_str().map(String::from))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(damaged)?;
+    let expected = replay.get("events").and_then(|e| e.as_array()).ok_or_else(damaged)?.to_vec();
+    let final_table = replay.get("final").ok_or_else(damaged)?;
+
+    let odds = odds.then(Tracker::default);
+    let mut fe = Playback { answers: answers.into_iter(), expected, seen: 0, events: Vec::new(), reviewer: Reviewer::default(), odds };
+    let mut table = setup(&mut fe, &settings).map_err(|e| e.to_string())?.ok_or("The replay has no players")?;
+    let dealt = table.clone();
+    play(&mut table, &mut fe, None).map_err(|e| e.to_string())?;
+
+    if fe.seen < fe.expected.len() {
+        fe.diverged(&format!("the game ended, but the recording goes on with\n  {}", fe.expected[fe.seen]));
+    }
+    let changed = changed_keys(final_table, &table_to_json(&table));
+    if !changed.is_empty() {
+        println!("Every event matched, but the table ended up different: {}", changed.join(", "));
+        std::process::exit(1);
+    }
+    Ok(Played { dealt, events: fe.events, reviewer: fe.reviewer, odds: fe.odds })
+}
+
+// Ok with the number of events checked
+pub fn verify(path: &Path) -> Result<usize, String> {
+    play_back(path, false).map(|played| played.events.len())
+}
+
+// The --review report for a replay, after checking it still plays the same
+pub fn review(path: &Path) -> Result<Vec<String>, String> {
+    Ok(play_back(path, false)?.reviewer.report())
+}
+
+// Every turn's chances of winning for a replay, then how far down the winner was
+pub fn odds(path: &Path) -> Result<Vec<String>, String> {
+    let played = play_back(path, true)?;
+    let odds = played.odds.unwrap_or_default();
+    let winner = played.events.iter().find_map(|event| match event {
+        Event::Win { player } => Some(*player),
+        _ => None,
+    });
+    let mut lines = odds.every_turn();
+    lines.extend(winner.and_then(|winner| odds.comeback(winner)));
+    Ok(lines)
+}
+
+// The game in a replay file as game notation, after checking it still plays the same
+pub fn export(path: &Path) -> Result<Notation, String> {
+    let played = play_back(path, false)?;
+    Ok(Notation::from_game(&played.dealt, &played.events))
+}
@@ -0,0 +1,214 @@
+// Named save slots and the crash recovery file, with the tables in them as unoler::persist writes them
+// The state is snapshotted at the start of every turn, and a panic hook dumps it along with the event log
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use unoler::events::{Event, Frontend, Prompt, event_json};
+use unoler::game::Table;
+use unoler::json::Json;
+use unoler::persist::{table_from_json, table_to_json};
+pub use unoler::persist::data_dir;
+
+use crate::binary;
+
+// The latest snapshot and every event so far, read by the panic hook
+// The snapshot is kept in the binary form since it's taken every turn, it only becomes JSON when written out
+struct Recovery {
+    snapshot: Option<Vec<u8>>,
+    events_at_snapshot: usize,
+    events: Vec<Json>,
+}
+
+static RECOVERY: Mutex<Recovery> = Mutex::new(Recovery { snapshot: None, events_at_snapshot: 0, events: Vec::new() });
+
+// Runs f on the recovery state, even if a panic poisoned the lock
+fn with_recovery<R>(f: impl FnOnce(&mut Recovery) -> R) -> R {
+    let mut guard = RECOVERY.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut guard)
+}
+
+fn recovery_path() -> PathBuf {
+    data_dir().join("recovery.json")
+}
+
+fn saves_dir() -> PathBuf {
+    data_dir().join("saves")
+}
+
+// Slot and profile names end up as file names, so keep them boring
+pub fn check_name(name: &str, what: &str) -> io::Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a valid {} name (use letters, numbers, - and _)", name, what)));
+    }
+    Ok(())
+}
+
+fn slot_path(slot: &str) -> io::Result<PathBuf> {
+    check_name(slot, "slot")?;
+    Ok(saves_dir().join(format!("{}.json", slot)))
+}
+
+// Called at the start of every turn
+pub fn snapshot(t: &Table) {
+    let snapshot = binary::encode(t);
+    with_recovery(|r| {
+        r.snapshot = Some(snapshot);
+        r.events_at_snapshot = r.events.len();
+    });
+}
+
+// Forgets the events of whatever came before, for a fresh game
+pub fn reset_log() {
+    with_recovery(|r| {
+        r.snapshot = None;
+        r.events_at_snapshot = 0;
+        r.events.clear();
+    });
+}
+
+// Forwards everything to the real frontend while keeping a log of the events
+pub struct Recorder {
+    inner: Box<dyn Frontend>,
+}
+
+impl Recorder {
+    pub fn new(inner: Box<dyn Frontend>) -> Self {
+        Recorder { inner }
+    }
+}
+
+impl Frontend for Recorder {
+    fn event(&mut self, event: &Event) {
+        with_recovery(|r| r.events.push(event_json(event)));
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        snapshot(table);
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        save_slot(slot).map_err(|e| e.to_string())
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        self.inner.game_over(table, winner);
+    }
+}
+
+// Writes the last snapshot plus the full event log, including the turn that crashed
+fn write_recovery() -> Option<PathBuf> {
+    let dump = with_recovery(|r| {
+        Some(Json::object(vec![
+            ("table", table_to_json(&binary::decode(r.snapshot.as_ref()?)?)),
+            ("events_at_snapshot", r.events_at_snapshot.into()),
+            ("events", Json::Array(r.events.clone())),
+        ]))
+    })?;
+
+    let path = recovery_path();
+    fs::create_dir_all(data_dir()).ok()?;
+    fs::write(&path, dump.to_string()).ok()?;
+    Some(path)
+}
+
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if let Some(path) = write_recovery() {
+            eprintln!("The game state was saved to {}. Launch UNOler again to resume.", path.display());
+        }
+    }));
+}
+
+// Reads a dump written by write_recovery or save_slot
+// The events from before the snapshot are put back in the log so another crash keeps the full history,
+// the ones after it belong to the crashed turn which gets played again
+fn load_dump(text: &str) -> Option<Table> {
+    let dump = Json::parse(text).ok()?;
+    let table = table_from_json(dump.get("table")?)?;
+
+    let mut events = dump.get("events").and_then(|e| e.as_array()).map(|e| e.to_vec()).unwrap_or_default();
+    let kept = dump.get("events_at_snapshot").and_then(|n| n.as_f64()).unwrap_or(0.0) as usize;
+    events.truncate(kept);
+    with_recovery(|r| r.events = events);
+    Some(table)
+}
+
+// A recovered game, if the last run crashed mid game
+pub fn load_recovery() -> Option<Table> {
+    load_dump(&fs::read_to_string(recovery_path()).ok()?)
+}
+
+pub fn clear_recovery() {
+    let _ = fs::remove_file(recovery_path());
+}
+
+// Saves the game as it was at the start of the current turn
+pub fn save_slot(slot: &str) -> io::Result<PathBuf> {
+    let path = slot_path(slot)?;
+    let dump = with_recovery(|r| {
+        Some(Json::object(vec![
+            ("table", table_to_json(&binary::decode(r.snapshot.as_ref()?)?)),
+            ("events_at_snapshot", r.events_at_snapshot.into()),
+            ("events", Json::Array(r.events[..r.events_at_snapshot].to_vec())),
+        ]))
+    }).ok_or(io::Error::other("There is no game to save yet"))?;
+
+    fs::create_dir_all(saves_dir())?;
+    fs::write(&path, dump.to_string())?;
+    Ok(path)
+}
+
+pub fn load_slot(slot: &str) -> io::Result<Table> {
+    let text = fs::read_to_string(slot_path(slot)?)?;
+    load_dump(&text).ok_or(io::Error::new(io::ErrorKind::InvalidData, format!("Save slot {} is damaged", slot)))
+}
+
+pub fn delete_slot(slot: &str) -> io::Result<()> {
+    fs::remove_file(slot_path(slot)?)
+}
+
+// Every slot name, sorted, with a short description of the game in it
+pub fn list_slots() -> io::Result<Vec<(String, String)>> {
+    let entries = match fs::read_dir(saves_dir()) {
+        Ok(entries) => entries,
+        // No saves folder just means nothing was saved yet
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut slots = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(slot) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+
+        let summary = fs::read_to_string(&path).ok()
+            .and_then(|text| Json::parse(&text).ok())
+            .and_then(|dump| table_from_json(dump.get("table")?))
+            .map(|t| {
+                let seed = t.seed.map_or(String::from("secure"), |s| format!("seed {}", s));
+                format!("{} players, {}'s turn, {}", t.hands.len(), t.names[t.game_state.current_player], seed)
+            })
+            .unwrap_or(String::from("damaged"));
+        slots.push((slot.to_string(), summary));
+    }
+    slots.sort();
+    Ok(slots)
+}
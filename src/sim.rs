@@ -0,0 +1,340 @@
+// `UNOler simulate`: plays lots of AI only games without printing any of them and reports how they went
+// Good for checking the engine at scale and for seeing how the difficulties stack up
+// `UNOler bench-ai` does the same for every pair of difficulties, head to head
+// `UNOler exhibition` plays the same few seeded games every time and prints how each one went
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+use unoler::events::{Event, Frontend, Prompt};
+use unoler::game::{Difficulty, Settings, difficulty_name, play, setup};
+use unoler::pool::{Pool, default_threads};
+use unoler::rng::{Randler, Rng};
+use unoler::rules::RuleSet;
+
+use crate::ratings::{Player, Ratings};
+
+// Games that go on longer than this are stopped and counted as unfinished
+pub const DEFAULT_TURN_LIMIT: usize = 10_000;
+
+// Counts what happens, every seat is an AI so nothing ever gets asked
+#[derive(Default)]
+struct Tally {
+    turns: usize,
+    reshuffles: usize,
+}
+
+impl Frontend for Tally {
+    fn event(&mut self, event: &Event) {
+        match event {
+            Event::TurnStart { .. } => self.turns += 1,
+            Event::Reshuffled { .. } | Event::NewDeck => self.reshuffles += 1,
+            _ => {},
+        }
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        match prompt {
+            Prompt::Continue | Prompt::Exit => String::new(),
+            _ => panic!("A simulated game asked for {}", prompt.name()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub games: usize,
+    pub seats: usize,
+    pub mix: Vec<Difficulty>, // Cycled over the seats
+    pub rules: RuleSet,
+    pub seed: u64, // Every game's seed comes from this one, so a whole run can be repeated
+    pub turn_limit: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Results {
+    pub wins: Vec<usize>, // Per seat
+    pub unfinished: usize,
+    pub turns: usize, // Totals over every game
+    pub reshuffles: usize,
+    pub winners: Vec<Option<usize>>, // Every game's winning seat, in the order the seeds were drawn
+}
+
+impl Results {
+    fn add(&mut self, other: &Results) {
+        for (wins, more) in self.wins.iter_mut().zip(&other.wins) {
+            *wins += more;
+        }
+        self.unfinished += other.unfinished;
+        self.turns += other.turns;
+        self.reshuffles += other.reshuffles;
+        self.winners.extend(&other.winners);
+    }
+}
+
+// The games with these seeds, one after another
+fn run_seeds(sim: &Simulation, seeds: &[u64]) -> Result<Results, Box<dyn std::error::Error>> {
+    let mut results = Results { wins: vec![0; sim.seats], ..Results::default() };
+
+    for &seed in seeds {
+        let settings = Settings {
+            players: Some(0),
+            ai_players: Some(sim.seats as u8),
+            difficulty: sim.mix.first().copied(),
+            ai_mix: sim.mix.clone(),
+            rules: sim.rules,
+            seed: Some(seed),
+            ..Settings::default()
+        };
+
+        let mut tally = Tally::default();
+        let Some(mut table) = setup(&mut tally, &settings)? else { break };
+        let winner = play(&mut table, &mut tally, Some(sim.turn_limit))?;
+        match winner {
+            Some(winner) => results.wins[winner - 1] += 1,
+            None => results.unfinished += 1,
+        }
+        results.winners.push(winner);
+        results.turns += tally.turns;
+        results.reshuffles += tally.reshuffles;
+    }
+
+    Ok(results)
+}
+
+// Games are handed to the workers this many at a time: enough that a job is worth queueing, few enough that a run
+// spreads evenly over the threads
+const GAMES_PER_JOB: usize = 32;
+
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+// --threads, before the first run. Nothing set is one per core
+pub fn set_threads(threads: usize) {
+    THREADS.store(threads, Ordering::Relaxed);
+}
+
+// Every run in the process shares one pool, bench-ai and tournaments do a lot of them
+fn pool() -> &'static Pool {
+    static POOL: OnceLock<Pool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let threads = match THREADS.load(Ordering::Relaxed) {
+            0 => default_threads(),
+            threads => threads,
+        };
+        Pool::new(threads, threads * 2)
+    })
+}
+
+pub fn run(sim: &Simulation) -> Result<Results, Box<dyn std::error::Error>> {
+    run_on(sim, pool())
+}
+
+// Game n of a run is dealt from the nth number drawn from the run's seed, whatever the machine or the thread count,
+// so results published with their seed can be played again to the game
+pub fn game_seeds(seed: u64, games: usize) -> Vec<u64> {
+    let mut rand = Randler::new(seed);
+    (0..games).map(|_| rand.next_u64()).collect()
+}
+
+// The command that runs the same games again, printed under the results. The version is there because a change to an
+// AI or the engine changes the games a seed plays
+pub fn reproduce(command: &str, rules: &RuleSet, seed: u64) -> String {
+    let rules = rules.flags();
+    let rules = if rules.is_empty() { rules } else { format!(" {}", rules) };
+    format!("Reproduce with UNOler {}: UNOler {}{} --seed {}", env!("CARGO_PKG_VERSION"), command, rules, seed)
+}
+
+// The seeds are drawn up front and split into jobs, so the totals come out the same however many threads there are
+fn run_on(sim: &Simulation, pool: &Pool) -> Result<Results, Box<dyn std::error::Error>> {
+    let seeds = game_seeds(sim.seed, sim.games);
+    let jobs: Vec<Vec<u64>> = seeds.chunks(GAMES_PER_JOB).map(<[u64]>::to_vec).collect();
+    let shared = Arc::new(sim.clone());
+    let parts = pool.map(jobs, move |seeds| run_seeds(&shared, &seeds).map_err(|e| e.to_string()))?;
+
+    let mut results = Results { wins: vec![0; sim.seats], ..Results::default() };
+    for part in parts {
+        results.add(&part?);
+    }
+    Ok(results)
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 * 100.0 / whole as f64 }
+}
+
+pub fn report(sim: &Simulation) -> Result<(), Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let results = run(sim)?;
+    let games = sim.games.max(1) as f64;
+
+    let threads = pool().threads();
+    println!(
+        "Simulated {} games with {} AI players (seed {}) in {:.1}s on {} thread{}\n",
+        sim.games, sim.seats, sim.seed, started.elapsed().as_secs_f64(), threads, if threads == 1 { "" } else { "s" },
+    );
+    println!("Seat  Difficulty  Wins      Win rate");
+    for (seat, wins) in results.wins.iter().enumerate() {
+        let difficulty = difficulty_name(sim.mix[seat % sim.mix.len()]);
+        println!("{:<5} {:<11} {:<9} {:.1}%", seat + 1, difficulty, wins, percent(*wins, sim.games));
+    }
+    if results.unfinished > 0 {
+        println!("Unfinished after {} turns: {} ({:.1}%)", sim.turn_limit, results.unfinished, percent(results.unfinished, sim.games));
+    }
+    println!("\nAverage turns: {:.1}", results.turns as f64 / games);
+    println!("Average reshuffles: {:.2}", results.reshuffles as f64 / games);
+    let mix = sim.mix.iter().map(|&d| difficulty_name(d)).collect::<Vec<_>>().join(",");
+    println!("\n{}", reproduce(&format!("simulate --games {} --players {} --ai-mix {}", sim.games, sim.seats, mix), &sim.rules, sim.seed));
+    Ok(())
+}
+
+pub const DIFFICULTIES: [Difficulty; 5] = [Difficulty::Calm, Difficulty::Aggressive, Difficulty::Skilled, Difficulty::Random, Difficulty::Expert];
+
+// 95% Wilson score interval, it behaves better than the textbook one near 0% and 100%
+fn wilson(wins: usize, games: usize) -> (f64, f64) {
+    if games == 0 {
+        return (0.0, 100.0);
+    }
+    let (n, p, z) = (games as f64, wins as f64 / games as f64, 1.96);
+    let center = (p + z * z / (2.0 * n)) / (1.0 + z * z / n);
+    let spread = z / (1.0 + z * z / n) * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt();
+    ((center - spread).max(0.0) * 100.0, (center + spread).min(1.0) * 100.0)
+}
+
+// Every pairing plays the same seeds, half the games with each AI going first so the first turn doesn't skew things.
+// With ratings the games are scored into them one by one as well
+pub fn bench(games: usize, rules: RuleSet, seed: u64, turn_limit: usize, mut ratings: Option<&mut Ratings>) -> Result<(), Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let half = games / 2;
+
+    println!("Win rate of the row AI against the column AI, {} games per pairing (seed {}), with 95% confidence intervals\n", half * 2, seed);
+    let mut header = format!("{:<11}", "");
+    for column in DIFFICULTIES {
+        header.push_str(&format!(" {:<20}", difficulty_name(column)));
+    }
+    println!("{}", header.trim_end());
+
+    for row in DIFFICULTIES {
+        let mut line = format!("{:<11}", difficulty_name(row));
+        for column in DIFFICULTIES {
+            let mut wins = 0;
+            let mut finished = 0;
+            for (mix, seat) in [(vec![row, column], 0), (vec![column, row], 1)] {
+                let seats = [Player::Ai(mix[0]), Player::Ai(mix[1])];
+                let results = run(&Simulation { games: half, seats: 2, mix, rules, seed, turn_limit })?;
+                if let Some(ratings) = ratings.as_deref_mut() {
+                    for winner in results.winners.iter().flatten() {
+                        ratings.record(&seats, *winner);
+                    }
+                }
+                wins += results.wins[seat];
+                finished += results.wins.iter().sum::<usize>();
+            }
+            let (low, high) = wilson(wins, finished);
+            line.push_str(&format!(" {:<20}", format!("{:.1}% ({:.1}-{:.1})", percent(wins, finished), low, high)));
+        }
+        println!("{}", line.trim_end());
+    }
+
+    println!("\nDone in {:.1}s, unfinished games are left out", started.elapsed().as_secs_f64());
+    println!("{}", reproduce(&format!("bench-ai --games {}", half * 2), &rules, seed));
+    Ok(())
+}
+
+use Difficulty::*;
+
+// Never change these, the point is an old table to compare against. Lineups are by seat
+pub const EXHIBITION: [(u64, &[Difficulty]); 10] = [
+    (1, &[Calm, Calm]),
+    (2, &[Skilled, Aggressive]),
+    (3, &[Random, Expert]),
+    (4, &[Calm, Aggressive, Skilled]),
+    (5, &[Expert, Random, Calm]),
+    (6, &[Skilled, Skilled, Skilled, Skilled]),
+    (7, &[Aggressive, Calm, Random, Skilled]),
+    (8, &[Expert, Skilled, Aggressive, Calm]),
+    (42, &[Random, Random, Random, Random, Random, Random]),
+    (1234, &[Calm, Skilled, Expert, Aggressive, Random]),
+];
+
+// One line per exhibition game. Nothing in it depends on the machine or the clock, so the same engine always prints
+// the same lines and a change in them means the games played differently
+pub fn exhibition(rules: RuleSet, turn_limit: usize) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut lines = vec![format!("{:<6} {:<47} {:<15} Turns", "Seed", "Seats", "Winner")];
+    for (seed, mix) in EXHIBITION {
+        let sim = Simulation { games: 1, seats: mix.len(), mix: mix.to_vec(), rules, seed, turn_limit };
+        let results = run_seeds(&sim, &[seed])?;
+        let seats = mix.iter().map(|d| difficulty_name(*d)).collect::<Vec<_>>().join(", ");
+        let winner = match results.winners[0] {
+            Some(seat) => format!("{} ({})", seat, difficulty_name(mix[seat - 1])),
+            None => String::from("unfinished"),
+        };
+        lines.push(format!("{:<6} {:<47} {:<15} {}", seed, seats, winner, results.turns));
+    }
+    Ok(lines)
+}
+
+pub fn exhibit(rules: RuleSet, turn_limit: usize) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}\n", rules.summary());
+    for line in exhibition(rules, turn_limit)? {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threads_share_the_games_without_changing_them() {
+        let sim = Simulation {
+            games: 75,
+            seats: 3,
+            mix: vec![Difficulty::Skilled, Difficulty::Calm],
+            rules: RuleSet::default(),
+            seed: 4,
+            turn_limit: DEFAULT_TURN_LIMIT,
+        };
+        let alone = run_on(&sim, &Pool::new(1, 1)).unwrap();
+        assert_eq!(alone.wins.iter().sum::<usize>() + alone.unfinished, 75);
+        assert_eq!(alone.winners.len(), 75);
+        assert_eq!(run_on(&sim, &Pool::new(4, 8)).unwrap(), alone);
+        assert_eq!(run_on(&sim, &Pool::new(64, 0)).unwrap(), alone);
+    }
+
+    #[test]
+    fn every_game_of_a_run_can_be_played_again_from_its_seed() {
+        let sim = Simulation { games: 12, seats: 2, mix: vec![Difficulty::Expert, Difficulty::Random], rules: RuleSet::default(), seed: 77, turn_limit: DEFAULT_TURN_LIMIT };
+        let run = run_on(&sim, &Pool::new(3, 6)).unwrap();
+        let seeds = game_seeds(77, 12);
+        assert_eq!(seeds[..5], game_seeds(77, 5), "a shorter run plays the first games of a longer one");
+        for (n, seed) in seeds.into_iter().enumerate() {
+            assert_eq!(run_seeds(&sim, &[seed]).unwrap().winners, [run.winners[n]], "game {}", n + 1);
+        }
+
+        let rules = RuleSet { draw_one: true, ..RuleSet::default() };
+        assert_eq!(reproduce("simulate --games 12", &rules, 77), format!("Reproduce with UNOler {}: UNOler simulate --games 12 --draw-one on --seed 77", env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn the_exhibition_comes_out_the_same_every_time() {
+        let lines = exhibition(RuleSet::default(), DEFAULT_TURN_LIMIT).unwrap();
+        assert_eq!(exhibition(RuleSet::default(), DEFAULT_TURN_LIMIT).unwrap(), lines);
+        // If an engine change moves these on purpose, update them in the same commit
+        let pinned = [
+            "1      calm, calm                                      1 (calm)        13",
+            "2      skilled, aggressive                             2 (aggressive)  28",
+            "3      random, expert                                  2 (expert)      30",
+            "4      calm, aggressive, skilled                       3 (skilled)     22",
+            "5      expert, random, calm                            1 (expert)      19",
+            "6      skilled, skilled, skilled, skilled              3 (skilled)     97",
+            "7      aggressive, calm, random, skilled               4 (skilled)     116",
+            "8      expert, skilled, aggressive, calm               2 (skilled)     122",
+            "42     random, random, random, random, random, random  3 (random)      53",
+            "1234   calm, skilled, expert, aggressive, random       4 (aggressive)  184",
+        ];
+        assert_eq!(lines[1..], pinned);
+        assert_ne!(exhibition(RuleSet { stacking: false, ..RuleSet::default() }, DEFAULT_TURN_LIMIT).unwrap(), lines, "the rules count");
+    }
+}
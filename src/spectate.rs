@@ -0,0 +1,249 @@
+// `UNOler spectate`: a whole game between AI seats, told one line a turn at the pace picked with --speed, or played
+// straight through with only the result at the end. The seats go around --ai-mix (or --difficulty) and are named for
+// their difficulty, so a demo or an AI change can be watched without anyone at the keyboard
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use unoler::cards::{Color, UNOCard, get_color};
+use unoler::events::{Event, Frontend, Prompt, TextFrontend};
+use unoler::game::{Difficulty, Settings, Table, difficulty_name, play, round_table, setup};
+
+// How long every turn stays up before the next one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    Instant, // Nothing but the result
+    Paced(Duration),
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Speed::Paced(Duration::from_secs(1))
+    }
+}
+
+impl FromStr for Speed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "slow" => Ok(Speed::Paced(Duration::from_secs(2))),
+            "normal" => Ok(Speed::default()),
+            "fast" => Ok(Speed::Paced(Duration::from_millis(250))),
+            "instant" => Ok(Speed::Instant),
+            seconds => match seconds.parse::<f64>() {
+                Ok(0.0) => Ok(Speed::Instant),
+                Ok(seconds) if seconds > 0.0 && seconds <= 60.0 => Ok(Speed::Paced(Duration::from_secs_f64(seconds))),
+                _ => Err(format!("Unknown speed {} (try slow, normal, fast, instant or a number of seconds a turn)", s)),
+            },
+        }
+    }
+}
+
+// What the seats are called, "Skilled #2" and so on, in seat order
+pub fn seat_names(mix: &[Difficulty], seats: usize) -> Vec<String> {
+    (0..seats).map(|i| {
+        let name = difficulty_name(mix[i % mix.len()]);
+        format!("{}{} #{}", name[..1].to_uppercase(), &name[1..], i + 1)
+    }).collect()
+}
+
+// The turn being told, printed once it's over
+struct Turn {
+    player: usize,
+    cards: usize, // Held when the turn started
+    top: String,
+    draws: usize,
+    actions: Vec<String>,
+    wild: Option<UNOCard>, // Told once its color is picked
+}
+
+pub struct Commentary {
+    text: TextFrontend, // Only for printing cards the way the rest of the game does
+    speed: Speed,
+    names: Vec<String>,
+    hands: Vec<usize>, // Sizes at the start of the turn
+    turns: usize,
+    turn: Option<Turn>,
+    notes: Vec<String>, // Table talk, under the turn it came up in
+}
+
+impl Commentary {
+    pub fn new(text: TextFrontend, speed: Speed) -> Self {
+        Commentary { text, speed, names: Vec::new(), hands: Vec::new(), turns: 0, turn: None, notes: Vec::new() }
+    }
+
+    fn card(&self, card: &UNOCard) -> String {
+        self.text.card(card)
+    }
+
+    fn name(&self, player: usize) -> String {
+        self.names.get(player.wrapping_sub(1)).cloned().unwrap_or(format!("Player #{}", player))
+    }
+
+    // The finished turn and whatever was said during it
+    fn finish(&mut self) -> Vec<String> {
+        let Some(turn) = self.turn.take() else { return std::mem::take(&mut self.notes) };
+        let mut parts = Vec::new();
+        if turn.draws > 0 {
+            parts.push(format!("draws {}", turn.draws));
+        }
+        parts.extend(turn.actions);
+        if parts.is_empty() {
+            parts.push(String::from("passes"));
+        }
+        let line = format!("{:>4}. {} ({} cards) on {}: {}", self.turns, self.name(turn.player), turn.cards, turn.top, parts.join(", "));
+        std::iter::once(line).chain(std::mem::take(&mut self.notes)).collect()
+    }
+
+    // The lines an event makes, if any are ready yet
+    fn narrate(&mut self, event: &Event) -> Vec<String> {
+        let action = match event {
+            Event::GameStart { names, seed, .. } => {
+                self.names = names.clone();
+                let seed = seed.map_or(String::from("a secure shuffle"), |s| format!("seed {}", s));
+                return vec![format!("{}, with {}\n", names.join(" vs "), seed)];
+            },
+            Event::TurnStart { player, last_played, .. } => {
+                let mut lines = self.finish();
+                self.turns += 1;
+                let cards = self.hands.get(player - 1).copied().unwrap_or(0);
+                self.turn = Some(Turn { player: *player, cards, top: self.card(last_played), draws: 0, actions: Vec::new(), wild: None });
+                lines.retain(|l| !l.is_empty());
+                return lines;
+            },
+            Event::Notice(message) => {
+                self.notes.push(format!("      {}", message));
+                return Vec::new();
+            },
+            // A caught bluff has the seat before draw on this turn, the challenge says so
+            Event::ForcedDraw { player, .. } if self.turn.as_ref().is_some_and(|t| t.player != *player) => return Vec::new(),
+            Event::Drew { .. } | Event::ForcedDraw { .. } => {
+                if let Some(turn) = self.turn.as_mut() {
+                    turn.draws += 1;
+                }
+                return Vec::new();
+            },
+            Event::Played { card, .. } if card.color == Color::NA => {
+                if let Some(turn) = self.turn.as_mut() {
+                    turn.wild = Some(*card);
+                }
+                return Vec::new();
+            },
+            Event::Played { card, .. } => format!("plays {}", self.card(card)),
+            Event::ColorChosen { color, .. } => match self.turn.as_mut().and_then(|t| t.wild.take()) {
+                Some(wild) => format!("plays {}", self.card(&UNOCard { color: *color, ..wild })),
+                None => format!("picks {}", get_color(color).to_lowercase()),
+            },
+            Event::Skipped { .. } => String::from("is skipped"),
+            Event::Traded { .. } => String::from("trades a card with the deck"),
+            Event::Uno { .. } => String::from("UNO!"),
+            Event::Challenge { against, bluff: true, .. } => format!("challenges the +4 and catches {} bluffing", self.name(*against)),
+            Event::Challenge { against, bluff: false, .. } => format!("challenges {}'s +4, which was no bluff", self.name(*against)),
+            Event::Reshuffled { .. } => String::from("the discard pile is shuffled back in"),
+            Event::NewDeck => String::from("a new deck is opened"),
+            Event::Win { player } => {
+                let mut lines = self.finish();
+                lines.push(format!("\n{} wins after {} turns", self.name(*player), self.turns));
+                return lines;
+            },
+            Event::InvalidInput(_) | Event::IllegalMove(_) | Event::LastCard(_) | Event::Hand(_) => return Vec::new(),
+        };
+        if let Some(turn) = self.turn.as_mut() {
+            turn.actions.push(action);
+        }
+        Vec::new()
+    }
+}
+
+impl Frontend for Commentary {
+    fn event(&mut self, event: &Event) {
+        let lines = self.narrate(event);
+        if self.speed != Speed::Instant {
+            lines.iter().for_each(|line| println!("{}", line));
+        }
+    }
+
+    // Nobody is asked anything, the pause between turns is where the pacing goes
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        if let (Prompt::Continue, Speed::Paced(delay)) = (prompt, self.speed) {
+            self.finish().iter().for_each(|line| println!("{}", line));
+            thread::sleep(delay);
+        }
+        String::new()
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.hands = table.hands.iter().map(|h| h.len()).collect();
+    }
+
+    fn save(&mut self, _slot: &str) -> Result<PathBuf, String> {
+        Err(String::from("spectated games aren't saved"))
+    }
+}
+
+// Deals and plays the game, then the scores, and under instant the only thing printed is how it ended
+pub fn run(settings: &Settings, fe: &mut dyn Frontend, speed: Speed, turn_limit: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(mut table) = setup(fe, settings)? else { return Ok(()) };
+    let winner = play(&mut table, fe, Some(turn_limit))?;
+    let Some(winner) = winner else {
+        println!("Nobody won in {} turns", turn_limit);
+        return Ok(());
+    };
+    if speed == Speed::Instant {
+        println!("{} wins", table.names[winner - 1]);
+    }
+    for line in round_table(&table, &table.names, winner) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::testing::card;
+
+    fn told(events: &[Event]) -> Vec<String> {
+        let mut commentary = Commentary::new(TextFrontend::new(false, false), Speed::Instant);
+        commentary.names = seat_names(&[Difficulty::Skilled, Difficulty::Calm], 2);
+        commentary.hands = vec![7, 7];
+        events.iter().flat_map(|e| commentary.narrate(e)).collect()
+    }
+
+    #[test]
+    fn speeds_read_like_the_help_says() {
+        assert_eq!("fast".parse(), Ok(Speed::Paced(Duration::from_millis(250))));
+        assert_eq!("0.5".parse(), Ok(Speed::Paced(Duration::from_millis(500))));
+        assert_eq!("0".parse(), Ok(Speed::Instant));
+        assert!("-1".parse::<Speed>().is_err());
+        assert!("soon".parse::<Speed>().is_err());
+    }
+
+    #[test]
+    fn seats_are_named_for_their_difficulty() {
+        assert_eq!(seat_names(&[Difficulty::Expert, Difficulty::Calm], 3), ["Expert #1", "Calm #2", "Expert #3"]);
+    }
+
+    #[test]
+    fn every_turn_is_one_line() {
+        let lines = told(&[
+            Event::TurnStart { player: 1, ai: true, last_played: card("red 4") },
+            Event::Drew { player: 1, card: None },
+            Event::Played { player: 1, ai: true, card: card("wild") },
+            Event::ColorChosen { player: 1, color: card("blue 1").color },
+            Event::Notice(String::from("Skilled #1: \"Tempo.\"")),
+            Event::TurnStart { player: 2, ai: true, last_played: card("wild blue") },
+            Event::Played { player: 2, ai: true, card: card("blue 2") },
+            Event::Win { player: 2 },
+        ]);
+        assert_eq!(lines, [
+            "   1. Skilled #1 (7 cards) on Red 4: draws 1, plays Wild Card (Blue)",
+            "      Skilled #1: \"Tempo.\"",
+            "   2. Calm #2 (7 cards) on Wild Card (Blue): plays Blue 2",
+            "\nCalm #2 wins after 2 turns",
+        ]);
+    }
+}
@@ -0,0 +1,151 @@
+// Local game statistics, off unless `enabled = true` under `[stats]` in the config file
+// Every finished game adds one line to stats.jsonl next to the saves: how many seats of each kind, the AI difficulties,
+// the rules, how many turns it took and whether a human or an AI won. No names, seeds or hands, and nothing is ever
+// sent anywhere. `UNOler stats` sums the file up, `UNOler stats clear` deletes it
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use unoler::game::{Difficulty, Table, difficulty_name};
+use unoler::json::Json;
+use unoler::rules::RuleSet;
+
+use crate::save;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub humans: usize,
+    pub ais: Vec<Difficulty>, // One per AI seat, in seat order
+    pub rules: RuleSet,
+    pub turns: usize,
+    pub winner: Option<Difficulty>, // None when a human won
+}
+
+impl GameRecord {
+    pub fn new(t: &Table, winner: usize, turns: usize) -> GameRecord {
+        let humans = t.players as usize;
+        let ais = t.difficulties[humans..].to_vec();
+        let winner = winner.checked_sub(humans + 1).map(|ai| ais[ai]);
+        GameRecord { humans, ais, rules: t.rules, turns, winner }
+    }
+
+    fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("humans", self.humans.into()),
+            ("ais", Json::Array(self.ais.iter().map(|d| difficulty_name(*d).into()).collect())),
+            ("rules", self.rules.to_json()),
+            ("turns", self.turns.into()),
+            ("winner", self.winner.map_or(Json::from("human"), |d| Json::from(difficulty_name(d)))),
+        ])
+    }
+
+    fn from_json(value: &Json) -> Option<GameRecord> {
+        let winner = match value.get("winner")?.as_str()? {
+            "human" => None,
+            ai => Some(ai.parse().ok()?),
+        };
+        Some(GameRecord {
+            humans: value.get("humans")?.as_f64()? as usize,
+            ais: value.get("ais")?.as_array()?.iter().map(|d| d.as_str()?.parse().ok()).collect::<Option<_>>()?,
+            rules: RuleSet::from_json(value.get("rules")?)?,
+            turns: value.get("turns")?.as_f64()? as usize,
+            winner,
+        })
+    }
+}
+
+pub fn stats_path() -> PathBuf {
+    save::data_dir().join("stats.jsonl")
+}
+
+pub fn append(path: &Path, record: &GameRecord) -> io::Result<()> {
+    fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", record.to_json())
+}
+
+// Lines that don't read back are skipped, one bad write shouldn't lose the rest
+pub fn load(path: &Path) -> io::Result<Vec<GameRecord>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(text.lines().filter_map(|line| Json::parse(line).ok().and_then(|v| GameRecord::from_json(&v))).collect())
+}
+
+pub fn clear(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 * 100.0 / whole as f64 }
+}
+
+// Only games with a human at the table say anything about how people do against the AIs
+pub fn summary(records: &[GameRecord]) -> Vec<String> {
+    let played: Vec<&GameRecord> = records.iter().filter(|r| r.humans > 0).collect();
+    if played.is_empty() {
+        return vec![String::from("No games recorded yet")];
+    }
+    let human_wins = played.iter().filter(|r| r.winner.is_none()).count();
+    let turns: usize = played.iter().map(|r| r.turns).sum();
+    let mut lines = vec![
+        format!("{} games, humans won {} ({:.1}%)", played.len(), human_wins, percent(human_wins, played.len())),
+        format!("{:.1} turns a game on average", turns as f64 / played.len() as f64),
+    ];
+
+    for difficulty in [Difficulty::Calm, Difficulty::Aggressive, Difficulty::Skilled, Difficulty::Random, Difficulty::Expert] {
+        let against: Vec<&&GameRecord> = played.iter().filter(|r| r.ais.contains(&difficulty)).collect();
+        if against.is_empty() {
+            continue;
+        }
+        let wins = against.iter().filter(|r| r.winner.is_none()).count();
+        let beaten_by = played.iter().filter(|r| r.winner == Some(difficulty)).count();
+        lines.push(format!(
+            "Against {}: {} games, humans won {:.1}%, {} AIs won {}",
+            difficulty_name(difficulty), against.len(), percent(wins, against.len()), difficulty_name(difficulty), beaten_by,
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(ais: &[Difficulty], winner: Option<Difficulty>) -> GameRecord {
+        GameRecord { humans: 1, ais: ais.to_vec(), rules: RuleSet::default(), turns: 30, winner }
+    }
+
+    #[test]
+    fn records_are_appended_and_read_back() {
+        let path = std::env::temp_dir().join(format!("unoler-stats-{}.jsonl", std::process::id()));
+        clear(&path).unwrap();
+        let first = record(&[Difficulty::Calm], None);
+        let second = record(&[Difficulty::Skilled, Difficulty::Calm], Some(Difficulty::Skilled));
+        append(&path, &first).unwrap();
+        append(&path, &second).unwrap();
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "not json\n").unwrap();
+        assert_eq!(load(&path).unwrap(), [first, second]);
+        clear(&path).unwrap();
+        assert!(load(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn summary_counts_human_wins() {
+        let records = [
+            record(&[Difficulty::Calm], None),
+            record(&[Difficulty::Skilled], Some(Difficulty::Skilled)),
+            GameRecord { humans: 0, ..record(&[Difficulty::Calm, Difficulty::Calm], Some(Difficulty::Calm)) },
+        ];
+        let lines = summary(&records);
+        assert_eq!(lines[0], "2 games, humans won 1 (50.0%)");
+        assert!(lines.iter().any(|l| l == "Against skilled: 1 games, humans won 0.0%, skilled AIs won 1"));
+        assert_eq!(summary(&[]), ["No games recorded yet"]);
+    }
+}
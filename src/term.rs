@@ -0,0 +1,221 @@
+// Raw terminal input for --keys, without a terminal library: stty switches the terminal out of line mode and back,
+// and keys are read as the bytes the terminal sends for them. Only real terminals on unix get this, everything else
+// stays in line mode
+
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(unix)]
+unsafe extern "C" {
+    unsafe fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Enter,
+    Backspace,
+    Escape,
+    Char(char),
+    Click { row: usize, col: usize }, // A left button press, counted from 1 like the terminal does
+    Position { row: usize, col: usize }, // The answer to where_am_i
+}
+
+pub fn available() -> bool {
+    cfg!(unix)
+        && io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+        && std::env::var("TERM").is_ok_and(|t| t != "dumb")
+}
+
+// stty works on whatever its stdin is, which has to be the terminal itself
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).stderr(Stdio::null()).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Keys come in one at a time without echo while this is alive, the old settings come back when it's dropped
+// (a panic included, so a crash doesn't leave the terminal unusable)
+pub struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    pub fn enter() -> Option<RawMode> {
+        RawMode::with(&["min", "1"])
+    }
+
+    // Reads give up after a tenth of a second without a key, for a screen that changes while nobody types
+    pub fn enter_ticking() -> Option<RawMode> {
+        RawMode::with(&["min", "0", "time", "1"])
+    }
+
+    fn with(wait: &[&str]) -> Option<RawMode> {
+        let saved = stty(&["-g"])?;
+        stty(&[&["-icanon", "-echo"], wait].concat())?;
+        Some(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+        let _ = io::stdout().flush();
+    }
+}
+
+// Mouse reports while this is alive, in the SGR form (ESC [ < button ; col ; row M) that works past column 223
+pub struct MouseReporting;
+
+impl MouseReporting {
+    pub fn enable() -> MouseReporting {
+        print!("\x1b[?1000h\x1b[?1006h");
+        let _ = io::stdout().flush();
+        MouseReporting
+    }
+}
+
+impl Drop for MouseReporting {
+    fn drop(&mut self) {
+        print!("\x1b[?1000l\x1b[?1006l");
+        let _ = io::stdout().flush();
+    }
+}
+
+// Rows and columns, from stty like everything else here
+pub fn size() -> Option<(usize, usize)> {
+    let size = stty(&["size"])?;
+    let (rows, cols) = size.split_once(' ')?;
+    Some((rows.parse().ok()?, cols.parse().ok()?))
+}
+
+// Scrolling stops one line short of the bottom while this is alive, so a status line there stays put
+pub struct ScrollRegion {
+    pub rows: usize,
+}
+
+impl ScrollRegion {
+    pub fn reserve_bottom_line(rows: usize) -> ScrollRegion {
+        // Setting the region moves the cursor to the top, so it's saved around it, after making sure it's not about
+        // to end up on the reserved line
+        print!("\r\n\x1b[1A\x1b7\x1b[1;{}r\x1b8", rows - 1);
+        let _ = io::stdout().flush();
+        ScrollRegion { rows }
+    }
+}
+
+impl Drop for ScrollRegion {
+    fn drop(&mut self) {
+        print!("\x1b7\x1b[r\x1b[{};1H\x1b[2K\x1b8", self.rows);
+        let _ = io::stdout().flush();
+    }
+}
+
+// Asks the terminal where the cursor is, the answer comes in with the keys as a Key::Position
+pub fn where_am_i() {
+    print!("\x1b[6n");
+    let _ = io::stdout().flush();
+}
+
+// The keys in a chunk of terminal input. Arrow keys are escape sequences, ESC [ and a letter (ESC O and a letter
+// in application mode), and an escape on its own is the escape key. Mouse presses and cursor positions are escape
+// sequences too, with their numbers before the last letter
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let text = String::from_utf8_lossy(bytes);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        keys.push(match c {
+            '\x1b' if matches!(chars.peek(), Some('[' | 'O')) => {
+                chars.next();
+                // Parameters like the 1;5 of a ctrl-arrow, up to the letter that ends the sequence
+                let mut params = String::new();
+                let mut last = None;
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() || c == '~' {
+                        last = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                let numbers: Vec<usize> = params.trim_start_matches('<').split(';').filter_map(|n| n.parse().ok()).collect();
+                match (last, numbers.as_slice()) {
+                    (Some('A'), _) => Key::Up,
+                    (Some('B'), _) => Key::Down,
+                    (Some('C'), _) => Key::Right,
+                    (Some('D'), _) => Key::Left,
+                    (Some('M'), [0, col, row]) if params.starts_with('<') => Key::Click { row: *row, col: *col },
+                    (Some('R'), [row, col]) => Key::Position { row: *row, col: *col },
+                    _ => continue,
+                }
+            },
+            '\x1b' => Key::Escape,
+            '\r' | '\n' => Key::Enter,
+            '\x7f' | '\x08' => Key::Backspace,
+            c => Key::Char(c),
+        });
+    }
+    keys
+}
+
+// Blocks until the terminal sends something, None when the input is closed
+pub fn read_keys() -> Option<Vec<Key>> {
+    let mut buffer = [0u8; 64];
+    match io::stdin().read(&mut buffer) {
+        Ok(0) | Err(_) => None,
+        Ok(n) => Some(parse_keys(&buffer[..n])),
+    }
+}
+
+// read_keys for ticking raw mode, where nothing for a tenth of a second is no keys. None when the terminal went away
+pub fn poll_keys() -> Option<Vec<Key>> {
+    let mut buffer = [0u8; 64];
+    io::stdin().read(&mut buffer).ok().map(|n| parse_keys(&buffer[..n]))
+}
+
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+// SIGWINCH has the same number on Linux, macOS and the BSDs. All the handler may do is set a flag, the ticking
+// read loop picks it up
+#[cfg(unix)]
+const SIGWINCH: i32 = 28;
+
+#[cfg(unix)]
+extern "C" fn on_resize(_: i32) {
+    RESIZED.store(true, Ordering::Relaxed);
+}
+
+// Only the first call does anything
+pub fn watch_resizes() {
+    static WATCHING: Once = Once::new();
+    WATCHING.call_once(|| {
+        #[cfg(unix)]
+        unsafe {
+            signal(SIGWINCH, on_resize);
+        }
+    });
+}
+
+// True once after every resize
+pub fn resized() -> bool {
+    RESIZED.swap(false, Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_sequences_become_keys() {
+        assert_eq!(parse_keys(b"\x1b[D\x1b[C\x1bOA\x1b[1;5B"), [Key::Left, Key::Right, Key::Up, Key::Down]);
+        assert_eq!(parse_keys(b"d\r\x7f\x1b"), [Key::Char('d'), Key::Enter, Key::Backspace, Key::Escape]);
+        assert_eq!(parse_keys(b"\x1b[3~x"), [Key::Char('x')], "keys nobody listens to are dropped");
+        assert_eq!(parse_keys(b"\x1b[<0;12;30M\x1b[<0;12;30m\x1b[<2;1;1M"), [Key::Click { row: 30, col: 12 }], "only left presses");
+        assert_eq!(parse_keys(b"\x1b[24;1R"), [Key::Position { row: 24, col: 1 }]);
+    }
+}
@@ -0,0 +1,258 @@
+// How long every human takes over their turns, from the moment they're asked to the answer. Only the questions of the
+// turn itself count (what to play, which color, whether to play the card just drawn), the pause menu doesn't
+//
+// The end of every game says how each of them did, and with [stats] enabled the totals are kept in thinking.json by
+// the name they play under, which `UNOler stats` shows. Batch runs answer from scripts and aren't timed at all
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use unoler::events::{Event, Frontend, Prompt};
+use unoler::game::Table;
+use unoler::json::Json;
+
+use crate::save;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Times {
+    pub turns: usize,
+    pub total: Duration,
+    pub longest: Duration,
+}
+
+impl Times {
+    pub fn add(&mut self, turn: Duration) {
+        self.turns += 1;
+        self.total += turn;
+        self.longest = self.longest.max(turn);
+    }
+
+    fn merge(&mut self, other: &Times) {
+        self.turns += other.turns;
+        self.total += other.total;
+        self.longest = self.longest.max(other.longest);
+    }
+
+    pub fn average(&self) -> Duration {
+        self.total.checked_div(self.turns as u32).unwrap_or_default()
+    }
+}
+
+// 4.2s, or 3m 05s once it's long enough for minutes to matter
+pub fn seconds(time: Duration) -> String {
+    let secs = time.as_secs_f64();
+    if secs < 60.0 { format!("{:.1}s", secs) } else { format!("{}m {:02}s", time.as_secs() / 60, time.as_secs() % 60) }
+}
+
+pub fn game_line(name: &str, times: &Times) -> String {
+    format!(
+        "{} thought for {} a turn, {} at most, {} in all",
+        name, seconds(times.average()), seconds(times.longest), seconds(times.total),
+    )
+}
+
+// Every human's totals over all their games
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profiles {
+    players: Vec<(String, Times)>,
+}
+
+impl Profiles {
+    pub fn record(&mut self, name: &str, times: &Times) {
+        match self.players.iter_mut().find(|(n, _)| n == name) {
+            Some((_, kept)) => kept.merge(times),
+            None => self.players.push((name.to_string(), *times)),
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        if self.players.is_empty() {
+            return Vec::new();
+        }
+        let mut lines = vec![String::new(), String::from("Thinking time")];
+        for (name, times) in &self.players {
+            lines.push(format!(
+                "  {:<20} {} a turn over {} turn{}, {} at most",
+                name, seconds(times.average()), times.turns, if times.turns == 1 { "" } else { "s" }, seconds(times.longest),
+            ));
+        }
+        lines
+    }
+
+    // Milliseconds, which is as fine as anybody's turn needs
+    fn to_json(&self) -> Json {
+        Json::Array(self.players.iter().map(|(name, times)| Json::object(vec![
+            ("name", name.as_str().into()),
+            ("turns", times.turns.into()),
+            ("total_ms", (times.total.as_millis() as usize).into()),
+            ("longest_ms", (times.longest.as_millis() as usize).into()),
+        ])).collect())
+    }
+
+    fn from_json(value: &Json) -> Option<Profiles> {
+        let millis = |p: &Json, key: &str| p.get(key)?.as_f64().map(|ms| Duration::from_millis(ms as u64));
+        let players = value.as_array()?.iter()
+            .map(|p| Some((p.get("name")?.as_str()?.to_string(), Times {
+                turns: p.get("turns")?.as_f64()? as usize,
+                total: millis(p, "total_ms")?,
+                longest: millis(p, "longest_ms")?,
+            })))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Profiles { players })
+    }
+}
+
+pub fn path() -> PathBuf {
+    save::data_dir().join("thinking.json")
+}
+
+// A file that doesn't read back starts everybody over, same as no file
+pub fn load(path: &Path) -> Profiles {
+    fs::read_to_string(path).ok()
+        .and_then(|text| Json::parse(&text).ok())
+        .and_then(|value| Profiles::from_json(&value))
+        .unwrap_or_default()
+}
+
+pub fn store(path: &Path, profiles: &Profiles) -> io::Result<()> {
+    fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    fs::write(path, profiles.to_json().to_string())
+}
+
+pub fn clear(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+// Times every human turn of a game and reports them when it's over
+pub struct Thinking {
+    inner: Box<dyn Frontend>,
+    profiles: Option<PathBuf>, // Where the totals go, None without [stats]
+    seats: Vec<Times>,
+    seat: Option<usize>, // The human whose turn it is
+    turn: Option<Duration>, // Their time so far this turn, over every question. None for a turn played for them
+}
+
+impl Thinking {
+    pub fn new(inner: Box<dyn Frontend>, profiles: Option<PathBuf>) -> Self {
+        Thinking { inner, profiles, seats: Vec::new(), seat: None, turn: None }
+    }
+
+    fn end_turn(&mut self) {
+        if let (Some(seat), Some(turn)) = (self.seat.take(), self.turn.take()) {
+            self.seats[seat].add(turn);
+        }
+    }
+}
+
+impl Frontend for Thinking {
+    fn event(&mut self, event: &Event) {
+        if let Event::GameStart { .. } = event {
+            self.seats.clear();
+            (self.seat, self.turn) = (None, None);
+        }
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        if !matches!(prompt, Prompt::Action { .. } | Prompt::Color { .. } | Prompt::PlayDrawn { .. } | Prompt::Challenge { .. }) || self.seat.is_none() {
+            return self.inner.prompt(prompt);
+        }
+        let asked = Instant::now();
+        let answer = self.inner.prompt(prompt);
+        *self.turn.get_or_insert_default() += asked.elapsed();
+        answer
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.end_turn();
+        self.seats.resize(table.hands.len(), Times::default());
+        let current = table.game_state.current_player;
+        self.seat = (!table.is_ai(current)).then_some(current);
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        self.end_turn();
+        let played: Vec<(&String, Times)> = table.names.iter().zip(std::mem::take(&mut self.seats)).filter(|(_, t)| t.turns > 0).collect();
+        for (name, times) in &played {
+            self.inner.event(&Event::Notice(game_line(name, times)));
+        }
+        if let Some(path) = self.profiles.as_ref().filter(|_| !played.is_empty()) {
+            let mut profiles = load(path);
+            played.iter().for_each(|(name, times)| profiles.record(name, times));
+            if let Err(e) = store(path, &profiles) {
+                eprintln!("Could not save the thinking times: {}", e);
+            }
+        }
+        self.inner.game_over(table, winner);
+    }
+
+    fn paused(&self) -> bool {
+        self.inner.paused()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::game::Difficulty;
+    use unoler::testing::{ScriptedFrontend, TestGame};
+
+    fn times(turns: &[u64]) -> Times {
+        let mut times = Times::default();
+        turns.iter().for_each(|&ms| times.add(Duration::from_millis(ms)));
+        times
+    }
+
+    #[test]
+    fn turns_add_up_per_player() {
+        let maria = times(&[2000, 6000, 1000]);
+        assert_eq!(game_line("Maria", &maria), "Maria thought for 3.0s a turn, 6.0s at most, 9.0s in all");
+        assert_eq!(seconds(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(Times::default().average(), Duration::ZERO);
+
+        let path = std::env::temp_dir().join(format!("unoler-thinking-{}.json", std::process::id()));
+        let mut profiles = Profiles::default();
+        profiles.record("Maria", &maria);
+        profiles.record("Bo", &times(&[500]));
+        profiles.record("Maria", &times(&[9000]));
+        store(&path, &profiles).unwrap();
+        assert_eq!(load(&path), profiles);
+        clear(&path).unwrap();
+        assert_eq!(profiles.lines()[2], "  Maria                4.5s a turn over 4 turns, 9.0s at most");
+    }
+
+    #[test]
+    fn only_questions_somebody_answered_are_timed() {
+        let mut game = TestGame::new(2).hand(0, &["red 5"]).hand(1, &["green 3"]).top("red 1").ai_from(1, Difficulty::Calm).build();
+        let mut script = ScriptedFrontend::default();
+        script.actions = ["d", "resume", "d"].map(String::from).into();
+        let mut fe = Thinking::new(Box::new(script), None);
+
+        fe.turn_start(game.table());
+        fe.prompt(&Prompt::Action { player: 1 });
+        fe.prompt(&Prompt::Menu { player: 1 });
+        fe.turn_start(game.table());
+        fe.turn_start(game.table());
+        assert_eq!(fe.seats[0].turns, 1, "a turn nobody was asked anything in isn't one");
+        game.table_mut().game_state.current_player = 1;
+        fe.turn_start(game.table());
+        fe.prompt(&Prompt::Action { player: 2 });
+        fe.end_turn();
+        assert_eq!(fe.seats[1], Times::default(), "AIs aren't timed");
+        assert_eq!(fe.seats[0].turns, 1);
+    }
+}
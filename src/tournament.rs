@@ -0,0 +1,467 @@
+// `UNOler tournament`: every AI in --ai-mix plays every other one heads-up, --games times a pairing, and the
+// standings come out as one table. Like bench-ai the games are played without showing them, half with each AI
+// going first, but only the entrants asked for play and they're ranked against the whole field
+// An AI here is its difficulty, the table talk personality comes with it and there are no weights to load
+
+use std::str::FromStr;
+use std::time::Instant;
+
+use unoler::events::{Event, Frontend, Prompt};
+use unoler::game::{Difficulty, Forfeited, Settings, difficulty_name, input, play, setup};
+use unoler::rng::{Randler, Rng};
+use unoler::rules::RuleSet;
+
+use crate::sim::{DEFAULT_TURN_LIMIT, Simulation, reproduce, run};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub entrant: Difficulty,
+    pub wins: usize,
+    pub losses: usize,
+    pub unfinished: usize,
+}
+
+impl Standing {
+    fn played(&self) -> usize {
+        self.wins + self.losses + self.unfinished
+    }
+}
+
+// One pairing, the first AI's wins and the second's
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pairing {
+    pub first: Difficulty,
+    pub second: Difficulty,
+    pub wins: (usize, usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tournament {
+    pub games: usize, // Per pairing
+    pub rules: RuleSet,
+    pub seed: u64, // Every pairing plays the games from this seed
+    pub turn_limit: usize,
+}
+
+// Each entrant once, in the order given
+pub fn entrants(mix: &[Difficulty]) -> Result<Vec<Difficulty>, String> {
+    let mut entrants: Vec<Difficulty> = Vec::new();
+    for &difficulty in mix {
+        if entrants.contains(&difficulty) {
+            return Err(format!("{} is in the tournament twice", difficulty_name(difficulty)));
+        }
+        entrants.push(difficulty);
+    }
+    if entrants.len() < 2 {
+        return Err(String::from("A tournament needs at least two different AIs"));
+    }
+    Ok(entrants)
+}
+
+// Plays every pairing, then the standings best first: most wins, then fewest losses, then the order given
+pub fn round_robin(entrants: &[Difficulty], tournament: &Tournament) -> Result<(Vec<Standing>, Vec<Pairing>), Box<dyn std::error::Error>> {
+    let mut standings: Vec<Standing> = entrants.iter()
+        .map(|&entrant| Standing { entrant, wins: 0, losses: 0, unfinished: 0 })
+        .collect();
+    let mut pairings = Vec::new();
+
+    for a in 0..entrants.len() {
+        for b in a + 1..entrants.len() {
+            let mut wins = (0, 0);
+            let mut unfinished = 0;
+            let first_half = tournament.games.div_ceil(2);
+            for (mix, games, a_seat) in [(vec![entrants[a], entrants[b]], first_half, 0), (vec![entrants[b], entrants[a]], tournament.games - first_half, 1)] {
+                let sim = Simulation { games, seats: 2, mix, rules: tournament.rules, seed: tournament.seed, turn_limit: tournament.turn_limit };
+                let results = run(&sim)?;
+                wins.0 += results.wins[a_seat];
+                wins.1 += results.wins[1 - a_seat];
+                unfinished += results.unfinished;
+            }
+            standings[a].wins += wins.0;
+            standings[a].losses += wins.1;
+            standings[b].wins += wins.1;
+            standings[b].losses += wins.0;
+            standings[a].unfinished += unfinished;
+            standings[b].unfinished += unfinished;
+            pairings.push(Pairing { first: entrants[a], second: entrants[b], wins });
+        }
+    }
+
+    standings.sort_by_key(|s| (std::cmp::Reverse(s.wins), s.losses));
+    Ok((standings, pairings))
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 * 100.0 / whole as f64 }
+}
+
+pub fn report(entrants: &[Difficulty], tournament: &Tournament) -> Result<(), Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let (standings, pairings) = round_robin(entrants, tournament)?;
+
+    println!(
+        "Round robin of {} AIs, {} games per pairing (seed {})\n",
+        entrants.len(), tournament.games, tournament.seed,
+    );
+    for pairing in &pairings {
+        println!("{:>11} {:>5} - {:<5} {}", difficulty_name(pairing.first), pairing.wins.0, pairing.wins.1, difficulty_name(pairing.second));
+    }
+
+    println!("\n#   AI          Played  Wins    Losses  Win rate");
+    for (place, standing) in standings.iter().enumerate() {
+        println!(
+            "{:<3} {:<11} {:<7} {:<7} {:<7} {:.1}%",
+            place + 1, difficulty_name(standing.entrant), standing.played(), standing.wins, standing.losses,
+            percent(standing.wins, standing.wins + standing.losses),
+        );
+    }
+    let unfinished: usize = standings.iter().map(|s| s.unfinished).sum::<usize>() / 2;
+    if unfinished > 0 {
+        println!("Unfinished after {} turns: {}, left out of the win rates", tournament.turn_limit, unfinished);
+    }
+
+    println!("\nDone in {:.1}s", started.elapsed().as_secs_f64());
+    let mix = entrants.iter().map(|&d| difficulty_name(d)).collect::<Vec<_>>().join(",");
+    println!("{}", reproduce(&format!("tournament --ai-mix {} --games {}", mix, tournament.games), &tournament.rules, tournament.seed));
+    Ok(())
+}
+
+// --bracket: a field of people and AIs playing one heads-up game a match, in one sitting
+// Knockout drops whoever loses until one is left. Swiss plays everybody every round against somebody on the same score
+// they haven't met yet, for as many rounds as a knockout of that size would take, and ranks by wins
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Knockout,
+    Swiss,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "knockout" | "single-elimination" => Ok(Format::Knockout),
+            "swiss" => Ok(Format::Swiss),
+            _ => Err(format!("Unknown bracket {} (try knockout or swiss)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entrant {
+    pub name: String,
+    pub ai: Option<Difficulty>, // None for a person at the keyboard
+}
+
+// The people from --names first, then the AIs from --ai-mix, named for their difficulty and place in the field
+pub fn field(names: &[String], mix: &[Difficulty]) -> Vec<Entrant> {
+    let people = names.iter().map(|name| Entrant { name: name.clone(), ai: None });
+    let ais = mix.iter().enumerate().map(|(i, &difficulty)| {
+        let name = difficulty_name(difficulty);
+        Entrant { name: format!("{}{} #{}", name[..1].to_uppercase(), &name[1..], names.len() + i + 1), ai: Some(difficulty) }
+    });
+    people.chain(ais).collect()
+}
+
+// Two entrants by their place in the field, or one with a bye
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    pub a: usize,
+    pub b: Option<usize>,
+    pub winner: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bracket {
+    pub format: Format,
+    pub entrants: Vec<Entrant>,
+    pub rounds: Vec<Vec<Match>>,
+    wins: Vec<usize>, // A bye counts as one
+    out: Vec<bool>, // Knocked out
+    byes: Vec<bool>, // Had one already, under swiss nobody gets two while somebody else hasn't had any
+}
+
+impl Bracket {
+    pub fn new(format: Format, entrants: Vec<Entrant>) -> Result<Bracket, String> {
+        if entrants.len() < 2 {
+            return Err(String::from("A bracket needs at least two entrants, from --names and --ai-mix"));
+        }
+        let n = entrants.len();
+        Ok(Bracket { format, entrants, rounds: Vec::new(), wins: vec![0; n], out: vec![false; n], byes: vec![false; n] })
+    }
+
+    fn swiss_rounds(&self) -> usize {
+        self.entrants.len().next_power_of_two().trailing_zeros() as usize
+    }
+
+    fn met(&self, a: usize, b: usize) -> bool {
+        self.rounds.iter().flatten().any(|m| (m.a, m.b) == (a, Some(b)) || (m.a, m.b) == (b, Some(a)))
+    }
+
+    // Everybody in order against the first one after them they haven't met that still lets the rest be paired
+    fn pair_up(&self, waiting: &[usize]) -> Option<Vec<(usize, usize)>> {
+        let Some((&a, rest)) = waiting.split_first() else { return Some(Vec::new()) };
+        for (i, &b) in rest.iter().enumerate() {
+            if self.met(a, b) {
+                continue;
+            }
+            let mut others = rest.to_vec();
+            others.remove(i);
+            if let Some(mut pairs) = self.pair_up(&others) {
+                pairs.insert(0, (a, b));
+                return Some(pairs);
+            }
+        }
+        None
+    }
+
+    pub fn finished(&self) -> bool {
+        match self.format {
+            Format::Knockout => self.out.iter().filter(|out| !**out).count() <= 1,
+            Format::Swiss => self.rounds.len() == self.swiss_rounds() && self.unplayed().is_none(),
+        }
+    }
+
+    // The next match of the current round without a winner
+    pub fn unplayed(&self) -> Option<usize> {
+        self.rounds.last()?.iter().position(|m| m.winner.is_none())
+    }
+
+    // Draws up the next round once the last one is over, false when the bracket is done
+    pub fn next_round(&mut self) -> bool {
+        if self.unplayed().is_some() || self.finished() {
+            return false;
+        }
+        let mut waiting: Vec<usize> = (0..self.entrants.len()).filter(|&i| !self.out[i]).collect();
+        if self.format == Format::Swiss {
+            // Most wins first, the field's order breaks ties
+            waiting.sort_by_key(|&i| std::cmp::Reverse(self.wins[i]));
+        }
+        let mut round = Vec::new();
+        if waiting.len() % 2 == 1 {
+            // The bye goes to the top of a knockout and the bottom of a swiss, to whoever hasn't had one
+            let order: Vec<usize> = match self.format {
+                Format::Knockout => waiting.clone(),
+                Format::Swiss => waiting.iter().rev().copied().collect(),
+            };
+            let bye = order.iter().copied().find(|&i| !self.byes[i]).unwrap_or(order[0]);
+            waiting.retain(|&i| i != bye);
+            round.push(Match { a: bye, b: None, winner: None });
+        }
+        // A knockout plays whoever's next to them, a swiss avoids rematches as long as there's a way to
+        let pairs = match self.format {
+            Format::Swiss => self.pair_up(&waiting),
+            Format::Knockout => None,
+        };
+        let pairs = pairs.unwrap_or_else(|| waiting.chunks(2).map(|pair| (pair[0], pair[1])).collect());
+        round.extend(pairs.into_iter().map(|(a, b)| Match { a, b: Some(b), winner: None }));
+        let bye = round.first().filter(|m| m.b.is_none()).map(|m| m.a);
+        self.rounds.push(round);
+        // Byes are won without playing
+        if let Some(a) = bye {
+            self.byes[a] = true;
+            self.record(0, a);
+        }
+        true
+    }
+
+    // The winner of a match in the current round, by place in the field
+    pub fn record(&mut self, index: usize, winner: usize) {
+        let Some(m) = self.rounds.last_mut().and_then(|r| r.get_mut(index)) else { return };
+        m.winner = Some(winner);
+        self.wins[winner] += 1;
+        if self.format == Format::Knockout
+            && let Some(loser) = [Some(m.a), m.b].into_iter().flatten().find(|&i| i != winner)
+        {
+            self.out[loser] = true;
+        }
+    }
+
+    // Best first: a knockout by how far everybody got, a swiss by wins, the field's order breaking ties
+    pub fn standings(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.entrants.len()).collect();
+        order.sort_by_key(|&i| (self.out[i], std::cmp::Reverse(self.wins[i])));
+        order
+    }
+
+    // Every round so far, then the standings
+    pub fn lines(&self) -> Vec<String> {
+        let name = |i: usize| self.entrants[i].name.as_str();
+        let mut lines = Vec::new();
+        for (r, round) in self.rounds.iter().enumerate() {
+            lines.push(format!("Round {}", r + 1));
+            for m in round {
+                let result = match (m.b, m.winner) {
+                    (None, _) => format!("  {} has a bye", name(m.a)),
+                    (Some(b), Some(w)) => format!("  {} vs {}: {} wins", name(m.a), name(b), name(w)),
+                    (Some(b), None) => format!("  {} vs {}", name(m.a), name(b)),
+                };
+                lines.push(result);
+            }
+        }
+        lines.push(String::from("Standings"));
+        for (place, i) in self.standings().into_iter().enumerate() {
+            let out = if self.out[i] { ", out" } else { "" };
+            lines.push(format!("  {}. {} ({} win{}{})", place + 1, name(i), self.wins[i], if self.wins[i] == 1 { "" } else { "s" }, out));
+        }
+        lines
+    }
+}
+
+// Nothing of an AI only match is shown, only who won it
+struct Quiet;
+
+impl Frontend for Quiet {
+    fn event(&mut self, _event: &Event) {}
+
+    fn prompt(&mut self, _prompt: &Prompt) -> String {
+        String::new()
+    }
+}
+
+// One game between two entrants, the people in it sit first. Games with nobody at the keyboard that run into the
+// turn limit are dealt again, a forfeit loses the match
+fn play_match(bracket: &Bracket, pair: [usize; 2], fe: &mut dyn Frontend, base: &Settings, seeds: &mut Randler) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut seats = pair;
+    seats.sort_by_key(|&i| bracket.entrants[i].ai.is_some());
+    let people: Vec<&Entrant> = seats.iter().map(|&i| &bracket.entrants[i]).filter(|e| e.ai.is_none()).collect();
+    let ais: Vec<&Entrant> = seats.iter().map(|&i| &bracket.entrants[i]).filter(|e| e.ai.is_some()).collect();
+    loop {
+        let settings = Settings {
+            players: Some(people.len() as u8),
+            ai_players: Some(ais.len() as u8),
+            difficulty: ais.first().and_then(|e| e.ai),
+            ai_mix: ais.iter().filter_map(|e| e.ai).collect(),
+            names: people.iter().map(|e| e.name.clone()).collect(),
+            ai_names: ais.iter().map(|e| e.name.clone()).collect(),
+            seed: Some(seeds.next_u64()),
+            ..base.clone()
+        };
+        let result = if people.is_empty() {
+            let mut quiet = Quiet;
+            let Some(mut table) = setup(&mut quiet, &settings)? else { return Err("the match had no seats".into()) };
+            play(&mut table, &mut quiet, Some(DEFAULT_TURN_LIMIT))
+        } else {
+            let Some(mut table) = setup(fe, &settings)? else { return Err("the match had no seats".into()) };
+            play(&mut table, fe, None)
+        };
+        match result {
+            Ok(Some(winner)) => return Ok(seats[winner - 1]),
+            Ok(None) => fe.event(&Event::Notice(format!("Nobody won in {} turns, dealing again", DEFAULT_TURN_LIMIT))),
+            Err(e) => match e.downcast_ref::<Forfeited>() {
+                Some(Forfeited(player)) => return Ok(seats[2 - player]),
+                None => return Err(e),
+            },
+        }
+    }
+}
+
+// Plays the whole bracket on the game's frontend, showing it before every round and once more at the end
+// Every game's seed comes from `seed`, so a bracket of AIs plays out the same every time
+pub fn play_bracket(bracket: &mut Bracket, fe: &mut dyn Frontend, base: &Settings, seed: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let mut seeds = Randler::new(seed);
+    // Without it a bracket dealt from a random seed couldn't be played again
+    fe.event(&Event::Notice(format!("Every match is dealt from seed {}, --seed {} deals them the same again", seed, seed)));
+    while bracket.next_round() {
+        for line in bracket.lines() {
+            fe.event(&Event::Notice(line));
+        }
+        let _: String = input(fe, Prompt::Continue, "Error")?;
+        while let Some(index) = bracket.unplayed() {
+            let m = bracket.rounds[bracket.rounds.len() - 1][index];
+            let Some(b) = m.b else { break };
+            let (a_name, b_name) = (bracket.entrants[m.a].name.clone(), bracket.entrants[b].name.clone());
+            fe.event(&Event::Notice(format!("Round {}: {} vs {}", bracket.rounds.len(), a_name, b_name)));
+            let winner = play_match(bracket, [m.a, b], fe, base, &mut seeds)?;
+            fe.event(&Event::Notice(format!("{} wins the match", bracket.entrants[winner].name)));
+            bracket.record(index, winner);
+        }
+    }
+    for line in bracket.lines() {
+        fe.event(&Event::Notice(line));
+    }
+    if let Some(&champion) = bracket.standings().first() {
+        fe.event(&Event::Notice(format!("{} wins the tournament!", bracket.entrants[champion].name)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn everybody_plays_everybody_else() {
+        let tournament = Tournament { games: 9, rules: RuleSet::default(), seed: 2, turn_limit: DEFAULT_TURN_LIMIT };
+        let field = [Difficulty::Calm, Difficulty::Random, Difficulty::Skilled];
+        let (standings, pairings) = round_robin(&field, &tournament).unwrap();
+
+        assert_eq!(pairings.len(), 3);
+        for standing in &standings {
+            assert_eq!(standing.played(), 18, "{:?}", standing);
+        }
+        assert_eq!(standings.iter().map(|s| s.wins).sum::<usize>(), standings.iter().map(|s| s.losses).sum());
+        assert!(standings.windows(2).all(|pair| pair[0].wins >= pair[1].wins));
+        assert_eq!(round_robin(&field, &tournament).unwrap().0, standings, "the same seed plays the same tournament");
+    }
+
+    #[test]
+    fn entrants_come_once_each() {
+        assert!(entrants(&[Difficulty::Calm, Difficulty::Skilled, Difficulty::Calm]).is_err());
+        assert!(entrants(&[Difficulty::Calm]).is_err());
+        assert_eq!(entrants(&[Difficulty::Expert, Difficulty::Calm]), Ok(vec![Difficulty::Expert, Difficulty::Calm]));
+    }
+
+    fn ais(n: usize) -> Vec<Entrant> {
+        field(&[], &vec![Difficulty::Calm; n])
+    }
+
+    // Plays a bracket out with the entrant first in the field always winning
+    fn lower_wins(bracket: &mut Bracket) {
+        while bracket.next_round() {
+            while let Some(i) = bracket.unplayed() {
+                let m = bracket.rounds[bracket.rounds.len() - 1][i];
+                bracket.record(i, m.a.min(m.b.unwrap_or(m.a)));
+            }
+        }
+    }
+
+    #[test]
+    fn a_knockout_gives_byes_and_ends_with_one_left() {
+        let mut bracket = Bracket::new(Format::Knockout, ais(5)).unwrap();
+        lower_wins(&mut bracket);
+
+        assert_eq!(bracket.rounds.len(), 3);
+        assert_eq!(bracket.rounds[0][0], Match { a: 0, b: None, winner: Some(0) }, "the top seed gets the bye");
+        assert!(bracket.finished());
+        assert_eq!(bracket.standings()[0], 0);
+        assert!(!bracket.next_round());
+    }
+
+    #[test]
+    fn a_swiss_pairs_new_opponents_on_the_same_score() {
+        let mut bracket = Bracket::new(Format::Swiss, ais(5)).unwrap();
+        lower_wins(&mut bracket);
+
+        assert_eq!(bracket.rounds.len(), 3, "as many rounds as a knockout of five");
+        let byes: Vec<usize> = bracket.rounds.iter().flatten().filter(|m| m.b.is_none()).map(|m| m.a).collect();
+        assert_eq!(byes, [4, 3, 2], "the bottom of the table, never twice");
+        let mut pairs: Vec<(usize, usize)> = bracket.rounds.iter().flatten().filter_map(|m| Some((m.a.min(m.b?), m.a.max(m.b?)))).collect();
+        let games = pairs.len();
+        pairs.sort();
+        pairs.dedup();
+        assert_eq!(pairs.len(), games, "nobody meets twice");
+        assert_eq!(bracket.standings()[0], 0);
+    }
+
+    #[test]
+    fn people_come_before_the_ais() {
+        let field = field(&[String::from("Ann")], &[Difficulty::Skilled]);
+        assert_eq!(field, [
+            Entrant { name: String::from("Ann"), ai: None },
+            Entrant { name: String::from("Skilled #2"), ai: Some(Difficulty::Skilled) },
+        ]);
+        assert!(Bracket::new(Format::Swiss, field[..1].to_vec()).is_err());
+        assert!("round-robin".parse::<Format>().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+// --transcript: the game written out as plain sentences, one line a turn, for pasting into a chat or a forum post
+//
+//     Turn 14: Maria played Green Skip. Bot-2 was skipped.
+//     Turn 16: Bot-3 drew 2 cards. Bot-3 played Green 4.
+//
+// Only what everyone at the table saw goes in, so nobody's hand or the cards they drew. --record is the one to keep for
+// replaying a game, this one is for people
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use unoler::cards::{Color, UNOCard, format_card_message, get_color};
+use unoler::events::{Event, Frontend, Prompt};
+use unoler::game::Table;
+
+#[derive(Default)]
+struct Story {
+    names: Vec<String>,
+    games: usize,
+    turns: usize,
+    turn: Vec<String>, // This turn's sentences so far
+    player: usize, // Whose turn it is
+    draws: usize, // Not said yet, a run of draws is one sentence
+    forced: bool, // Whether they were drawn for a stack or a penalty
+    wild: Option<UNOCard>, // Said once its color is picked
+    last: Option<String>, // The line before, held back in case the next turn is only a skip
+}
+
+impl Story {
+    fn name(&self, player: usize) -> String {
+        self.names.get(player.wrapping_sub(1)).cloned().unwrap_or(format!("Player #{}", player))
+    }
+
+    fn say_draws(&mut self) {
+        let (draws, name) = (std::mem::take(&mut self.draws), self.name(self.player));
+        match (draws, self.forced) {
+            (0, _) => {},
+            (1, false) => self.turn.push(format!("{} drew a card.", name)),
+            (n, false) => self.turn.push(format!("{} drew {} cards.", name, n)),
+            (1, true) => self.turn.push(format!("{} had to draw a card.", name)),
+            (n, true) => self.turn.push(format!("{} had to draw {} cards.", name, n)),
+        }
+    }
+
+    // The line for the turn that just ended and the one held back, whichever are finished
+    fn end_turn(&mut self) -> Vec<String> {
+        self.say_draws();
+        let sentences = std::mem::take(&mut self.turn);
+        if sentences.is_empty() {
+            return Vec::new();
+        }
+        let skipped = format!("{} was skipped.", self.name(self.player));
+        if sentences == [skipped.clone()] && let Some(last) = self.last.as_mut() {
+            last.push(' ');
+            last.push_str(&skipped);
+            return Vec::new();
+        }
+        let line = format!("Turn {}: {}", self.turns, sentences.join(" "));
+        self.last.replace(line).into_iter().collect()
+    }
+
+    // Finished lines, in order
+    fn tell(&mut self, event: &Event) -> Vec<String> {
+        if !matches!(event, Event::Drew { .. } | Event::ForcedDraw { .. }) {
+            self.say_draws();
+        }
+        let name = self.name(self.player);
+        let sentence = match event {
+            Event::GameStart { names, seed, .. } => {
+                self.names = names.clone();
+                self.games += 1;
+                self.turns = 0;
+                let seed = seed.map_or(String::from("a secure shuffle"), |s| format!("seed {}", s));
+                let mut lines: Vec<String> = self.last.take().into_iter().collect();
+                if self.games > 1 {
+                    lines.push(String::new());
+                }
+                lines.push(format!("Game {}: {}, with {}", self.games, names.join(", "), seed));
+                return lines;
+            },
+            Event::TurnStart { player, .. } => {
+                let lines = self.end_turn();
+                self.turns += 1;
+                self.player = *player;
+                return lines;
+            },
+            // A caught bluff has the seat before draw on this turn, the challenge says so
+            Event::ForcedDraw { player, .. } if *player != self.player => return Vec::new(),
+            Event::Drew { .. } | Event::ForcedDraw { .. } => {
+                self.forced = matches!(event, Event::ForcedDraw { .. });
+                self.draws += 1;
+                return Vec::new();
+            },
+            Event::Played { card, .. } if card.color == Color::NA => {
+                self.wild = Some(*card);
+                return Vec::new();
+            },
+            Event::Played { card, .. } => format!("{} played {}.", name, format_card_message(card)),
+            Event::ColorChosen { color, .. } => match self.wild.take() {
+                Some(wild) => format!("{} played {}.", name, format_card_message(&UNOCard { color: *color, ..wild })),
+                None => format!("{} picked {}.", name, get_color(color).to_lowercase()),
+            },
+            Event::Traded { .. } => format!("{} traded a card with the deck.", name),
+            Event::Skipped { .. } => format!("{} was skipped.", name),
+            Event::Uno { .. } => format!("{} called UNO!", name),
+            Event::Challenge { against, bluff: true, .. } => format!("{} challenged the +4 and caught {} bluffing.", name, self.name(*against)),
+            Event::Challenge { against, bluff: false, .. } => format!("{} challenged {}'s +4, which was no bluff.", name, self.name(*against)),
+            Event::Reshuffled { .. } => String::from("The discard pile was shuffled back into the deck."),
+            Event::NewDeck => String::from("A new deck was opened."),
+            Event::Win { player } => {
+                self.turn.push(format!("{} won!", self.name(*player)));
+                let mut lines = self.end_turn();
+                lines.extend(self.last.take());
+                lines.push(format!("{} won game {} after {} turns.", self.name(*player), self.games, self.turns));
+                return lines;
+            },
+            Event::Notice(_) | Event::InvalidInput(_) | Event::IllegalMove(_) | Event::LastCard(_) | Event::Hand(_) => return Vec::new(),
+        };
+        self.turn.push(sentence);
+        Vec::new()
+    }
+}
+
+pub struct Transcript {
+    inner: Box<dyn Frontend>,
+    path: PathBuf,
+    file: BufWriter<File>,
+    story: Story,
+}
+
+impl Transcript {
+    pub fn new(inner: Box<dyn Frontend>, path: &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Could not write the transcript to {}: {}", path.display(), e))?;
+        Ok(Transcript { inner, path: path.to_path_buf(), file: BufWriter::new(file), story: Story::default() })
+    }
+
+    fn write(&mut self, lines: Vec<String>) {
+        let written = lines.iter().try_for_each(|line| writeln!(self.file, "{}", line)).and_then(|_| self.file.flush());
+        if let Err(e) = written {
+            eprintln!("Could not write the transcript to {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl Frontend for Transcript {
+    fn event(&mut self, event: &Event) {
+        let lines = self.story.tell(event);
+        self.write(lines);
+        self.inner.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        self.inner.prompt(prompt)
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.inner.turn_start(table);
+    }
+
+    fn save(&mut self, slot: &str) -> Result<PathBuf, String> {
+        self.inner.save(slot)
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.inner.settings(change)
+    }
+
+    // A game that ended without a winner still gets its last turn
+    fn game_over(&mut self, table: &Table, winner: Option<usize>) {
+        let mut lines = self.story.end_turn();
+        lines.extend(self.story.last.take());
+        self.write(lines);
+        self.inner.game_over(table, winner);
+    }
+
+    fn paused(&self) -> bool {
+        self.inner.paused()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::testing::card;
+
+    #[test]
+    fn turns_become_sentences() {
+        let names = vec![String::from("Maria"), String::from("Bot-2")];
+        let events = [
+            Event::GameStart { players: 1, ai_players: 1, seed: Some(3), names },
+            Event::TurnStart { player: 1, ai: false, last_played: card("green 5") },
+            Event::Hand(vec![card("green skip")]),
+            Event::Played { player: 1, ai: false, card: card("green skip") },
+            Event::TurnStart { player: 2, ai: true, last_played: card("green skip") },
+            Event::Skipped { player: 2 },
+            Event::TurnStart { player: 1, ai: false, last_played: card("green skip") },
+            Event::Drew { player: 1, card: Some(card("red 2")) },
+            Event::Drew { player: 1, card: Some(card("wild")) },
+            Event::Played { player: 1, ai: false, card: card("wild") },
+            Event::ColorChosen { player: 1, color: Color::Blue },
+            Event::Uno { player: 1 },
+            Event::TurnStart { player: 2, ai: true, last_played: card("wild blue") },
+            Event::ForcedDraw { player: 2, card: None },
+            Event::ForcedDraw { player: 2, card: None },
+            Event::Win { player: 1 },
+        ];
+        let mut story = Story::default();
+        let lines: Vec<String> = events.iter().flat_map(|e| story.tell(e)).collect();
+        assert_eq!(lines, [
+            "Game 1: Maria, Bot-2, with seed 3",
+            "Turn 1: Maria played Green Skip. Bot-2 was skipped.",
+            "Turn 3: Maria drew 2 cards. Maria played Wild Card (Blue). Maria called UNO!",
+            "Turn 4: Bot-2 had to draw 2 cards. Maria won!",
+            "Maria won game 1 after 4 turns.",
+        ]);
+    }
+}
@@ -0,0 +1,537 @@
+// --tui: the hand as one row with a cursor instead of a numbered list. Left and right move, enter plays the card
+// under the cursor, d draws, s shows the table again, h moves the cursor to what a Skilled AI would play and : types
+// a command the old way (save <slot> and the rest). In a terminal that reports the mouse, clicking a card plays it
+// and the buttons under the hand do what d, s and h do. Under uno-penalty u plays the card like enter and calls UNO
+// with it, there's no button for it since forgetting is part of the game. Everything else
+// is printed by the text frontend underneath, and the whole thing falls back to it when the terminal can't be put in
+// raw mode. The bottom line of the screen is kept for a status bar with whose turn it is and how the table stands.
+// With a turn timer the seconds left count down next to the buttons, and the card is drawn when they run out. After
+// three turns in a row run out like that the player is taken to have left and the AI plays their seat. A hand
+// too wide for the screen wraps onto more lines, and everything is drawn again to fit when the terminal is resized
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use unoler::ai::get_move_ai;
+use unoler::cards::{UNOCard, colors, format_card_message};
+use unoler::events::{Event, Frontend, Prompt, TextFrontend};
+use unoler::game::{Difficulty, Table, default_name};
+use unoler::rng::Randler;
+use unoler::rules::allowed_move;
+
+use crate::term::{self, Key, MouseReporting, RawMode, ScrollRegion};
+
+const HELP: &str = "left/right pick, enter plays, u plays it calling UNO, d draws, t trades it, s shows the table, h hints, m or esc pauses, : types a command";
+// Turns in a row a player can let the timer run out on before the AI takes their seat
+const TIMEOUTS_BEFORE_TAKEOVER: u8 = 3;
+const BUTTONS: [(&str, char); 3] = [("[Draw]", 'd'), ("[Show]", 's'), ("[Hint]", 'h')];
+
+// How many columns some text takes on screen, leaving out the color escapes
+fn width(text: &str) -> usize {
+    let mut escape = false;
+    text.chars()
+        .filter(|&c| {
+            match (escape, c) {
+                (false, '\x1b') => escape = true,
+                (true, c) if c.is_ascii_alphabetic() => escape = false,
+                (false, _) => return true,
+                _ => {},
+            }
+            false
+        })
+        .count()
+}
+
+// What a key does at the action prompt
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Answer(String),
+    Note(String),
+    Type, // Back to line mode for one answer
+    Nothing,
+}
+
+pub struct Tui {
+    text: TextFrontend,
+    hand: Vec<UNOCard>, // Sorted the way the game numbers it
+    last_played: Option<UNOCard>,
+    uno: bool,
+    force_play: bool,
+    colors: u8, // How many the deck has, for the color keys
+    cursor: usize,
+    row: Option<usize>, // The screen row of the hand's first line, once the terminal has said where the cursor is
+    lines: usize, // How many lines the hand took the last time it was drawn
+    cols: usize,
+    turn_timer: Option<Duration>,
+    timeouts: Vec<u8>, // Turns in a row each player ran out of time on, by seat
+    status: String,
+    bar: Option<ScrollRegion>, // Kept from the first turn on, dropping it gives the line back
+}
+
+// Whose turn, which way play goes, what's waiting to be drawn, how many cards are left and which rules
+fn status_line(t: &Table) -> String {
+    let player = t.game_state.player_number();
+    let name = t.names.get(player - 1).cloned().unwrap_or_else(|| default_name(player));
+    let direction = if t.game_state.direction > 0 { "clockwise" } else { "counterclockwise" };
+    let stack = if t.add_queue > 0 { format!("+{} waiting", t.add_queue) } else { String::from("nothing to draw") };
+    let rules = t.rules.preset().unwrap_or("custom");
+    format!(" {}'s turn | {} | {} | {} cards in the deck | {} rules ", name, direction, stack, t.deck.len(), rules)
+}
+
+// Whole seconds rounded up, so the countdown shows 1 until the very end instead of 0 for the last second
+fn seconds_left(deadline: Instant, now: Instant) -> u64 {
+    let left = deadline.saturating_duration_since(now);
+    left.as_secs() + u64::from(left.subsec_nanos() > 0)
+}
+
+impl Tui {
+    pub fn new(text: TextFrontend) -> Self {
+        Tui {
+            text,
+            hand: Vec::new(),
+            last_played: None,
+            uno: false,
+            force_play: false,
+            colors: 4,
+            cursor: 0,
+            row: None,
+            lines: 0,
+            cols: usize::MAX, // Until the terminal says otherwise, the hand is one line
+            turn_timer: None,
+            timeouts: Vec::new(),
+            status: String::new(),
+            bar: None,
+        }
+    }
+
+    // How long the action prompt waits before drawing for the player, 0 waits forever
+    pub fn turn_timer(mut self, seconds: u8) -> Self {
+        self.turn_timer = Some(Duration::from_secs(seconds.into())).filter(|t| !t.is_zero());
+        self
+    }
+
+    fn playable(&self, i: usize) -> bool {
+        self.last_played.is_some_and(|last| allowed_move(self.hand[i], last))
+    }
+
+    // Skilled doesn't roll any dice for a card, only for colors, so a fixed generator is as good as the game's
+    fn hint(&self) -> Option<usize> {
+        let last = self.last_played?;
+        let pick = get_move_ai(&self.hand, last, Difficulty::Skilled, self.uno, None, &mut Randler::new(0));
+        pick.or_else(|| if self.force_play { (0..self.hand.len()).find(|&i| self.playable(i)) } else { None })
+    }
+
+    fn key(&mut self, key: Key) -> Step {
+        let count = self.hand.len().max(1);
+        match key {
+            Key::Left | Key::Up => self.cursor = (self.cursor + count - 1) % count,
+            Key::Right | Key::Down => self.cursor = (self.cursor + 1) % count,
+            Key::Click { row, col } => return self.click(row, col),
+            Key::Position { row, .. } => self.row = Some(row.saturating_sub(self.lines)),
+            Key::Enter | Key::Char('u') if self.cursor < self.hand.len() => {
+                if !self.playable(self.cursor) {
+                    let top = self.last_played.map_or(String::new(), |c| format_card_message(&c));
+                    return Step::Note(format!("{} can't go on {}", format_card_message(&self.hand[self.cursor]), top));
+                }
+                let call = if key == Key::Char('u') { " uno" } else { "" };
+                return Step::Answer(format!("{}{}", self.cursor + 1, call));
+            },
+            Key::Char('d') => return Step::Answer(String::from("d")),
+            Key::Char('s') => return Step::Answer(String::from("s")),
+            Key::Char('t') if self.cursor < self.hand.len() => return Step::Answer(format!("t {}", self.cursor + 1)),
+            Key::Char(':') => return Step::Type,
+            Key::Escape | Key::Char('m') => return Step::Answer(String::from("menu")),
+            Key::Char('h') => match self.hint() {
+                Some(i) => {
+                    self.cursor = i;
+                    return Step::Note(format!("Hint: play {}", format_card_message(&self.hand[i])));
+                },
+                None => return Step::Note(String::from("Hint: nothing to play, draw")),
+            },
+            _ => {},
+        }
+        Step::Nothing
+    }
+
+    // Clicks outside the hand and the buttons, or before the hand's row is known, do nothing
+    fn click(&mut self, row: usize, col: usize) -> Step {
+        let Some(top) = self.row.filter(|top| row >= *top) else { return Step::Nothing };
+        if row - top < self.lines {
+            if let Some(i) = self.card_at(row - top, col) {
+                self.cursor = i;
+                return self.key(Key::Enter);
+            }
+        } else if row - top == self.lines {
+            let mut start = 1;
+            for (label, key) in BUTTONS {
+                if (start..start + label.len()).contains(&col) {
+                    return self.key(Key::Char(key));
+                }
+                start += label.len() + 1;
+            }
+        }
+        Step::Nothing
+    }
+
+    // Every card takes its own width and one column on each side for the cursor marks
+    fn card_width(&self, i: usize) -> usize {
+        width(&self.text.card(&self.hand[i])) + 2
+    }
+
+    // The cards on each line of the hand, as many as fit the screen before the next line starts
+    fn layout(&self) -> Vec<Vec<usize>> {
+        let mut lines: Vec<Vec<usize>> = vec![Vec::new()];
+        let mut used = 0;
+        for i in 0..self.hand.len() {
+            let card = self.card_width(i);
+            if used + card > self.cols && lines.last().is_some_and(|line| !line.is_empty()) {
+                lines.push(Vec::new());
+                used = 0;
+            }
+            if let Some(line) = lines.last_mut() {
+                line.push(i);
+            }
+            used += card;
+        }
+        lines
+    }
+
+    // Lines of the hand count from 0, columns from 1 like the terminal's
+    fn card_at(&self, line: usize, col: usize) -> Option<usize> {
+        let mut start = 1;
+        for &i in self.layout().get(line)? {
+            let end = start + self.card_width(i);
+            if (start..end).contains(&col) {
+                return Some(i);
+            }
+            start = end;
+        }
+        None
+    }
+
+    fn hand_lines(&self) -> Vec<String> {
+        let card = |i: usize| {
+            let text = self.text.card(&self.hand[i]);
+            if i == self.cursor { format!("\x1b[7m>{}<\x1b[0m", text) } else { format!(" {} ", text) }
+        };
+        self.layout().iter().map(|line| line.iter().map(|&i| card(i)).collect()).collect()
+    }
+
+    // The screen's width for wrapping the hand, and the status bar's line again when the height changed
+    fn fit(&mut self) {
+        let Some((rows, cols)) = term::size() else { return };
+        self.cols = cols;
+        if self.bar.as_ref().is_some_and(|bar| bar.rows != rows) {
+            self.bar = None;
+        }
+    }
+
+    // On the reserved bottom line, cut to the width of the screen
+    fn draw_status(&mut self) {
+        if self.bar.is_none()
+            && let Some((rows, _)) = term::size().filter(|(rows, _)| *rows > 2)
+        {
+            self.bar = Some(ScrollRegion::reserve_bottom_line(rows));
+        }
+        let Some(bar) = &self.bar else { return };
+        let status: String = self.status.chars().take(self.cols).collect();
+        print!("\x1b7\x1b[{};1H\x1b[2K\x1b[7m{}\x1b[0m\x1b8", bar.rows, status);
+        io::stdout().flush().expect("Failed to flush terminal.");
+    }
+
+    // The hand and the buttons with the note under it, drawn over the last drawing after the first time. A hand that
+    // got shorter leaves lines under the new drawing, those are cleared
+    fn draw(&mut self, note: &str, again: bool) {
+        if again && self.lines > 0 {
+            print!("\x1b[{}A", self.lines);
+        }
+        let lines = self.hand_lines();
+        for line in &lines {
+            print!("\r\x1b[2K{}\r\n", line);
+        }
+        let buttons: Vec<&str> = BUTTONS.iter().map(|(label, _)| *label).collect();
+        let under: String = format!("{}  {}", buttons.join(" "), note).chars().take(self.cols).collect();
+        print!("\x1b[2K{}", under);
+        if again && self.lines > lines.len() {
+            print!("\x1b7{}\x1b8", "\r\n\x1b[2K".repeat(self.lines - lines.len()));
+        }
+        self.lines = lines.len();
+        io::stdout().flush().expect("Failed to flush terminal.");
+    }
+
+    // The buttons line is where the cursor stops, the answer says which row the hand ended up on
+    fn locate(&mut self) {
+        self.row = None;
+        term::where_am_i();
+    }
+
+    // Drawing for the player, or after too many turns in a row handing their seat to the AI
+    fn timed_out(&mut self, prompt: &Prompt) -> String {
+        let Prompt::Action { player } = *prompt else { return String::from("d") };
+        if self.timeouts.len() < player {
+            self.timeouts.resize(player, 0);
+        }
+        self.timeouts[player - 1] += 1;
+        if self.timeouts[player - 1] >= TIMEOUTS_BEFORE_TAKEOVER {
+            self.timeouts[player - 1] = 0;
+            println!("\r\nOut of time {} turns in a row, the AI takes over", TIMEOUTS_BEFORE_TAKEOVER);
+            return String::from("takeover");
+        }
+        println!("\r\nOut of time, drawing");
+        String::from("d")
+    }
+
+    fn answered(&mut self, prompt: &Prompt) {
+        if let Prompt::Action { player } = *prompt && let Some(timeouts) = self.timeouts.get_mut(player - 1) {
+            *timeouts = 0;
+        }
+    }
+
+    fn action(&mut self, prompt: &Prompt) -> String {
+        // Ticking, so the countdown and a resized screen get drawn without waiting for a key
+        let Some(raw) = RawMode::enter_ticking() else {
+            self.text.event(&Event::Hand(self.hand.clone()));
+            return self.text.prompt(prompt);
+        };
+        term::watch_resizes();
+        let mouse = MouseReporting::enable();
+        let deadline = self.turn_timer.map(|t| Instant::now() + t);
+        let countdown = |note: &str| match deadline {
+            Some(deadline) => format!("{}s left  {}", seconds_left(deadline, Instant::now()), note),
+            None => note.to_string(),
+        };
+        self.cursor = self.cursor.min(self.hand.len().saturating_sub(1));
+        self.fit();
+        // Clearing the screen between turns takes the bar with it
+        self.draw_status();
+        let mut note = String::from(HELP);
+        self.lines = 0;
+        self.draw(&countdown(&note), false);
+        self.locate();
+        let mut shown = deadline.map(|d| seconds_left(d, Instant::now()));
+        loop {
+            if let Some(deadline) = deadline {
+                let left = seconds_left(deadline, Instant::now());
+                if left == 0 {
+                    drop(mouse);
+                    drop(raw);
+                    return self.timed_out(prompt);
+                }
+                if shown != Some(left) {
+                    shown = Some(left);
+                    self.draw(&countdown(&note), true);
+                }
+            }
+            if term::resized() {
+                self.fit();
+                self.draw_status();
+                self.draw(&countdown(&note), true);
+                self.locate();
+            }
+            let Some(keys) = term::poll_keys() else {
+                drop(mouse);
+                drop(raw);
+                println!();
+                return String::new();
+            };
+            for key in keys {
+                match self.key(key) {
+                    Step::Answer(answer) => {
+                        drop(mouse);
+                        println!();
+                        self.answered(prompt);
+                        return answer;
+                    },
+                    Step::Note(n) => {
+                        note = n;
+                        self.draw(&countdown(&note), true);
+                    },
+                    Step::Type => {
+                        drop(mouse);
+                        drop(raw);
+                        println!();
+                        self.answered(prompt);
+                        return self.text.prompt(prompt);
+                    },
+                    Step::Nothing if matches!(key, Key::Position { .. }) => {},
+                    Step::Nothing => {
+                        note = String::from(HELP);
+                        self.draw(&countdown(&note), true);
+                    },
+                }
+            }
+        }
+    }
+
+    // One key for the questions with a handful of answers
+    fn choice(&mut self, prompt: &Prompt, question: &str, answers: &[(char, &str)]) -> String {
+        let Some(_raw) = RawMode::enter() else { return self.text.prompt(prompt) };
+        print!("{} ", question);
+        io::stdout().flush().expect("Failed to flush terminal.");
+        while let Some(keys) = term::read_keys() {
+            for key in keys {
+                if let Key::Char(c) = key
+                    && let Some((_, answer)) = answers.iter().find(|(k, _)| *k == c.to_ascii_lowercase())
+                {
+                    println!("{}", answer);
+                    return answer.to_string();
+                }
+            }
+        }
+        String::new()
+    }
+}
+
+impl Frontend for Tui {
+    fn event(&mut self, event: &Event) {
+        match event {
+            // The action prompt shows the hand itself
+            Event::Hand(hand) => {
+                self.hand = hand.clone();
+                return;
+            },
+            Event::TurnStart { last_played, .. } => {
+                self.last_played = Some(*last_played);
+                self.cursor = 0;
+            },
+            Event::Drew { card: Some(card), .. } => {
+                self.hand.push(*card);
+                self.hand.sort();
+            },
+            Event::Traded { gave, got: Some(got), .. } => {
+                if let Some(index) = self.hand.iter().position(|c| c == gave) {
+                    self.hand[index] = *got;
+                    self.hand.sort();
+                }
+            },
+            _ => {},
+        }
+        self.text.event(event);
+    }
+
+    fn prompt(&mut self, prompt: &Prompt) -> String {
+        match prompt {
+            Prompt::Action { .. } => self.action(prompt),
+            Prompt::Color { .. } => {
+                let all = [('r', "red"), ('g', "green"), ('y', "yellow"), ('b', "blue"), ('p', "purple"), ('o', "orange")];
+                let colors = &all[..colors(self.colors).len()];
+                let keys: Vec<String> = colors.iter().map(|(key, _)| key.to_string()).collect();
+                let question = format!("Pick a color: {} or {}", keys[..keys.len() - 1].join(", "), keys[keys.len() - 1]);
+                self.choice(prompt, &question, colors)
+            },
+            Prompt::PlayDrawn { .. } => self.choice(prompt, "Play the card you drew? y, u to call UNO with it, or n", &[('y', "y"), ('u', "y uno"), ('n', "n")]),
+            Prompt::Challenge { .. } => self.choice(prompt, "Challenge the +4 as a bluff? y or n", &[('y', "y"), ('n', "n")]),
+            Prompt::Rematch => self.choice(prompt, "Rematch? y or n", &[('y', "y"), ('n', "n")]),
+            _ => self.text.prompt(prompt),
+        }
+    }
+
+    fn turn_start(&mut self, table: &Table) {
+        self.uno = table.uno_detection_panic;
+        self.force_play = table.rules.force_play;
+        self.colors = table.rules.colors;
+        self.status = status_line(table);
+        self.text.turn_start(table);
+        self.draw_status();
+    }
+
+    fn settings(&mut self, change: Option<(&str, &str)>) -> Result<String, String> {
+        self.text.settings(change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unoler::testing::{card, cards};
+
+    fn at(hand: &[&str], top: &str) -> Tui {
+        let mut tui = Tui::new(TextFrontend::new(false, false));
+        tui.hand = cards(hand);
+        tui.last_played = Some(card(top));
+        tui
+    }
+
+    #[test]
+    fn arrows_pick_and_enter_plays() {
+        let mut tui = at(&["red 5", "blue 7", "green 1"], "blue 2");
+        assert_eq!(tui.key(Key::Left), Step::Nothing);
+        assert_eq!(tui.cursor, 2, "left from the first card wraps around");
+        assert!(matches!(tui.key(Key::Enter), Step::Note(note) if note == "Green 1 can't go on Blue 2"));
+        tui.key(Key::Right);
+        tui.key(Key::Right);
+        assert_eq!(tui.key(Key::Enter), Step::Answer(String::from("2")));
+        assert_eq!(tui.key(Key::Char('u')), Step::Answer(String::from("2 uno")));
+        assert_eq!(tui.key(Key::Char('d')), Step::Answer(String::from("d")));
+        assert_eq!(tui.key(Key::Char(':')), Step::Type);
+        assert_eq!(tui.key(Key::Escape), Step::Answer(String::from("menu")));
+    }
+
+    #[test]
+    fn hints_move_the_cursor() {
+        let mut tui = at(&["red 5", "blue 7", "green 1"], "blue 2");
+        assert!(matches!(tui.key(Key::Char('h')), Step::Note(_)));
+        assert_eq!(tui.cursor, 1);
+        let mut stuck = at(&["red 5"], "blue 2");
+        assert_eq!(stuck.key(Key::Char('h')), Step::Note(String::from("Hint: nothing to play, draw")));
+    }
+
+    #[test]
+    fn clicks_play_cards_and_press_buttons() {
+        let mut tui = at(&["red 5", "blue 7", "green 1"], "blue 2");
+        assert_eq!(tui.key(Key::Click { row: 10, col: 8 }), Step::Nothing, "nothing until the hand's row is known");
+        tui.lines = 1;
+        tui.key(Key::Position { row: 11, col: 30 });
+        // " Red 5 " is columns 1 to 7, " Blue 7 " 8 to 15
+        assert_eq!(tui.key(Key::Click { row: 10, col: 8 }), Step::Answer(String::from("2")));
+        assert!(matches!(tui.key(Key::Click { row: 10, col: 1 }), Step::Note(_)), "a card that can't go is only a note");
+        assert_eq!(tui.key(Key::Click { row: 10, col: 40 }), Step::Nothing);
+        assert_eq!(tui.key(Key::Click { row: 11, col: 3 }), Step::Answer(String::from("d")));
+        assert_eq!(tui.key(Key::Click { row: 11, col: 10 }), Step::Answer(String::from("s")));
+        assert!(matches!(tui.key(Key::Click { row: 11, col: 15 }), Step::Note(_)));
+        assert_eq!(tui.cursor, 1, "the hint button moves the cursor like h");
+        assert_eq!(width("\x1b[1;97;41m[R] Red 5\x1b[0m"), 9);
+    }
+
+    #[test]
+    fn narrow_screens_wrap_the_hand() {
+        let mut tui = at(&["red 5", "blue 7", "green 1", "yellow 2"], "green 7");
+        assert_eq!(tui.layout(), [vec![0, 1, 2, 3]]);
+        // With the cursor marks the cards take 7, 8, 9 and 10 columns
+        tui.cols = 19;
+        assert_eq!(tui.layout(), [vec![0, 1], vec![2, 3]]);
+        assert_eq!(tui.hand_lines()[1], " Green 1  Yellow 2 ");
+        tui.cols = 3;
+        assert_eq!(tui.layout().len(), 4, "a card wider than the screen still gets a line");
+        tui.cols = 19;
+        tui.lines = 2;
+        tui.key(Key::Position { row: 20, col: 1 });
+        assert_eq!(tui.row, Some(18));
+        assert_eq!(tui.key(Key::Click { row: 19, col: 3 }), Step::Answer(String::from("3")));
+        assert_eq!(tui.key(Key::Click { row: 20, col: 3 }), Step::Answer(String::from("d")));
+    }
+
+    #[test]
+    fn the_status_bar_shows_the_table() {
+        use unoler::rules::RuleSet;
+        use unoler::testing::TestGame;
+
+        let game = TestGame::new(3).hand(1, &["red 5"]).deck(&["blue 1", "blue 2"]).build();
+        let line = status_line(game.table());
+        assert_eq!(line, " Player #1's turn | clockwise | nothing to draw | 2 cards in the deck | house rules ");
+        let mut game = TestGame::new(3).rules(RuleSet { hand_size: 5, ..RuleSet::default() }).build();
+        game.table_mut().add_queue = 4;
+        game.table_mut().game_state.reverse();
+        assert!(status_line(game.table()).contains("| counterclockwise | +4 waiting |"));
+        assert!(status_line(game.table()).ends_with("custom rules "));
+    }
+
+    #[test]
+    fn the_countdown_rounds_up() {
+        let now = Instant::now();
+        assert_eq!(seconds_left(now + Duration::from_millis(9500), now), 10);
+        assert_eq!(seconds_left(now + Duration::from_secs(3), now), 3);
+        assert_eq!(seconds_left(now, now + Duration::from_secs(1)), 0);
+        assert_eq!(Tui::new(TextFrontend::new(false, false)).turn_timer(0).turn_timer, None, "0 is no timer");
+    }
+}
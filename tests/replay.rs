@@ -0,0 +1,67 @@
+// Every recorded game in tests/replays has to play out exactly the same on the current engine
+// When a change is meant to alter how games go, record them again (see the README) and review the diff
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn verify(path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_UNOler_static"))
+        .args(["replay", "verify"])
+        .arg(path)
+        .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .output()
+        .expect("Failed to run the game")
+}
+
+fn replays() -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("replays");
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().path()).collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn recorded_games_play_out_the_same() {
+    let paths = replays();
+    assert!(!paths.is_empty());
+    for path in paths {
+        let output = verify(&path);
+        assert!(output.status.success(), "{}: {}", path.display(), String::from_utf8_lossy(&output.stdout));
+    }
+}
+
+#[test]
+fn a_changed_deal_is_caught() {
+    // Dealing one more card than in the recording changes the game from the first turn
+    let original = fs::read_to_string(&replays()[0]).unwrap();
+    let changed = original.replacen("\"hand-size\":3", "\"hand-size\":4", 1);
+    assert_ne!(original, changed);
+
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("changed_replay.json");
+    fs::write(&path, changed).unwrap();
+    let output = verify(&path);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("diverged"), "{}", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn recorded_games_export_to_notation_that_plays_back() {
+    let run = |args: &[&str], path: &Path| {
+        Command::new(env!("CARGO_BIN_EXE_UNOler_static"))
+            .args(args)
+            .arg(path)
+            .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+            .output()
+            .expect("Failed to run the game")
+    };
+    for path in replays() {
+        let exported = run(&["notation", "export"], &path);
+        assert!(exported.status.success(), "{}: {}", path.display(), String::from_utf8_lossy(&exported.stderr));
+
+        let notation = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(path.with_extension("ugn").file_name().unwrap());
+        fs::write(&notation, &exported.stdout).unwrap();
+        let played = run(&["notation", "play"], &notation);
+        assert!(played.status.success(), "{}: {}", notation.display(), String::from_utf8_lossy(&played.stderr));
+    }
+}
@@ -0,0 +1,72 @@
+// Whole games played through the binary with --script, checked against the result they had when they were recorded
+// A change that makes a recorded move illegal or changes who wins shows up here
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn script(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("scripts").join(name)
+}
+
+fn run(script: &PathBuf, seed: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_UNOler_static"))
+        .args(["--players", "1", "--ai-players", "1", "--difficulty", "calm", "--hand-size", "3", "--seed", seed, "--script"])
+        .arg(script)
+        // Keep the tester's own config file out of it
+        .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .env_remove("UNOLER_RULES")
+        .output()
+        .expect("Failed to run the game")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[test]
+fn recorded_game_still_ends_the_same() {
+    let output = run(&script("calm_seed10.txt"), "10");
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(stdout(&output), r#"{"result":"win","winner":1,"name":"Player #1","turns":23,"seed":"10"}"#);
+}
+
+#[test]
+fn a_refused_move_stops_the_game() {
+    // The same game on another seed deals different hands, so the recorded moves stop fitting
+    let output = run(&script("calm_seed10.txt"), "3");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("of the script"), "{}", stdout(&output));
+    assert!(stdout(&output).contains("was refused"), "{}", stdout(&output));
+}
+
+#[test]
+fn running_out_of_script_is_an_error() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("short_script.txt");
+    std::fs::write(&path, "# Only the first move\n3\n").unwrap();
+    let output = run(&path, "10");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("The script ran out"), "{}", stdout(&output));
+}
+
+fn puzzle(name: &str, answers: &str) -> Output {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("puzzle_{}.txt", name));
+    std::fs::write(&path, answers).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_UNOler_static"))
+        .args(["puzzle", name, "--script"])
+        .arg(&path)
+        .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .env_remove("UNOLER_RULES")
+        .output()
+        .expect("Failed to run the game")
+}
+
+#[test]
+fn puzzles_play_from_a_script() {
+    // The wild, then the color the opponent can't answer, then the last card
+    let output = puzzle("color-choice", "2\nyellow\n1\n");
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains(r#""winner":1"#), "{}", stdout(&output));
+
+    let output = puzzle("color-choice", "2\nblue\n");
+    assert!(stdout(&output).contains(r#""winner":2"#), "{}", stdout(&output));
+}
@@ -0,0 +1,55 @@
+// Loads the wasm build of the engine and hides the memory juggling
+// Build it with: cargo build -p unoler-engine --release --target wasm32-unknown-unknown
+// then serve target/wasm32-unknown-unknown/release/unoler.wasm next to this file
+//
+//   const game = await UNOler.load("unoler.wasm");
+//   let view = game.start({ players: 1, ai_players: 2, difficulty: "skilled" });
+//   view = game.act("3");          // whatever the player would have typed: a card number, "d", "red", ...
+//   const news = game.events(seen); // events since the ones already shown
+
+export class UNOler {
+    static async load(url) {
+        const { instance } = await WebAssembly.instantiateStreaming(fetch(url), {});
+        return new UNOler(instance.exports);
+    }
+
+    constructor(exports) {
+        this.wasm = exports;
+    }
+
+    // Copies text into a buffer the engine frees once it's read it
+    input(text) {
+        const bytes = new TextEncoder().encode(text);
+        const ptr = this.wasm.unoler_alloc(bytes.length);
+        new Uint8Array(this.wasm.memory.buffer, ptr, bytes.length).set(bytes);
+        return [ptr, bytes.length];
+    }
+
+    // Every call leaves its JSON answer in the engine's memory until the next one
+    output(len) {
+        const bytes = new Uint8Array(this.wasm.memory.buffer, this.wasm.unoler_output(), len);
+        const value = JSON.parse(new TextDecoder().decode(bytes));
+        if (value !== null && value.error !== undefined) {
+            throw new Error(value.error);
+        }
+        return value;
+    }
+
+    // No OS to get a seed from in here, so one is made up unless the settings have it
+    start(settings) {
+        const seed = settings.seed ?? crypto.getRandomValues(new BigUint64Array(1))[0].toString();
+        return this.output(this.wasm.unoler_start(...this.input(JSON.stringify({ ...settings, seed }))));
+    }
+
+    act(answer) {
+        return this.output(this.wasm.unoler_act(...this.input(answer)));
+    }
+
+    view() {
+        return this.output(this.wasm.unoler_view());
+    }
+
+    events(since = 0) {
+        return this.output(this.wasm.unoler_events(since));
+    }
+}